@@ -0,0 +1,225 @@
+//! Playbook热路径基准：apply_delta、日志重放、批量载入、as_prompt、保存/加载
+//!
+//! 运行：`cargo bench --bench playbook`，修改热路径前后对比结果防止性能回退。
+
+use std::{collections::BTreeMap, hint::black_box, sync::Arc};
+
+use ace_rs::models::{
+    delta::{DeltaBatch, DeltaOperation, OperationType},
+    playbook::{Bullet, Playbook},
+    replay::{JournalEntry, ReplayFilter},
+};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+
+const SIZES: [usize; 2] = [10_000, 100_000];
+const SECTIONS: usize = 20;
+
+fn add_batch(n: usize) -> DeltaBatch {
+    let operations = (0..n)
+        .map(|i| DeltaOperation {
+            type_: OperationType::Add,
+            section: format!("section {}", i % SECTIONS),
+            content: Some(format!("strategy number {} for the benchmark workload", i)),
+            bullet_id: None,
+            merged_ids: Vec::new(),
+            merge_strategy: None,
+            metadata: BTreeMap::from([("helpful".to_string(), (i % 5) as i32)]),
+            example_id: None,
+            input: None,
+            output: None,
+            skill: None,
+        })
+        .collect();
+    DeltaBatch {
+        reasoning: "bench".to_string(),
+        operations,
+    }
+}
+
+fn playbook(n: usize) -> Playbook {
+    let mut pb = Playbook::with_capacity(n);
+    pb.apply_delta(add_batch(n)).unwrap();
+    pb
+}
+
+fn bench_apply_delta(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apply_delta");
+    group.sample_size(10);
+    for n in SIZES {
+        group.bench_with_input(BenchmarkId::new("add", n), &n, |b, &n| {
+            b.iter_batched(
+                || (Playbook::new(), add_batch(n)),
+                |(mut pb, batch)| {
+                    pb.apply_delta(batch).unwrap();
+                    pb
+                },
+                BatchSize::LargeInput,
+            )
+        });
+
+        let base = playbook(n);
+        let ids: Vec<String> = base.bullets.keys().cloned().collect();
+        group.bench_with_input(BenchmarkId::new("tag", n), &n, |b, _| {
+            b.iter_batched(
+                || {
+                    let operations = ids
+                        .iter()
+                        .map(|id| DeltaOperation {
+                            type_: OperationType::Tag,
+                            section: String::new(),
+                            content: None,
+                            bullet_id: Some(id.clone()),
+                            merged_ids: Vec::new(),
+                            merge_strategy: None,
+                            metadata: BTreeMap::from([("helpful".to_string(), 1)]),
+                            example_id: None,
+                            input: None,
+                            output: None,
+                            skill: None,
+                        })
+                        .collect();
+                    (
+                        base.clone(),
+                        DeltaBatch {
+                            reasoning: String::new(),
+                            operations,
+                        },
+                    )
+                },
+                |(mut pb, batch)| {
+                    pb.apply_delta(batch).unwrap();
+                    pb
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// 100k个操作的Delta日志：每条100个操作，先ADD（指定ID）再交替UPDATE/TAG
+fn journal(ops: usize) -> Vec<JournalEntry> {
+    let per_entry = 100;
+    let ids = ops / 2;
+    let operations: Vec<DeltaOperation> = (0..ops)
+        .map(|i| {
+            let id = format!("bench-{:06}", i % ids);
+            let section = format!("section {}", i % SECTIONS);
+            match i / ids {
+                0 => {
+                    let mut op = DeltaOperation::add(section, format!("strategy {}", i));
+                    op.bullet_id = Some(id);
+                    op.metadata.insert("helpful".to_string(), 1);
+                    op
+                }
+                _ if i % 2 == 0 => DeltaOperation {
+                    type_: OperationType::Update,
+                    content: Some(format!("revised strategy {}", i)),
+                    bullet_id: Some(id),
+                    ..DeltaOperation::add(section, "")
+                },
+                _ => DeltaOperation::tag(
+                    section,
+                    id,
+                    BTreeMap::from([("helpful".to_string(), 1), ("harmful".to_string(), -1)]),
+                ),
+            }
+        })
+        .collect();
+    operations
+        .chunks(per_entry)
+        .enumerate()
+        .map(|(i, chunk)| JournalEntry {
+            run: None,
+            applied_at: chrono::DateTime::from_timestamp(i as i64, 0).unwrap(),
+            delta: DeltaBatch { reasoning: String::new(), operations: chunk.to_vec() },
+        })
+        .collect()
+}
+
+fn bench_replay(c: &mut Criterion) {
+    let mut group = c.benchmark_group("replay");
+    group.sample_size(10);
+    let entries = journal(100_000);
+    group.bench_function("100k_ops", |b| {
+        b.iter(|| black_box(ReplayFilter::default().replay(&entries).unwrap()))
+    });
+    group.finish();
+}
+
+fn bench_bulk_load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bulk_load");
+    group.sample_size(10);
+    for n in SIZES {
+        let bullets: Vec<Bullet> =
+            playbook(n).bullets.into_values().map(Arc::unwrap_or_clone).collect();
+        group.bench_with_input(BenchmarkId::new("bulk", n), &bullets, |b, bullets| {
+            b.iter_batched(
+                || bullets.clone(),
+                |bullets| {
+                    let mut pb = Playbook::new();
+                    pb.bulk_load(bullets).unwrap();
+                    pb
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("per_bullet", n), &bullets, |b, bullets| {
+            b.iter_batched(
+                || bullets.clone(),
+                |bullets| {
+                    let mut pb = Playbook::new();
+                    for bullet in bullets {
+                        let metadata = BTreeMap::from([("helpful".to_string(), bullet.helpful)]);
+                        let (id, now) = (Some(bullet.id), bullet.created_at);
+                        pb.add_bullet_at(&*bullet.section, bullet.content, id, Some(metadata), now);
+                    }
+                    pb
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_as_prompt(c: &mut Criterion) {
+    let mut group = c.benchmark_group("as_prompt");
+    group.sample_size(10);
+    for n in SIZES {
+        let pb = playbook(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &pb, |b, pb| {
+            b.iter(|| black_box(pb.as_prompt()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_save_load(c: &mut Criterion) {
+    let dir = std::env::temp_dir().join("ace-rs-bench");
+    let mut group = c.benchmark_group("persist");
+    group.sample_size(10);
+    for n in SIZES {
+        let pb = playbook(n);
+        let path = dir.join(format!("playbook-{}.json", n));
+        group.bench_with_input(BenchmarkId::new("save", n), &pb, |b, pb| {
+            b.iter(|| pb.save_to_file(&path).unwrap())
+        });
+        pb.save_to_file(&path).unwrap();
+        group.bench_with_input(BenchmarkId::new("load", n), &path, |b, path| {
+            b.iter(|| black_box(Playbook::load_from_file(path).unwrap()))
+        });
+    }
+    group.finish();
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+criterion_group!(
+    benches,
+    bench_apply_delta,
+    bench_replay,
+    bench_bulk_load,
+    bench_as_prompt,
+    bench_save_load
+);
+criterion_main!(benches);
@@ -0,0 +1,267 @@
+//! 归因热力图：把一次评估中每个子弹“是否被使用”与“样本是否答对”交叉汇总
+//! （子弹 × 答对/答错），导出为CSV或HTML，让人直接看到功劳与过错落在哪些子弹上
+//!
+//! 子弹的使用情况来自 [`EvalRecord::bullet_ids`]
+//! （见 [`evaluate_attributed`](crate::eval::evaluate_attributed)），同一样本中重复的ID只计一次。
+//! `lift`为使用该子弹时的正确率减去未使用时的正确率，任一侧没有样本时为`None`。
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+
+use crate::eval::{EvalRecord, EvalReport};
+use crate::models::playbook::{BulletId, Playbook};
+
+/// 热力图中的一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributionRow {
+    pub bullet_id: BulletId,
+    /// 不在Playbook中的子弹（如评估后被删除）为空
+    pub section: String,
+    pub content: String,
+    pub used_correct: usize,
+    pub used_incorrect: usize,
+    pub unused_correct: usize,
+    pub unused_incorrect: usize,
+}
+
+impl AttributionRow {
+    pub fn used(&self) -> usize {
+        self.used_correct + self.used_incorrect
+    }
+
+    /// 使用该子弹时的正确率
+    pub fn used_accuracy(&self) -> Option<f64> {
+        rate(self.used_correct, self.used_incorrect)
+    }
+
+    pub fn unused_accuracy(&self) -> Option<f64> {
+        rate(self.unused_correct, self.unused_incorrect)
+    }
+
+    pub fn lift(&self) -> Option<f64> {
+        Some(self.used_accuracy()? - self.unused_accuracy()?)
+    }
+}
+
+fn rate(correct: usize, incorrect: usize) -> Option<f64> {
+    let total = correct + incorrect;
+    (total > 0).then(|| correct as f64 / total as f64)
+}
+
+/// 子弹 × 答对/答错 的汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttributionHeatmap {
+    pub samples: usize,
+    /// 按Playbook的章节与子弹顺序，其后是不在Playbook中的子弹
+    pub rows: Vec<AttributionRow>,
+}
+
+impl AttributionHeatmap {
+    /// 汇总评估报告；Playbook提供行的顺序与内容，其中从未被使用的子弹也会列出
+    pub fn from_report(playbook: &Playbook, report: &EvalReport) -> Self {
+        Self::from_records(playbook, &report.records)
+    }
+
+    pub fn from_records(playbook: &Playbook, records: &[EvalRecord]) -> Self {
+        let mut order: Vec<BulletId> = Vec::new();
+        let mut used: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for (_, ids) in playbook.sorted_sections() {
+            order.extend(ids.iter().cloned());
+        }
+        for record in records {
+            let passed = record.judgement.passed;
+            let ids: BTreeSet<&BulletId> = record.bullet_ids.iter().collect();
+            for id in ids {
+                let counts = used.entry(id.as_str()).or_insert_with(|| {
+                    if !playbook.bullets.contains_key(id) {
+                        order.push(id.clone());
+                    }
+                    (0, 0)
+                });
+                if passed {
+                    counts.0 += 1;
+                } else {
+                    counts.1 += 1;
+                }
+            }
+        }
+        let correct = records.iter().filter(|r| r.judgement.passed).count();
+        let incorrect = records.len() - correct;
+        let rows = order
+            .into_iter()
+            .map(|id| {
+                let (used_correct, used_incorrect) =
+                    used.get(id.as_str()).copied().unwrap_or_default();
+                let (section, content) = playbook
+                    .bullets
+                    .get(&id)
+                    .map(|b| (b.section.to_string(), b.content.clone()))
+                    .unwrap_or_default();
+                AttributionRow {
+                    bullet_id: id,
+                    section,
+                    content,
+                    used_correct,
+                    used_incorrect,
+                    unused_correct: correct - used_correct,
+                    unused_incorrect: incorrect - used_incorrect,
+                }
+            })
+            .collect();
+        Self { samples: records.len(), rows }
+    }
+
+    /// 按`lift`从高到低排列，没有`lift`的排在最后
+    pub fn ranked(&self) -> Vec<&AttributionRow> {
+        let mut ranked: Vec<&AttributionRow> = self.rows.iter().collect();
+        ranked.sort_by(|a, b| match (a.lift(), b.lift()) {
+            (Some(x), Some(y)) => y.total_cmp(&x),
+            (x, y) => y.is_some().cmp(&x.is_some()),
+        });
+        ranked
+    }
+
+    /// 每个子弹一行，带表头
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "bullet_id,section,content,used_correct,used_incorrect,unused_correct,\
+             unused_incorrect,lift\n",
+        );
+        for row in &self.rows {
+            let lift = row.lift().map(|l| format!("{:.3}", l)).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{},{}",
+                csv_field(&row.bullet_id),
+                csv_field(&row.section),
+                csv_field(&row.content),
+                row.used_correct,
+                row.used_incorrect,
+                row.unused_correct,
+                row.unused_incorrect,
+                lift
+            );
+        }
+        out
+    }
+
+    /// 独立的HTML表格：答对/答错的使用次数按最大值着色（绿/红），`lift`按正负着色
+    pub fn to_html(&self) -> String {
+        let max = self.rows.iter().flat_map(|r| [r.used_correct, r.used_incorrect]).max();
+        let max = max.unwrap_or(0).max(1) as f64;
+        let mut out = String::from(
+            "<table class=\"attribution\" style=\"border-collapse:collapse\">\n<thead><tr>\
+             <th>bullet</th><th>section</th><th>content</th><th>correct</th>\
+             <th>incorrect</th><th>lift</th></tr></thead>\n<tbody>\n",
+        );
+        for row in &self.rows {
+            let _ = write!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td>{}{}",
+                escape_html(&row.bullet_id),
+                escape_html(&row.section),
+                escape_html(&row.content),
+                heat_cell(row.used_correct, row.used_correct as f64 / max, "0,160,0"),
+                heat_cell(row.used_incorrect, row.used_incorrect as f64 / max, "200,0,0"),
+            );
+            let lift = match row.lift() {
+                Some(l) if l >= 0.0 => heat_cell(format!("{:+.3}", l), l, "0,160,0"),
+                Some(l) => heat_cell(format!("{:+.3}", l), -l, "200,0,0"),
+                None => "<td>-</td>".to_string(),
+            };
+            let _ = writeln!(out, "{}</tr>", lift);
+        }
+        let _ = write!(out, "</tbody>\n<caption>{} samples</caption>\n</table>\n", self.samples);
+        out
+    }
+}
+
+fn heat_cell(value: impl core::fmt::Display, intensity: f64, rgb: &str) -> String {
+    format!(
+        "<td style=\"background:rgba({},{:.2});text-align:right\">{}</td>",
+        rgb,
+        intensity.clamp(0.0, 1.0),
+        value
+    )
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    use crate::eval::{ExactMatchJudge, evaluate_attributed};
+    use crate::models::sample::Sample;
+
+    #[test]
+    fn test_heatmap_from_attributed_eval() {
+        let mut pb = Playbook::new();
+        let capital = pb.add_bullet("geography", "The capital of France is Paris", None, None);
+        let noise = pb.add_bullet("style", "Answer with \"one\" word, <b>briefly</b>", None, None);
+        let idle = pb.add_bullet("style", "Never used", None, None);
+        let sample = Sample { ground_truth: Some("Paris".to_string()), ..Sample::new("capital?") };
+        let items = vec![
+            (&sample, "Paris".to_string(), vec![capital.clone(), noise.clone()]),
+            (&sample, "Paris".to_string(), vec![capital.clone(), capital.clone()]),
+            (&sample, "Lyon".to_string(), vec![noise.clone(), "gone-1".to_string()]),
+        ];
+        let report = evaluate_attributed(&ExactMatchJudge, items).unwrap();
+        let heatmap = AttributionHeatmap::from_report(&pb, &report);
+
+        assert_eq!(heatmap.samples, 3);
+        let ids: Vec<&str> = heatmap.rows.iter().map(|r| r.bullet_id.as_str()).collect();
+        assert_eq!(ids, [capital.as_str(), noise.as_str(), idle.as_str(), "gone-1"]);
+        let row = &heatmap.rows[0];
+        assert_eq!((row.used_correct, row.used_incorrect), (2, 0));
+        assert_eq!((row.unused_correct, row.unused_incorrect), (0, 1));
+        assert_eq!(row.lift(), Some(1.0));
+        assert_eq!(heatmap.rows[1].lift(), Some(-0.5));
+        assert_eq!(heatmap.rows[2].used(), 0);
+        assert_eq!(heatmap.rows[2].lift(), None);
+        assert!(heatmap.rows[3].section.is_empty());
+        let ranked: Vec<&str> = heatmap.ranked().iter().map(|r| r.bullet_id.as_str()).collect();
+        assert_eq!(ranked[..2], [capital.as_str(), noise.as_str()]);
+
+        let csv = heatmap.to_csv();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.contains(&format!(
+            "{},geography,The capital of France is Paris,2,0,0,1,1.000",
+            capital
+        )));
+        assert!(csv.contains("\"Answer with \"\"one\"\" word, <b>briefly</b>\""));
+
+        let html = heatmap.to_html();
+        assert_eq!(html.matches("<tr>").count(), 5);
+        assert!(html.contains("&lt;b&gt;briefly") && !html.contains("<b>"));
+        assert!(html.contains("rgba(0,160,0,1.00)") && html.contains("3 samples"));
+    }
+}
@@ -0,0 +1,94 @@
+//! 时间来源：Playbook中的所有时间戳都经由 [`Clock`] 获取
+//!
+//! 生产环境用系统时钟；测试和回放注入 [`ManualClock`]，同样的操作序列得到逐字节一致的Playbook。
+
+use alloc::sync::Arc;
+use core::{
+    fmt,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// 可在多个Playbook间共享的时钟
+pub type SharedClock = Arc<dyn Clock>;
+
+/// 默认时钟：有std时为系统时钟；no_std下没有系统时间，固定在UNIX纪元，需自行注入
+pub fn default_clock() -> SharedClock {
+    #[cfg(feature = "std")]
+    {
+        Arc::new(SystemClock)
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        Arc::new(ManualClock::new(DateTime::UNIX_EPOCH))
+    }
+}
+
+/// 系统时钟（`Utc::now`）
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// 手动控制的时钟，只有调用`set`/`advance`时才前进
+#[derive(Debug)]
+pub struct ManualClock {
+    nanos: AtomicI64,
+}
+
+impl ManualClock {
+    /// 纳秒精度，可表示1677年至2262年
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            nanos: AtomicI64::new(start.timestamp_nanos_opt().unwrap_or_default()),
+        }
+    }
+
+    pub fn set(&self, time: DateTime<Utc>) {
+        self.nanos
+            .store(time.timestamp_nanos_opt().unwrap_or_default(), Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, delta: TimeDelta) {
+        let step = delta.num_nanoseconds().unwrap_or(i64::MAX);
+        let _ = self
+            .nanos
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| Some(n.saturating_add(step)));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let clock = ManualClock::new(start);
+        assert_eq!(clock.now(), start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(TimeDelta::seconds(90));
+        assert_eq!(clock.now(), start + TimeDelta::seconds(90));
+
+        clock.set(start);
+        assert_eq!(clock.now(), start);
+    }
+}
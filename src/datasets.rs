@@ -0,0 +1,280 @@
+//! 常见代理评测数据集的加载器，直接产出 [`Sample`]
+//!
+//! - JSONL问答：每行一个对象，字段名可配置
+//! - AppWorld风格的任务描述：`instruction` + `supervisor` + `datetime`
+//! - HotpotQA风格的多跳问答：`context`为`[标题, [句子...]]`列表
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::models::sample::Sample;
+
+#[derive(Debug, Error)]
+pub enum DatasetError {
+    #[error("第{line}行JSON解析错误：{source}")]
+    Json {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("第{line}行缺少字段：{field}")]
+    MissingField { line: usize, field: String },
+    #[cfg(feature = "std")]
+    #[error("IO错误：{0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// JSONL问答数据集的字段映射
+#[derive(Debug, Clone)]
+pub struct QaFields {
+    pub question: String,
+    pub answer: Option<String>,
+    pub context: Option<String>,
+}
+
+impl Default for QaFields {
+    fn default() -> Self {
+        Self {
+            question: "question".to_string(),
+            answer: Some("answer".to_string()),
+            context: Some("context".to_string()),
+        }
+    }
+}
+
+/// 解析JSONL问答数据；映射之外的字段放入`metadata`，空行跳过
+pub fn parse_jsonl_qa(text: &str, fields: &QaFields) -> Result<Vec<Sample>, DatasetError> {
+    jsonl_objects(text)
+        .map(|item| {
+            let (line, mut obj) = item?;
+            let question = take_string(&mut obj, &fields.question)
+                .ok_or_else(|| missing(line, &fields.question))?;
+            let ground_truth = fields.answer.as_ref().and_then(|k| take_string(&mut obj, k));
+            let context = fields
+                .context
+                .as_ref()
+                .and_then(|k| take_string(&mut obj, k))
+                .unwrap_or_default();
+            Ok(Sample {
+                question,
+                context,
+                ground_truth,
+                metadata: obj.into_iter().collect(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct AppWorldTask {
+    #[serde(default)]
+    task_id: Option<String>,
+    instruction: String,
+    #[serde(default)]
+    supervisor: Option<serde_json::Map<String, Value>>,
+    #[serde(default)]
+    datetime: Option<String>,
+    #[serde(default)]
+    answer: Option<Value>,
+}
+
+/// 解析AppWorld风格的任务描述（单个对象、对象数组或JSONL均可）
+///
+/// 上下文由supervisor信息与任务时间拼成；`answer`存在时作为ground truth。
+pub fn parse_appworld_tasks(text: &str) -> Result<Vec<Sample>, DatasetError> {
+    json_records(text)?
+        .into_iter()
+        .map(|(line, value)| {
+            let task: AppWorldTask =
+                serde_json::from_value(value).map_err(|source| DatasetError::Json { line, source })?;
+            let mut context = Vec::new();
+            if let Some(supervisor) = &task.supervisor {
+                let profile: Vec<String> = supervisor
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, value_text(v)))
+                    .collect();
+                context.push(format!("Supervisor: {}", profile.join(", ")));
+            }
+            if let Some(datetime) = &task.datetime {
+                context.push(format!("Current datetime: {}", datetime));
+            }
+
+            let mut sample = Sample::new(task.instruction);
+            sample.context = context.join("\n");
+            sample.ground_truth = task.answer.as_ref().map(value_text);
+            if let Some(task_id) = task.task_id {
+                sample.metadata.insert("task_id".to_string(), Value::String(task_id));
+            }
+            Ok(sample)
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct HotpotRecord {
+    #[serde(default, rename = "_id")]
+    id: Option<String>,
+    question: String,
+    #[serde(default)]
+    answer: Option<String>,
+    #[serde(default)]
+    context: Vec<(String, Vec<String>)>,
+    #[serde(default)]
+    supporting_facts: Option<Value>,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    level: Option<String>,
+}
+
+/// 解析HotpotQA风格的多跳问答（JSON数组或JSONL）
+///
+/// 每个段落渲染为`标题: 句子...`，段落间空行分隔；id/type/level/supporting_facts放入metadata。
+pub fn parse_hotpotqa(text: &str) -> Result<Vec<Sample>, DatasetError> {
+    json_records(text)?
+        .into_iter()
+        .map(|(line, value)| {
+            let record: HotpotRecord =
+                serde_json::from_value(value).map_err(|source| DatasetError::Json { line, source })?;
+            let paragraphs: Vec<String> = record
+                .context
+                .iter()
+                .map(|(title, sentences)| format!("{}: {}", title, sentences.concat().trim()))
+                .collect();
+
+            let mut sample = Sample::new(record.question);
+            sample.context = paragraphs.join("\n\n");
+            sample.ground_truth = record.answer;
+            let extra = [
+                ("id", record.id.map(Value::String)),
+                ("type", record.kind.map(Value::String)),
+                ("level", record.level.map(Value::String)),
+                ("supporting_facts", record.supporting_facts),
+            ];
+            for (key, value) in extra {
+                if let Some(value) = value {
+                    sample.metadata.insert(key.to_string(), value);
+                }
+            }
+            Ok(sample)
+        })
+        .collect()
+}
+
+#[cfg(feature = "std")]
+pub fn load_jsonl_qa(
+    path: impl AsRef<std::path::Path>,
+    fields: &QaFields,
+) -> Result<Vec<Sample>, DatasetError> {
+    parse_jsonl_qa(&std::fs::read_to_string(path)?, fields)
+}
+
+#[cfg(feature = "std")]
+pub fn load_appworld_tasks(path: impl AsRef<std::path::Path>) -> Result<Vec<Sample>, DatasetError> {
+    parse_appworld_tasks(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(feature = "std")]
+pub fn load_hotpotqa(path: impl AsRef<std::path::Path>) -> Result<Vec<Sample>, DatasetError> {
+    parse_hotpotqa(&std::fs::read_to_string(path)?)
+}
+
+// --------------------------
+// 辅助函数
+// --------------------------
+
+type JsonObject = serde_json::Map<String, Value>;
+
+/// 逐行解析JSONL对象，返回(行号, 对象)，行号从1开始
+fn jsonl_objects(text: &str) -> impl Iterator<Item = Result<(usize, JsonObject), DatasetError>> + '_ {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .map(|obj| (i + 1, obj))
+                .map_err(|source| DatasetError::Json { line: i + 1, source })
+        })
+}
+
+/// 整体是JSON数组/对象时按元素拆分（行号记为1），否则按JSONL处理
+fn json_records(text: &str) -> Result<Vec<(usize, Value)>, DatasetError> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(Value::Array(items)) => Ok(items.into_iter().map(|v| (1, v)).collect()),
+        Ok(value @ Value::Object(_)) => Ok(Vec::from([(1, value)])),
+        _ => jsonl_objects(text)
+            .map(|item| item.map(|(line, obj)| (line, Value::Object(obj))))
+            .collect(),
+    }
+}
+
+fn take_string(obj: &mut JsonObject, key: &str) -> Option<String> {
+    obj.remove(key).map(|v| value_text(&v))
+}
+
+/// 字符串取原值，其余类型用JSON表示
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn missing(line: usize, field: &str) -> DatasetError {
+    DatasetError::MissingField {
+        line,
+        field: field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonl_qa() {
+        let text = r#"{"question": "1+1?", "answer": "2", "difficulty": "easy"}
+
+{"question": "capital of France?", "answer": "Paris", "context": "geography"}"#;
+        let samples = parse_jsonl_qa(text, &QaFields::default()).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].ground_truth.as_deref(), Some("2"));
+        assert_eq!(samples[0].metadata["difficulty"], "easy");
+        assert_eq!(samples[1].context, "geography");
+
+        let err = parse_jsonl_qa("{\"q\": 1}\n", &QaFields::default()).unwrap_err();
+        assert!(matches!(err, DatasetError::MissingField { line: 1, .. }));
+        let err = parse_jsonl_qa("{}\nnot json", &QaFields::default()).unwrap_err();
+        assert!(matches!(err, DatasetError::MissingField { line: 1, .. }));
+    }
+
+    #[test]
+    fn test_appworld_tasks() {
+        let text = r#"[{"task_id": "82e2fac_1", "instruction": "Pay my phone bill",
+            "supervisor": {"first_name": "Ada"}, "datetime": "2023-05-18T12:00:00"}]"#;
+        let samples = parse_appworld_tasks(text).unwrap();
+        assert_eq!(samples[0].question, "Pay my phone bill");
+        assert!(samples[0].context.contains("first_name: Ada"));
+        assert!(samples[0].context.contains("2023-05-18"));
+        assert_eq!(samples[0].metadata["task_id"], "82e2fac_1");
+    }
+
+    #[test]
+    fn test_hotpotqa() {
+        let text = r#"{"_id": "5a8b", "question": "Which city?", "answer": "Paris",
+            "context": [["France", ["France is a country. ", "Its capital is Paris."]]],
+            "supporting_facts": [["France", 1]], "type": "bridge", "level": "easy"}"#;
+        let samples = parse_hotpotqa(text).unwrap();
+        assert_eq!(samples[0].ground_truth.as_deref(), Some("Paris"));
+        assert_eq!(samples[0].context, "France: France is a country. Its capital is Paris.");
+        assert_eq!(samples[0].metadata["type"], "bridge");
+        assert_eq!(samples[0].metadata["supporting_facts"][0][1], 1);
+    }
+}
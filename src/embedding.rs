@@ -0,0 +1,646 @@
+//! 子弹内容的向量表示：[`Embedder`] 抽象具体模型，[`EmbeddingStore`] 按内容哈希缓存向量，
+//! 只为内容变化过的子弹重新计算
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::delta::DeltaBatch;
+use crate::models::playbook::{BulletId, Playbook};
+
+#[derive(Debug, Error)]
+pub enum EmbeddingError {
+    #[error("向量模型错误：{0}")]
+    Provider(String),
+    #[error("向量模型输出不合法：{0}")]
+    InvalidOutput(String),
+}
+
+pub trait Embedder: Send + Sync {
+    /// 模型标识；与缓存中的不同时全部重新计算
+    fn model(&self) -> &str;
+
+    /// 按输入顺序返回每段文本的向量，长度相同
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingError>;
+
+    /// 服务端对单次请求文本数的上限，[`Batching::batch_size`]超过时按此截断
+    fn max_batch_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<T: Embedder + ?Sized> Embedder for Arc<T> {
+    fn model(&self) -> &str {
+        (**self).model()
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        (**self).embed(texts)
+    }
+
+    fn max_batch_size(&self) -> Option<usize> {
+        (**self).max_batch_size()
+    }
+}
+
+/// 批量计算向量的方式，可序列化写入运行配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Batching {
+    /// 每次`embed`调用的最大文本数
+    pub batch_size: usize,
+    /// 同时进行的`embed`调用数
+    pub concurrency: usize,
+}
+
+impl Default for Batching {
+    fn default() -> Self {
+        Self { batch_size: 64, concurrency: 1 }
+    }
+}
+
+/// 按`batching`分批计算向量，返回顺序与`texts`一致
+///
+/// 并发时各批在作用域线程中执行；任一批失败时不再发出新的请求，返回按批次顺序的第一个错误。
+pub fn embed_batched(
+    embedder: &dyn Embedder,
+    texts: &[&str],
+    batching: Batching,
+) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+    let limit = embedder.max_batch_size().unwrap_or(usize::MAX);
+    let chunks: Vec<&[&str]> = texts.chunks(batching.batch_size.min(limit).max(1)).collect();
+    let embed = |chunk: &[&str]| {
+        let embedded = embedder.embed(chunk)?;
+        if embedded.len() != chunk.len() {
+            return Err(EmbeddingError::InvalidOutput(format!(
+                "{} vectors for {} texts",
+                embedded.len(),
+                chunk.len()
+            )));
+        }
+        Ok(embedded)
+    };
+
+    let workers = batching.concurrency.clamp(1, chunks.len().max(1));
+    if workers == 1 {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for chunk in chunks {
+            vectors.extend(embed(chunk)?);
+        }
+        return Ok(vectors);
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<_>>> = chunks.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(chunk) = chunks.get(i) else { break };
+                    let result = embed(chunk);
+                    if result.is_err() {
+                        // 已领取的批次照常完成，之后的批次不再发出
+                        next.store(chunks.len(), Ordering::Relaxed);
+                    }
+                    *results[i].lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+                }
+            });
+        }
+    });
+    // 批次按顺序领取，未执行的批次都在第一个失败的批次之后
+    let mut vectors = Vec::with_capacity(texts.len());
+    for result in results {
+        if let Some(result) = result.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            vectors.extend(result?);
+        }
+    }
+    Ok(vectors)
+}
+
+/// 向量模型不可用，操作已退回词法方法
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Degraded {
+    /// 受影响的操作，如`retrieve`、`dedup`
+    pub operation: &'static str,
+    pub error: String,
+}
+
+/// 接收 [`Degraded`] 事件，如写日志或转发为 [`WebhookEvent::Degraded`](crate::webhook::WebhookEvent::Degraded)
+pub trait DegradationSink: std::fmt::Debug + Send + Sync {
+    fn degraded(&self, event: &Degraded);
+}
+
+pub type SharedDegradationSink = Arc<dyn DegradationSink>;
+
+/// 在内存中保存收到的事件
+#[derive(Debug, Default)]
+pub struct DegradationLog(Mutex<Vec<Degraded>>);
+
+impl DegradationLog {
+    pub fn events(&self) -> Vec<Degraded> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl DegradationSink for DegradationLog {
+    fn degraded(&self, event: &Degraded) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
+
+/// 内容哈希（FNV-1a，跨平台稳定，可写入文件）
+pub fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// 余弦相似度；长度不同或任一为零向量时为0
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+// --------------------------
+// 本地向量：不依赖外部API或模型文件
+// --------------------------
+
+/// 特征哈希向量：ASCII单词、非ASCII字符及相邻字符二元组哈希到固定维度（带符号），
+/// 再做L2归一化
+///
+/// 只反映词面重叠，不理解同义词；离线环境下用于检索与去重足够，
+/// 需要语义时换成真正的模型（如`OnnxEmbedder`）。
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+    model: String,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        let dimensions = dimensions.max(1);
+        Self {
+            dimensions,
+            model: format!("hashing-{}", dimensions),
+        }
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimensions];
+        let mut add = |feature: &str| {
+            let hash = content_hash(feature);
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(hash % self.dimensions as u64) as usize] += sign;
+        };
+
+        let mut word = String::new();
+        let mut prev: Option<char> = None;
+        for c in text.chars().flat_map(char::to_lowercase) {
+            if c.is_ascii_alphanumeric() {
+                word.push(c);
+                prev = None;
+                continue;
+            }
+            if !word.is_empty() {
+                add(&std::mem::take(&mut word));
+            }
+            if !c.is_alphanumeric() {
+                prev = None;
+                continue;
+            }
+            if let Some(p) = prev {
+                add(&String::from_iter([p, c]));
+            }
+            add(c.encode_utf8(&mut [0u8; 4]));
+            prev = Some(c);
+        }
+        if !word.is_empty() {
+            add(&word);
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        Ok(texts.iter().map(|text| self.embed_one(text)).collect())
+    }
+}
+
+// --------------------------
+// 本地ONNX模型：运行时加载onnxruntime动态库，模型文件预先下载到本地
+// --------------------------
+
+/// 本地ONNX句向量模型（fastembed），需要 `local-embed` feature
+///
+/// onnxruntime以动态库加载：`ORT_DYLIB_PATH`指向`libonnxruntime`，否则按系统库路径查找。
+#[cfg(feature = "local-embed")]
+pub struct OnnxEmbedder {
+    model: String,
+    session: Mutex<fastembed::TextEmbedding>,
+}
+
+#[cfg(feature = "local-embed")]
+impl OnnxEmbedder {
+    /// 从本地目录加载`model.onnx`与HuggingFace格式的分词器文件（`tokenizer.json`、`config.json`、
+    /// `special_tokens_map.json`、`tokenizer_config.json`），模型标识为`onnx-<目录名>`
+    pub fn from_dir(
+        dir: impl AsRef<std::path::Path>,
+        pooling: fastembed::Pooling,
+    ) -> Result<Self, EmbeddingError> {
+        let dir = dir.as_ref();
+        let read = |name: &str| {
+            std::fs::read(dir.join(name)).map_err(|err| {
+                EmbeddingError::Provider(format!("{}: {}", dir.join(name).display(), err))
+            })
+        };
+        let tokenizer_files = fastembed::TokenizerFiles {
+            tokenizer_file: read("tokenizer.json")?,
+            config_file: read("config.json")?,
+            special_tokens_map_file: read("special_tokens_map.json")?,
+            tokenizer_config_file: read("tokenizer_config.json")?,
+        };
+        let model = fastembed::UserDefinedEmbeddingModel::new(read("model.onnx")?, tokenizer_files)
+            .with_pooling(pooling);
+        let session = fastembed::TextEmbedding::try_new_from_user_defined(
+            model,
+            fastembed::InitOptionsUserDefined::default(),
+        )
+        .map_err(|err| EmbeddingError::Provider(err.to_string()))?;
+        let name = dir.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        Ok(Self {
+            model: format!("onnx-{}", name),
+            session: Mutex::new(session),
+        })
+    }
+}
+
+#[cfg(feature = "local-embed")]
+impl std::fmt::Debug for OnnxEmbedder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnnxEmbedder").field("model", &self.model).finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "local-embed")]
+impl Embedder for OnnxEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        // 会话推理需要可变引用，并发的批次在此排队
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let vectors =
+            session.embed(texts, None).map_err(|err| EmbeddingError::Provider(err.to_string()))?;
+        if vectors.len() != texts.len() {
+            return Err(EmbeddingError::InvalidOutput(format!(
+                "{} vectors for {} texts",
+                vectors.len(),
+                texts.len()
+            )));
+        }
+        Ok(vectors)
+    }
+}
+
+/// 一次`refresh`的结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RefreshStats {
+    /// 命中缓存的子弹数
+    pub reused: usize,
+    /// 新计算的向量数（内容相同的子弹共用一个）
+    pub computed: usize,
+    /// 不再被任何子弹使用而丢弃的向量数
+    pub dropped: usize,
+}
+
+pub struct EmbeddingStore {
+    embedder: Arc<dyn Embedder>,
+    pub(crate) model: String,
+    pub(crate) dimensions: usize,
+    /// 内容哈希 → 向量
+    pub(crate) vectors: HashMap<u64, Vec<f32>>,
+    /// 子弹 → 内容哈希（由refresh根据Playbook重建，不持久化）
+    bullets: BTreeMap<BulletId, u64>,
+    batching: Batching,
+}
+
+impl std::fmt::Debug for EmbeddingStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddingStore")
+            .field("model", &self.model)
+            .field("dimensions", &self.dimensions)
+            .field("vectors", &self.vectors.len())
+            .field("bullets", &self.bullets.len())
+            .field("batching", &self.batching)
+            .finish()
+    }
+}
+
+impl EmbeddingStore {
+    pub fn new(embedder: Arc<dyn Embedder>) -> Self {
+        let model = embedder.model().to_string();
+        Self::with_vectors(embedder, model, 0, HashMap::new())
+    }
+
+    pub(crate) fn with_vectors(
+        embedder: Arc<dyn Embedder>,
+        model: String,
+        dimensions: usize,
+        vectors: HashMap<u64, Vec<f32>>,
+    ) -> Self {
+        Self {
+            embedder,
+            model,
+            dimensions,
+            vectors,
+            bullets: BTreeMap::new(),
+            batching: Batching::default(),
+        }
+    }
+
+    /// `refresh`计算缺失向量时的分批与并发方式
+    pub fn with_batching(mut self, batching: Batching) -> Self {
+        self.batching = batching;
+        self
+    }
+
+    pub fn batching(&self) -> Batching {
+        self.batching
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// 向量维度（还没有向量时为0）
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// 缓存的向量数
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    /// 子弹的向量（需先对其所在Playbook调用`refresh`）
+    pub fn get(&self, bullet_id: &str) -> Option<&[f32]> {
+        self.vectors.get(self.bullets.get(bullet_id)?).map(Vec::as_slice)
+    }
+
+    /// 用同一个模型计算查询文本的向量
+    pub fn embed_query(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        self.embedder
+            .embed(&[text])?
+            .pop()
+            .ok_or_else(|| EmbeddingError::InvalidOutput("no vector for query".to_string()))
+    }
+
+    /// 与Playbook同步：只为缓存中没有的内容计算向量，丢弃不再使用的向量；
+    /// 模型变化时清空缓存
+    pub fn refresh(&mut self, playbook: &Playbook) -> Result<RefreshStats, EmbeddingError> {
+        if self.embedder.model() != self.model {
+            self.model = self.embedder.model().to_string();
+            self.dimensions = 0;
+            self.vectors.clear();
+        }
+
+        let bullets: BTreeMap<BulletId, u64> = playbook
+            .bullets
+            .values()
+            .map(|b| (b.id.clone(), content_hash(&b.content)))
+            .collect();
+        let mut missing: BTreeMap<u64, &str> = BTreeMap::new();
+        let mut stats = RefreshStats::default();
+        for bullet in playbook.bullets.values() {
+            let hash = bullets[&bullet.id];
+            if self.vectors.contains_key(&hash) {
+                stats.reused += 1;
+            } else {
+                missing.insert(hash, &bullet.content);
+            }
+        }
+
+        let missing: Vec<(u64, &str)> = missing.into_iter().collect();
+        let texts: Vec<&str> = missing.iter().map(|(_, text)| *text).collect();
+        let embedded = embed_batched(&*self.embedder, &texts, self.batching)?;
+        for ((hash, _), vector) in missing.iter().zip(embedded) {
+            if self.dimensions == 0 {
+                self.dimensions = vector.len();
+            }
+            if vector.len() != self.dimensions {
+                return Err(EmbeddingError::InvalidOutput(format!(
+                    "vector of length {} (expected {})",
+                    vector.len(),
+                    self.dimensions
+                )));
+            }
+            self.vectors.insert(*hash, vector);
+            stats.computed += 1;
+        }
+
+        let live: HashSet<u64> = bullets.values().copied().collect();
+        let before = self.vectors.len();
+        self.vectors.retain(|hash, _| live.contains(hash));
+        stats.dropped = before - self.vectors.len();
+        self.bullets = bullets;
+        Ok(stats)
+    }
+
+    /// 按向量余弦相似度合并重复子弹，规则同 [`Playbook::dedup_delta`]。向量模型不可用时
+    /// 退回词集合相似度（阈值`lexical_similarity`）并向`sink`报告，而不是让维护任务失败
+    pub fn dedup_delta(
+        &mut self,
+        playbook: &Playbook,
+        min_similarity: f32,
+        lexical_similarity: f64,
+        sink: &dyn DegradationSink,
+    ) -> DeltaBatch {
+        if let Err(e) = self.refresh(playbook) {
+            sink.degraded(&Degraded { operation: "dedup", error: e.to_string() });
+            return playbook.dedup_delta(lexical_similarity);
+        }
+        let similar = |a: &Option<&[f32]>, b: &Option<&[f32]>| {
+            a.zip(*b).is_some_and(|(a, b)| cosine_similarity(a, b) >= min_similarity)
+        };
+        let mut delta = playbook.dedup_delta_by(|b| self.get(&b.id), similar, &mut ());
+        delta.reasoning.push_str(" (embeddings)");
+        delta
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 按字节统计的确定性向量，记录被要求计算的文本数
+    #[derive(Debug, Default)]
+    pub(crate) struct CountingEmbedder {
+        pub model: String,
+        pub calls: AtomicUsize,
+    }
+
+    impl Embedder for CountingEmbedder {
+        fn model(&self) -> &str {
+            &self.model
+        }
+
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+            self.calls.fetch_add(texts.len(), Ordering::SeqCst);
+            Ok(texts
+                .iter()
+                .map(|t| vec![t.len() as f32, t.chars().count() as f32, 1.0])
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_refresh_recomputes_only_changed_content() {
+        let embedder = Arc::new(CountingEmbedder::default());
+        let mut store = EmbeddingStore::new(embedder.clone());
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        pb.add_bullet("api usage", "重试要指数退避", None, None);
+        pb.add_bullet("debugging", "重试要指数退避", None, None);
+
+        let stats = store.refresh(&pb).unwrap();
+        assert_eq!((stats.reused, stats.computed), (0, 2));
+        assert_eq!(store.dimensions(), 3);
+        assert_eq!(store.refresh(&pb).unwrap().reused, 3);
+
+        pb.update_bullet(&a, Some("分页时带上cursor和limit".into()), None).unwrap();
+        let stats = store.refresh(&pb).unwrap();
+        assert_eq!((stats.reused, stats.computed, stats.dropped), (2, 1, 1));
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(store.get(&a).unwrap()[0], "分页时带上cursor和limit".len() as f32);
+    }
+
+    /// 记录每次请求的文本数；`fail_on`包含该文本时整批失败
+    #[derive(Debug, Default)]
+    struct RecordingEmbedder {
+        max_batch: Option<usize>,
+        fail_on: Option<&'static str>,
+        batches: Mutex<Vec<usize>>,
+    }
+
+    impl Embedder for RecordingEmbedder {
+        fn model(&self) -> &str {
+            "recording"
+        }
+
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+            self.batches.lock().unwrap().push(texts.len());
+            if texts.iter().any(|t| Some(*t) == self.fail_on) {
+                return Err(EmbeddingError::Provider("rate limited".into()));
+            }
+            Ok(texts.iter().map(|t| vec![t.parse().unwrap()]).collect())
+        }
+
+        fn max_batch_size(&self) -> Option<usize> {
+            self.max_batch
+        }
+    }
+
+    #[test]
+    fn test_embed_batched() {
+        let numbers: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let texts: Vec<&str> = numbers.iter().map(String::as_str).collect();
+        let expected: Vec<Vec<f32>> = (0..7).map(|i| vec![i as f32]).collect();
+
+        let embedder = RecordingEmbedder::default();
+        let batching = Batching { batch_size: 2, concurrency: 3 };
+        assert_eq!(embed_batched(&embedder, &texts, batching).unwrap(), expected);
+        let mut batches = embedder.batches.lock().unwrap().clone();
+        batches.sort_unstable();
+        assert_eq!(batches, [1, 2, 2, 2]);
+
+        let capped = RecordingEmbedder { max_batch: Some(3), ..Default::default() };
+        let batching = Batching { batch_size: 100, concurrency: 1 };
+        assert_eq!(embed_batched(&capped, &texts, batching).unwrap(), expected);
+        assert_eq!(*capped.batches.lock().unwrap(), [3, 3, 1]);
+
+        let failing = RecordingEmbedder { fail_on: Some("4"), ..Default::default() };
+        let batching = Batching { batch_size: 1, concurrency: 2 };
+        let err = embed_batched(&failing, &texts, batching).unwrap_err();
+        assert!(matches!(err, EmbeddingError::Provider(_)));
+        assert!(failing.batches.lock().unwrap().len() < 7);
+        assert!(embed_batched(&failing, &[], batching).unwrap().is_empty());
+
+        // refresh按配置分批
+        let embedder = Arc::new(RecordingEmbedder::default());
+        let mut store = EmbeddingStore::new(embedder.clone())
+            .with_batching(Batching { batch_size: 4, concurrency: 2 });
+        let mut pb = Playbook::new();
+        for text in &texts {
+            pb.add_bullet("numbers", *text, None, None);
+        }
+        assert_eq!(store.refresh(&pb).unwrap().computed, 7);
+        assert_eq!(embedder.batches.lock().unwrap().iter().sum::<usize>(), 7);
+        assert_eq!(store.get("numbers-00005").unwrap(), [4.0]);
+    }
+
+    #[test]
+    fn test_hashing_embedder() {
+        let embedder = HashingEmbedder::default();
+        let texts = [
+            "Retry the payment API with exponential backoff",
+            "retry payment api calls with backoff",
+            "先看日志再看指标",
+            "先看日志",
+        ];
+        let v = embedder.embed(&texts).unwrap();
+        assert_eq!(v[0].len(), 256);
+        assert!((cosine_similarity(&v[0], &v[0]) - 1.0).abs() < 1e-5);
+        assert!(cosine_similarity(&v[0], &v[1]) > 0.5);
+        assert!(cosine_similarity(&v[2], &v[3]) > cosine_similarity(&v[0], &v[3]));
+        assert_eq!(embedder.model(), "hashing-256");
+    }
+
+    #[cfg(feature = "local-embed")]
+    #[test]
+    fn test_onnx_embedder_reports_missing_files() {
+        let dir = std::env::temp_dir().join(format!("ace-onnx-missing-{}", std::process::id()));
+        let err = OnnxEmbedder::from_dir(&dir, fastembed::Pooling::Mean).unwrap_err();
+        assert!(matches!(&err, EmbeddingError::Provider(msg) if msg.contains("tokenizer.json")));
+    }
+}
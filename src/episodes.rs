@@ -0,0 +1,428 @@
+//! 情景记忆：原样保存每次任务的经过与结果（[`Episode`]），与提炼后的子弹分开存放
+//!
+//! [`Consolidation`] 定期把未整理的情景交给 [`EpisodeReflector`] 提炼成洞见，
+//! 经 [`QualityFilter`] 过滤后作为ADD操作写入Playbook——先记住发生了什么，再慢慢总结规律。
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeSet,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::feedback::Outcome;
+use crate::models::delta::{DeltaBatch, DeltaOperation};
+use crate::models::playbook::{BulletId, Playbook, PlaybookError};
+use crate::models::timestamp;
+use crate::reflection::{FilterOutcome, Insight, QualityFilter, ReflectionError};
+
+#[derive(Debug, Error)]
+pub enum EpisodeError {
+    #[error(transparent)]
+    Reflection(#[from] ReflectionError),
+    #[error(transparent)]
+    Playbook(#[from] PlaybookError),
+}
+
+/// 一次任务的原始记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Episode {
+    pub id: u64,
+    pub task: String,
+    pub outcome: Outcome,
+    /// 过程摘要、报错信息等
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub details: String,
+    /// 生成时用到的子弹
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bullet_ids: Vec<BulletId>,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub recorded_at: DateTime<Utc>,
+    /// 生成时实际注入的上下文（开启记录时才有），见 [`EpisodeStore::set_context`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<ContextLog>,
+}
+
+/// 一次生成实际注入提示词的Playbook内容，供事后分析子弹与结果的关联
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContextLog {
+    /// 注入的子弹（按渲染顺序）
+    pub bullets: Vec<LoggedBullet>,
+    /// 渲染出的完整文本
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggedBullet {
+    pub id: BulletId,
+    /// 渲染时的内容（之后子弹可能被修改或删除）
+    pub content: String,
+}
+
+impl ContextLog {
+    /// 按给定ID记录子弹当前的内容（不存在的ID忽略）
+    pub fn capture<'a>(
+        playbook: &Playbook,
+        bullet_ids: impl IntoIterator<Item = &'a BulletId>,
+        text: impl Into<String>,
+    ) -> Self {
+        let bullets = bullet_ids
+            .into_iter()
+            .filter_map(|id| playbook.get_bullet(id))
+            .map(|b| LoggedBullet { id: b.id.clone(), content: b.content.clone() })
+            .collect();
+        Self { bullets, text: text.into() }
+    }
+}
+
+/// 情景记录（只追加）及整理进度
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EpisodeStore {
+    pub episodes: Vec<Episode>,
+    next_id: u64,
+    /// ID不大于此值的情景已整理过
+    #[serde(default)]
+    consolidated_through: u64,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "timestamp::deserialize_option"
+    )]
+    last_consolidated_at: Option<DateTime<Utc>>,
+}
+
+impl EpisodeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次任务，返回情景ID
+    pub fn record(
+        &mut self,
+        task: impl Into<String>,
+        outcome: Outcome,
+        details: impl Into<String>,
+        bullet_ids: Vec<BulletId>,
+        now: DateTime<Utc>,
+    ) -> u64 {
+        self.next_id += 1;
+        self.episodes.push(Episode {
+            id: self.next_id,
+            task: task.into(),
+            outcome,
+            details: details.into(),
+            bullet_ids,
+            recorded_at: now,
+            context: None,
+        });
+        self.next_id
+    }
+
+    /// 为已记录的情景附上生成时的上下文；情景不存在（或已被清理）时返回false
+    pub fn set_context(&mut self, episode_id: u64, context: ContextLog) -> bool {
+        match self.episodes.binary_search_by_key(&episode_id, |e| e.id) {
+            Ok(i) => {
+                self.episodes[i].context = Some(context);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.episodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.episodes.is_empty()
+    }
+
+    /// 还没整理过的情景（按记录顺序）
+    pub fn pending(&self) -> &[Episode] {
+        let start = self.episodes.partition_point(|e| e.id <= self.consolidated_through);
+        &self.episodes[start..]
+    }
+
+    pub fn last_consolidated_at(&self) -> Option<DateTime<Utc>> {
+        self.last_consolidated_at
+    }
+
+    /// 删除已整理且早于`before`的情景，返回删除数
+    pub fn prune_consolidated(&mut self, before: DateTime<Utc>) -> usize {
+        let len = self.episodes.len();
+        let through = self.consolidated_through;
+        self.episodes.retain(|e| e.id > through || e.recorded_at >= before);
+        len - self.episodes.len()
+    }
+}
+
+/// 把一批情景提炼为候选洞见
+pub trait EpisodeReflector {
+    fn distill(
+        &self,
+        episodes: &[Episode],
+        playbook: &Playbook,
+    ) -> Result<Vec<Insight>, ReflectionError>;
+}
+
+impl<T: EpisodeReflector + ?Sized> EpisodeReflector for Box<T> {
+    fn distill(
+        &self,
+        episodes: &[Episode],
+        playbook: &Playbook,
+    ) -> Result<Vec<Insight>, ReflectionError> {
+        (**self).distill(episodes, playbook)
+    }
+}
+
+/// 整理任务的配置，可序列化进运行配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Consolidation {
+    /// 未整理的情景达到该数量才运行
+    pub min_episodes: usize,
+    /// 每次最多交给reflector的情景数
+    pub max_batch: usize,
+    /// 两次整理的最小间隔（秒）
+    pub interval_secs: u64,
+    /// 洞见没有指定章节时写入的章节
+    pub default_section: String,
+    pub filter: QualityFilter,
+}
+
+impl Default for Consolidation {
+    fn default() -> Self {
+        Self {
+            min_episodes: 10,
+            max_batch: 50,
+            interval_secs: 3600,
+            default_section: "consolidated".to_string(),
+            filter: QualityFilter::default(),
+        }
+    }
+}
+
+/// 一次整理的结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsolidationReport {
+    /// 本次整理的情景数
+    pub episodes: usize,
+    /// 写入Playbook的子弹
+    pub added: Vec<BulletId>,
+    pub filtered: FilterOutcome,
+}
+
+impl Consolidation {
+    /// 未整理的情景够多且距上次整理已超过间隔
+    pub fn is_due(&self, store: &EpisodeStore, now: DateTime<Utc>) -> bool {
+        let interval = i64::try_from(self.interval_secs)
+            .ok()
+            .and_then(TimeDelta::try_seconds)
+            .unwrap_or(TimeDelta::MAX);
+        store.pending().len() >= self.min_episodes.max(1)
+            && store.last_consolidated_at.is_none_or(|last| now - last >= interval)
+    }
+
+    /// 到期时运行 [`Consolidation::run`]，否则返回`None`
+    pub fn run_if_due(
+        &self,
+        store: &mut EpisodeStore,
+        playbook: &mut Playbook,
+        reflector: &dyn EpisodeReflector,
+    ) -> Result<Option<ConsolidationReport>, EpisodeError> {
+        if !self.is_due(store, playbook.clock().now()) {
+            return Ok(None);
+        }
+        self.run(store, playbook, reflector).map(Some)
+    }
+
+    /// 整理最早的至多`max_batch`个未整理情景。reflector或写入失败时不推进进度，下次重试
+    pub fn run(
+        &self,
+        store: &mut EpisodeStore,
+        playbook: &mut Playbook,
+        reflector: &dyn EpisodeReflector,
+    ) -> Result<ConsolidationReport, EpisodeError> {
+        let now = playbook.clock().now();
+        let pending = store.pending();
+        let batch = &pending[..pending.len().min(self.max_batch.max(1))];
+        let Some(last) = batch.last().map(|e| e.id) else {
+            return Ok(ConsolidationReport::default());
+        };
+
+        let insights = reflector.distill(batch, playbook)?;
+        let filtered = self.filter.filter(playbook, insights);
+        let operations: Vec<DeltaOperation> = filtered
+            .kept
+            .iter()
+            .map(|insight| {
+                let section = insight.section.as_deref().unwrap_or(&self.default_section);
+                DeltaOperation::add(section, &insight.content)
+            })
+            .collect();
+        let episodes = batch.len();
+        let before: BTreeSet<BulletId> = playbook.bullets.keys().cloned().collect();
+        playbook.apply_delta_at(
+            DeltaBatch {
+                reasoning: format!("consolidate {} episodes", episodes),
+                operations,
+            },
+            now,
+        )?;
+        let added = playbook
+            .bullets()
+            .into_iter()
+            .filter(|b| !before.contains(&b.id))
+            .map(|b| b.id.clone())
+            .collect();
+
+        store.consolidated_through = last;
+        store.last_consolidated_at = Some(now);
+        Ok(ConsolidationReport {
+            episodes,
+            added,
+            filtered,
+        })
+    }
+}
+
+#[cfg(feature = "llm")]
+pub use llm_reflector::LlmEpisodeReflector;
+
+#[cfg(feature = "llm")]
+mod llm_reflector {
+    use core::fmt::Write as _;
+
+    use super::*;
+    use crate::llm::{LlmClient, extract_json};
+
+    /// 让LLM阅读一批情景，总结出可复用的策略
+    #[derive(Debug)]
+    pub struct LlmEpisodeReflector<C> {
+        client: C,
+    }
+
+    impl<C: LlmClient> LlmEpisodeReflector<C> {
+        pub fn new(client: C) -> Self {
+            Self { client }
+        }
+    }
+
+    impl<C: LlmClient> EpisodeReflector for LlmEpisodeReflector<C> {
+        fn distill(
+            &self,
+            episodes: &[Episode],
+            playbook: &Playbook,
+        ) -> Result<Vec<Insight>, ReflectionError> {
+            let mut prompt = String::from(
+                "Below are recent task episodes and their outcomes. Distill reusable, specific \
+                 strategies that explain the successes or would have prevented the failures. \
+                 Skip anything the playbook already covers.\n\nEpisodes:\n",
+            );
+            for e in episodes {
+                let _ = write!(prompt, "- [{}] {} | outcome: {}", e.id, e.task, e.outcome.key());
+                if !e.details.is_empty() {
+                    let _ = write!(prompt, " | details: {}", e.details);
+                }
+                prompt.push('\n');
+            }
+            let _ = write!(
+                prompt,
+                "\nPlaybook:\n{}\n\nReply with JSON only: {{\"insights\": [{{\"content\": \"...\", \
+                 \"section\": \"...\", \"confidence\": <number between 0 and 1>}}]}}",
+                playbook.as_prompt()
+            );
+
+            let text = self.client.complete(&prompt)?.text;
+            extract_json(&text)
+                .and_then(|value| serde_json::from_value(value["insights"].clone()).ok())
+                .ok_or(ReflectionError::InvalidOutput(text))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{sync::Arc, vec};
+
+    use crate::clock::{Clock as _, ManualClock};
+
+    #[derive(Debug)]
+    struct FixedReflector(Vec<Insight>);
+
+    impl EpisodeReflector for FixedReflector {
+        fn distill(
+            &self,
+            episodes: &[Episode],
+            _: &Playbook,
+        ) -> Result<Vec<Insight>, ReflectionError> {
+            assert!(!episodes.is_empty());
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_consolidation_distills_pending_episodes() {
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let mut store = EpisodeStore::new();
+        let config = Consolidation {
+            min_episodes: 2,
+            max_batch: 2,
+            ..Default::default()
+        };
+        let reflector = FixedReflector(vec![
+            Insight::new("Retry payment calls with exponential backoff on 503"),
+            Insight {
+                section: Some("api usage".into()),
+                ..Insight::new("Pass the cursor token when paginating list endpoints")
+            },
+            Insight::new("be careful"),
+        ]);
+
+        let now = clock.now();
+        store.record("refund order 42", Outcome::ToolErrored, "503 from payments", vec![], now);
+        assert!(config.run_if_due(&mut store, &mut pb, &reflector).unwrap().is_none());
+        store.record("list all orders", Outcome::TestPassed, "", vec![], now);
+        store.record("list users", Outcome::TestPassed, "", vec![], now);
+
+        let report = config.run_if_due(&mut store, &mut pb, &reflector).unwrap().unwrap();
+        assert_eq!((report.episodes, report.added.len()), (2, 2));
+        assert_eq!(report.filtered.dropped.len(), 1);
+        let sections: Vec<&str> = pb.sorted_sections().into_iter().map(|(s, _)| &**s).collect();
+        assert_eq!(sections, ["api usage", "consolidated"]);
+        assert_eq!(store.pending().len(), 1);
+
+        // 未到间隔不运行；到期后剩下的一条不足min_episodes
+        assert!(!config.is_due(&store, clock.now()));
+        clock.advance(TimeDelta::hours(2));
+        assert!(!config.is_due(&store, clock.now()));
+        assert_eq!(store.prune_consolidated(clock.now()), 2);
+        assert_eq!(store.episodes[0].id, 3);
+    }
+
+    #[cfg(feature = "llm")]
+    #[test]
+    fn test_llm_reflector_parses_insights() {
+        use crate::llm::DummyLlmClient;
+
+        let client = DummyLlmClient::with_responses([
+            r#"{"insights": [{"content": "Check the payments status page before retrying"}]}"#,
+            "nothing learned",
+        ]);
+        let reflector = LlmEpisodeReflector::new(&client);
+        let mut store = EpisodeStore::new();
+        store.record("refund order 42", Outcome::ToolErrored, "503", vec![], DateTime::UNIX_EPOCH);
+
+        let insights = reflector.distill(store.pending(), &Playbook::new()).unwrap();
+        assert_eq!(insights[0].section, None);
+        assert!(client.prompts()[0].contains("- [1] refund order 42 | outcome: tool_errored"));
+        assert!(reflector.distill(store.pending(), &Playbook::new()).is_err());
+    }
+}
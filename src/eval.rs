@@ -0,0 +1,442 @@
+//! 评估：用Judge给生成结果打分，结果与每个样本的评分理由一起保存；[`ablate`]逐个去掉
+//! 章节或子弹重新评估，衡量哪些知识真正起作用
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::playbook::{BulletId, Playbook};
+use crate::models::sample::Sample;
+
+#[derive(Debug, Error)]
+pub enum EvalError {
+    #[cfg(feature = "llm")]
+    #[error("Judge调用LLM失败：{0}")]
+    Llm(#[from] crate::llm::LlmError),
+    #[error("Judge输出无效：{0}")]
+    InvalidJudgement(String),
+    #[error("生成失败：{0}")]
+    Generation(String),
+}
+
+/// 单个样本的评分
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Judgement {
+    /// 归一化到 [0, 1]
+    pub score: f64,
+    pub passed: bool,
+    pub rationale: String,
+}
+
+pub trait Judge {
+    fn judge(&self, sample: &Sample, output: &str) -> Result<Judgement, EvalError>;
+}
+
+/// 与ground truth做规范化后的精确匹配（忽略大小写与首尾空白），无ground truth时判为不通过
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExactMatchJudge;
+
+impl Judge for ExactMatchJudge {
+    fn judge(&self, sample: &Sample, output: &str) -> Result<Judgement, EvalError> {
+        let passed = sample
+            .ground_truth
+            .as_deref()
+            .is_some_and(|truth| truth.trim().to_lowercase() == output.trim().to_lowercase());
+        Ok(Judgement {
+            score: if passed { 1.0 } else { 0.0 },
+            passed,
+            rationale: if passed { "exact match" } else { "mismatch" }.to_string(),
+        })
+    }
+}
+
+/// 一条评估记录：样本、生成结果与评分理由
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalRecord {
+    pub sample: Sample,
+    pub output: String,
+    pub judgement: Judgement,
+    /// 生成归因到的子弹（见 [`evaluate_attributed`]）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bullet_ids: Vec<BulletId>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub records: Vec<EvalRecord>,
+}
+
+impl EvalReport {
+    pub fn accuracy(&self) -> f64 {
+        self.mean(|r| if r.judgement.passed { 1.0 } else { 0.0 })
+    }
+
+    pub fn mean_score(&self) -> f64 {
+        self.mean(|r| r.judgement.score)
+    }
+
+    fn mean(&self, f: impl Fn(&EvalRecord) -> f64) -> f64 {
+        if self.records.is_empty() {
+            return 0.0;
+        }
+        self.records.iter().map(f).sum::<f64>() / self.records.len() as f64
+    }
+}
+
+/// 对(样本, 生成结果)逐个评分
+pub fn evaluate<'a, J, I>(judge: &J, items: I) -> Result<EvalReport, EvalError>
+where
+    J: Judge + ?Sized,
+    I: IntoIterator<Item = (&'a Sample, String)>,
+{
+    let items = items.into_iter().map(|(sample, output)| (sample, output, Vec::new()));
+    evaluate_attributed(judge, items)
+}
+
+/// 同 [`evaluate`]，并记录每个样本归因到的子弹，可用于
+/// [`AttributionHeatmap`](crate::attribution::AttributionHeatmap)
+pub fn evaluate_attributed<'a, J, I>(judge: &J, items: I) -> Result<EvalReport, EvalError>
+where
+    J: Judge + ?Sized,
+    I: IntoIterator<Item = (&'a Sample, String, Vec<BulletId>)>,
+{
+    let records = items
+        .into_iter()
+        .map(|(sample, output, bullet_ids)| {
+            let judgement = judge.judge(sample, &output)?;
+            Ok(EvalRecord {
+                sample: sample.clone(),
+                output,
+                judgement,
+                bullet_ids,
+            })
+        })
+        .collect::<Result<_, EvalError>>()?;
+    Ok(EvalReport { records })
+}
+
+/// 消融对象
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AblationTarget {
+    /// 整个章节（可用别名）
+    Section(String),
+    Bullet(BulletId),
+}
+
+/// 去掉一个对象后的评估结果；差值为消融后减去基线，负数说明这部分知识有帮助
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AblationResult {
+    pub target: AblationTarget,
+    /// 实际去掉的子弹数（对象不存在时为0，不重新评估，差值为0）
+    pub removed: usize,
+    pub accuracy: f64,
+    pub accuracy_delta: f64,
+    pub score_delta: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AblationReport {
+    /// 完整Playbook上的评估
+    pub baseline: EvalReport,
+    /// 与`targets`一一对应
+    pub results: Vec<AblationResult>,
+}
+
+/// 逐个去掉`targets`中的对象，用`generate`在剩余的Playbook上重新生成并评分。
+/// 每个对象单独消融（不累积），原Playbook不变
+pub fn ablate<J, G>(
+    playbook: &Playbook,
+    eval_set: &[Sample],
+    targets: &[AblationTarget],
+    judge: &J,
+    mut generate: G,
+) -> Result<AblationReport, EvalError>
+where
+    J: Judge + ?Sized,
+    G: FnMut(&Playbook, &Sample) -> Result<String, EvalError>,
+{
+    let mut run = |pb: &Playbook| {
+        let outputs = eval_set
+            .iter()
+            .map(|sample| Ok((sample, generate(pb, sample)?)))
+            .collect::<Result<Vec<_>, EvalError>>()?;
+        evaluate(judge, outputs)
+    };
+    let baseline = run(playbook)?;
+    let (base_accuracy, base_score) = (baseline.accuracy(), baseline.mean_score());
+
+    let mut results = Vec::with_capacity(targets.len());
+    for target in targets {
+        let ids: Vec<BulletId> = match target {
+            AblationTarget::Section(section) => {
+                let section = playbook.resolve_section(section);
+                playbook.sections.get(&*section).cloned().unwrap_or_default()
+            }
+            AblationTarget::Bullet(id) => {
+                playbook.bullets.contains_key(id).then(|| id.clone()).into_iter().collect()
+            }
+        };
+        let (accuracy, score) = if ids.is_empty() {
+            (base_accuracy, base_score)
+        } else {
+            let mut ablated = playbook.clone();
+            for id in &ids {
+                ablated.remove_bullet(id);
+            }
+            let report = run(&ablated)?;
+            (report.accuracy(), report.mean_score())
+        };
+        results.push(AblationResult {
+            target: target.clone(),
+            removed: ids.len(),
+            accuracy,
+            accuracy_delta: accuracy - base_accuracy,
+            score_delta: score - base_score,
+        });
+    }
+    Ok(AblationReport { baseline, results })
+}
+
+impl AblationReport {
+    /// 按准确率下降从大到小排列（最重要的知识在前）
+    pub fn ranked(&self) -> Vec<&AblationResult> {
+        let mut ranked: Vec<&AblationResult> = self.results.iter().collect();
+        ranked.sort_by(|a, b| a.accuracy_delta.total_cmp(&b.accuracy_delta));
+        ranked
+    }
+
+    /// 每个对象一行：`target<TAB>removed<TAB>accuracy_delta`
+    pub fn summary(&self) -> String {
+        let mut out = format!("baseline\t-\t{:.3}", self.baseline.accuracy());
+        for r in &self.results {
+            let target = match &r.target {
+                AblationTarget::Section(s) => format!("section:{}", s),
+                AblationTarget::Bullet(id) => format!("bullet:{}", id),
+            };
+            let _ = write!(out, "\n{}\t{}\t{:+.3}", target, r.removed, r.accuracy_delta);
+        }
+        out
+    }
+}
+
+#[cfg(feature = "llm")]
+pub use llm_judge::{CalibrationExample, JudgeConfig, LlmJudge};
+
+#[cfg(feature = "llm")]
+mod llm_judge {
+    use alloc::format;
+    use core::fmt::Write as _;
+
+    use super::*;
+    use crate::llm::{LlmClient, extract_json};
+
+    /// 校准用的参考样例，写入提示词作为打分锚点
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct CalibrationExample {
+        pub question: String,
+        pub output: String,
+        pub score: u32,
+        pub rationale: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct JudgeConfig {
+        /// 评分标准；为空时按与ground truth是否一致打分
+        pub rubric: Option<String>,
+        /// 模型给出 0..=max_score 的整数分，再归一化
+        pub max_score: u32,
+        /// 归一化分数达到该值视为通过
+        pub pass_threshold: f64,
+        /// 重复评分次数，取平均以降低单次评分的方差
+        pub votes: usize,
+        pub examples: Vec<CalibrationExample>,
+    }
+
+    impl Default for JudgeConfig {
+        fn default() -> Self {
+            Self {
+                rubric: None,
+                max_score: 10,
+                pass_threshold: 0.7,
+                votes: 1,
+                examples: Vec::new(),
+            }
+        }
+    }
+
+    /// 用独立的LLM给生成结果打分
+    #[derive(Debug)]
+    pub struct LlmJudge<C> {
+        client: C,
+        config: JudgeConfig,
+    }
+
+    impl<C: LlmClient> LlmJudge<C> {
+        pub fn new(client: C) -> Self {
+            Self::with_config(client, JudgeConfig::default())
+        }
+
+        pub fn with_config(client: C, config: JudgeConfig) -> Self {
+            Self { client, config }
+        }
+
+        pub fn config(&self) -> &JudgeConfig {
+            &self.config
+        }
+
+        fn prompt(&self, sample: &Sample, output: &str) -> String {
+            let cfg = &self.config;
+            let mut prompt = format!(
+                "You are an impartial judge. Score the response from 0 to {} and explain briefly.\n",
+                cfg.max_score
+            );
+            match &cfg.rubric {
+                Some(rubric) => {
+                    let _ = write!(prompt, "\nRubric:\n{}\n", rubric);
+                }
+                None => prompt.push_str("\nScore how well the response matches the reference answer.\n"),
+            }
+            for example in &cfg.examples {
+                let _ = write!(
+                    prompt,
+                    "\nExample:\nQuestion: {}\nResponse: {}\nScore: {}\nRationale: {}\n",
+                    example.question, example.output, example.score, example.rationale
+                );
+            }
+            let _ = write!(prompt, "\nQuestion: {}\n", sample.question);
+            if !sample.context.is_empty() {
+                let _ = writeln!(prompt, "Context: {}", sample.context);
+            }
+            if let Some(truth) = &sample.ground_truth {
+                let _ = writeln!(prompt, "Reference answer: {}", truth);
+            }
+            let _ = write!(
+                prompt,
+                "Response: {}\n\nReply with JSON only: {{\"score\": <integer>, \"rationale\": \"...\"}}",
+                output
+            );
+            prompt
+        }
+
+        fn vote(&self, prompt: &str) -> Result<(f64, String), EvalError> {
+            let text = self.client.complete(prompt)?.text;
+            let value = extract_json(&text)
+                .ok_or_else(|| EvalError::InvalidJudgement(format!("no JSON object in: {}", text)))?;
+            let score = value["score"]
+                .as_f64()
+                .ok_or_else(|| EvalError::InvalidJudgement(format!("missing score in: {}", text)))?;
+            let max = self.config.max_score.max(1) as f64;
+            let rationale = value["rationale"].as_str().unwrap_or_default().to_string();
+            Ok(((score / max).clamp(0.0, 1.0), rationale))
+        }
+    }
+
+    impl<C: LlmClient> Judge for LlmJudge<C> {
+        fn judge(&self, sample: &Sample, output: &str) -> Result<Judgement, EvalError> {
+            let prompt = self.prompt(sample, output);
+            let votes = (0..self.config.votes.max(1))
+                .map(|_| self.vote(&prompt))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let score = votes.iter().map(|(s, _)| s).sum::<f64>() / votes.len() as f64;
+            let rationale = votes
+                .into_iter()
+                .map(|(_, r)| r)
+                .filter(|r| !r.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Judgement {
+                score,
+                passed: score >= self.config.pass_threshold,
+                rationale,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(truth: &str) -> Sample {
+        Sample {
+            ground_truth: Some(truth.to_string()),
+            ..Sample::new("capital of France?")
+        }
+    }
+
+    #[test]
+    fn test_exact_match_report() {
+        let (a, b) = (sample("Paris"), sample("Paris"));
+        let report = evaluate(
+            &ExactMatchJudge,
+            [(&a, " paris ".to_string()), (&b, "Lyon".to_string())],
+        )
+        .unwrap();
+        assert_eq!(report.accuracy(), 0.5);
+        assert_eq!(report.records[1].judgement.rationale, "mismatch");
+    }
+
+    #[test]
+    fn test_ablate_sections_and_bullets() {
+        let mut pb = Playbook::new();
+        let capital = pb.add_bullet("geography", "The capital of France is Paris", None, None);
+        let filler = pb.add_bullet("style", "Answer in one word", None, None);
+        let samples = [sample("Paris"), sample("Paris")];
+        // 提示词里有首都知识时答对
+        let generate = |pb: &Playbook, _: &Sample| {
+            Ok(if pb.as_prompt().contains("Paris") { "Paris" } else { "Lyon" }.to_string())
+        };
+        let targets = [
+            AblationTarget::Section("style".to_string()),
+            AblationTarget::Bullet(capital.clone()),
+            AblationTarget::Section("missing".to_string()),
+        ];
+        let report = ablate(&pb, &samples, &targets, &ExactMatchJudge, generate).unwrap();
+
+        assert_eq!(report.baseline.accuracy(), 1.0);
+        let deltas: Vec<(usize, f64)> =
+            report.results.iter().map(|r| (r.removed, r.accuracy_delta)).collect();
+        assert_eq!(deltas, [(1, 0.0), (1, -1.0), (0, 0.0)]);
+        assert_eq!(report.ranked()[0].target, AblationTarget::Bullet(capital.clone()));
+        assert!(report.summary().contains(&format!("bullet:{}\t1\t-1.000", capital)));
+        assert!(pb.get_bullet(&filler).is_some());
+
+        let err = ablate(&pb, &samples, &targets, &ExactMatchJudge, |_, _| {
+            Err(EvalError::Generation("timeout".to_string()))
+        });
+        assert!(err.is_err());
+    }
+
+    #[cfg(feature = "llm")]
+    #[test]
+    fn test_llm_judge_averages_votes() {
+        use crate::llm::DummyLlmClient;
+
+        let client = DummyLlmClient::with_responses([
+            r#"{"score": 8, "rationale": "mostly right"}"#,
+            r#"```json
+{"score": 6, "rationale": "missing detail"}
+```"#,
+        ]);
+        let config = JudgeConfig {
+            rubric: Some("Must name the city.".to_string()),
+            votes: 2,
+            ..JudgeConfig::default()
+        };
+        let judge = LlmJudge::with_config(&client, config);
+        let judgement = judge.judge(&sample("Paris"), "Paris, France").unwrap();
+
+        assert!((judgement.score - 0.7).abs() < 1e-9);
+        assert!(judgement.passed);
+        assert!(judgement.rationale.contains("missing detail"));
+        assert!(client.prompts()[0].contains("Must name the city."));
+    }
+}
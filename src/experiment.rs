@@ -0,0 +1,242 @@
+//! A/B实验：把一部分查询路由到候选Playbook，分别记录结果，晋升前用统计检验对比两个版本
+//!
+//! 路由按查询键做稳定哈希，同一个键总是落在同一个版本上，便于复现。
+
+use serde::{Deserialize, Serialize};
+
+use crate::eval::Judgement;
+use crate::models::playbook::Playbook;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Variant {
+    /// 当前线上版本
+    Control,
+    /// 待验证的候选版本
+    Candidate,
+}
+
+/// 单个版本的累计结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct VariantStats {
+    pub trials: u64,
+    pub passed: u64,
+    pub score_sum: f64,
+}
+
+impl VariantStats {
+    pub fn pass_rate(&self) -> f64 {
+        if self.trials == 0 {
+            return 0.0;
+        }
+        self.passed as f64 / self.trials as f64
+    }
+
+    pub fn mean_score(&self) -> f64 {
+        if self.trials == 0 {
+            return 0.0;
+        }
+        self.score_sum / self.trials as f64
+    }
+}
+
+/// 两个版本的对比（通过率的双比例z检验）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentReport {
+    pub control: VariantStats,
+    pub candidate: VariantStats,
+    /// 候选版本通过率 - 对照版本通过率
+    pub lift: f64,
+    pub z_score: f64,
+    /// 双侧p值；任一版本没有样本或方差为0时为1
+    pub p_value: f64,
+}
+
+impl ExperimentReport {
+    /// 候选版本在给定显著性水平下显著优于对照版本
+    pub fn candidate_wins(&self, alpha: f64) -> bool {
+        self.lift > 0.0 && self.p_value < alpha
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Experiment {
+    name: String,
+    control: Playbook,
+    candidate: Playbook,
+    candidate_fraction: f64,
+    control_stats: VariantStats,
+    candidate_stats: VariantStats,
+}
+
+impl Experiment {
+    /// `candidate_fraction`为路由到候选版本的查询比例，会被限制在[0, 1]
+    pub fn new(name: impl Into<String>, control: Playbook, candidate: Playbook, candidate_fraction: f64) -> Self {
+        Self {
+            name: name.into(),
+            control,
+            candidate,
+            candidate_fraction: candidate_fraction.clamp(0.0, 1.0),
+            control_stats: VariantStats::default(),
+            candidate_stats: VariantStats::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 按查询键路由；实验名参与哈希，不同实验的分桶互不相关
+    pub fn route(&self, key: &str) -> Variant {
+        let hash = fnv1a(fnv1a(FNV_OFFSET, self.name.as_bytes()), key.as_bytes());
+        let bucket = (hash >> 11) as f64 / (1u64 << 53) as f64;
+        if bucket < self.candidate_fraction {
+            Variant::Candidate
+        } else {
+            Variant::Control
+        }
+    }
+
+    pub fn playbook(&self, variant: Variant) -> &Playbook {
+        match variant {
+            Variant::Control => &self.control,
+            Variant::Candidate => &self.candidate,
+        }
+    }
+
+    pub fn playbook_mut(&mut self, variant: Variant) -> &mut Playbook {
+        match variant {
+            Variant::Control => &mut self.control,
+            Variant::Candidate => &mut self.candidate,
+        }
+    }
+
+    /// 路由并返回对应的Playbook
+    pub fn assign(&self, key: &str) -> (Variant, &Playbook) {
+        let variant = self.route(key);
+        (variant, self.playbook(variant))
+    }
+
+    pub fn record(&mut self, variant: Variant, judgement: &Judgement) {
+        let stats = match variant {
+            Variant::Control => &mut self.control_stats,
+            Variant::Candidate => &mut self.candidate_stats,
+        };
+        stats.trials += 1;
+        stats.passed += u64::from(judgement.passed);
+        stats.score_sum += judgement.score;
+    }
+
+    pub fn stats(&self, variant: Variant) -> &VariantStats {
+        match variant {
+            Variant::Control => &self.control_stats,
+            Variant::Candidate => &self.candidate_stats,
+        }
+    }
+
+    pub fn report(&self) -> ExperimentReport {
+        let (c, t) = (self.control_stats, self.candidate_stats);
+        let lift = t.pass_rate() - c.pass_rate();
+        let (mut z_score, mut p_value) = (0.0, 1.0);
+        if c.trials > 0 && t.trials > 0 {
+            let pooled = (c.passed + t.passed) as f64 / (c.trials + t.trials) as f64;
+            let se = (pooled * (1.0 - pooled) * (1.0 / c.trials as f64 + 1.0 / t.trials as f64)).sqrt();
+            if se > 0.0 {
+                z_score = lift / se;
+                p_value = (2.0 * (1.0 - normal_cdf(z_score.abs()))).clamp(0.0, 1.0);
+            }
+        }
+        ExperimentReport {
+            control: c,
+            candidate: t,
+            lift,
+            z_score,
+            p_value,
+        }
+    }
+
+    /// 结束实验，交出两个版本的Playbook（对照, 候选）
+    pub fn into_playbooks(self) -> (Playbook, Playbook) {
+        (self.control, self.candidate)
+    }
+}
+
+// --------------------------
+// 辅助函数
+// --------------------------
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// 标准正态分布CDF（Abramowitz-Stegun 7.1.26近似erf，误差<1.5e-7）
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / core::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * z);
+    let poly = t
+        * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn judgement(passed: bool) -> Judgement {
+        Judgement {
+            score: if passed { 1.0 } else { 0.0 },
+            passed,
+            rationale: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_routing_is_stable_and_respects_fraction() {
+        let exp = Experiment::new("prompt-v2", Playbook::new(), Playbook::new(), 0.3);
+        let candidates = (0..2000)
+            .filter(|i| exp.route(&format!("query-{}", i)) == Variant::Candidate)
+            .count();
+        assert!((500..700).contains(&candidates), "got {}", candidates);
+        assert_eq!(exp.route("query-7"), exp.route("query-7"));
+
+        let all = Experiment::new("x", Playbook::new(), Playbook::new(), 1.0);
+        assert_eq!(all.route("anything"), Variant::Candidate);
+        let none = Experiment::new("x", Playbook::new(), Playbook::new(), 0.0);
+        assert_eq!(none.route("anything"), Variant::Control);
+    }
+
+    #[test]
+    fn test_report_detects_significant_lift() {
+        let mut candidate = Playbook::new();
+        candidate.add_bullet("api usage", "分页时带上cursor", None, None);
+        let mut exp = Experiment::new("cursor", Playbook::new(), candidate, 0.5);
+        assert_eq!(exp.report().p_value, 1.0);
+
+        for i in 0..200 {
+            exp.record(Variant::Control, &judgement(i % 2 == 0));
+            exp.record(Variant::Candidate, &judgement(i % 5 != 0));
+        }
+        let report = exp.report();
+        assert!((report.control.pass_rate() - 0.5).abs() < 1e-9);
+        assert!((report.lift - 0.3).abs() < 1e-9);
+        assert!(report.z_score > 6.0);
+        assert!(report.candidate_wins(0.05));
+
+        let (_, winner) = exp.into_playbooks();
+        assert_eq!(winner.bullets().len(), 1);
+    }
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-3);
+        assert!((normal_cdf(-1.96) - 0.025).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,166 @@
+//! 环境反馈：把任务结果（测试通过、工具报错、用户点踩……）映射为归因子弹上的TAG增量
+//!
+//! 不需要调用reflector，直接从环境信号中学习。
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::delta::{DeltaBatch, DeltaOperation};
+use crate::models::playbook::{BulletId, Playbook};
+
+/// 任务结果信号
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    TestPassed,
+    TestFailed,
+    ToolErrored,
+    ThumbsUp,
+    ThumbsDown,
+    /// 使用方自定义的信号，需在策略中配置
+    Custom(String),
+}
+
+impl Outcome {
+    /// 策略表中的键
+    pub fn key(&self) -> &str {
+        match self {
+            Outcome::TestPassed => "test_passed",
+            Outcome::TestFailed => "test_failed",
+            Outcome::ToolErrored => "tool_errored",
+            Outcome::ThumbsUp => "thumbs_up",
+            Outcome::ThumbsDown => "thumbs_down",
+            Outcome::Custom(name) => name,
+        }
+    }
+}
+
+/// 结果 → 标签增量的映射，可序列化进配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagPolicy {
+    pub rules: BTreeMap<String, BTreeMap<String, i32>>,
+}
+
+impl Default for TagPolicy {
+    fn default() -> Self {
+        let rules = [
+            ("test_passed", "helpful", 1),
+            ("test_failed", "harmful", 1),
+            ("tool_errored", "harmful", 1),
+            ("thumbs_up", "helpful", 1),
+            ("thumbs_down", "harmful", 1),
+        ]
+        .into_iter()
+        .map(|(outcome, tag, inc)| (outcome.to_string(), BTreeMap::from([(tag.to_string(), inc)])))
+        .collect();
+        Self { rules }
+    }
+}
+
+impl TagPolicy {
+    /// 设置（覆盖）某个结果的增量
+    pub fn set(&mut self, outcome: &Outcome, tag: &str, increment: i32) -> &mut Self {
+        self.rules
+            .entry(outcome.key().to_string())
+            .or_default()
+            .insert(tag.to_string(), increment);
+        self
+    }
+
+    /// 多个结果的增量按标签累加；未配置的结果忽略，累加为0的标签去掉
+    pub fn increments(&self, outcomes: &[Outcome]) -> BTreeMap<String, i32> {
+        let mut total = BTreeMap::new();
+        for rule in outcomes.iter().filter_map(|o| self.rules.get(o.key())) {
+            for (tag, inc) in rule {
+                let entry: &mut i32 = total.entry(tag.clone()).or_default();
+                *entry = entry.saturating_add(*inc);
+            }
+        }
+        total.retain(|_, inc| *inc != 0);
+        total
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OutcomeTagger {
+    policy: TagPolicy,
+}
+
+impl OutcomeTagger {
+    pub fn new(policy: TagPolicy) -> Self {
+        Self { policy }
+    }
+
+    pub fn policy(&self) -> &TagPolicy {
+        &self.policy
+    }
+
+    /// 为归因到的子弹生成TAG操作（不存在的子弹跳过）
+    pub fn delta(&self, playbook: &Playbook, bullet_ids: &[BulletId], outcomes: &[Outcome]) -> DeltaBatch {
+        let increments = self.policy.increments(outcomes);
+        let operations = if increments.is_empty() {
+            Vec::new()
+        } else {
+            bullet_ids
+                .iter()
+                .filter_map(|id| playbook.get_bullet(id))
+                .map(|bullet| DeltaOperation::tag(&*bullet.section, &bullet.id, increments.clone()))
+                .collect()
+        };
+        let keys: Vec<&str> = outcomes.iter().map(Outcome::key).collect();
+        DeltaBatch {
+            reasoning: format!("outcome feedback: {}", keys.join(", ")),
+            operations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_tags_attributed_bullets() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        let b = pb.add_bullet("debugging", "先看日志", None, None);
+
+        let tagger = OutcomeTagger::default();
+        let delta = tagger.delta(&pb, &[a.clone(), "gone-1".into()], &[Outcome::TestFailed]);
+        assert_eq!(delta.operations.len(), 1);
+        pb.apply_delta(delta).unwrap();
+        assert_eq!(pb.get_bullet(&a).unwrap().harmful, 1);
+        assert_eq!(pb.get_bullet(&b).unwrap().harmful, 0);
+    }
+
+    #[test]
+    fn test_custom_policy_combines_outcomes() {
+        let mut policy = TagPolicy::default();
+        policy.set(&Outcome::Custom("slow".into()), "neutral", 2);
+        policy.set(&Outcome::ThumbsUp, "helpful", 3);
+
+        let increments = policy.increments(&[
+            Outcome::ThumbsUp,
+            Outcome::Custom("slow".into()),
+            Outcome::Custom("unconfigured".into()),
+        ]);
+        assert_eq!(increments, BTreeMap::from([("helpful".into(), 3), ("neutral".into(), 2)]));
+
+        // 相互抵消的增量不产生操作
+        policy.set(&Outcome::TestFailed, "helpful", -1);
+        policy.rules.get_mut("test_failed").unwrap().remove("harmful");
+        assert!(policy.increments(&[Outcome::TestPassed, Outcome::TestFailed]).is_empty());
+
+        let mut pb = Playbook::new();
+        let id = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        let outcomes = [Outcome::TestPassed, Outcome::TestFailed];
+        let delta = OutcomeTagger::new(policy).delta(&pb, &[id], &outcomes);
+        assert!(delta.operations.is_empty());
+    }
+}
@@ -0,0 +1,12 @@
+//! 十六进制编码（摘要、签名与随机token的文本形式）
+
+use alloc::string::String;
+use core::fmt::Write as _;
+
+/// 小写十六进制
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
@@ -1 +1,36 @@
-pub mod models;
\ No newline at end of file
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod attribution;
+pub mod clock;
+pub mod datasets;
+#[cfg(feature = "std")]
+pub mod embedding;
+pub mod episodes;
+pub mod eval;
+#[cfg(feature = "std")]
+pub mod experiment;
+pub mod feedback;
+#[cfg(feature = "std")]
+mod hex;
+#[cfg(feature = "llm")]
+pub mod llm;
+pub mod maintenance;
+pub mod models;
+#[cfg(feature = "persist")]
+pub mod persist;
+pub mod progress;
+pub mod promotion;
+pub mod prompts;
+#[cfg(feature = "std")]
+pub mod quarantine;
+pub mod reflection;
+#[cfg(feature = "std")]
+pub mod retrieval;
+#[cfg(feature = "llm")]
+pub mod roles;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "std")]
+pub mod webhook;
@@ -0,0 +1,806 @@
+//! LLM客户端抽象（对齐Python的`LLMClient`），具体提供商由使用方实现
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::clock::{ManualClock, SharedClock, default_clock};
+
+#[derive(Debug, Error)]
+pub enum LlmError {
+    #[error("LLM提供商错误：{0}")]
+    Provider(String),
+    #[error("LLM响应无法解析：{0}")]
+    InvalidResponse(String),
+    #[error("路由配置引用了未注册的模型：{0}")]
+    UnknownModel(String),
+    #[error("LLM调用超时（{0:?}）")]
+    Timeout(Duration),
+    /// 熔断器打开期间不再调用该提供商
+    #[error("{name}已熔断，{retry_at}后重试")]
+    CircuitOpen { name: String, retry_at: DateTime<Utc> },
+    /// 回放时遇到录制中没有的提示词，说明本次运行与录制的运行已经分叉
+    #[error("回放分叉：录制中没有该提示词的响应：{0}")]
+    ReplayMiss(String),
+}
+
+impl LlmError {
+    /// 提供商侧的失败（出错、超时、熔断），可以换用备用模型
+    pub fn is_provider_failure(&self) -> bool {
+        matches!(self, LlmError::Provider(_) | LlmError::Timeout(_) | LlmError::CircuitOpen { .. })
+    }
+
+    fn attributed_to(self, name: &str) -> Self {
+        match self {
+            LlmError::Provider(e) => LlmError::Provider(format!("{}: {}", name, e)),
+            e => e,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LlmResponse {
+    pub text: String,
+    /// 提供商原始响应（可选，便于调试）
+    pub raw: Option<Value>,
+}
+
+impl LlmResponse {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            raw: None,
+        }
+    }
+}
+
+/// 模型发起的工具调用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// 流式补全中的一个事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// 一段输出文本
+    Text(String),
+    ToolCall(ToolCall),
+}
+
+pub trait LlmClient: Send + Sync {
+    fn complete(&self, prompt: &str) -> Result<LlmResponse, LlmError>;
+
+    /// 流式补全：每收到一段输出就调用`on_event`，结束时返回完整响应。
+    /// 默认实现不分段，等`complete`返回后一次性发出全部文本
+    fn complete_stream(
+        &self,
+        prompt: &str,
+        on_event: &mut dyn FnMut(StreamEvent),
+    ) -> Result<LlmResponse, LlmError> {
+        let response = self.complete(prompt)?;
+        on_event(StreamEvent::Text(response.text.clone()));
+        Ok(response)
+    }
+}
+
+impl<T: LlmClient + ?Sized> LlmClient for &T {
+    fn complete(&self, prompt: &str) -> Result<LlmResponse, LlmError> {
+        (**self).complete(prompt)
+    }
+
+    fn complete_stream(
+        &self,
+        prompt: &str,
+        on_event: &mut dyn FnMut(StreamEvent),
+    ) -> Result<LlmResponse, LlmError> {
+        (**self).complete_stream(prompt, on_event)
+    }
+}
+
+impl<T: LlmClient + ?Sized> LlmClient for std::sync::Arc<T> {
+    fn complete(&self, prompt: &str) -> Result<LlmResponse, LlmError> {
+        (**self).complete(prompt)
+    }
+
+    fn complete_stream(
+        &self,
+        prompt: &str,
+        on_event: &mut dyn FnMut(StreamEvent),
+    ) -> Result<LlmResponse, LlmError> {
+        (**self).complete_stream(prompt, on_event)
+    }
+}
+
+/// 按顺序返回预置响应的客户端，用于测试和离线演示
+#[derive(Debug, Default)]
+pub struct DummyLlmClient {
+    responses: Mutex<VecDeque<String>>,
+    prompts: Mutex<Vec<String>>,
+    /// 流式补全时每段的字符数，0表示不分段
+    chunk_chars: usize,
+}
+
+impl DummyLlmClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_responses<I, S>(responses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let client = Self::new();
+        for response in responses {
+            client.queue(response);
+        }
+        client
+    }
+
+    /// 流式补全时按`chars`个字符一段发出
+    pub fn with_chunking(mut self, chars: usize) -> Self {
+        self.chunk_chars = chars;
+        self
+    }
+
+    pub fn queue(&self, response: impl Into<String>) {
+        self.responses.lock().unwrap().push_back(response.into());
+    }
+
+    /// 收到过的全部提示词（按调用顺序）
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().unwrap().clone()
+    }
+}
+
+impl LlmClient for DummyLlmClient {
+    fn complete(&self, prompt: &str) -> Result<LlmResponse, LlmError> {
+        self.prompts.lock().unwrap().push(prompt.to_string());
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .map(LlmResponse::new)
+            .ok_or_else(|| LlmError::Provider("DummyLlmClient has no queued responses".to_string()))
+    }
+
+    fn complete_stream(
+        &self,
+        prompt: &str,
+        on_event: &mut dyn FnMut(StreamEvent),
+    ) -> Result<LlmResponse, LlmError> {
+        let response = self.complete(prompt)?;
+        let chars: Vec<char> = response.text.chars().collect();
+        let size = if self.chunk_chars == 0 { chars.len().max(1) } else { self.chunk_chars };
+        for chunk in chars.chunks(size) {
+            on_event(StreamEvent::Text(chunk.iter().collect()));
+        }
+        Ok(response)
+    }
+}
+
+/// 流水线中调用LLM的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    Generate,
+    Reflect,
+    Curate,
+    Judge,
+}
+
+/// 各阶段使用的模型：每个阶段是按优先级排列的模型名，第一个为主模型，其余为备用；
+/// 未配置的阶段使用`default`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingConfig {
+    pub default: Vec<String>,
+    pub stages: BTreeMap<PipelineStage, Vec<String>>,
+}
+
+impl RoutingConfig {
+    pub fn models_for(&self, stage: PipelineStage) -> &[String] {
+        self.stages.get(&stage).unwrap_or(&self.default)
+    }
+}
+
+/// 按顺序尝试多个客户端：提供商侧失败（[`LlmError::is_provider_failure`]）时换下一个，
+/// 其他错误直接返回；全部失败时返回最后一个错误
+#[derive(Clone, Default)]
+pub struct FallbackClient {
+    clients: Vec<(String, Arc<dyn LlmClient>)>,
+}
+
+impl FallbackClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_client(mut self, name: impl Into<String>, client: Arc<dyn LlmClient>) -> Self {
+        self.clients.push((name.into(), client));
+        self
+    }
+
+    /// 按尝试顺序的模型名
+    pub fn models(&self) -> Vec<&str> {
+        self.clients.iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+impl core::fmt::Debug for FallbackClient {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FallbackClient").field("models", &self.models()).finish()
+    }
+}
+
+impl LlmClient for FallbackClient {
+    fn complete(&self, prompt: &str) -> Result<LlmResponse, LlmError> {
+        let mut last = LlmError::Provider("no model configured".to_string());
+        for (name, client) in &self.clients {
+            match client.complete(prompt) {
+                Err(e) if e.is_provider_failure() => last = e.attributed_to(name),
+                result => return result,
+            }
+        }
+        Err(last)
+    }
+
+    /// 已经发出过事件的模型出错时不再切换，避免两个模型的输出拼在一起
+    fn complete_stream(
+        &self,
+        prompt: &str,
+        on_event: &mut dyn FnMut(StreamEvent),
+    ) -> Result<LlmResponse, LlmError> {
+        let mut last = LlmError::Provider("no model configured".to_string());
+        for (name, client) in &self.clients {
+            let mut emitted = false;
+            let result = client.complete_stream(prompt, &mut |event| {
+                emitted = true;
+                on_event(event);
+            });
+            match result {
+                Err(e) if e.is_provider_failure() && !emitted => last = e.attributed_to(name),
+                result => return result,
+            }
+        }
+        Err(last)
+    }
+}
+
+/// [`TimeoutClient::new`]使用的工作线程数
+pub const DEFAULT_TIMEOUT_WORKERS: usize = 4;
+
+/// 给每次调用加上时限。调用在固定数量的工作线程中进行，超时后直接返回 [`LlmError::Timeout`]
+///
+/// 超时不会取消已经开始的调用：`LlmClient`是同步接口，工作线程会一直等到提供商返回，
+/// 结果被丢弃，期间占用一个工作线程；排队中尚未开始的调用在超时后不再执行。
+/// 工作线程与排队位置都占满时（例如提供商持续挂起），新的调用立即返回提供商错误，
+/// 不会无限制地创建线程。流式调用超时前已发出的事件照常送达。
+#[derive(Clone)]
+pub struct TimeoutClient {
+    calls: mpsc::SyncSender<Call>,
+    workers: usize,
+    timeout: Duration,
+}
+
+/// 交给工作线程的一次调用
+struct Call {
+    prompt: String,
+    /// 调用方已超时返回
+    abandoned: Arc<AtomicBool>,
+    reply: mpsc::Sender<StreamMessage>,
+}
+
+impl TimeoutClient {
+    pub fn new(inner: Arc<dyn LlmClient>, timeout: Duration) -> Self {
+        Self::with_workers(inner, timeout, DEFAULT_TIMEOUT_WORKERS)
+    }
+
+    /// 启动`workers`个工作线程（至少一个），最多另有同样数量的调用排队；
+    /// 所有克隆都被丢弃后，工作线程在当前调用结束时退出
+    pub fn with_workers(inner: Arc<dyn LlmClient>, timeout: Duration, workers: usize) -> Self {
+        let workers = workers.max(1);
+        let (calls, queue) = mpsc::sync_channel(workers);
+        let queue = Arc::new(Mutex::new(queue));
+        for _ in 0..workers {
+            let (inner, queue) = (inner.clone(), queue.clone());
+            std::thread::spawn(move || run_calls(&*inner, &queue));
+        }
+        Self { calls, workers, timeout }
+    }
+}
+
+fn run_calls(inner: &dyn LlmClient, queue: &Mutex<mpsc::Receiver<Call>>) {
+    loop {
+        let call = queue.lock().unwrap_or_else(PoisonError::into_inner).recv();
+        let Ok(call) = call else {
+            return;
+        };
+        if call.abandoned.load(Ordering::Acquire) {
+            continue;
+        }
+        let events = call.reply.clone();
+        // 提供商panic时丢弃回复通道，调用方收到断开；工作线程继续服务
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            inner.complete_stream(&call.prompt, &mut |event| {
+                let _ = events.send(StreamMessage::Event(event));
+            })
+        }));
+        if let Ok(result) = result {
+            let _ = call.reply.send(StreamMessage::Done(result));
+        }
+    }
+}
+
+impl core::fmt::Debug for TimeoutClient {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TimeoutClient")
+            .field("timeout", &self.timeout)
+            .field("workers", &self.workers)
+            .finish()
+    }
+}
+
+enum StreamMessage {
+    Event(StreamEvent),
+    Done(Result<LlmResponse, LlmError>),
+}
+
+impl LlmClient for TimeoutClient {
+    fn complete(&self, prompt: &str) -> Result<LlmResponse, LlmError> {
+        self.complete_stream(prompt, &mut |_| {})
+    }
+
+    fn complete_stream(
+        &self,
+        prompt: &str,
+        on_event: &mut dyn FnMut(StreamEvent),
+    ) -> Result<LlmResponse, LlmError> {
+        let (reply, rx) = mpsc::channel();
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let call = Call { prompt: prompt.to_string(), abandoned: abandoned.clone(), reply };
+        if self.calls.try_send(call).is_err() {
+            return Err(LlmError::Provider(format!(
+                "all {} LLM workers are busy",
+                self.workers
+            )));
+        }
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(StreamMessage::Event(event)) => on_event(event),
+                Ok(StreamMessage::Done(result)) => return result,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    abandoned.store(true, Ordering::Release);
+                    return Err(LlmError::Timeout(self.timeout));
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(LlmError::Provider("LLM call panicked".to_string()));
+                }
+            }
+        }
+    }
+}
+
+/// 熔断器：连续`threshold`次提供商侧失败后打开，`cooldown`内的调用直接返回
+/// [`LlmError::CircuitOpen`]（由 [`FallbackClient`] 换用备用模型，或由调用方暂停运行）。
+/// 冷却结束后放行一次试探调用，成功则关闭，失败则再次打开
+#[derive(Debug)]
+pub struct CircuitBreaker<C> {
+    name: String,
+    inner: C,
+    threshold: u32,
+    cooldown: TimeDelta,
+    clock: SharedClock,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    failures: u32,
+    open_until: Option<DateTime<Utc>>,
+}
+
+impl<C: LlmClient> CircuitBreaker<C> {
+    pub fn new(name: impl Into<String>, inner: C, threshold: u32, cooldown: TimeDelta) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+            threshold: threshold.max(1),
+            cooldown,
+            clock: default_clock(),
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 打开状态下可以再次调用的时刻
+    pub fn retry_at(&self) -> Option<DateTime<Utc>> {
+        let state = self.state.lock().unwrap();
+        state.open_until.filter(|until| *until > self.clock.now())
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.retry_at().is_some()
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.state.lock().unwrap().failures
+    }
+
+    fn guarded(
+        &self,
+        call: impl FnOnce() -> Result<LlmResponse, LlmError>,
+    ) -> Result<LlmResponse, LlmError> {
+        if let Some(retry_at) = self.retry_at() {
+            return Err(LlmError::CircuitOpen { name: self.name.clone(), retry_at });
+        }
+        let result = call();
+        let mut state = self.state.lock().unwrap();
+        match &result {
+            Err(e) if e.is_provider_failure() => {
+                state.failures += 1;
+                if state.failures >= self.threshold {
+                    state.open_until = Some(self.clock.now() + self.cooldown);
+                }
+            }
+            Err(_) => {}
+            Ok(_) => *state = BreakerState::default(),
+        }
+        result
+    }
+}
+
+impl<C: LlmClient> LlmClient for CircuitBreaker<C> {
+    fn complete(&self, prompt: &str) -> Result<LlmResponse, LlmError> {
+        self.guarded(|| self.inner.complete(prompt))
+    }
+
+    fn complete_stream(
+        &self,
+        prompt: &str,
+        on_event: &mut dyn FnMut(StreamEvent),
+    ) -> Result<LlmResponse, LlmError> {
+        self.guarded(|| self.inner.complete_stream(prompt, on_event))
+    }
+}
+
+/// 按阶段路由到不同模型（如生成用便宜的模型，reflect/curate用强模型）
+#[derive(Debug, Clone)]
+pub struct ModelRouter {
+    config: RoutingConfig,
+    stages: BTreeMap<PipelineStage, FallbackClient>,
+    default: FallbackClient,
+}
+
+impl ModelRouter {
+    /// 用已注册的模型构建路由；配置中引用了未注册的模型名时报错
+    pub fn new(
+        config: RoutingConfig,
+        models: &BTreeMap<String, Arc<dyn LlmClient>>,
+    ) -> Result<Self, LlmError> {
+        let chain = |names: &[String]| {
+            names.iter().try_fold(FallbackClient::new(), |chain, name| {
+                let client =
+                    models.get(name).ok_or_else(|| LlmError::UnknownModel(name.clone()))?;
+                Ok(chain.with_client(name.clone(), client.clone()))
+            })
+        };
+        let default = chain(&config.default)?;
+        let stages = config
+            .stages
+            .iter()
+            .map(|(stage, names)| Ok((*stage, chain(names)?)))
+            .collect::<Result<_, LlmError>>()?;
+        Ok(Self { config, stages, default })
+    }
+
+    pub fn config(&self) -> &RoutingConfig {
+        &self.config
+    }
+
+    /// 该阶段的客户端（带备用模型），可直接传给各角色
+    pub fn client(&self, stage: PipelineStage) -> FallbackClient {
+        self.stages.get(&stage).unwrap_or(&self.default).clone()
+    }
+}
+
+/// 录制的一次调用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedCall {
+    pub prompt: String,
+    pub text: String,
+}
+
+/// 一次运行的录制：起始时刻、随机种子与全部LLM调用。用 [`Recording::clock`] 与
+/// [`Recording::client`] 重新执行同样的流水线，得到逐字节一致的Playbook
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recording {
+    pub started_at: DateTime<Utc>,
+    pub seed: u64,
+    pub calls: Vec<RecordedCall>,
+}
+
+impl Recording {
+    /// 停在录制起始时刻的时钟，注入Playbook与各角色
+    pub fn clock(&self) -> Arc<ManualClock> {
+        Arc::new(ManualClock::new(self.started_at))
+    }
+
+    pub fn client(&self) -> ReplayClient {
+        ReplayClient::new(self.calls.iter().cloned())
+    }
+}
+
+/// 记录经过的每次调用（成功的），用于之后的回放
+#[derive(Debug, Default)]
+pub struct RecordingClient<C> {
+    inner: C,
+    calls: Mutex<Vec<RecordedCall>>,
+}
+
+impl<C: LlmClient> RecordingClient<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner, calls: Mutex::default() }
+    }
+
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    pub fn recording(&self, started_at: DateTime<Utc>, seed: u64) -> Recording {
+        Recording { started_at, seed, calls: self.calls() }
+    }
+
+    fn record(&self, prompt: &str, result: &Result<LlmResponse, LlmError>) {
+        if let Ok(response) = result {
+            let call = RecordedCall { prompt: prompt.to_string(), text: response.text.clone() };
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+}
+
+impl<C: LlmClient> LlmClient for RecordingClient<C> {
+    fn complete(&self, prompt: &str) -> Result<LlmResponse, LlmError> {
+        let result = self.inner.complete(prompt);
+        self.record(prompt, &result);
+        result
+    }
+
+    fn complete_stream(
+        &self,
+        prompt: &str,
+        on_event: &mut dyn FnMut(StreamEvent),
+    ) -> Result<LlmResponse, LlmError> {
+        let result = self.inner.complete_stream(prompt, on_event);
+        self.record(prompt, &result);
+        result
+    }
+}
+
+/// 按提示词返回录制的响应；同一提示词出现多次时按录制顺序依次返回
+#[derive(Debug, Default)]
+pub struct ReplayClient {
+    responses: Mutex<BTreeMap<String, VecDeque<String>>>,
+}
+
+impl ReplayClient {
+    pub fn new(calls: impl IntoIterator<Item = RecordedCall>) -> Self {
+        let mut responses: BTreeMap<String, VecDeque<String>> = BTreeMap::new();
+        for call in calls {
+            responses.entry(call.prompt).or_default().push_back(call.text);
+        }
+        Self { responses: Mutex::new(responses) }
+    }
+
+    /// 尚未被回放的响应数
+    pub fn remaining(&self) -> usize {
+        self.responses.lock().unwrap().values().map(VecDeque::len).sum()
+    }
+}
+
+impl LlmClient for ReplayClient {
+    fn complete(&self, prompt: &str) -> Result<LlmResponse, LlmError> {
+        let mut responses = self.responses.lock().unwrap();
+        responses
+            .get_mut(prompt)
+            .and_then(VecDeque::pop_front)
+            .map(LlmResponse::new)
+            .ok_or_else(|| LlmError::ReplayMiss(prompt.chars().take(80).collect()))
+    }
+}
+
+/// 从模型输出中提取第一个JSON对象（兼容```json代码块和前后的说明文字）
+pub fn extract_json(text: &str) -> Option<Value> {
+    if let Ok(value @ Value::Object(_)) = serde_json::from_str(text.trim()) {
+        return Some(value);
+    }
+    let mut search = text;
+    while let Some(start) = search.find('{') {
+        let candidate = &search[start..];
+        let mut stream = serde_json::Deserializer::from_str(candidate).into_iter::<Value>();
+        if let Some(Ok(value @ Value::Object(_))) = stream.next() {
+            return Some(value);
+        }
+        search = &candidate[1..];
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dummy_client_replays_in_order() {
+        let client = DummyLlmClient::with_responses(["a", "b"]);
+        assert_eq!(client.complete("p1").unwrap().text, "a");
+        assert_eq!(client.complete("p2").unwrap().text, "b");
+        assert!(client.complete("p3").is_err());
+        assert_eq!(client.prompts(), vec!["p1", "p2", "p3"]);
+    }
+
+    #[test]
+    fn test_router_per_stage_with_fallback() {
+        struct Down;
+        impl LlmClient for Down {
+            fn complete(&self, _: &str) -> Result<LlmResponse, LlmError> {
+                Err(LlmError::Provider("503".to_string()))
+            }
+        }
+        let cheap = Arc::new(DummyLlmClient::with_responses(["cheap"]));
+        let strong = Arc::new(DummyLlmClient::with_responses(["strong", "strong again"]));
+        let models: BTreeMap<String, Arc<dyn LlmClient>> = BTreeMap::from([
+            ("cheap".to_string(), cheap.clone() as Arc<dyn LlmClient>),
+            ("strong".to_string(), strong.clone() as Arc<dyn LlmClient>),
+            ("down".to_string(), Arc::new(Down) as Arc<dyn LlmClient>),
+        ]);
+        let config: RoutingConfig = serde_json::from_str(
+            r#"{"default": ["cheap"],
+                "stages": {"reflect": ["down", "strong"], "curate": ["strong"]}}"#,
+        )
+        .unwrap();
+        let router = ModelRouter::new(config.clone(), &models).unwrap();
+
+        assert_eq!(router.client(PipelineStage::Generate).complete("q").unwrap().text, "cheap");
+        assert_eq!(router.client(PipelineStage::Reflect).models(), ["down", "strong"]);
+        assert_eq!(router.client(PipelineStage::Reflect).complete("r").unwrap().text, "strong");
+        assert_eq!(router.client(PipelineStage::Judge).models(), ["cheap"]);
+        assert_eq!(strong.prompts(), ["r"]);
+
+        // 备用模型也失败时返回最后的提供商错误
+        let err = FallbackClient::new()
+            .with_client("down", models["down"].clone())
+            .complete("x")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "LLM提供商错误：down: 503");
+        // 非提供商错误不换模型
+        struct Garbled;
+        impl LlmClient for Garbled {
+            fn complete(&self, _: &str) -> Result<LlmResponse, LlmError> {
+                Err(LlmError::InvalidResponse("truncated".to_string()))
+            }
+        }
+        cheap.queue("unused");
+        let chain = FallbackClient::new()
+            .with_client("garbled", Arc::new(Garbled))
+            .with_client("cheap", models["cheap"].clone());
+        assert!(matches!(chain.complete("y"), Err(LlmError::InvalidResponse(_))));
+        assert_eq!(cheap.prompts(), ["q"]);
+
+        let mut bad = config;
+        bad.stages.insert(PipelineStage::Judge, vec!["gpt-missing".to_string()]);
+        let err = ModelRouter::new(bad, &models).unwrap_err();
+        assert!(matches!(err, LlmError::UnknownModel(m) if m == "gpt-missing"));
+    }
+
+    #[test]
+    fn test_timeout_and_circuit_breaker() {
+        use crate::clock::ManualClock;
+
+        #[derive(Debug)]
+        struct Slow;
+        impl LlmClient for Slow {
+            fn complete(&self, _: &str) -> Result<LlmResponse, LlmError> {
+                std::thread::sleep(Duration::from_millis(500));
+                Ok(LlmResponse::new("late"))
+            }
+        }
+        let slow = TimeoutClient::new(Arc::new(Slow), Duration::from_millis(20));
+        assert!(matches!(slow.complete("p"), Err(LlmError::Timeout(_))));
+        // 超时的调用仍在工作线程中运行，不再为每次调用新建线程
+        let stuck = TimeoutClient::with_workers(Arc::new(Slow), Duration::from_millis(20), 1);
+        assert!(matches!(stuck.complete("1"), Err(LlmError::Timeout(_))));
+        assert!(matches!(stuck.complete("2"), Err(LlmError::Timeout(_))));
+        assert!(matches!(stuck.complete("3"), Err(LlmError::Provider(e)) if e.contains("busy")));
+        let fast = TimeoutClient::new(
+            Arc::new(DummyLlmClient::with_responses(["ok"]).with_chunking(1)),
+            Duration::from_secs(5),
+        );
+        let mut chunks = 0;
+        assert_eq!(fast.complete_stream("p", &mut |_| chunks += 1).unwrap().text, "ok");
+        assert_eq!(chunks, 2);
+
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let flaky = Arc::new(DummyLlmClient::with_responses(["{}"]));
+        let breaker = CircuitBreaker::new("flaky", flaky.clone(), 2, TimeDelta::minutes(5))
+            .with_clock(clock.clone());
+        // 队列为空时DummyLlmClient返回提供商错误
+        assert_eq!(breaker.complete("1").unwrap().text, "{}");
+        assert!(breaker.complete("2").is_err());
+        assert!(!breaker.is_open());
+        assert!(breaker.complete("3").is_err());
+        assert_eq!(breaker.consecutive_failures(), 2);
+        assert!(matches!(breaker.complete("4"), Err(LlmError::CircuitOpen { .. })));
+        assert_eq!(flaky.prompts().len(), 3);
+
+        let backup = DummyLlmClient::with_responses(["backup"]);
+        let chain = FallbackClient::new()
+            .with_client("flaky", Arc::new(breaker))
+            .with_client("backup", Arc::new(backup));
+        assert_eq!(chain.complete("5").unwrap().text, "backup");
+        assert_eq!(flaky.prompts().len(), 3);
+
+        // 冷却后放行试探调用，成功即关闭
+        let breaker = CircuitBreaker::new("flaky", flaky.clone(), 1, TimeDelta::minutes(5))
+            .with_clock(clock.clone());
+        assert!(breaker.complete("6").is_err());
+        assert_eq!(breaker.retry_at(), Some(DateTime::UNIX_EPOCH + TimeDelta::minutes(5)));
+        clock.advance(TimeDelta::minutes(5));
+        flaky.queue("recovered");
+        assert_eq!(breaker.complete("7").unwrap().text, "recovered");
+        assert_eq!(breaker.consecutive_failures(), 0);
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let live = DummyLlmClient::with_responses(["a1", "b", "a2"]);
+        let recorder = RecordingClient::new(&live);
+        for prompt in ["a", "b", "a"] {
+            recorder.complete(prompt).unwrap();
+        }
+        assert!(recorder.complete("c").is_err());
+        let recording = recorder.recording(DateTime::UNIX_EPOCH, 7);
+        assert_eq!(recording.calls.len(), 3);
+
+        let json = serde_json::to_string(&recording).unwrap();
+        let recording: Recording = serde_json::from_str(&json).unwrap();
+        assert_eq!(crate::clock::Clock::now(&*recording.clock()), DateTime::UNIX_EPOCH);
+        let replay = recording.client();
+        assert_eq!(replay.complete("a").unwrap().text, "a1");
+        assert_eq!(replay.complete("b").unwrap().text, "b");
+        assert_eq!(replay.complete("a").unwrap().text, "a2");
+        assert_eq!(replay.remaining(), 0);
+        let err = replay.complete("a").unwrap_err();
+        assert!(matches!(err, LlmError::ReplayMiss(_)) && !err.is_provider_failure());
+    }
+
+    #[test]
+    fn test_extract_json() {
+        let text = "Sure, here it is:\n```json\n{\"score\": 8, \"rationale\": \"ok {fine}\"}\n```";
+        let value = extract_json(text).unwrap();
+        assert_eq!(value["score"], 8);
+        assert_eq!(value["rationale"], "ok {fine}");
+        assert!(extract_json("no json here").is_none());
+    }
+}
@@ -1,3 +1,348 @@
-fn main() {
-    println!("Hello, world!");
+//! ace-rs命令行，需要 `cli` feature
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use ace_rs::clock::ManualClock;
+use ace_rs::models::delta::DeltaBatch;
+use ace_rs::models::playbook::Playbook;
+use ace_rs::models::replay::{JournalEntry, ReplayFilter};
+use ace_rs::models::review::{ReviewItem, ReviewQueue, ReviewStatus};
+use ace_rs::persist::fts::MappedBm25Index;
+use ace_rs::progress::Progress;
+use ace_rs::prompts::PromptSet;
+use ace_rs::retrieval::Bm25Index;
+use ace_rs::roles::RecordedRun;
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
+
+fn cli() -> Command {
+    let queue = || {
+        Arg::new("queue")
+            .long("queue")
+            .value_name("FILE")
+            .help("审核队列文件")
+            .required(true)
+            .value_parser(value_parser!(PathBuf))
+    };
+    let playbook = || {
+        Arg::new("playbook")
+            .long("playbook")
+            .value_name("FILE")
+            .help("Playbook文件（JSON）")
+            .required(true)
+            .value_parser(value_parser!(PathBuf))
+    };
+    let id = || Arg::new("id").required(true).value_parser(value_parser!(u64));
+    let note = || Arg::new("note").long("note").help("审核备注");
+
+    let review = Command::new("review")
+        .about("查看与处理待审核的Delta")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("list")
+                .about("列出待审核项")
+                .arg(queue())
+                .arg(Arg::new("all").long("all").action(ArgAction::SetTrue).help("包括已处理的项")),
+        )
+        .subcommand(
+            Command::new("show")
+                .about("显示审核项的Delta与金丝雀diff")
+                .arg(id())
+                .arg(queue()),
+        )
+        .subcommand(
+            Command::new("approve")
+                .about("批准并应用到Playbook")
+                .arg(id())
+                .arg(queue())
+                .arg(playbook())
+                .arg(note()),
+        )
+        .subcommand(Command::new("reject").about("拒绝").arg(id()).arg(queue()).arg(note()))
+        .subcommand(
+            Command::new("edit")
+                .about("用修改后的Delta替换审核项内容")
+                .arg(id())
+                .arg(queue())
+                .arg(playbook())
+                .arg(
+                    Arg::new("delta")
+                        .long("delta")
+                        .value_name("FILE")
+                        .help("DeltaBatch文件（JSON）")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        );
+    let file = |name: &'static str, help: &'static str| {
+        Arg::new(name).long(name).value_name("FILE").help(help).value_parser(value_parser!(PathBuf))
+    };
+    let replay = Command::new("replay")
+        .about("重新执行一次录制的运行（回放LLM响应）或按Delta日志重放，得到逐字节一致的Playbook")
+        .arg(file("recording", "录制的运行（RecordedRun的JSON）"))
+        .arg(file("base", "录制开始时的Playbook（--recording，默认为空Playbook）"))
+        .arg(
+            Arg::new("prompts")
+                .long("prompts")
+                .value_name("DIR")
+                .help("录制时使用的reflector.txt / curator.txt（--recording，默认为内置模板）")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(file("journal", "Delta日志（JSONL，每行一个JournalEntry）"))
+        .group(ArgGroup::new("source").args(["recording", "journal"]).required(true))
+        .arg(file("out", "写出重放得到的Playbook"))
+        .arg(file("expect", "与该Playbook文件逐字节比较，不一致时报错"));
+    let search = Command::new("search")
+        .about("BM25全文搜索子弹；索引保存在Playbook旁，内容变化时只重新索引变化的子弹")
+        .arg(Arg::new("query").required(true))
+        .arg(playbook())
+        .arg(file("index", "索引文件（默认为Playbook文件名加.fts）"))
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .default_value("10")
+                .help("最多输出的结果数")
+                .value_parser(value_parser!(usize)),
+        );
+    let command = Command::new("ace-rs")
+        .subcommand_required(true)
+        .subcommand(review)
+        .subcommand(replay)
+        .subcommand(search);
+    #[cfg(feature = "server")]
+    let command = command.subcommand(
+        Command::new("serve")
+            .about("启动HTTP服务与管理页（/dashboard），修改后写回Playbook文件")
+            .arg(playbook())
+            .arg(file("keys", "API key文件（ApiKeys的JSON）").required(true))
+            .arg(file("queue", "审核队列文件，审核后写回"))
+            .arg(
+                Arg::new("snapshots")
+                    .long("snapshots")
+                    .value_name("DIR")
+                    .help("/compare使用的快照目录")
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(Arg::new("addr").long("addr").default_value("127.0.0.1:8080").help("监听地址")),
+    );
+    command
+}
+
+fn main() -> ExitCode {
+    match run(cli().get_matches()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(matches: ArgMatches) -> Result<(), Box<dyn Error>> {
+    match matches.subcommand() {
+        Some(("review", matches)) => review(matches),
+        Some(("replay", matches)) => replay(matches),
+        Some(("search", matches)) => search(matches),
+        #[cfg(feature = "server")]
+        Some(("serve", matches)) => serve(matches),
+        _ => unreachable!("subcommand_required"),
+    }
+}
+
+fn review(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let (command, args) = matches.subcommand().expect("subcommand_required");
+    let path = |name: &str| args.get_one::<PathBuf>(name).expect("required");
+    let queue_path = path("queue");
+    let mut queue = ReviewQueue::load(queue_path)?;
+    let id = || *args.get_one::<u64>("id").expect("required");
+    let note = || args.get_one::<String>("note").cloned();
+
+    match command {
+        "list" => {
+            let all = args.get_flag("all");
+            for item in queue.items.iter().filter(|i| all || i.status == ReviewStatus::Pending) {
+                print_summary(item);
+            }
+            return Ok(());
+        }
+        "show" => {
+            let item = queue.get(id()).ok_or_else(|| format!("审核项不存在: {}", id()))?;
+            print_summary(item);
+            println!("{}", serde_json::to_string_pretty(&item.delta)?);
+            if let Some(diff) = &item.canary_diff {
+                print!("{}", diff);
+            }
+            return Ok(());
+        }
+        "approve" => {
+            let mut playbook = Playbook::load_from_file(path("playbook"))?;
+            let report = queue.approve(id(), &mut playbook, note())?;
+            playbook.save_to_file(path("playbook"))?;
+            println!(
+                "approved {}: {} operations, +{} -{} bullets",
+                id(),
+                report.operations,
+                report.impact.added,
+                report.impact.removed
+            );
+        }
+        "reject" => {
+            queue.reject(id(), note(), chrono::Utc::now())?;
+            println!("rejected {}", id());
+        }
+        "edit" => {
+            let playbook = Playbook::load_from_file(path("playbook"))?;
+            let data = std::fs::read(path("delta"))?;
+            let delta: DeltaBatch = serde_json::from_slice(&data)?;
+            let item = queue.edit(id(), delta, &playbook)?;
+            print!("{}", item.canary_diff.as_deref().unwrap_or_default());
+        }
+        _ => unreachable!("unknown review subcommand"),
+    }
+    queue.save(queue_path)?;
+    Ok(())
+}
+
+fn replay(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let playbook = match args.get_one::<PathBuf>("recording") {
+        Some(path) => replay_recording(path, args)?,
+        None => replay_journal(args.get_one::<PathBuf>("journal").expect("group required"))?,
+    };
+    let json = playbook.to_json()?;
+    if let Some(out) = args.get_one::<PathBuf>("out") {
+        std::fs::write(out, &json)?;
+    }
+    if let Some(expect) = args.get_one::<PathBuf>("expect") {
+        let expected = std::fs::read_to_string(expect)?;
+        if let Some((line, (a, b))) =
+            json.lines().zip(expected.lines()).enumerate().find(|(_, (a, b))| a != b)
+        {
+            return Err(format!("第{}行不一致：\n- {}\n+ {}", line + 1, b, a).into());
+        }
+        if json.len() != expected.len() {
+            return Err(format!("长度不一致：{} != {}", json.len(), expected.len()).into());
+        }
+        println!("identical to {}", expect.display());
+    }
+    Ok(())
+}
+
+/// 在录制开始时的Playbook上重新执行录制的运行
+fn replay_recording(path: &Path, args: &ArgMatches) -> Result<Playbook, Box<dyn Error>> {
+    let run: RecordedRun = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let mut playbook = match args.get_one::<PathBuf>("base") {
+        Some(base) => Playbook::load_from_file(base)?,
+        None => Playbook::new(),
+    };
+    let prompts = match args.get_one::<PathBuf>("prompts") {
+        Some(dir) => PromptSet::load_dir(dir)?,
+        None => PromptSet::default(),
+    };
+    let applied = run.replay(&mut playbook, prompts)?;
+    println!(
+        "replayed {} trajectories: {} LLM calls, {} operations applied",
+        run.trajectories.len(),
+        run.recording.calls.len(),
+        applied
+    );
+    Ok(playbook)
+}
+
+/// 按Delta日志重放：时钟固定在日志起点，重放结果不受执行时刻影响
+fn replay_journal(path: &Path) -> Result<Playbook, Box<dyn Error>> {
+    let journal = std::fs::read_to_string(path)?;
+    let entries = journal
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<JournalEntry>, _>>()?;
+    let start = entries.first().map(|e| e.applied_at).unwrap_or_default();
+    let mut playbook = Playbook::with_clock(Arc::new(ManualClock::new(start)));
+    let mut bar = |p: &Progress| eprint!("\r{}", p.bar(30));
+    let report =
+        ReplayFilter::default().replay_onto_with_progress(&mut playbook, &entries, &mut bar)?;
+    eprintln!();
+    println!(
+        "replayed {} entries: {} operations applied, {} dangling",
+        report.entries, report.applied, report.dangling
+    );
+    Ok(playbook)
+}
+
+fn search(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let path = args.get_one::<PathBuf>("playbook").expect("required");
+    let query = args.get_one::<String>("query").expect("required");
+    let limit = *args.get_one::<usize>("limit").expect("default");
+    let index_path = args.get_one::<PathBuf>("index").cloned().unwrap_or_else(|| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".fts");
+        PathBuf::from(name)
+    });
+    let playbook = Playbook::load_from_file(path)?;
+
+    // 索引与Playbook一致时直接在映射上查询；否则增量更新（没有索引时全量建立）后保存
+    let scores = match MappedBm25Index::open(&index_path) {
+        Ok(mapped) if mapped.is_current(&playbook) => mapped.scores(query),
+        opened => {
+            let mut index = match opened {
+                Ok(mapped) => mapped.to_index(),
+                Err(_) => Bm25Index::build(&Playbook::new()),
+            };
+            let reindexed = index.refresh(&playbook);
+            index.save(&index_path)?;
+            eprintln!("indexed {} bullets ({} changed)", index.len(), reindexed);
+            index.scores(query)
+        }
+    };
+
+    let mut ranked: Vec<_> = scores.into_iter().collect();
+    ranked.sort_by(|(a, x), (b, y)| y.total_cmp(x).then_with(|| a.cmp(b)));
+    for (id, score) in ranked.into_iter().take(limit) {
+        let Some(bullet) = playbook.get_bullet(&id) else { continue };
+        let content = bullet.content.lines().next().unwrap_or_default();
+        println!("{:.3}\t{}\t{}\t{}", score, id, bullet.section, content);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+fn serve(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    use ace_rs::server::auth::ApiKeys;
+    use ace_rs::server::compare::SnapshotStore;
+    use ace_rs::server::http::{self, ServerState};
+
+    let path = args.get_one::<PathBuf>("playbook").expect("required");
+    let keys = std::fs::read(args.get_one::<PathBuf>("keys").expect("required"))?;
+    let keys: ApiKeys = serde_json::from_slice(&keys)?;
+    let playbook = Playbook::load_from_file(path)?;
+    let mut state = ServerState::new(playbook, keys).with_playbook_file(path);
+    if let Some(queue) = args.get_one::<PathBuf>("queue") {
+        state = state.with_reviews(ReviewQueue::load(queue)?, Some(queue.clone()));
+    }
+    if let Some(dir) = args.get_one::<PathBuf>("snapshots") {
+        state = state.with_snapshots(SnapshotStore::new(dir));
+    }
+    let addr = args.get_one::<String>("addr").expect("default");
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        eprintln!("listening on http://{}/dashboard", listener.local_addr()?);
+        http::serve(listener, Arc::new(state)).await
+    })?;
+    Ok(())
+}
+
+fn print_summary(item: &ReviewItem) {
+    let reason = if item.reason.is_empty() { "-" } else { &item.reason };
+    println!(
+        "{}\t{:?}\t{}\t{} ops\t{}\t{}",
+        item.id,
+        item.status,
+        item.submitted_at.to_rfc3339(),
+        item.delta.operations.len(),
+        reason,
+        item.delta.reasoning
+    );
 }
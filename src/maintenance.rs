@@ -0,0 +1,626 @@
+//! 定期维护：按配置的间隔对Playbook执行计数衰减、去重、清理死子弹、归档过期子弹、容量压缩与
+//! 统计快照
+//!
+//! [`Maintenance::tick`] 只执行已到期的任务，不依赖任何运行时：可以由异步运行时的定时器
+//! 驱动，也可以用 [`Maintenance::spawn`]（需要`std`）在后台线程中按固定间隔轮询，
+//! 或用`Maintenance::spawn_tokio`（需要`tokio` feature）在tokio任务中轮询。
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::delta::{DeltaBatch, DeltaOperation, OperationType};
+use crate::models::playbook::{Bullet, Playbook, PlaybookError, SectionName};
+use crate::models::stats::StatsSnapshot;
+use crate::progress::{ProgressSink, ProgressTracker};
+use crate::reflection::{jaccard, tokens};
+
+/// 一种维护任务
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "job", rename_all = "snake_case")]
+pub enum Job {
+    /// 所有计数乘以`factor`（四舍五入），让旧反馈的权重逐渐降低；冻结的章节不衰减
+    Decay { factor: f64 },
+    /// 同章节内词集相似度不低于`min_similarity`的子弹合并到较早的一条
+    Dedup { min_similarity: f64 },
+    /// 删除超过`dead_after_secs`未修改也未使用的子弹
+    Prune { dead_after_secs: u64 },
+    /// 删除harmful显著多于helpful的子弹（见 [`Playbook::significantly_harmful`]）
+    PruneHarmful { z: f64 },
+    /// 把已过期的子弹移入归档（见 [`crate::models::expiry`]）
+    Archive,
+    /// 按容量配置淘汰子弹
+    Compact,
+    /// 生成统计快照
+    Stats,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    #[serde(flatten)]
+    pub job: Job,
+    /// 执行间隔（秒）
+    pub every_secs: u64,
+}
+
+/// 维护任务配置，可序列化进运行配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    pub jobs: Vec<ScheduledJob>,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        const HOUR: u64 = 3600;
+        const DAY: u64 = 24 * HOUR;
+        let job = |job, every_secs| ScheduledJob { job, every_secs };
+        Self {
+            jobs: Vec::from([
+                job(Job::Decay { factor: 0.9 }, DAY),
+                job(Job::Dedup { min_similarity: 0.8 }, HOUR),
+                job(Job::Prune { dead_after_secs: 30 * DAY }, DAY),
+                job(Job::Archive, HOUR),
+                job(Job::Compact, HOUR),
+                job(Job::Stats, HOUR),
+            ]),
+        }
+    }
+}
+
+/// 一次任务的执行结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobReport {
+    /// 计数有变化的子弹数
+    Decayed { bullets: usize },
+    Deduped { removed: usize },
+    Pruned { removed: usize },
+    Archived { archived: usize },
+    Compacted { removed: usize },
+    Snapshot(StatsSnapshot),
+    /// 任务出错（`code`见 [`PlaybookError::code`]），下次tick重试；其余任务照常执行
+    Failed { job: Job, code: &'static str, message: String },
+}
+
+/// 维护调度器，记录每个任务上次执行的时间
+#[derive(Debug, Clone)]
+pub struct Maintenance {
+    config: MaintenanceConfig,
+    last_run: Vec<Option<DateTime<Utc>>>,
+}
+
+impl Maintenance {
+    pub fn new(config: MaintenanceConfig) -> Self {
+        let last_run = alloc::vec![None; config.jobs.len()];
+        Self { config, last_run }
+    }
+
+    pub fn config(&self) -> &MaintenanceConfig {
+        &self.config
+    }
+
+    /// 以Playbook的时钟为准执行到期的任务
+    pub fn tick(&mut self, playbook: &mut Playbook) -> Vec<JobReport> {
+        let now = playbook.clock().now();
+        self.tick_at(playbook, now)
+    }
+
+    /// 执行到期的任务（按配置顺序）。每个任务第一次tick时只开始计时、不执行，
+    /// 避免每次重启都立即衰减一遍计数。出错的任务记为 [`JobReport::Failed`]、下次tick重试，
+    /// 不影响之后的任务。执行了任务时通知Playbook的观察者（见 [`crate::models::observer`]）
+    pub fn tick_at(&mut self, playbook: &mut Playbook, now: DateTime<Utc>) -> Vec<JobReport> {
+        let mut reports = Vec::new();
+        for (scheduled, last_run) in self.config.jobs.iter().zip(&mut self.last_run) {
+            let Some(last) = *last_run else {
+                *last_run = Some(now);
+                continue;
+            };
+            if now - last < interval(scheduled.every_secs) {
+                continue;
+            }
+            match run_job(&scheduled.job, playbook, now) {
+                Ok(report) => {
+                    reports.push(report);
+                    *last_run = Some(now);
+                }
+                Err(e) => reports.push(JobReport::Failed {
+                    job: scheduled.job.clone(),
+                    code: e.code(),
+                    message: e.to_string(),
+                }),
+            }
+        }
+        if !reports.is_empty() {
+            playbook.notify_maintenance(&reports);
+        }
+        reports
+    }
+
+    /// 最近一个任务的到期时间；还没tick过时为`None`
+    pub fn next_due(&self) -> Option<DateTime<Utc>> {
+        self.config
+            .jobs
+            .iter()
+            .zip(&self.last_run)
+            .filter_map(|(job, last)| (*last)?.checked_add_signed(interval(job.every_secs)))
+            .min()
+    }
+}
+
+fn interval(secs: u64) -> TimeDelta {
+    i64::try_from(secs)
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .unwrap_or(TimeDelta::MAX)
+}
+
+fn run_job(
+    job: &Job,
+    playbook: &mut Playbook,
+    now: DateTime<Utc>,
+) -> Result<JobReport, PlaybookError> {
+    let report = match *job {
+        Job::Decay { factor } => JobReport::Decayed {
+            bullets: playbook.decay_counters(factor),
+        },
+        Job::Dedup { min_similarity } => {
+            let delta = playbook.dedup_delta(min_similarity);
+            let removed = count_removes(&delta);
+            playbook.apply_delta_at(delta, now)?;
+            JobReport::Deduped { removed }
+        }
+        Job::Prune { dead_after_secs } => {
+            let delta = playbook.prune_dead_delta(interval(dead_after_secs));
+            let removed = delta.operations.len();
+            playbook.apply_delta_at(delta, now)?;
+            JobReport::Pruned { removed }
+        }
+        Job::PruneHarmful { z } => {
+            let delta = playbook.prune_harmful_delta(z);
+            let removed = delta.operations.len();
+            playbook.apply_delta_at(delta, now)?;
+            JobReport::Pruned { removed }
+        }
+        Job::Archive => JobReport::Archived {
+            archived: playbook.archive_expired_at(now).len(),
+        },
+        Job::Compact => JobReport::Compacted {
+            removed: playbook.compact().len(),
+        },
+        Job::Stats => JobReport::Snapshot(playbook.snapshot_stats()),
+    };
+    Ok(report)
+}
+
+fn count_removes(delta: &DeltaBatch) -> usize {
+    delta.operations.iter().filter(|op| op.type_ == OperationType::Remove).count()
+}
+
+impl Playbook {
+    /// 子弹的计数（包括自定义标签）乘以`factor`（限制在0到1，四舍五入），不更新`updated_at`。
+    /// 冻结的Playbook与冻结章节中的子弹不变。返回计数有变化的子弹数
+    pub fn decay_counters(&mut self, factor: f64) -> usize {
+        let factor = factor.clamp(0.0, 1.0);
+        // 计数非负，加0.5后截断即四舍五入（no_std下没有f64::round）
+        let decay = |count: &u32| (*count as f64 * factor + 0.5) as u32;
+        let frozen: BTreeSet<SectionName> =
+            self.sections.keys().filter(|s| self.is_section_frozen(s)).cloned().collect();
+        let mut changed = 0;
+        for bullet in self.bullets.values_mut() {
+            if frozen.contains(&bullet.section) {
+                continue;
+            }
+            let before = [bullet.helpful, bullet.harmful, bullet.neutral];
+            let after = before.each_ref().map(decay);
+            let custom_changed = bullet.custom.values().any(|count| decay(count) != *count);
+            if before != after || custom_changed {
+                // 只有计数真的变化时才复制共享的子弹
+                let bullet = Arc::make_mut(bullet);
+                [bullet.helpful, bullet.harmful, bullet.neutral] = after;
+                bullet.custom.values_mut().for_each(|count| *count = decay(count));
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.rebuild_rollups();
+        }
+        changed
+    }
+
+    /// 合并重复子弹的DeltaBatch：同章节内与较早子弹相似度不低于`min_similarity`的子弹被删除，
+    /// 计数累加到较早的那条上。固定的子弹不会被删除
+    pub fn dedup_delta(&self, min_similarity: f64) -> DeltaBatch {
+        self.dedup_delta_with_progress(min_similarity, &mut ())
+    }
+
+    /// 同`dedup_delta`，每比较完一个子弹报告一次进度
+    pub fn dedup_delta_with_progress(
+        &self,
+        min_similarity: f64,
+        sink: &mut dyn ProgressSink,
+    ) -> DeltaBatch {
+        let similar = |a: &BTreeSet<String>, b: &BTreeSet<String>| jaccard(a, b) >= min_similarity;
+        self.dedup_delta_by(|b| tokens(&b.content), similar, sink)
+    }
+
+    /// 去重的通用实现：`key`为每个子弹计算一次比较用的表示（词集合、向量……）
+    pub(crate) fn dedup_delta_by<K>(
+        &self,
+        key: impl Fn(&Bullet) -> K,
+        similar: impl Fn(&K, &K) -> bool,
+        sink: &mut dyn ProgressSink,
+    ) -> DeltaBatch {
+        let total = self.sections.values().map(Vec::len).sum();
+        let mut progress = ProgressTracker::new(sink, self.clock().clone(), "dedup", Some(total));
+        let mut operations = Vec::new();
+        for (section, bullet_ids) in self.sorted_sections() {
+            let mut kept: Vec<(&Bullet, K, [i32; 3])> = Vec::new();
+            for bullet in bullet_ids.iter().filter_map(|id| self.bullets.get(id)) {
+                progress.advance(1);
+                let words = key(bullet);
+                let original = kept
+                    .iter_mut()
+                    .filter(|_| !bullet.pinned)
+                    .find(|(_, w, _)| similar(w, &words));
+                match original {
+                    Some((_, _, merged)) => {
+                        let counts = [bullet.helpful, bullet.harmful, bullet.neutral];
+                        for (total, count) in merged.iter_mut().zip(counts) {
+                            let count = i32::try_from(count).unwrap_or(i32::MAX);
+                            *total = total.saturating_add(count);
+                        }
+                        operations.push(DeltaOperation::remove(&**section, &bullet.id));
+                    }
+                    None => kept.push((bullet, words, [0; 3])),
+                }
+            }
+            for (bullet, _, [helpful, harmful, neutral]) in kept {
+                let metadata: BTreeMap<String, i32> =
+                    [("helpful", helpful), ("harmful", harmful), ("neutral", neutral)]
+                        .into_iter()
+                        .filter(|&(_, n)| n > 0)
+                        .map(|(tag, n)| (tag.into(), n))
+                        .collect();
+                if !metadata.is_empty() {
+                    operations.push(DeltaOperation::tag(&**section, &bullet.id, metadata));
+                }
+            }
+        }
+        let mut delta = DeltaBatch {
+            reasoning: String::new(),
+            operations,
+        };
+        delta.reasoning = format!("merge {} duplicate bullets", count_removes(&delta));
+        delta
+    }
+
+    /// harmful多于helpful且差异在给定样本量下显著的子弹（固定的子弹除外）
+    ///
+    /// 对helpful占比做得分检验：H0为占比0.5，`(harmful - helpful)² > z²·(helpful + harmful)`
+    /// 时拒绝，等价于helpful占比的Wilson区间上界低于0.5。`z = 1.96`时两次harmful不算显著，
+    /// 五次harmful、零次helpful才算；neutral不参与
+    pub fn significantly_harmful(&self, z: f64) -> Vec<&Bullet> {
+        self.bullets()
+            .into_iter()
+            .filter(|b| {
+                let (helpful, harmful) = (b.helpful as f64, b.harmful as f64);
+                let diff = harmful - helpful;
+                !b.pinned && diff > 0.0 && diff * diff > z * z * (helpful + harmful)
+            })
+            .collect()
+    }
+
+    /// 删除显著有害子弹的DeltaBatch
+    pub fn prune_harmful_delta(&self, z: f64) -> DeltaBatch {
+        let operations: Vec<DeltaOperation> = self
+            .significantly_harmful(z)
+            .into_iter()
+            .map(|b| DeltaOperation::remove(&*b.section, &b.id))
+            .collect();
+        DeltaBatch {
+            reasoning: format!("prune {} significantly harmful bullets", operations.len()),
+            operations,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+mod thread {
+    use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+    use std::sync::{Arc, Mutex, PoisonError};
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+
+    use super::*;
+
+    /// 后台维护线程的句柄
+    #[derive(Debug)]
+    pub struct MaintenanceHandle {
+        stop: Sender<()>,
+        thread: JoinHandle<Maintenance>,
+    }
+
+    impl MaintenanceHandle {
+        /// 通知线程退出并等待，返回调度器（保留各任务的上次执行时间）；
+        /// 线程panic时（如`on_report`中）返回panic的内容
+        pub fn stop(self) -> std::thread::Result<Maintenance> {
+            let _ = self.stop.send(());
+            self.thread.join()
+        }
+    }
+
+    impl Maintenance {
+        /// 在后台线程中每隔`poll`执行一次 [`Maintenance::tick`]；
+        /// 有任务执行（包括出错的任务）时把结果交给`on_report`。tick期间持有Playbook的锁
+        pub fn spawn(
+            mut self,
+            playbook: Arc<Mutex<Playbook>>,
+            poll: Duration,
+            mut on_report: impl FnMut(Vec<JobReport>) + Send + 'static,
+        ) -> MaintenanceHandle {
+            let (stop, stopped) = mpsc::channel();
+            let thread = std::thread::spawn(move || {
+                loop {
+                    let reports = {
+                        let mut playbook = playbook.lock().unwrap_or_else(PoisonError::into_inner);
+                        self.tick(&mut playbook)
+                    };
+                    if !reports.is_empty() {
+                        on_report(reports);
+                    }
+                    if stopped.recv_timeout(poll) != Err(RecvTimeoutError::Timeout) {
+                        return self;
+                    }
+                }
+            });
+            MaintenanceHandle { stop, thread }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub use thread::MaintenanceHandle;
+
+#[cfg(feature = "tokio")]
+mod task {
+    use std::sync::{Arc, Mutex, PoisonError};
+    use std::time::Duration;
+
+    use tokio::sync::oneshot;
+    use tokio::task::{JoinError, JoinHandle};
+
+    use super::*;
+
+    /// tokio维护任务的句柄
+    #[derive(Debug)]
+    pub struct MaintenanceTask {
+        stop: oneshot::Sender<()>,
+        task: JoinHandle<Maintenance>,
+    }
+
+    impl MaintenanceTask {
+        /// 通知任务退出并等待，返回调度器；任务panic或被取消时返回`JoinError`
+        pub async fn stop(self) -> Result<Maintenance, JoinError> {
+            let _ = self.stop.send(());
+            self.task.await
+        }
+    }
+
+    impl Maintenance {
+        /// 同 [`Maintenance::spawn`]，在当前tokio运行时中以任务运行（需在运行时内调用）
+        ///
+        /// tick是同步的，执行期间占用一个工作线程并持有Playbook的锁，锁不会跨越`await`。
+        pub fn spawn_tokio(
+            mut self,
+            playbook: Arc<Mutex<Playbook>>,
+            poll: Duration,
+            mut on_report: impl FnMut(Vec<JobReport>) + Send + 'static,
+        ) -> MaintenanceTask {
+            let (stop, mut stopped) = oneshot::channel();
+            let task = tokio::spawn(async move {
+                loop {
+                    let reports = {
+                        let mut playbook = playbook.lock().unwrap_or_else(PoisonError::into_inner);
+                        self.tick(&mut playbook)
+                    };
+                    if !reports.is_empty() {
+                        on_report(reports);
+                    }
+                    if tokio::time::timeout(poll, &mut stopped).await.is_ok() {
+                        return self;
+                    }
+                }
+            });
+            MaintenanceTask { stop, task }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub use task::MaintenanceTask;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+
+    use crate::clock::{Clock as _, ManualClock};
+
+    #[test]
+    fn test_tick_runs_due_jobs() {
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let first = pb.add_bullet("api usage", "Pass the cursor token when paginating", None, None);
+        pb.tag_bullet(&first, "helpful", 10).unwrap();
+        let dup = pb.add_bullet("api usage", "Pass the cursor token when paginating.", None, None);
+        pb.tag_bullet(&dup, "helpful", 2).unwrap();
+        let expiring = pb.add_bullet("debugging", "Read the logs first", None, None);
+        pb.set_expires_at(&expiring, Some(clock.now() + TimeDelta::minutes(90))).unwrap();
+
+        let config: MaintenanceConfig = serde_json::from_value(serde_json::json!({
+            "jobs": [
+                {"job": "decay", "factor": 0.5, "every_secs": 7200},
+                {"job": "dedup", "min_similarity": 0.8, "every_secs": 3600},
+                {"job": "archive", "every_secs": 3600},
+                {"job": "stats", "every_secs": 3600}
+            ]
+        }))
+        .unwrap();
+        let mut maintenance = Maintenance::new(config);
+        // 第一次tick只开始计时
+        assert!(maintenance.tick(&mut pb).is_empty());
+        assert_eq!(maintenance.next_due(), Some(clock.now() + TimeDelta::hours(1)));
+
+        clock.advance(TimeDelta::hours(1));
+        let reports = maintenance.tick(&mut pb);
+        assert_eq!(reports[0], JobReport::Deduped { removed: 1 });
+        assert_eq!(reports[1], JobReport::Archived { archived: 0 });
+        assert!(matches!(&reports[2], JobReport::Snapshot(s) if s.bullets == 2 && s.helpful == 12));
+        assert!(pb.get_bullet(&dup).is_none());
+
+        clock.advance(TimeDelta::hours(1));
+        let reports = maintenance.tick(&mut pb);
+        assert_eq!(reports[0], JobReport::Decayed { bullets: 1 });
+        assert_eq!(reports[2], JobReport::Archived { archived: 1 });
+        assert!(pb.archived.contains_key(&expiring));
+        assert_eq!(pb.get_bullet(&first).unwrap().helpful, 6);
+        assert_eq!(pb.section_rollup("api usage").unwrap().helpful, 6);
+    }
+
+    #[test]
+    fn test_decay_rounds_and_skips_frozen_sections() {
+        let mut pb = Playbook::new();
+        let api = pb.add_bullet("api usage", "先分页", None, None);
+        pb.tag_bullet(&api, "helpful", 3).unwrap();
+        pb.tag_bullet(&api, "harmful", 1).unwrap();
+        let logs = pb.add_bullet("debugging", "先看日志", None, None);
+        pb.tag_bullet(&logs, "helpful", 5).unwrap();
+        pb.freeze_section("debugging");
+
+        assert_eq!(pb.decay_counters(0.5), 1);
+        let api = pb.get_bullet(&api).unwrap();
+        // 1.5与0.5四舍五入
+        assert_eq!((api.helpful, api.harmful), (2, 1));
+        assert_eq!(pb.get_bullet(&logs).unwrap().helpful, 5);
+
+        pb.set_frozen(true);
+        assert_eq!(pb.decay_counters(0.1), 0);
+        assert_eq!(pb.get_bullet(&logs).unwrap().helpful, 5);
+    }
+
+    #[test]
+    fn test_failing_job_does_not_stop_the_tick() {
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        pb.add_bullet("api usage", "Pass the cursor token when paginating", None, None);
+        let dup = pb.add_bullet("api usage", "Pass the cursor token when paginating.", None, None);
+        pb.freeze_section("api usage");
+        let job = |job, every_secs| ScheduledJob { job, every_secs };
+        let jobs = alloc::vec![
+            job(Job::Dedup { min_similarity: 0.8 }, 3600),
+            job(Job::Stats, 3600),
+        ];
+        let mut maintenance = Maintenance::new(MaintenanceConfig { jobs });
+        assert!(maintenance.tick(&mut pb).is_empty());
+
+        clock.advance(TimeDelta::hours(1));
+        let reports = maintenance.tick(&mut pb);
+        assert!(matches!(&reports[0], JobReport::Failed { code: "frozen", .. }));
+        assert!(matches!(&reports[1], JobReport::Snapshot(s) if s.bullets == 2));
+        // 出错的任务下次tick重试，成功的任务等到下一个间隔
+        pb.unfreeze_section("api usage");
+        clock.advance(TimeDelta::minutes(1));
+        assert_eq!(maintenance.tick(&mut pb), [JobReport::Deduped { removed: 1 }]);
+        assert!(pb.get_bullet(&dup).is_none());
+    }
+
+    /// 每秒一次统计快照的调度器，已在`pb`上开始计时，`pb`的时钟前进一秒后到期
+    #[cfg(feature = "std")]
+    fn stats_due(pb: &mut Playbook, clock: &ManualClock) -> Maintenance {
+        let job = ScheduledJob { job: Job::Stats, every_secs: 1 };
+        let mut maintenance = Maintenance::new(MaintenanceConfig { jobs: alloc::vec![job] });
+        assert!(maintenance.tick(pb).is_empty());
+        clock.advance(TimeDelta::seconds(1));
+        maintenance
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_spawn_stops_and_reports_panics() {
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let maintenance = stats_due(&mut pb, &clock);
+        let pb = Arc::new(Mutex::new(pb));
+        let handle = Maintenance::new(MaintenanceConfig { jobs: Vec::new() })
+            .spawn(pb.clone(), Duration::from_millis(1), |_| {});
+        assert!(handle.stop().is_ok());
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = maintenance.spawn(pb, Duration::from_millis(1), move |_| {
+            tx.send(()).unwrap();
+            panic!("report sink failed");
+        });
+        rx.recv().unwrap();
+        assert!(handle.stop().is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_spawn_tokio_runs_until_stopped() {
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        let runtime =
+            tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let maintenance = stats_due(&mut pb, &clock);
+        let pb = Arc::new(Mutex::new(pb));
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let maintenance = runtime.block_on(async move {
+            let task = maintenance.spawn_tokio(pb, Duration::from_millis(1), move |report| {
+                sink.lock().unwrap().push(report);
+            });
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            task.stop().await.unwrap()
+        });
+        assert_eq!(maintenance.next_due(), Some(DateTime::UNIX_EPOCH + TimeDelta::seconds(2)));
+        assert!(matches!(&reports.lock().unwrap()[..], [reports] if reports.len() == 1));
+    }
+
+    #[test]
+    fn test_prune_significantly_harmful() {
+        let mut pb = Playbook::new();
+        let few = pb.add_bullet("api usage", "两次harmful", None, None);
+        pb.tag_bullet(&few, "harmful", 2).unwrap();
+        let bad = pb.add_bullet("api usage", "五次harmful", None, None);
+        pb.tag_bullet(&bad, "harmful", 5).unwrap();
+        let mixed = pb.add_bullet("api usage", "有好有坏", None, None);
+        pb.tag_bullet(&mixed, "helpful", 8).unwrap();
+        pb.tag_bullet(&mixed, "harmful", 12).unwrap();
+        let pinned = pb.add_bullet("api usage", "固定的", None, None);
+        pb.tag_bullet(&pinned, "harmful", 9).unwrap();
+        pb.set_pinned(&pinned, true).unwrap();
+
+        let harmful: Vec<&str> =
+            pb.significantly_harmful(1.96).iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(harmful, [bad.as_str()]);
+        assert_eq!(pb.significantly_harmful(1.0).len(), 2);
+
+        let job = serde_json::from_str(r#"{"job": "prune_harmful", "z": 1.96}"#).unwrap();
+        let report = run_job(&job, &mut pb, DateTime::UNIX_EPOCH).unwrap();
+        assert_eq!(report, JobReport::Pruned { removed: 1 });
+        assert!(pb.get_bullet(&bad).is_none() && pb.get_bullet(&few).is_some());
+    }
+}
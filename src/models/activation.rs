@@ -0,0 +1,116 @@
+//! 按运行上下文激活子弹：子弹可带 [`Bullet::conditions`]（如`env=prod`、
+//! `customer_tier=enterprise`），渲染时与调用方提供的上下文比对，全部满足才输出，
+//! 让同一个Playbook服务多种运行环境
+//!
+//! 条件值可用`|`列出多个可选值（`env=prod|staging`）。上下文中缺少某个键时该条件不满足。
+//! 不带上下文的渲染（`as_prompt`等）不检查条件。
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use crate::models::context::{Candidate, ContextError, ContextRequest, ContextStage};
+use crate::models::playbook::{Bullet, BulletId, Playbook, PlaybookError};
+
+/// 渲染时提供的上下文（键 → 值）
+pub type ActivationContext = BTreeMap<String, String>;
+
+impl Bullet {
+    /// 在给定上下文中是否激活
+    pub fn is_active_in(&self, context: &ActivationContext) -> bool {
+        self.conditions.iter().all(|(key, expected)| {
+            context.get(key).is_some_and(|value| expected.split('|').any(|e| e.trim() == value))
+        })
+    }
+}
+
+impl Playbook {
+    /// 设置子弹的一个激活条件，值为空时删除该条件
+    pub fn set_condition(
+        &mut self,
+        bullet_id: &str,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<(), PlaybookError> {
+        let (key, value) = (key.into(), value.into());
+        self.modify_bullet(bullet_id, |bullet| {
+            if value.is_empty() {
+                bullet.conditions.remove(&key);
+            } else {
+                bullet.conditions.insert(key, value);
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// 只渲染在上下文中激活的子弹，顺序与`as_prompt`一致
+    pub fn as_prompt_in_context(&self, context: &ActivationContext) -> String {
+        let ids: Vec<BulletId> = self
+            .bullets
+            .values()
+            .filter(|b| b.is_active_in(context))
+            .map(|b| b.id.clone())
+            .collect();
+        self.as_prompt_for_bullets(&ids)
+    }
+}
+
+/// 上下文流水线中的阶段：去掉在上下文中未激活的候选
+#[derive(Debug, Clone, Default)]
+pub struct ActivationFilter {
+    pub context: ActivationContext,
+}
+
+impl ContextStage for ActivationFilter {
+    fn apply<'a>(
+        &self,
+        _request: &ContextRequest<'a>,
+        mut candidates: Vec<Candidate<'a>>,
+    ) -> Result<Vec<Candidate<'a>>, ContextError> {
+        candidates.retain(|c| c.bullet.is_active_in(&self.context));
+        Ok(candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    use crate::models::context::ContextPipeline;
+
+    fn context(pairs: &[(&str, &str)]) -> ActivationContext {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_render_in_context() {
+        let mut pb = Playbook::new();
+        let always = pb.add_bullet("ops", "变更前先看监控", None, None);
+        let prod = pb.add_bullet("ops", "生产环境变更需要审批", None, None);
+        let enterprise = pb.add_bullet("support", "企业客户走专属通道", None, None);
+        pb.set_condition(&prod, "env", "prod|staging").unwrap();
+        pb.set_condition(&enterprise, "env", "prod").unwrap();
+        pb.set_condition(&enterprise, "customer_tier", "enterprise").unwrap();
+        assert!(pb.set_condition("missing", "env", "prod").is_err());
+
+        let prompt = pb.as_prompt_in_context(&context(&[("env", "staging")]));
+        assert!(prompt.contains(&always) && prompt.contains(&prod));
+        assert!(!prompt.contains(&enterprise));
+        let ctx = context(&[("env", "prod"), ("customer_tier", "enterprise")]);
+        assert!(pb.as_prompt_in_context(&ctx).contains(&enterprise));
+        let prompt = pb.as_prompt_in_context(&ActivationContext::new());
+        assert!(prompt.contains(&always) && !prompt.contains(&prod));
+        assert!(pb.as_prompt().contains(&enterprise));
+
+        let pipeline = ContextPipeline::new()
+            .with_stage(ActivationFilter { context: context(&[("env", "dev")]) });
+        let selected = pipeline.select(&pb, "").unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].bullet.id, always);
+
+        pb.set_condition(&prod, "env", "").unwrap();
+        assert!(pb.bullets[&prod].conditions.is_empty());
+        let restored = Playbook::from_json(&pb.to_json().unwrap()).unwrap();
+        assert_eq!(restored.bullets[&enterprise].conditions, pb.bullets[&enterprise].conditions);
+    }
+}
@@ -0,0 +1,168 @@
+//! 入库前去掉策展人常写的套话和Markdown标记（"The agent should remember that…"、`**加粗**`、
+//! 行首的`#`/`>`），让子弹保持精炼、少占token
+//!
+//! [`BoilerplateStripper`] 实现了 [`ContentPolicy`]，与 [`PiiRedactor`](crate::models::pii::PiiRedactor)
+//! 一样挂到apply_delta上使用，可放进 [`PolicyChain`](crate::models::policy::PolicyChain)。
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::policy::{ContentPolicy, PolicyDecision};
+
+/// 默认去掉的开头套话（忽略ASCII大小写）
+pub const DEFAULT_PREFIXES: &[&str] = &[
+    "the agent should remember that",
+    "the agent should always",
+    "the agent should",
+    "it is important to note that",
+    "it is important to",
+    "remember that",
+    "make sure to",
+    "key insight:",
+    "lesson learned:",
+    "important:",
+    "strategy:",
+    "note:",
+    "tip:",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoilerplateStripper {
+    /// 要去掉的开头套话，按顺序反复匹配（如`Note: Remember that …`两层都去掉）
+    pub prefixes: Vec<String>,
+    /// 去掉`**`/`__`强调标记和行首的标题、引用符号
+    pub strip_markdown: bool,
+    /// 去掉套话后把首字母改为大写（仅ASCII）
+    pub capitalize: bool,
+}
+
+impl Default for BoilerplateStripper {
+    fn default() -> Self {
+        Self {
+            prefixes: DEFAULT_PREFIXES.iter().map(|p| p.to_string()).collect(),
+            strip_markdown: true,
+            capitalize: true,
+        }
+    }
+}
+
+impl BoilerplateStripper {
+    /// 返回精简后的内容；没有变化或精简后为空时返回`None`
+    pub fn strip(&self, content: &str) -> Option<String> {
+        let mut text =
+            if self.strip_markdown { strip_markdown(content) } else { content.trim().to_string() };
+
+        let mut stripped_prefix = false;
+        'outer: loop {
+            for prefix in self.prefixes.iter().filter(|p| !p.is_empty()) {
+                let matched =
+                    text.get(..prefix.len()).is_some_and(|h| h.eq_ignore_ascii_case(prefix));
+                // 只在词边界处截断，"notes"不会被当作"note"
+                let at_boundary = || {
+                    !prefix.ends_with(char::is_alphanumeric)
+                        || !text[prefix.len()..].starts_with(char::is_alphanumeric)
+                };
+                if matched && at_boundary() {
+                    text =
+                        text[prefix.len()..].trim_start_matches([' ', ',', ':', '\t']).to_string();
+                    stripped_prefix = true;
+                    continue 'outer;
+                }
+            }
+            break;
+        }
+        if stripped_prefix
+            && self.capitalize
+            && let Some(first) = text.get(..1).filter(|c| c.is_ascii())
+        {
+            text = first.to_ascii_uppercase() + &text[1..];
+        }
+
+        (!text.is_empty() && text != content).then_some(text)
+    }
+}
+
+impl ContentPolicy for BoilerplateStripper {
+    fn check(&self, _section: &str, content: &str) -> PolicyDecision {
+        self.strip(content).map_or(PolicyDecision::Allow, PolicyDecision::Redact)
+    }
+}
+
+/// 去掉强调标记与每行行首的`#`、`>`，并去掉首尾空白（代码块与行内代码不动）
+fn strip_markdown(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_code_block = false;
+    for (i, line) in content.trim().lines().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+        if in_code_block || line.trim_start().starts_with("```") {
+            out.push_str(line);
+            continue;
+        }
+        let line = line.trim_start().trim_start_matches(['#', '>']).trim_start();
+        // 反引号之间是行内代码，保留原样
+        for (j, part) in line.split('`').enumerate() {
+            if j > 0 {
+                out.push('`');
+            }
+            if j % 2 == 0 {
+                out.push_str(&part.replace("**", "").replace("__", ""));
+            } else {
+                out.push_str(part);
+            }
+        }
+    }
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_boilerplate() {
+        let stripper = BoilerplateStripper::default();
+        assert_eq!(
+            stripper.strip("The agent should remember that pagination needs a cursor.").as_deref(),
+            Some("Pagination needs a cursor.")
+        );
+        assert_eq!(
+            stripper.strip("**Note:** remember that `__init__` runs first").as_deref(),
+            Some("`__init__` runs first")
+        );
+        assert_eq!(
+            stripper.strip("## Tip: always pass **cursor**").as_deref(),
+            Some("Always pass cursor")
+        );
+        assert_eq!(stripper.strip("Notes are kept per ticket"), None);
+        assert_eq!(stripper.strip("分页时带上cursor"), None);
+        assert_eq!(stripper.strip("Note:"), None);
+        assert_eq!(
+            stripper.strip("> Use retries\n```\n**kwargs\n```").as_deref(),
+            Some("Use retries\n```\n**kwargs\n```")
+        );
+
+        let custom = BoilerplateStripper {
+            prefixes: vec!["经验：".to_string()],
+            strip_markdown: false,
+            capitalize: false,
+        };
+        assert_eq!(
+            custom.check("s", "经验：先看日志"),
+            PolicyDecision::Redact("先看日志".to_string())
+        );
+        assert_eq!(custom.check("s", "**先看日志**"), PolicyDecision::Allow);
+
+        let config: BoilerplateStripper = serde_json::from_str(r#"{"capitalize": false}"#).unwrap();
+        assert_eq!(config.prefixes.len(), DEFAULT_PREFIXES.len());
+    }
+}
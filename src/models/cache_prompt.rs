@@ -0,0 +1,131 @@
+//! 适配服务端提示词缓存的渲染：固定和长期未变的子弹组成稳定前缀，近期变化的子弹放在最后
+//!
+//! 服务端缓存按最长公共前缀命中。`as_prompt`按章节排列，任何子弹的计数变化都会改变其后
+//! 所有内容；这里前缀内的子弹按`updated_at`从旧到新排列，新变稳定的子弹追加在前缀末尾，
+//! 已有前缀保持不变。
+
+use alloc::{string::String, vec::Vec};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::models::playbook::{Bullet, Playbook, write_section_header};
+
+/// 分成稳定前缀与易变后缀的提示词
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheAwarePrompt {
+    /// 固定的子弹及超过稳定期未修改的子弹
+    pub prefix: String,
+    /// 稳定期内修改过的子弹
+    pub suffix: String,
+    /// 前缀的FNV-1a哈希；相同说明服务端缓存可以命中
+    pub prefix_hash: u64,
+}
+
+impl CacheAwarePrompt {
+    /// 完整提示词（前缀+后缀）
+    pub fn text(&self) -> String {
+        let mut text = self.prefix.clone();
+        if !text.is_empty() && !self.suffix.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&self.suffix);
+        text
+    }
+}
+
+impl Playbook {
+    /// 按稳定性渲染：固定的子弹在最前，其次是`stable_after`内未修改的子弹（旧的在前），
+    /// 最后是近期修改过的子弹
+    pub fn as_prompt_cache_aware(&self, stable_after: TimeDelta) -> CacheAwarePrompt {
+        self.as_prompt_cache_aware_at(stable_after, self.clock().now())
+    }
+
+    pub fn as_prompt_cache_aware_at(
+        &self,
+        stable_after: TimeDelta,
+        now: DateTime<Utc>,
+    ) -> CacheAwarePrompt {
+        let cutoff = now - stable_after;
+        let (mut stable, mut volatile): (Vec<&Bullet>, Vec<&Bullet>) = self
+            .sections
+            .values()
+            .flatten()
+            .filter_map(|id| self.get_bullet(id))
+            .filter(|b| !b.is_expired_at(now))
+            .partition(|b| b.pinned || b.updated_at <= cutoff);
+        stable.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| a.updated_at.cmp(&b.updated_at))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        volatile.sort_by(|a, b| a.updated_at.cmp(&b.updated_at).then_with(|| a.id.cmp(&b.id)));
+
+        let prefix = render_runs(self, &stable);
+        CacheAwarePrompt {
+            prefix_hash: fnv1a(&prefix),
+            suffix: render_runs(self, &volatile),
+            prefix,
+        }
+    }
+}
+
+/// 按给定顺序输出，章节变化时写一次章节标题
+fn render_runs(playbook: &Playbook, bullets: &[&Bullet]) -> String {
+    let mut out = String::new();
+    let mut current: Option<&str> = None;
+    for b in bullets {
+        if current != Some(&*b.section) {
+            write_section_header(&mut out, &b.section);
+            current = Some(&b.section);
+        }
+        playbook.write_bullet(&mut out, b, &b.content);
+    }
+    out
+}
+
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn test_prefix_survives_churn() {
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let rules = pb.add_bullet("rules", "Never share customer emails", None, None);
+        clock.advance(TimeDelta::hours(1));
+        let old = pb.add_bullet("api usage", "Pass the cursor token when paginating", None, None);
+        clock.advance(TimeDelta::hours(1));
+        pb.set_pinned(&rules, true).unwrap();
+        let fresh = pb.add_bullet("api usage", "Retry with exponential backoff", None, None);
+
+        let window = TimeDelta::minutes(30);
+        let before = pb.as_prompt_cache_aware(window);
+        assert!(before.prefix.find(&*rules).unwrap() < before.prefix.find(&*old).unwrap());
+        assert!(before.suffix.contains(&*fresh) && !before.prefix.contains(&*fresh));
+        assert!(before.text().starts_with(&before.prefix));
+
+        // 易变子弹的计数变化、新增子弹都不影响前缀
+        pb.tag_bullet(&fresh, "helpful", 3).unwrap();
+        pb.add_bullet("debugging", "Read the logs first", None, None);
+        let after = pb.as_prompt_cache_aware(window);
+        assert_eq!(after.prefix_hash, before.prefix_hash);
+        assert_ne!(after.suffix, before.suffix);
+
+        // 子弹变稳定后追加在前缀末尾，原前缀仍是新前缀的前缀
+        clock.advance(TimeDelta::hours(1));
+        let later = pb.as_prompt_cache_aware(window);
+        assert!(later.prefix.starts_with(&before.prefix));
+        assert_ne!(later.prefix_hash, before.prefix_hash);
+        assert!(later.suffix.is_empty());
+    }
+}
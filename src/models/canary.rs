@@ -0,0 +1,187 @@
+//! 金丝雀渲染差异：应用Delta前后各渲染一次提示词，用统一diff展示模型实际看到的上下文变化
+//!
+//! 审核者看Delta操作列表很难想象最终效果（改写、去重、容量淘汰都会影响渲染结果），
+//! 直接看渲染文本的diff更可靠。
+
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+
+use crate::models::delta::DeltaBatch;
+use crate::models::playbook::{Playbook, PlaybookError};
+use crate::models::rate_guard::DeltaImpact;
+
+/// diff中每处变化前后保留的上下文行数
+pub const DIFF_CONTEXT: usize = 3;
+
+/// 一次Delta应用的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyReport {
+    pub operations: usize,
+    pub impact: DeltaImpact,
+    /// 应用前后`as_prompt`的统一diff；未启用金丝雀或渲染结果不变时为`None`
+    pub canary_diff: Option<String>,
+}
+
+impl Playbook {
+    /// 应用Delta并返回报告；`canary`为真时附带渲染差异
+    pub fn apply_delta_with_report(
+        &mut self,
+        delta: DeltaBatch,
+        canary: bool,
+    ) -> Result<ApplyReport, PlaybookError> {
+        let now = self.clock().now();
+        self.apply_delta_with_report_at(delta, canary, now)
+    }
+
+    pub fn apply_delta_with_report_at(
+        &mut self,
+        delta: DeltaBatch,
+        canary: bool,
+        now: DateTime<Utc>,
+    ) -> Result<ApplyReport, PlaybookError> {
+        let operations = delta.operations.len();
+        let impact = self.delta_impact(&delta);
+        let before = canary.then(|| self.as_prompt());
+        self.apply_delta_at(delta, now)?;
+        let canary_diff = before
+            .map(|before| unified_diff(&before, &self.as_prompt(), DIFF_CONTEXT))
+            .filter(|diff| !diff.is_empty());
+        Ok(ApplyReport {
+            operations,
+            impact,
+            canary_diff,
+        })
+    }
+
+    /// 在副本上应用Delta，返回渲染差异（不修改Playbook），供审核时预览
+    pub fn canary_diff(&self, delta: &DeltaBatch) -> Result<String, PlaybookError> {
+        let mut preview = self.clone();
+        preview.apply_delta(delta.clone())?;
+        Ok(unified_diff(&self.as_prompt(), &preview.as_prompt(), DIFF_CONTEXT))
+    }
+}
+
+/// 按行比较的统一diff（`---`/`+++`文件头加`@@`分块），没有差异时返回空串
+pub fn unified_diff(before: &str, after: &str, context: usize) -> String {
+    let edits = diff_lines(before, after);
+    let changed: Vec<usize> = (0..edits.len()).filter(|&i| edits[i].0 != ' ').collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // 每个编辑之前的旧/新行号
+    let mut positions = Vec::with_capacity(edits.len());
+    let (mut old, mut new) = (0, 0);
+    for (tag, _) in &edits {
+        positions.push((old, new));
+        old += usize::from(*tag != '+');
+        new += usize::from(*tag != '-');
+    }
+
+    // 间隔不超过两倍上下文的变化合并到同一块
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        match hunks.last_mut() {
+            Some((_, end)) if i - *end <= 2 * context => *end = i,
+            _ => hunks.push((i, i)),
+        }
+    }
+
+    let mut out = String::from("--- before\n+++ after\n");
+    for (start, end) in hunks {
+        let from = start.saturating_sub(context);
+        let hunk = &edits[from..(end + context + 1).min(edits.len())];
+        let old_len = hunk.iter().filter(|(tag, _)| *tag != '+').count();
+        let new_len = hunk.iter().filter(|(tag, _)| *tag != '-').count();
+        let (old_pos, new_pos) = positions[from];
+        let line = |pos: usize, len: usize| if len == 0 { pos } else { pos + 1 };
+        let _ = writeln!(
+            out,
+            "@@ -{},{} +{},{} @@",
+            line(old_pos, old_len),
+            old_len,
+            line(new_pos, new_len),
+            new_len
+        );
+        for (tag, text) in hunk {
+            let _ = writeln!(out, "{}{}", tag, text);
+        }
+    }
+    out
+}
+
+/// 基于最长公共子序列的行级编辑序列：`' '`保留、`'-'`删除、`'+'`插入
+fn diff_lines<'a>(before: &'a str, after: &'a str) -> Vec<(char, &'a str)> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+    let (n, m) = (a.len(), b.len());
+    // lcs[i][j]：a[i..]与b[j..]的最长公共子序列长度
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            edits.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            edits.push(('-', a[i]));
+            i += 1;
+        } else {
+            edits.push(('+', b[j]));
+            j += 1;
+        }
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    use crate::models::delta::DeltaOperation;
+
+    #[test]
+    fn test_unified_diff_hunks() {
+        let before = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj";
+        let after = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk";
+        assert_eq!(
+            unified_diff(before, after, 1),
+            "--- before\n+++ after\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -10,1 +10,2 @@\n j\n+k\n"
+        );
+        assert!(unified_diff(before, before, 3).is_empty());
+    }
+
+    #[test]
+    fn test_apply_with_canary_diff() {
+        let mut pb = Playbook::new();
+        let id = pb.add_bullet("rules", "Never share customer emails", None, None);
+        let delta = DeltaBatch {
+            reasoning: "refine".to_string(),
+            operations: vec![
+                DeltaOperation::add("rules", "Mask phone numbers in replies"),
+                DeltaOperation::remove("rules", &id),
+            ],
+        };
+        let preview = pb.canary_diff(&delta).unwrap();
+        let report = pb.apply_delta_with_report(delta, true).unwrap();
+        assert_eq!(report.canary_diff.as_deref(), Some(preview.as_str()));
+        assert_eq!((report.impact.added, report.impact.removed), (1, 1));
+        let diff = report.canary_diff.unwrap();
+        assert!(diff.contains(&format!("\n-- [{}] Never share customer emails", id)));
+        assert!(diff.contains("Mask phone numbers in replies"));
+    }
+}
@@ -0,0 +1,309 @@
+//! 按请求组装提示词的流水线：检索 → 过滤 → 去重 → 排序 → 按预算截断 → 渲染
+//!
+//! 每一步都是 [`ContextStage`] 对象，渲染由 [`ContextRenderer`] 完成；需要定制时替换或插入
+//! 阶段即可，不必改写`as_prompt`。
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeSet,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Debug;
+
+use thiserror::Error;
+
+use crate::models::playbook::{Bullet, Playbook, write_section_header};
+use crate::reflection::{jaccard, tokens};
+
+#[derive(Debug, Error)]
+pub enum ContextError {
+    #[error("上下文阶段{stage}失败：{message}")]
+    Stage { stage: String, message: String },
+}
+
+/// 一次组装请求
+#[derive(Debug, Clone, Copy)]
+pub struct ContextRequest<'a> {
+    pub playbook: &'a Playbook,
+    /// 任务描述，供检索等阶段使用
+    pub query: &'a str,
+}
+
+/// 流水线中的候选子弹；初始分数为Playbook评分（[`Playbook::score`]），检索等阶段可以改写
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate<'a> {
+    pub bullet: &'a Bullet,
+    pub score: f64,
+}
+
+pub trait ContextStage: Debug + Send + Sync {
+    fn apply<'a>(
+        &self,
+        request: &ContextRequest<'a>,
+        candidates: Vec<Candidate<'a>>,
+    ) -> Result<Vec<Candidate<'a>>, ContextError>;
+}
+
+pub trait ContextRenderer: Debug + Send + Sync {
+    fn render(&self, request: &ContextRequest<'_>, candidates: &[Candidate<'_>]) -> String;
+}
+
+#[derive(Debug)]
+pub struct ContextPipeline<'s> {
+    stages: Vec<Box<dyn ContextStage + 's>>,
+    renderer: Box<dyn ContextRenderer + 's>,
+}
+
+impl Default for ContextPipeline<'_> {
+    fn default() -> Self {
+        Self {
+            stages: Vec::new(),
+            renderer: Box::new(SectionRenderer),
+        }
+    }
+}
+
+impl<'s> ContextPipeline<'s> {
+    /// 没有任何阶段、用 [`SectionRenderer`] 渲染的流水线，输出与`as_prompt`相同
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加一个阶段（按追加顺序执行）
+    pub fn with_stage(mut self, stage: impl ContextStage + 's) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    pub fn with_renderer(mut self, renderer: impl ContextRenderer + 's) -> Self {
+        self.renderer = Box::new(renderer);
+        self
+    }
+
+    /// 依次执行各阶段，返回最终入选的子弹
+    pub fn select<'a>(
+        &self,
+        playbook: &'a Playbook,
+        query: &'a str,
+    ) -> Result<Vec<Candidate<'a>>, ContextError> {
+        let request = ContextRequest { playbook, query };
+        let now = playbook.clock().now();
+        let mut candidates: Vec<Candidate<'a>> = playbook
+            .sorted_sections()
+            .into_iter()
+            .flat_map(|(_, ids)| ids)
+            .filter_map(|id| playbook.bullets.get(id))
+            .filter(|b| !b.is_expired_at(now))
+            .map(|bullet| Candidate {
+                bullet,
+                score: playbook.score(bullet),
+            })
+            .collect();
+        for stage in &self.stages {
+            candidates = stage.apply(&request, candidates)?;
+        }
+        Ok(candidates)
+    }
+
+    pub fn run(&self, playbook: &Playbook, query: &str) -> Result<String, ContextError> {
+        let candidates = self.select(playbook, query)?;
+        Ok(self.renderer.render(&ContextRequest { playbook, query }, &candidates))
+    }
+}
+
+// --------------------------
+// 内置阶段
+// --------------------------
+
+/// 只保留指定章节
+#[derive(Debug, Clone, Default)]
+pub struct SectionFilter {
+    pub sections: BTreeSet<String>,
+}
+
+impl SectionFilter {
+    pub fn new<I: IntoIterator<Item = S>, S: Into<String>>(sections: I) -> Self {
+        Self {
+            sections: sections.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl ContextStage for SectionFilter {
+    fn apply<'a>(
+        &self,
+        _request: &ContextRequest<'a>,
+        mut candidates: Vec<Candidate<'a>>,
+    ) -> Result<Vec<Candidate<'a>>, ContextError> {
+        candidates.retain(|c| self.sections.contains(&*c.bullet.section));
+        Ok(candidates)
+    }
+}
+
+/// 丢弃分数低于阈值的候选
+#[derive(Debug, Clone, Copy)]
+pub struct MinScore(pub f64);
+
+impl ContextStage for MinScore {
+    fn apply<'a>(
+        &self,
+        _request: &ContextRequest<'a>,
+        mut candidates: Vec<Candidate<'a>>,
+    ) -> Result<Vec<Candidate<'a>>, ContextError> {
+        candidates.retain(|c| c.score >= self.0);
+        Ok(candidates)
+    }
+}
+
+/// 与前面已保留的候选词重合度（Jaccard）达到阈值的候选被丢弃，保留先出现的
+#[derive(Debug, Clone, Copy)]
+pub struct Dedupe {
+    pub max_similarity: f64,
+}
+
+impl Default for Dedupe {
+    fn default() -> Self {
+        Self {
+            max_similarity: 0.8,
+        }
+    }
+}
+
+impl ContextStage for Dedupe {
+    fn apply<'a>(
+        &self,
+        _request: &ContextRequest<'a>,
+        candidates: Vec<Candidate<'a>>,
+    ) -> Result<Vec<Candidate<'a>>, ContextError> {
+        let mut kept: Vec<(Candidate<'a>, BTreeSet<String>)> =
+            Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            let words = tokens(&candidate.bullet.content);
+            if kept.iter().all(|(_, seen)| jaccard(&words, seen) < self.max_similarity) {
+                kept.push((candidate, words));
+            }
+        }
+        Ok(kept.into_iter().map(|(c, _)| c).collect())
+    }
+}
+
+/// 按分数从高到低排序（稳定排序，同分保持原顺序）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrderByScore;
+
+impl ContextStage for OrderByScore {
+    fn apply<'a>(
+        &self,
+        _request: &ContextRequest<'a>,
+        mut candidates: Vec<Candidate<'a>>,
+    ) -> Result<Vec<Candidate<'a>>, ContextError> {
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(candidates)
+    }
+}
+
+/// 按当前顺序累加每个子弹渲染行的估算token数，超出预算后截断
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBudget {
+    pub max_tokens: usize,
+}
+
+impl ContextStage for TokenBudget {
+    fn apply<'a>(
+        &self,
+        request: &ContextRequest<'a>,
+        mut candidates: Vec<Candidate<'a>>,
+    ) -> Result<Vec<Candidate<'a>>, ContextError> {
+        let mut used = 0;
+        let mut line = String::new();
+        let fits = candidates
+            .iter()
+            .take_while(|c| {
+                line.clear();
+                let b = c.bullet;
+                request.playbook.write_bullet(&mut line, b, &b.content);
+                used += estimate_tokens(&line);
+                used <= self.max_tokens
+            })
+            .count();
+        candidates.truncate(fits);
+        Ok(candidates)
+    }
+}
+
+/// 粗略的token估算：约4个字节一个token
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+// --------------------------
+// 渲染
+// --------------------------
+
+/// 与`as_prompt`相同的格式：章节按名称排序，章节内按候选顺序
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SectionRenderer;
+
+impl ContextRenderer for SectionRenderer {
+    fn render(&self, request: &ContextRequest<'_>, candidates: &[Candidate<'_>]) -> String {
+        let mut out = String::new();
+        for (section, _) in request.playbook.sorted_sections() {
+            let mut in_section =
+                candidates.iter().filter(|c| c.bullet.section == *section).peekable();
+            if in_section.peek().is_none() {
+                continue;
+            }
+            write_section_header(&mut out, section);
+            for Candidate { bullet: b, .. } in in_section {
+                request.playbook.write_bullet(&mut out, b, &b.content);
+            }
+        }
+        out
+    }
+}
+
+/// 按候选顺序逐行输出，不分章节（排序结果直接体现在提示词中）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlatRenderer;
+
+impl ContextRenderer for FlatRenderer {
+    fn render(&self, request: &ContextRequest<'_>, candidates: &[Candidate<'_>]) -> String {
+        let mut out = String::new();
+        for Candidate { bullet: b, .. } in candidates {
+            request.playbook.write_bullet(&mut out, b, &b.content);
+        }
+        out.trim_start().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_stages() {
+        let mut pb = Playbook::new();
+        pb.add_bullet("api usage", "Pass the cursor token when paginating", None, None);
+        let dup = pb.add_bullet("api usage", "pass the cursor token when paginating!", None, None);
+        let best = pb.add_bullet("api usage", "Retry with exponential backoff", None, None);
+        pb.add_bullet("debugging", "Read the logs first", None, None);
+        pb.tag_bullet(&best, "helpful", 5).unwrap();
+
+        assert_eq!(ContextPipeline::new().run(&pb, "").unwrap(), pb.as_prompt());
+
+        let pipeline = ContextPipeline::new()
+            .with_stage(SectionFilter::new(["api usage"]))
+            .with_stage(Dedupe::default())
+            .with_stage(OrderByScore)
+            .with_stage(TokenBudget { max_tokens: 50 })
+            .with_renderer(FlatRenderer);
+        let selected = pipeline.select(&pb, "").unwrap();
+        let ids: Vec<&str> = selected.iter().map(|c| c.bullet.id.as_str()).collect();
+        assert_eq!(ids, [best.as_str(), "api-00001"]);
+        assert!(!ids.contains(&dup.as_str()));
+
+        let prompt = pipeline.run(&pb, "").unwrap();
+        assert!(prompt.starts_with(&format!("- [{}]", best)));
+    }
+}
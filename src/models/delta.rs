@@ -1,12 +1,17 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::models::skill::Skill;
+use crate::models::tag::Tag;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum DeltaError {
     #[error("JSON解析错误：{0}")]
     JsonParseError(#[from] serde_json::Error),
-    #[error("无效的操作类型：{0}（仅支持ADD/UPDATE/TAG/REMOVE）")]
+    #[error("无效的操作类型：{0}（仅支持add/update/tag/remove/move/merge/add_example/remove_example）")]
     InvalidOperationType(String),
     #[error("字段缺失：{0}（必填字段）")]
     MissingRequiredField(String),
@@ -14,27 +19,94 @@ pub enum DeltaError {
     IntegerOverflow(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "UPPERCASE")]
+/// 序列化为小写（`add`、`add_example`……）；反序列化不区分大小写，Python版的大写同样接受
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
 pub enum OperationType {
     Add,
     Update,
     Tag,
     Remove,
+    Move,
+    Merge,
+    AddExample,
+    RemoveExample,
 }
 
-impl std::fmt::Display for OperationType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl OperationType {
+    pub const ALL: [OperationType; 8] = [
+        OperationType::Add,
+        OperationType::Update,
+        OperationType::Tag,
+        OperationType::Remove,
+        OperationType::Move,
+        OperationType::Merge,
+        OperationType::AddExample,
+        OperationType::RemoveExample,
+    ];
+
+    pub fn as_str(self) -> &'static str {
         match self {
-            OperationType::Add => write!(f, "ADD"),
-            OperationType::Update => write!(f, "UPDATE"),
-            OperationType::Tag => write!(f, "TAG"),
-            OperationType::Remove => write!(f, "REMOVE"),
+            OperationType::Add => "add",
+            OperationType::Update => "update",
+            OperationType::Tag => "tag",
+            OperationType::Remove => "remove",
+            OperationType::Move => "move",
+            OperationType::Merge => "merge",
+            OperationType::AddExample => "add_example",
+            OperationType::RemoveExample => "remove_example",
         }
     }
 }
 
+impl core::fmt::Display for OperationType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OperationType {
+    type Err = DeltaError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|op| op.as_str().eq_ignore_ascii_case(name))
+            .ok_or_else(|| DeltaError::InvalidOperationType(name.into()))
+    }
+}
+
+impl<'de> Deserialize<'de> for OperationType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 计数增量只保留可解析的标签（见 [`Tag::parse`]），其他键（模型臆造的标签）直接丢弃
+fn tag_counts<'de, D>(deserializer: D) -> Result<BTreeMap<String, i32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut counts = BTreeMap::<String, i32>::deserialize(deserializer)?;
+    counts.retain(|k, _| Tag::parse(k).is_some());
+    Ok(counts)
+}
+
+/// MERGE未给出`content`时合并后的内容
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// 保留`bullet_id`的内容
+    #[default]
+    Keep,
+    /// 按顺序拼接各子弹的内容（以空格分隔，跳过重复的内容）
+    Concatenate,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct DeltaOperation {
     #[serde(rename = "type")]
@@ -46,23 +118,156 @@ pub struct DeltaOperation {
     
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bullet_id: Option<String>,
+
+    /// MERGE时并入`bullet_id`并被删除的子弹
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merged_ids: Vec<String>,
+
+    /// MERGE时合并内容的方式（与`content`互斥，都不设置时保留`bullet_id`的内容）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_strategy: Option<MergeStrategy>,
     
-    #[serde(default = "HashMap::new")]
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
-    pub metadata: HashMap<String, i32>,
+    /// 标签计数（ADD/UPDATE为初始值，TAG为增量），反序列化时丢弃未知标签
+    #[serde(default, deserialize_with = "tag_counts")]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, i32>,
+
+    /// 示例ID（ADD_EXAMPLE可选，REMOVE_EXAMPLE必填）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example_id: Option<String>,
+
+    /// ADD_EXAMPLE的示例输入（`bullet_id`为关联的子弹，可选）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+
+    /// ADD/UPDATE时附带的工具使用信息，子弹成为技能（见 [`crate::models::skill`]）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill: Option<Skill>,
 }
 
 impl DeltaOperation {
-    pub fn from_json(payload: &serde_json::Value) -> Result<Self, DeltaError> {
-        let mut op: Self = serde_json::from_value(payload.clone())?;
+    /// 构造ADD操作（ID由Playbook生成）
+    pub fn add(section: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            type_: OperationType::Add,
+            section: section.into(),
+            content: Some(content.into()),
+            bullet_id: None,
+            merged_ids: Vec::new(),
+            merge_strategy: None,
+            metadata: BTreeMap::new(),
+            example_id: None,
+            input: None,
+            output: None,
+            skill: None,
+        }
+    }
 
-        // 验证TAG操作的metadata
-        if op.type_ == OperationType::Tag {
-            let valid_tags = ["helpful", "harmful", "neutral"];
-            op.metadata.retain(|k, _| valid_tags.contains(&k.as_str()));
+    /// 构造TAG操作
+    pub fn tag(
+        section: impl Into<String>,
+        bullet_id: impl Into<String>,
+        metadata: BTreeMap<String, i32>,
+    ) -> Self {
+        Self {
+            type_: OperationType::Tag,
+            section: section.into(),
+            content: None,
+            bullet_id: Some(bullet_id.into()),
+            merged_ids: Vec::new(),
+            merge_strategy: None,
+            metadata,
+            example_id: None,
+            input: None,
+            output: None,
+            skill: None,
         }
+    }
 
-        Ok(op)
+    /// 构造REMOVE操作
+    pub fn remove(section: impl Into<String>, bullet_id: impl Into<String>) -> Self {
+        Self {
+            type_: OperationType::Remove,
+            section: section.into(),
+            content: None,
+            bullet_id: Some(bullet_id.into()),
+            merged_ids: Vec::new(),
+            merge_strategy: None,
+            metadata: BTreeMap::new(),
+            example_id: None,
+            input: None,
+            output: None,
+            skill: None,
+        }
+    }
+
+    /// 构造MOVE操作：把子弹移到`section`章节，保留ID、计数与时间戳
+    pub fn move_to(section: impl Into<String>, bullet_id: impl Into<String>) -> Self {
+        Self {
+            type_: OperationType::Move,
+            ..Self::remove(section, bullet_id)
+        }
+    }
+
+    /// 构造MERGE操作：`merged_ids`并入`bullet_id`后删除；设置`content`时替换合并后的内容，
+    /// 设置`merge_strategy`时按策略合并
+    pub fn merge(
+        section: impl Into<String>,
+        bullet_id: impl Into<String>,
+        merged_ids: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            type_: OperationType::Merge,
+            merged_ids: merged_ids.into_iter().map(Into::into).collect(),
+            ..Self::remove(section, bullet_id)
+        }
+    }
+
+    /// 构造ADD_EXAMPLE操作（`bullet_id`为空时示例属于整个章节）
+    pub fn add_example(
+        section: impl Into<String>,
+        bullet_id: Option<String>,
+        input: impl Into<String>,
+        output: impl Into<String>,
+    ) -> Self {
+        Self {
+            type_: OperationType::AddExample,
+            section: section.into(),
+            content: None,
+            bullet_id,
+            merged_ids: Vec::new(),
+            merge_strategy: None,
+            metadata: BTreeMap::new(),
+            example_id: None,
+            input: Some(input.into()),
+            output: Some(output.into()),
+            skill: None,
+        }
+    }
+
+    /// 构造REMOVE_EXAMPLE操作
+    pub fn remove_example(section: impl Into<String>, example_id: impl Into<String>) -> Self {
+        Self {
+            type_: OperationType::RemoveExample,
+            section: section.into(),
+            content: None,
+            bullet_id: None,
+            merged_ids: Vec::new(),
+            merge_strategy: None,
+            metadata: BTreeMap::new(),
+            example_id: Some(example_id.into()),
+            input: None,
+            output: None,
+            skill: None,
+        }
+    }
+
+    /// 与`serde_json::from_value`等价，直接从&Value反序列化，避免整棵JSON树的clone
+    pub fn from_json(payload: &serde_json::Value) -> Result<Self, DeltaError> {
+        Ok(Self::deserialize(payload)?)
     }
 
     pub fn to_json(&self) -> Result<serde_json::Value, DeltaError> {
@@ -71,6 +276,7 @@ impl DeltaOperation {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct DeltaBatch {
     #[serde(default)]
@@ -82,8 +288,7 @@ pub struct DeltaBatch {
 
 impl DeltaBatch {
     pub fn from_json(payload: &serde_json::Value) -> Result<Self, DeltaError> {
-        let batch: Self = serde_json::from_value(payload.clone())?;
-        Ok(batch)
+        Ok(Self::deserialize(payload)?)
     }
 
     pub fn to_json(&self) -> Result<serde_json::Value, DeltaError> {
@@ -91,9 +296,21 @@ impl DeltaBatch {
     }
 }
 
+/// 对单个DeltaBatch的规模限制，防止失控的curator一次性写入大量子弹（`None`表示不限制）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaLimits {
+    /// 每批最多的操作数
+    pub max_operations: Option<usize>,
+    /// ADD/UPDATE内容的最大字符数
+    pub max_content_chars: Option<usize>,
+    /// 每批每个章节最多新增的子弹数
+    pub max_new_per_section: Option<usize>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::{string::ToString, vec};
     use serde_json::json;
 
     #[test]
@@ -157,7 +374,7 @@ mod tests {
             "bullet_id": "123",
             "metadata": {
                 "helpful": 1,
-                "invalid_tag": 5,  // 被过滤掉
+                "Invalid_Tag": 5,  // 被过滤掉
                 "neutral": 2
             }
         });
@@ -166,7 +383,7 @@ mod tests {
         assert_eq!(delta.metadata.len(), 2); // 只保留helpful和neutral
         assert!(delta.metadata.contains_key("helpful"));
         assert!(delta.metadata.contains_key("neutral"));
-        assert!(!delta.metadata.contains_key("invalid_tag"));
+        assert!(!delta.metadata.contains_key("Invalid_Tag"));
     }
 
     #[test]
@@ -180,4 +397,30 @@ mod tests {
         let result = DeltaOperation::from_json(&json);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_serde_round_trip_matches_json_helpers() {
+        let text = r#"{"reasoning":"r","operations":[
+            {"type":"add","section":"api","content":"先分页","metadata":{"helpful":1,"Bogus":2}},
+            {"type":"Add_Example","section":"api","input":"q","output":"a"}]}"#;
+        let batch: DeltaBatch = serde_json::from_str(text).unwrap();
+        assert_eq!(batch.operations[0].type_, OperationType::Add);
+        assert_eq!(batch.operations[0].metadata.keys().collect::<Vec<_>>(), ["helpful"]);
+        assert_eq!(batch.operations[1].type_, OperationType::AddExample);
+
+        let json = batch.to_json().unwrap();
+        let text = serde_json::to_string(&batch).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&text).unwrap(), json);
+        assert_eq!(json["operations"][0]["type"], "add");
+        assert_eq!(json["operations"][1]["type"], "add_example");
+        assert!(json["operations"][0].get("type_").is_none());
+        let parsed = DeltaBatch::from_json(&json).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+
+        let err = DeltaOperation::from_json(&json!({"type": "RENAME", "section": "api"}));
+        assert!(err.unwrap_err().to_string().contains("RENAME"));
+        let parsed: OperationType = "REMOVE_EXAMPLE".parse().unwrap();
+        assert_eq!(parsed, OperationType::RemoveExample);
+        assert!(matches!("rename".parse::<OperationType>(), Err(DeltaError::InvalidOperationType(_))));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,246 @@
+//! 两个Playbook之间的差异表示为DeltaBatch：对旧版本应用 [`Playbook::diff`] 的结果即得到新版本，
+//! 多台机器之间同步时只需传输差异，而不是完整的JSON
+//!
+//! 只表达Delta操作能描述的状态：子弹的章节、内容、计数与技能，以及示例。语言版本、激活条件、
+//! 固定、过期时间与时间戳不参与比较；新增的子弹追加在章节末尾，已有子弹在章节内的顺序不变。
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec::Vec,
+};
+
+use crate::models::delta::{DeltaBatch, DeltaOperation, OperationType};
+use crate::models::example::Example;
+use crate::models::playbook::{Bullet, Playbook};
+use crate::models::tag::Tag;
+
+fn count_value(count: u32) -> i32 {
+    i32::try_from(count).unwrap_or(i32::MAX)
+}
+
+/// 以指定ID重建`bullet`的ADD操作（只带非零计数）
+fn add(bullet: &Bullet) -> DeltaOperation {
+    let mut op = DeltaOperation::add(&*bullet.section, bullet.content.clone());
+    op.bullet_id = Some(bullet.id.clone());
+    op.metadata = bullet
+        .tags()
+        .filter_map(|tag| {
+            let count = bullet.count(&tag);
+            (count > 0).then(|| (tag.as_str().into(), count_value(count)))
+        })
+        .collect();
+    op.skill = bullet.skill.clone();
+    op
+}
+
+fn add_example(example: &Example) -> DeltaOperation {
+    let mut op = DeltaOperation::add_example(
+        &*example.section,
+        example.bullet_id.clone(),
+        example.input.clone(),
+        example.output.clone(),
+    );
+    op.example_id = Some(example.id.clone());
+    op
+}
+
+/// 把`old`变为`new`的操作：内容或技能变化时为UPDATE（带变化的计数），只有计数变化时为TAG
+fn change(old: &Bullet, new: &Bullet) -> Option<DeltaOperation> {
+    let tags: BTreeSet<Tag> = old.tags().chain(new.tags()).collect();
+    let counts = tags.into_iter().filter(|tag| old.count(tag) != new.count(tag));
+    if old.content != new.content || old.skill != new.skill {
+        let mut update = DeltaOperation::add(&*new.section, new.content.clone());
+        update.type_ = OperationType::Update;
+        update.bullet_id = Some(new.id.clone());
+        update.metadata =
+            counts.map(|tag| (tag.as_str().into(), count_value(new.count(&tag)))).collect();
+        update.skill = new.skill.clone();
+        return Some(update);
+    }
+    let increments: BTreeMap<String, i32> = counts
+        .map(|tag| {
+            let diff = new.count(&tag) as i64 - old.count(&tag) as i64;
+            (tag.as_str().into(), diff.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+        })
+        .collect();
+    (!increments.is_empty()).then(|| DeltaOperation::tag(&*new.section, &new.id, increments))
+}
+
+impl Playbook {
+    /// 把`self`变为`other`的最小Delta：对`self`应用后，子弹与示例与`other`一致（见模块说明）
+    ///
+    /// 操作顺序为REMOVE、REMOVE_EXAMPLE，再按`other`的章节顺序逐个子弹ADD/MOVE/UPDATE/TAG，
+    /// 最后ADD_EXAMPLE。UPDATE不能清除技能，技能被去掉的子弹以REMOVE加ADD重建。
+    pub fn diff(&self, other: &Playbook) -> DeltaBatch {
+        let mut operations = Vec::new();
+        // 被删除（包括重建）的子弹，关联的示例随之删除
+        let mut removed: BTreeSet<&str> = BTreeSet::new();
+        let (mut added, mut deleted, mut moved, mut changed) = (0, 0, 0, 0);
+        for old in self.bullets() {
+            let rebuilt = other
+                .bullets
+                .get(&old.id)
+                .is_none_or(|new| old.skill.is_some() && new.skill.is_none());
+            if rebuilt {
+                operations.push(DeltaOperation::remove(&*old.section, &old.id));
+                removed.insert(&old.id);
+                deleted += usize::from(!other.bullets.contains_key(&old.id));
+            }
+        }
+        for old in self.examples.values() {
+            let dropped = old.bullet_id.as_deref().is_some_and(|id| removed.contains(id));
+            if !dropped && !other.examples.contains_key(&old.id) {
+                operations.push(DeltaOperation::remove_example(&*old.section, &old.id));
+            }
+        }
+
+        let ordered = other.sorted_sections().into_iter().flat_map(|(_, ids)| ids);
+        for new in ordered.filter_map(|id| other.bullets.get(id)) {
+            let Some(old) = self.bullets.get(&new.id).filter(|old| !removed.contains(&*old.id))
+            else {
+                operations.push(add(new));
+                if self.bullets.contains_key(&new.id) {
+                    changed += 1;
+                } else {
+                    added += 1;
+                }
+                continue;
+            };
+            if old.section != new.section {
+                operations.push(DeltaOperation::move_to(&*new.section, &new.id));
+                moved += 1;
+            }
+            if let Some(op) = change(old, new) {
+                operations.push(op);
+                changed += 1;
+            }
+        }
+
+        for new in other.examples.values() {
+            let kept = self.examples.get(&new.id).is_some_and(|old| {
+                // 关联子弹的示例随子弹移动，章节不必单独比较
+                (old.bullet_id.is_some() || old.section == new.section)
+                    && old.bullet_id == new.bullet_id
+                    && old.input == new.input
+                    && old.output == new.output
+                    && old.bullet_id.as_deref().is_none_or(|id| !removed.contains(id))
+            });
+            if !kept {
+                operations.push(add_example(new));
+            }
+        }
+
+        DeltaBatch {
+            reasoning: format!(
+                "diff: {} added, {} removed, {} moved, {} changed",
+                added, deleted, moved, changed
+            ),
+            operations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    use crate::models::skill::Skill;
+
+    type BulletState = (String, String, String, [u32; 3], Option<Skill>);
+
+    /// 比较可由Delta表达的状态
+    fn state(pb: &Playbook) -> Vec<BulletState> {
+        pb.bullets()
+            .into_iter()
+            .map(|b| {
+                let counts = Tag::ALL.map(|tag| b.count(&tag));
+                (b.id.clone(), b.section.to_string(), b.content.clone(), counts, b.skill.clone())
+            })
+            .collect()
+    }
+
+    fn examples(pb: &Playbook) -> Vec<(String, String, Option<String>, String)> {
+        pb.examples
+            .values()
+            .map(|e| (e.id.clone(), e.section.to_string(), e.bullet_id.clone(), e.input.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_transforms_one_playbook_into_the_other() {
+        let mut old = Playbook::new();
+        let a = old.add_bullet("api", "先分页", None, None);
+        let b = old.add_bullet("api", "重试要退避", None, None);
+        let c = old.add_bullet("db", "索引要覆盖查询", None, None);
+        let d = old.add_bullet("db", "用连接池", None, None);
+        old.tag_bullet(&a, "helpful", 2).unwrap();
+        let skill = Skill { tool: "psql".to_string(), ..Skill::default() };
+        old.modify_bullet(&d, |bullet| {
+            bullet.skill = Some(skill);
+            Ok(())
+        })
+        .unwrap();
+        let ex_b = old.add_example("api", "q", "a", Some(b.clone())).unwrap();
+        old.add_example("db", "q2", "a2", Some(c.clone())).unwrap();
+        let ex_d = old.add_example("db", "q3", "a3", Some(d.clone())).unwrap();
+
+        assert!(old.diff(&old).operations.is_empty());
+
+        let mut new = old.clone();
+        new.tag_bullet(&a, "helpful", 3).unwrap();
+        new.update_bullet(&b, Some("重试要指数退避".into()), None).unwrap();
+        new.move_bullet(&b, "retry").unwrap();
+        new.remove_bullet(&c);
+        new.modify_bullet(&d, |bullet| {
+            bullet.skill = None;
+            Ok(())
+        })
+        .unwrap();
+        let e = new.add_bullet("db", "慢查询先看执行计划", None, Some([("harmful".into(), 1)].into()));
+        new.add_example("db", "q4", "a4", Some(e.clone())).unwrap();
+        new.remove_example(&ex_b);
+
+        let delta = old.diff(&new);
+        let types: Vec<OperationType> = delta.operations.iter().map(|op| op.type_).collect();
+        assert_eq!(
+            types,
+            [
+                OperationType::Remove,
+                OperationType::Remove,
+                OperationType::RemoveExample,
+                OperationType::Tag,
+                OperationType::Add,
+                OperationType::Add,
+                OperationType::Move,
+                OperationType::Update,
+                OperationType::AddExample,
+                OperationType::AddExample,
+            ]
+        );
+        assert_eq!(delta.reasoning, "diff: 1 added, 1 removed, 1 moved, 3 changed");
+
+        let mut synced = old.clone();
+        synced.apply_delta(delta).unwrap();
+        assert_eq!(state(&synced), state(&new));
+        assert_eq!(examples(&synced), examples(&new));
+        assert!(synced.examples.contains_key(&ex_d));
+        assert!(synced.diff(&new).operations.is_empty());
+    }
+
+    #[test]
+    fn test_replica_generates_ids_after_synced_ones() {
+        let mut primary = Playbook::new();
+        primary.add_bullet("api", "先分页", None, None);
+        let synced_id = primary.add_bullet("api", "重试要退避", None, None);
+
+        let mut replica = Playbook::new();
+        replica.apply_delta(replica.diff(&primary)).unwrap();
+        let id = replica.add_bullet("api", "副本新增", None, None);
+        assert_ne!(id, synced_id);
+        assert_eq!(replica.bullets().len(), 3);
+        assert_eq!(replica.get_bullet(&synced_id).unwrap().content, "重试要退避");
+    }
+}
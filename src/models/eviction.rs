@@ -0,0 +1,215 @@
+//! 容量限制与淘汰：Playbook超出容量时按 [`EvictionPolicy`] 选出要删除的子弹
+//!
+//! 内置策略 [`Eviction`] 可序列化，随 [`Capacity`] 写进运行配置，
+//! 保证同样的配置得到同样的淘汰结果。
+
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::{cmp::Ordering, fmt::Debug};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::playbook::{Bullet, BulletId, Playbook};
+
+pub trait EvictionPolicy: Debug + Send + Sync {
+    /// `Less`表示`a`比`b`先被淘汰
+    fn compare(
+        &self,
+        playbook: &Playbook,
+        a: &Bullet,
+        b: &Bullet,
+        now: DateTime<Utc>,
+    ) -> Ordering;
+}
+
+/// 内置淘汰策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Eviction {
+    /// 最久未使用的先淘汰（从未使用的按创建时间算）
+    #[default]
+    LeastRecentlyUsed,
+    /// Playbook评分（[`Playbook::scorer`]）最低的先淘汰
+    LowestScore,
+    /// 创建最早的先淘汰
+    Oldest,
+}
+
+impl EvictionPolicy for Eviction {
+    fn compare(
+        &self,
+        playbook: &Playbook,
+        a: &Bullet,
+        b: &Bullet,
+        now: DateTime<Utc>,
+    ) -> Ordering {
+        match self {
+            Self::LeastRecentlyUsed => {
+                let last_used = |b: &Bullet| b.last_used_at.unwrap_or(b.created_at);
+                last_used(a).cmp(&last_used(b))
+            }
+            Self::LowestScore => {
+                let scorer = playbook.scorer();
+                scorer.score(a, now).total_cmp(&scorer.score(b, now))
+            }
+            Self::Oldest => a.created_at.cmp(&b.created_at),
+        }
+    }
+}
+
+/// Playbook容量（`None`表示不限制），每次apply_delta后执行
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capacity {
+    pub max_bullets: Option<usize>,
+    pub max_per_section: Option<usize>,
+    #[serde(default)]
+    pub eviction: Eviction,
+}
+
+impl Playbook {
+    /// 按淘汰顺序排列子弹（先淘汰的在前）；策略判为相同时创建早的在前，再按ID
+    pub fn eviction_order<'a>(
+        &'a self,
+        policy: &dyn EvictionPolicy,
+        bullets: impl IntoIterator<Item = &'a Bullet>,
+    ) -> Vec<&'a Bullet> {
+        let now = self.clock().now();
+        let mut ordered: Vec<&Bullet> = bullets.into_iter().collect();
+        ordered.sort_by(|a, b| {
+            policy
+                .compare(self, a, b, now)
+                .then_with(|| a.created_at.cmp(&b.created_at))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        ordered
+    }
+
+    /// 按 [`Capacity`] 及其淘汰策略删除超出容量的子弹，返回被删除的子弹
+    pub fn compact(&mut self) -> Vec<Bullet> {
+        let policy = self.capacity().eviction;
+        self.compact_with(&policy)
+    }
+
+    /// 用指定策略执行容量限制：先逐章节限制，再限制总数
+    pub fn compact_with(&mut self, policy: &dyn EvictionPolicy) -> Vec<Bullet> {
+        let victims = self.eviction_victims(policy);
+        victims.iter().filter_map(|id| self.remove_bullet(id)).collect()
+    }
+
+    /// 超出容量时要淘汰的子弹（不修改Playbook）；固定的子弹照样占容量但不会被淘汰，
+    /// 固定子弹过多时章节或总数可能仍超出容量
+    pub(crate) fn eviction_victims(&self, policy: &dyn EvictionPolicy) -> BTreeSet<BulletId> {
+        let capacity = *self.capacity();
+        let mut victims: BTreeSet<BulletId> = BTreeSet::new();
+
+        if let Some(max) = capacity.max_per_section {
+            for bullet_ids in self.sections.values().filter(|ids| ids.len() > max) {
+                let bullets = bullet_ids
+                    .iter()
+                    .filter_map(|id| self.get_bullet(id))
+                    .filter(|b| !b.pinned);
+                let ordered = self.eviction_order(policy, bullets);
+                let excess = (bullet_ids.len() - max).min(ordered.len());
+                victims.extend(ordered[..excess].iter().map(|b| b.id.clone()));
+            }
+        }
+        let remaining = self.bullets.len() - victims.len();
+        if let Some(max) = capacity.max_bullets
+            && remaining > max
+        {
+            let bullets = self
+                .bullets
+                .values()
+                .map(|b| &**b)
+                .filter(|b| !b.pinned && !victims.contains(&b.id));
+            let ordered = self.eviction_order(policy, bullets);
+            let excess = (remaining - max).min(ordered.len());
+            victims.extend(ordered[..excess].iter().map(|b| b.id.clone()));
+        }
+
+        victims
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use chrono::TimeDelta;
+
+    use crate::clock::ManualClock;
+    use crate::models::delta::{DeltaBatch, DeltaOperation};
+
+    #[test]
+    fn test_capacity_evicts_by_policy() {
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let mut ids = Vec::new();
+        for content in ["分页时带上cursor", "重试要指数退避", "先看日志"] {
+            ids.push(pb.add_bullet("api usage", content, None, None));
+            clock.advance(TimeDelta::hours(1));
+        }
+        pb.mark_used(&ids[..1]);
+        pb.tag_bullet(&ids[1], "helpful", 2).unwrap();
+
+        let lru: Vec<&str> = pb
+            .eviction_order(&Eviction::LeastRecentlyUsed, pb.bullets())
+            .iter()
+            .map(|b| b.id.as_str())
+            .collect();
+        assert_eq!(lru, [ids[1].as_str(), ids[2].as_str(), ids[0].as_str()]);
+
+        pb.set_capacity(Capacity {
+            max_bullets: Some(2),
+            max_per_section: None,
+            eviction: Eviction::LowestScore,
+        });
+        let delta = DeltaBatch {
+            reasoning: "new insight".into(),
+            operations: alloc::vec![DeltaOperation::tag(
+                "api usage",
+                &ids[0],
+                [("helpful".into(), 1)].into(),
+            )],
+        };
+        pb.apply_delta(delta).unwrap();
+        assert_eq!(pb.bullets.len(), 2);
+        assert!(pb.get_bullet(&ids[2]).is_none());
+
+        let config = r#"{"max_bullets": 1, "max_per_section": null, "eviction": "oldest"}"#;
+        let config: Capacity = serde_json::from_str(config).unwrap();
+        pb.set_capacity(config);
+        let evicted = pb.compact();
+        assert_eq!(evicted[0].id, ids[0]);
+    }
+
+    #[test]
+    fn test_pinned_bullets_are_never_evicted() {
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let mut ids = Vec::new();
+        for content in ["分页时带上cursor", "重试要指数退避", "先看日志"] {
+            ids.push(pb.add_bullet("api usage", content, None, None));
+            clock.advance(TimeDelta::hours(1));
+        }
+        pb.set_pinned(&ids[0], true).unwrap();
+
+        pb.set_capacity(Capacity {
+            max_bullets: None,
+            max_per_section: Some(2),
+            eviction: Eviction::Oldest,
+        });
+        let evicted: Vec<BulletId> = pb.compact().into_iter().map(|b| b.id).collect();
+        assert_eq!(evicted, [ids[1].clone()]);
+
+        // 只剩固定的子弹时宁可超出容量
+        pb.set_pinned(&ids[2], true).unwrap();
+        pb.set_capacity(Capacity {
+            max_bullets: Some(1),
+            max_per_section: None,
+            eviction: Eviction::Oldest,
+        });
+        assert!(pb.compact().is_empty());
+        assert_eq!(pb.bullets.len(), 2);
+    }
+}
@@ -0,0 +1,236 @@
+//! Few-shot示例：关联到章节或子弹的输入/输出对
+//!
+//! 具体的示例与策略子弹是两类知识：单独存储在 [`Playbook::examples`]，
+//! 有自己的Delta操作（ADD_EXAMPLE/REMOVE_EXAMPLE），渲染时按自己的token预算截断，
+//! 不占用子弹的预算。
+
+use alloc::{collections::BTreeSet, format, string::String, vec::Vec};
+use core::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::context::estimate_tokens;
+use crate::models::playbook::{BulletId, Playbook, PlaybookError, SectionName};
+use crate::models::timestamp;
+
+/// 示例ID（如 `ex-00042`）
+pub type ExampleId = String;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Example {
+    pub id: ExampleId,
+    pub section: SectionName,
+    /// 示例说明的子弹；为空时属于整个章节
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bullet_id: Option<BulletId>,
+    pub input: String,
+    pub output: String,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "timestamp::schema"))]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Playbook {
+    /// 添加示例，返回示例ID。`bullet_id`必须是已有子弹
+    pub fn add_example(
+        &mut self,
+        section: &str,
+        input: impl Into<String>,
+        output: impl Into<String>,
+        bullet_id: Option<BulletId>,
+    ) -> Result<ExampleId, PlaybookError> {
+        let now = self.clock().now();
+        self.add_example_at(section, input, output, bullet_id, None, now)
+    }
+
+    /// 以给定时间戳添加示例；指定的ID已存在时替换旧示例，之后生成的ID排在其序号之后
+    pub fn add_example_at(
+        &mut self,
+        section: &str,
+        input: impl Into<String>,
+        output: impl Into<String>,
+        bullet_id: Option<BulletId>,
+        example_id: Option<ExampleId>,
+        now: DateTime<Utc>,
+    ) -> Result<ExampleId, PlaybookError> {
+        if let Some(id) = bullet_id.as_deref()
+            && !self.bullets.contains_key(id)
+        {
+            return Err(PlaybookError::BulletNotFound(id.into()));
+        }
+        let id = match example_id {
+            Some(id) => {
+                self.reserve_id(&id);
+                id
+            }
+            None => {
+                let id = self.next_example_id();
+                self.next_id += 1;
+                id
+            }
+        };
+        let example = Example {
+            id: id.clone(),
+            section: self.intern_section(section),
+            bullet_id,
+            input: input.into(),
+            output: output.into(),
+            created_at: now,
+        };
+        self.examples.insert(id.clone(), example);
+        self.touch();
+        Ok(id)
+    }
+
+    /// 不指定ID添加示例时将生成的ID
+    pub(crate) fn next_example_id(&self) -> ExampleId {
+        format!("ex-{:05}", self.next_id + 1)
+    }
+
+    pub fn remove_example(&mut self, example_id: &str) -> Option<Example> {
+        let example = self.examples.remove(example_id)?;
+        self.touch();
+        Some(example)
+    }
+
+    /// 关联到某个子弹的示例
+    pub fn bullet_examples<'a>(&'a self, bullet_id: &'a str) -> impl Iterator<Item = &'a Example> {
+        self.examples
+            .values()
+            .filter(move |e| e.bullet_id.as_deref() == Some(bullet_id))
+    }
+
+    /// 渲染全部示例，累计估算token数超过`max_tokens`后截断
+    pub fn as_prompt_examples(&self, max_tokens: usize) -> String {
+        render_examples(self.ordered_examples(|_| true), max_tokens)
+    }
+
+    /// 只渲染与给定子弹相关的示例：关联到这些子弹的，以及这些子弹所在章节的章节级示例
+    pub fn as_prompt_examples_for(&self, bullet_ids: &[BulletId], max_tokens: usize) -> String {
+        let sections: BTreeSet<&str> = bullet_ids
+            .iter()
+            .filter_map(|id| self.bullets.get(id))
+            .map(|b| &*b.section)
+            .collect();
+        let examples = self.ordered_examples(|e| match &e.bullet_id {
+            Some(id) => bullet_ids.contains(id),
+            None => sections.contains(&*e.section),
+        });
+        render_examples(examples, max_tokens)
+    }
+
+    /// 按章节名排序，章节内章节级示例在前，再按ID
+    fn ordered_examples(&self, keep: impl Fn(&Example) -> bool) -> Vec<&Example> {
+        let mut examples: Vec<&Example> = self.examples.values().filter(|e| keep(e)).collect();
+        examples.sort_by(|a, b| {
+            a.section
+                .cmp(&b.section)
+                .then_with(|| a.bullet_id.is_some().cmp(&b.bullet_id.is_some()))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        examples
+    }
+}
+
+fn render_examples(examples: Vec<&Example>, max_tokens: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+    let mut current: Option<&str> = None;
+    let mut block = String::new();
+    for example in examples {
+        block.clear();
+        if current != Some(&*example.section) {
+            if !out.is_empty() {
+                block.push('\n');
+            }
+            let _ = write!(block, "## Examples: {}", example.section);
+        }
+        let _ = write!(block, "\n### [{}]", example.id);
+        if let Some(bullet_id) = &example.bullet_id {
+            let _ = write!(block, " for [{}]", bullet_id);
+        }
+        let _ = write!(block, "\nInput: {}\nOutput: {}", example.input, example.output);
+
+        used += estimate_tokens(&block);
+        if used > max_tokens {
+            break;
+        }
+        out.push_str(&block);
+        current = Some(&example.section);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    use crate::models::delta::{DeltaBatch, DeltaOperation};
+
+    #[test]
+    fn test_examples_via_delta_and_budget() {
+        let mut pb = Playbook::new();
+        let cursor = pb.add_bullet("api usage", "Paginate with the cursor token", None, None);
+        let logs = pb.add_bullet("debugging", "Read the logs first", None, None);
+        pb.apply_delta(DeltaBatch {
+            reasoning: "worked examples".to_string(),
+            operations: vec![
+                DeltaOperation::add_example(
+                    "api usage",
+                    Some(cursor.clone()),
+                    "list all orders",
+                    "GET /orders?cursor=<next>",
+                ),
+                DeltaOperation::add_example("api usage", None, "list users", "GET /users"),
+                DeltaOperation::add_example(
+                    "debugging",
+                    Some(logs.clone()),
+                    "500 error",
+                    "tail app.log",
+                ),
+            ],
+        })
+        .unwrap();
+        assert_eq!(pb.examples.len(), 3);
+        assert_eq!(pb.bullet_examples(&cursor).count(), 1);
+
+        let all = pb.as_prompt_examples(usize::MAX);
+        assert!(all.starts_with("## Examples: api usage\n### [ex-"));
+        assert!(all.find("GET /users").unwrap() < all.find("GET /orders").unwrap());
+        assert!(all.contains(&format!("for [{}]\nInput: 500 error\nOutput: tail app.log", logs)));
+
+        let scoped = pb.as_prompt_examples_for(core::slice::from_ref(&cursor), usize::MAX);
+        assert!(scoped.contains("GET /orders") && !scoped.contains("tail app.log"));
+        assert!(pb.as_prompt_examples(20).len() < all.len());
+
+        // 删除子弹时一并删除它的示例
+        pb.remove_bullet(&cursor);
+        assert_eq!(pb.examples.len(), 2);
+        let id = pb.examples.keys().next().unwrap().clone();
+        let remove = DeltaOperation::remove_example("api usage", &id);
+        let batch = |operations| DeltaBatch {
+            reasoning: String::new(),
+            operations,
+        };
+        pb.apply_delta(batch(vec![remove])).unwrap();
+        assert!(!pb.examples.contains_key(&id));
+
+        let unknown = DeltaOperation::add_example("api usage", Some("nope".into()), "a", "b");
+        assert!(pb.apply_delta(batch(vec![unknown])).is_err());
+    }
+
+    #[test]
+    fn test_generated_example_ids_skip_explicit_ones() {
+        let mut pb = Playbook::new();
+        let now = pb.clock().now();
+        pb.add_example_at("api", "q1", "a1", None, Some("ex-00001".into()), now).unwrap();
+        pb.add_example_at("api", "q2", "a2", None, Some("ex-00007".into()), now).unwrap();
+        let id = pb.add_example("api", "q3", "a3", None).unwrap();
+        assert_eq!(id, "ex-00008");
+        assert_eq!(pb.examples.len(), 3);
+        assert_eq!(pb.examples["ex-00001"].input, "q1");
+    }
+}
@@ -0,0 +1,121 @@
+//! 有时效的知识：子弹可设置 [`Bullet::expires_at`]（如某个将要废弃的API版本的用法），
+//! 过期后所有渲染路径都不再输出它，维护任务 [`Job::Archive`](crate::maintenance::Job::Archive)
+//! 再把它移入 [`Playbook::archived`]
+//!
+//! 归档的子弹保留原ID与计数，可用 [`Playbook::restore_archived`] 恢复。
+
+use alloc::{sync::Arc, vec::Vec};
+
+use chrono::{DateTime, Utc};
+
+use crate::models::playbook::{Bullet, BulletId, Playbook, PlaybookError};
+
+impl Bullet {
+    /// 到`now`为止是否已过期（到期时刻本身算过期）
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+impl Playbook {
+    /// 设置或清除子弹的过期时间（不更新`updated_at`）
+    pub fn set_expires_at(
+        &mut self,
+        bullet_id: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), PlaybookError> {
+        self.modify_bullet(bullet_id, |bullet| {
+            bullet.expires_at = expires_at;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// 已过期但尚未归档的子弹
+    pub fn expired_bullets(&self) -> Vec<&Bullet> {
+        self.expired_bullets_at(self.clock().now())
+    }
+
+    pub fn expired_bullets_at(&self, now: DateTime<Utc>) -> Vec<&Bullet> {
+        self.bullets().into_iter().filter(|b| b.is_expired_at(now)).collect()
+    }
+
+    /// 把过期的子弹移入归档，返回归档的ID；子弹关联的示例随之删除
+    pub fn archive_expired(&mut self) -> Vec<BulletId> {
+        let now = self.clock().now();
+        self.archive_expired_at(now)
+    }
+
+    pub fn archive_expired_at(&mut self, now: DateTime<Utc>) -> Vec<BulletId> {
+        let ids: Vec<BulletId> =
+            self.expired_bullets_at(now).into_iter().map(|b| b.id.clone()).collect();
+        for id in &ids {
+            if let Some(bullet) = self.remove_bullet(id) {
+                self.archived.insert(id.clone(), bullet);
+            }
+        }
+        ids
+    }
+
+    /// 把归档的子弹放回原章节末尾，并设置新的过期时间（`None`表示不再过期）
+    pub fn restore_archived(
+        &mut self,
+        bullet_id: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), PlaybookError> {
+        let mut bullet = self
+            .archived
+            .remove(bullet_id)
+            .ok_or_else(|| PlaybookError::BulletNotFound(bullet_id.into()))?;
+        bullet.expires_at = expires_at;
+        self.sections.entry(bullet.section.clone()).or_default().push(bullet.id.clone());
+        self.bullets.insert(bullet.id.clone(), Arc::new(bullet));
+        self.rebuild_rollups();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+
+    use chrono::TimeDelta;
+
+    use crate::clock::{Clock as _, ManualClock};
+
+    #[test]
+    fn test_expired_bullets_hidden_and_archived() {
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let v1 = pb.add_bullet("api usage", "v1接口用offset分页", None, None);
+        let v2 = pb.add_bullet("api usage", "v2接口用cursor分页", None, None);
+        pb.set_expires_at(&v1, Some(clock.now() + TimeDelta::days(30))).unwrap();
+        assert!(pb.set_expires_at("missing", None).is_err());
+        assert!(pb.as_prompt().contains(&v1));
+
+        clock.advance(TimeDelta::days(30));
+        assert_eq!(pb.expired_bullets().len(), 1);
+        for prompt in [
+            pb.as_prompt(),
+            pb.as_prompt_ranked(),
+            pb.as_prompt_for_bullets(core::slice::from_ref(&v1)),
+        ] {
+            assert!(!prompt.contains(&v1));
+        }
+        assert!(pb.as_prompt().contains(&v2));
+
+        let restored = Playbook::from_json(&pb.to_json().unwrap()).unwrap();
+        assert_eq!(restored.bullets[&v1].expires_at, pb.bullets[&v1].expires_at);
+
+        assert_eq!(pb.archive_expired(), [v1.as_str()]);
+        assert!(pb.get_bullet(&v1).is_none());
+        assert_eq!(pb.archived[&v1].content, "v1接口用offset分页");
+        assert_eq!(pb.section_rollup("api usage").unwrap().bullets, 1);
+
+        pb.restore_archived(&v1, None).unwrap();
+        assert!(pb.as_prompt().contains(&v1));
+        assert!(pb.archived.is_empty());
+        assert!(pb.restore_archived(&v1, None).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! 错误模式知识库：记录见过的错误签名、根因与修复方法
+//!
+//! 任务出错后用 [`Playbook::match_failure`] 按错误文本查出相关记录，只把这些修复方法注入提示词，
+//! 而不是整本Playbook。
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::playbook::Playbook;
+use crate::models::timestamp;
+use crate::reflection::tokens;
+
+/// 失败记录ID（如 `fail-00007`）
+pub type FailureId = String;
+
+/// [`Playbook::match_failure`] 的默认阈值：签名中至少这一比例的词出现在错误文本里
+pub const MATCH_THRESHOLD: f64 = 0.6;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Failure {
+    pub id: FailureId,
+    /// 错误签名：错误信息中稳定的部分（错误码、异常类型、关键短语），不含行号、路径等易变内容
+    pub signature: String,
+    pub root_cause: String,
+    pub fix: String,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "timestamp::schema"))]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 一条匹配结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FailureMatch<'a> {
+    pub failure: &'a Failure,
+    /// 匹配度，0到1；签名整体出现在错误文本中时为1
+    pub score: f64,
+}
+
+impl Playbook {
+    /// 添加失败记录，返回ID
+    pub fn add_failure(
+        &mut self,
+        signature: impl Into<String>,
+        root_cause: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> FailureId {
+        let now = self.clock().now();
+        self.add_failure_at(signature, root_cause, fix, now)
+    }
+
+    pub fn add_failure_at(
+        &mut self,
+        signature: impl Into<String>,
+        root_cause: impl Into<String>,
+        fix: impl Into<String>,
+        now: DateTime<Utc>,
+    ) -> FailureId {
+        self.next_id += 1;
+        let id = format!("fail-{:05}", self.next_id);
+        let failure = Failure {
+            id: id.clone(),
+            signature: signature.into(),
+            root_cause: root_cause.into(),
+            fix: fix.into(),
+            created_at: now,
+        };
+        self.failures.insert(id.clone(), failure);
+        self.touch();
+        id
+    }
+
+    pub fn remove_failure(&mut self, failure_id: &str) -> Option<Failure> {
+        let failure = self.failures.remove(failure_id)?;
+        self.touch();
+        Some(failure)
+    }
+
+    /// 以 [`MATCH_THRESHOLD`] 查找与错误文本相关的记录，匹配度高的在前
+    pub fn match_failure(&self, error_text: &str) -> Vec<FailureMatch<'_>> {
+        self.match_failure_with(error_text, MATCH_THRESHOLD)
+    }
+
+    /// 签名（忽略大小写）整体出现在错误文本中记为1，否则为签名的词出现在错误文本中的比例；
+    /// 低于`min_score`的丢弃。同分按ID
+    pub fn match_failure_with(&self, error_text: &str, min_score: f64) -> Vec<FailureMatch<'_>> {
+        let lowered = error_text.to_lowercase();
+        let words = tokens(error_text);
+        let mut matches: Vec<FailureMatch<'_>> = self
+            .failures
+            .values()
+            .filter_map(|failure| {
+                let signature = tokens(&failure.signature);
+                if signature.is_empty() {
+                    return None;
+                }
+                let score = if lowered.contains(&failure.signature.to_lowercase()) {
+                    1.0
+                } else {
+                    signature.intersection(&words).count() as f64 / signature.len() as f64
+                };
+                (score >= min_score).then_some(FailureMatch { failure, score })
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches
+    }
+
+    /// 渲染匹配到的记录（按给定顺序）
+    pub fn as_prompt_failures(&self, matches: &[FailureMatch<'_>]) -> String {
+        let mut out = String::new();
+        for FailureMatch { failure, .. } in matches {
+            if out.is_empty() {
+                out.push_str("## Known failures");
+            }
+            let _ = write!(
+                out,
+                "\n- [{}] {}\n  Root cause: {}\n  Fix: {}",
+                failure.id, failure.signature, failure.root_cause, failure.fix
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_failure_by_signature() {
+        let mut pb = Playbook::new();
+        let refused = pb.add_failure(
+            "connection refused",
+            "the service was not started",
+            "start the dev server before running tests",
+        );
+        let mismatch = pb.add_failure(
+            "E0308 mismatched types expected String",
+            "passed &str where String is required",
+            "call .to_string() or change the parameter to &str",
+        );
+        pb.add_failure("rate limit exceeded 429", "too many requests", "back off and retry");
+
+        let error = "error[E0308]: mismatched types\n --> src/main.rs:4:18\n expected `String`, \
+                     found `&str`";
+        let matches = pb.match_failure(error);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].failure.id, mismatch);
+        // 缺少错误码时仍按词的覆盖率匹配
+        let partial = pb.match_failure("mismatched types: expected String");
+        assert_eq!((partial[0].failure.id.as_str(), partial[0].score), (mismatch.as_str(), 0.8));
+
+        let matches = pb.match_failure("GET /health: Connection Refused (os error 111)");
+        assert_eq!((matches[0].failure.id.as_str(), matches[0].score), (refused.as_str(), 1.0));
+        assert!(pb.match_failure("index out of bounds").is_empty());
+
+        let prompt = pb.as_prompt_failures(&matches);
+        assert_eq!(
+            prompt,
+            format!(
+                "## Known failures\n- [{}] connection refused\n  Root cause: the service was not \
+                 started\n  Fix: start the dev server before running tests",
+                refused
+            )
+        );
+    }
+}
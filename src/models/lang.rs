@@ -0,0 +1,124 @@
+//! 子弹内容的语言：检测结果记在 [`Bullet::lang`]，渲染时可只输出某种语言的子弹，
+//! 让混合语言的Playbook服务只说一种语言的代理
+//!
+//! 语言代码为ISO 639-3（`eng`、`cmn`、`jpn`…）。检测需要 `lang-detect` feature，
+//! 也可以由调用方用 [`Playbook::set_bullet_lang`] 自行标注。
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    vec::Vec,
+};
+
+use crate::models::playbook::{Bullet, BulletId, Playbook, PlaybookError};
+
+impl Playbook {
+    /// 标注子弹的语言（`None`表示未知）
+    pub fn set_bullet_lang(
+        &mut self,
+        bullet_id: &str,
+        lang: Option<String>,
+    ) -> Result<(), PlaybookError> {
+        self.modify_bullet(bullet_id, |bullet| {
+            bullet.lang = lang;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// 各语言的子弹数（未标注语言的不计）
+    pub fn languages(&self) -> BTreeMap<&str, usize> {
+        let mut counts = BTreeMap::new();
+        for lang in self.bullets.values().filter_map(|b| b.lang.as_deref()) {
+            *counts.entry(lang).or_default() += 1;
+        }
+        counts
+    }
+
+    /// 只渲染指定语言的子弹；`include_unknown`时也输出未标注语言的子弹
+    pub fn as_prompt_in_lang(&self, lang: &str, include_unknown: bool) -> String {
+        let matches = |b: &Bullet| match &b.lang {
+            Some(l) => l.eq_ignore_ascii_case(lang),
+            None => include_unknown,
+        };
+        let ids: Vec<BulletId> =
+            self.bullets.values().filter(|b| matches(b)).map(|b| b.id.clone()).collect();
+        self.as_prompt_for_bullets(&ids)
+    }
+}
+
+/// 检测文本语言；文本太短或结果不可靠时返回`None`
+#[cfg(feature = "lang-detect")]
+pub fn detect_lang(text: &str) -> Option<String> {
+    let info = whatlang::detect(text)?;
+    info.is_reliable().then(|| info.lang().code().into())
+}
+
+#[cfg(feature = "lang-detect")]
+impl Playbook {
+    /// 检测子弹内容的语言并写入 [`Bullet::lang`]，返回更新的子弹数；
+    /// `overwrite`为false时跳过已标注的子弹，检测不出的子弹保持不变
+    pub fn detect_languages(&mut self, overwrite: bool) -> usize {
+        let detected: Vec<(BulletId, String)> = self
+            .bullets
+            .values()
+            .filter(|b| overwrite || b.lang.is_none())
+            .filter_map(|b| Some((b.id.clone(), detect_lang(&b.content)?)))
+            .collect();
+        let updated = detected.len();
+        for (id, lang) in detected {
+            let _ = self.set_bullet_lang(&id, Some(lang));
+        }
+        updated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_by_lang() {
+        let mut pb = Playbook::new();
+        let en = pb.add_bullet("api usage", "Always pass the cursor when paginating", None, None);
+        let zh = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        let unknown = pb.add_bullet("api usage", "GET /orders?cursor=", None, None);
+        pb.set_bullet_lang(&en, Some("eng".to_string())).unwrap();
+        pb.set_bullet_lang(&zh, Some("cmn".to_string())).unwrap();
+        assert!(pb.set_bullet_lang("missing", None).is_err());
+        assert_eq!(pb.languages(), BTreeMap::from([("cmn", 1), ("eng", 1)]));
+
+        let prompt = pb.as_prompt_in_lang("ENG", false);
+        assert!(prompt.contains(&en) && !prompt.contains(&zh) && !prompt.contains(&unknown));
+        let prompt = pb.as_prompt_in_lang("cmn", true);
+        assert!(!prompt.contains(&en) && prompt.contains(&zh) && prompt.contains(&unknown));
+
+        let restored = Playbook::from_json(&pb.to_json().unwrap()).unwrap();
+        assert_eq!(restored.bullets[&zh].lang.as_deref(), Some("cmn"));
+        assert_eq!(restored.bullets[&unknown].lang, None);
+    }
+
+    #[cfg(feature = "lang-detect")]
+    #[test]
+    fn test_detect_languages() {
+        let mut pb = Playbook::new();
+        let en = pb.add_bullet(
+            "debugging",
+            "Check the service logs before restarting anything in production",
+            None,
+            None,
+        );
+        let zh = pb.add_bullet(
+            "debugging",
+            "在生产环境重启任何服务之前，先检查服务日志和监控",
+            None,
+            None,
+        );
+        pb.add_bullet("debugging", "ok", None, None);
+
+        assert_eq!(pb.detect_languages(false), 2);
+        assert_eq!(pb.bullets[&en].lang.as_deref(), Some("eng"));
+        assert_eq!(pb.bullets[&zh].lang.as_deref(), Some("cmn"));
+        assert_eq!(pb.detect_languages(false), 0);
+    }
+}
@@ -0,0 +1,201 @@
+//! 子弹行的渲染格式：用`{id}`、`{content}`、`{section}`、`{helpful}`、`{harmful}`、`{neutral}`、
+//! `{counters}`（即`helpful=1, harmful=0, neutral=2`）组成的模板代替固定的
+//! `- [{id}] {content} ({counters})`，如`- {content} <!-- {id} -->`
+//!
+//! 格式在解析时校验（配置反序列化时即报错），渲染时不再检查。`{{`、`}}`表示字面的花括号。
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Write as _};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// 默认格式，与 [`Playbook::parse_prompt`](crate::models::playbook::Playbook::parse_prompt) 对应
+pub const DEFAULT_LINE_FORMAT: &str = "- [{id}] {content} ({counters})";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LineFormatError {
+    #[error("未知的占位符 {{{0}}}")]
+    UnknownField(String),
+    #[error("花括号未闭合: {0}")]
+    Unbalanced(String),
+    #[error("格式必须包含 {{content}}")]
+    MissingContent,
+    #[error("格式不能包含换行")]
+    Newline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    Content,
+    Section,
+    Helpful,
+    Harmful,
+    Neutral,
+    Counters,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// 校验过的子弹行格式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct LineFormat {
+    spec: String,
+    segments: Vec<Segment>,
+}
+
+impl Default for LineFormat {
+    fn default() -> Self {
+        Self::parse(DEFAULT_LINE_FORMAT).expect("默认格式有效")
+    }
+}
+
+impl LineFormat {
+    pub fn parse(spec: &str) -> Result<Self, LineFormatError> {
+        if spec.contains(['\n', '\r']) {
+            return Err(LineFormatError::Newline);
+        }
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = spec;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            let tail = &rest[i..];
+            if let Some(after) = tail.strip_prefix("{{").or_else(|| tail.strip_prefix("}}")) {
+                literal.push_str(&tail[..1]);
+                rest = after;
+                continue;
+            }
+            let Some(end) = tail.strip_prefix('{').and_then(|t| t.find('}')) else {
+                return Err(LineFormatError::Unbalanced(spec.to_string()));
+            };
+            let name = &tail[1..end + 1];
+            let field = match name {
+                "id" => Field::Id,
+                "content" => Field::Content,
+                "section" => Field::Section,
+                "helpful" => Field::Helpful,
+                "harmful" => Field::Harmful,
+                "neutral" => Field::Neutral,
+                "counters" => Field::Counters,
+                _ => return Err(LineFormatError::UnknownField(name.to_string())),
+            };
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(core::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Field(field));
+            rest = &tail[end + 2..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        if !segments.contains(&Segment::Field(Field::Content)) {
+            return Err(LineFormatError::MissingContent);
+        }
+        Ok(Self { spec: spec.to_string(), segments })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.spec
+    }
+
+    /// 换行后写入一行，计数顺序为 helpful / harmful / neutral
+    pub fn write(
+        &self,
+        out: &mut String,
+        section: &str,
+        id: &str,
+        content: &str,
+        counters: [u32; 3],
+    ) {
+        let [helpful, harmful, neutral] = counters;
+        out.push('\n');
+        for segment in &self.segments {
+            let _ = match segment {
+                Segment::Literal(text) => out.write_str(text),
+                Segment::Field(Field::Id) => out.write_str(id),
+                Segment::Field(Field::Content) => out.write_str(content),
+                Segment::Field(Field::Section) => out.write_str(section),
+                Segment::Field(Field::Helpful) => write!(out, "{}", helpful),
+                Segment::Field(Field::Harmful) => write!(out, "{}", harmful),
+                Segment::Field(Field::Neutral) => write!(out, "{}", neutral),
+                Segment::Field(Field::Counters) => {
+                    write!(out, "helpful={}, harmful={}, neutral={}", helpful, harmful, neutral)
+                }
+            };
+        }
+    }
+}
+
+impl TryFrom<String> for LineFormat {
+    type Error = LineFormatError;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        Self::parse(&spec)
+    }
+}
+
+impl From<LineFormat> for String {
+    fn from(format: LineFormat) -> Self {
+        format.spec
+    }
+}
+
+impl fmt::Display for LineFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::models::playbook::Playbook;
+
+    #[test]
+    fn test_line_format() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        pb.tag_bullet(&a, "helpful", 2).unwrap();
+        let default_prompt = pb.as_prompt();
+        assert_eq!(
+            default_prompt,
+            format!("## api usage\n- [{}] 分页时带上cursor (helpful=2, harmful=0, neutral=0)", a)
+        );
+
+        pb.set_line_format(
+            LineFormat::parse("* {content} <!-- {id} +{helpful} {{x}} -->").unwrap(),
+        );
+        assert_eq!(
+            pb.as_prompt(),
+            format!("## api usage\n* 分页时带上cursor <!-- {} +2 {{x}} -->", a)
+        );
+
+        let format: LineFormat = serde_json::from_str("\"{section}: {content}\"").unwrap();
+        assert_eq!(serde_json::to_string(&format).unwrap(), "\"{section}: {content}\"");
+        pb.set_line_format(format);
+        assert_eq!(pb.as_prompt(), "## api usage\napi usage: 分页时带上cursor");
+
+        for (spec, err) in [
+            ("- {id}", LineFormatError::MissingContent),
+            ("- {content} {score}", LineFormatError::UnknownField("score".to_string())),
+            ("- {content", LineFormatError::Unbalanced("- {content".to_string())),
+            ("- {content}\n", LineFormatError::Newline),
+        ] {
+            assert_eq!(LineFormat::parse(spec).unwrap_err(), err);
+        }
+        assert!(serde_json::from_str::<LineFormat>("\"{id}\"").is_err());
+        assert_eq!(LineFormat::default().as_str(), DEFAULT_LINE_FORMAT);
+    }
+}
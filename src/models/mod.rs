@@ -1,2 +1,38 @@
+pub mod activation;
+pub mod boilerplate;
+pub mod cache_prompt;
+pub mod canary;
+pub mod context;
 pub mod delta;
-pub mod playbook;
\ No newline at end of file
+pub mod diff;
+pub mod eviction;
+pub mod example;
+pub mod expiry;
+pub mod failure;
+pub mod lang;
+pub mod line_format;
+pub mod observer;
+pub mod pii;
+pub mod playbook;
+pub mod policy;
+pub mod rate_guard;
+pub mod registry;
+pub mod replay;
+pub mod review;
+pub mod sample;
+pub mod sampling;
+pub mod stats;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod scoring;
+pub mod search;
+pub mod sections;
+pub mod signing;
+pub mod skill;
+pub mod snapshot;
+pub mod tag;
+pub mod template;
+pub mod timestamp;
+pub mod undo;
+pub mod validation;
+pub mod vars;
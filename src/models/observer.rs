@@ -0,0 +1,126 @@
+//! Playbook观察者：Delta成功应用、维护任务执行之后收到通知（webhook、审计日志等）
+//!
+//! 观察者登记在Playbook上（[`Playbook::add_observer`]），不序列化，也不随`clone`复制：
+//! 预览、快照等副本上的修改不会产生通知。事务应用（[`Playbook::apply_delta_transactional`]、
+//! `DeltaLog::apply`）在整批提交之后才通知，回滚的批次不通知。
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::fmt::{self, Debug};
+
+use chrono::{DateTime, Utc};
+
+use crate::maintenance::JobReport;
+use crate::models::playbook::Playbook;
+use crate::models::rate_guard::DeltaImpact;
+
+/// 一次成功应用的Delta
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedDelta {
+    pub reasoning: String,
+    pub operations: usize,
+    pub impact: DeltaImpact,
+    pub at: DateTime<Utc>,
+}
+
+pub trait PlaybookObserver: Debug + Send + Sync {
+    /// 非空的Delta应用成功之后调用（包括维护任务应用的Delta）；`playbook`为应用后的状态
+    fn on_delta_applied(&self, playbook: &Playbook, applied: &AppliedDelta) {
+        let _ = (playbook, applied);
+    }
+
+    /// [`Maintenance::tick`](crate::maintenance::Maintenance::tick)执行了任务之后调用
+    fn on_maintenance(&self, playbook: &Playbook, reports: &[JobReport]) {
+        let _ = (playbook, reports);
+    }
+}
+
+pub type SharedObserver = Arc<dyn PlaybookObserver>;
+
+/// 登记的观察者；克隆得到空列表
+#[derive(Default)]
+pub(crate) struct Observers(Vec<SharedObserver>);
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.0).finish()
+    }
+}
+
+impl Observers {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Playbook {
+    /// 登记观察者（见模块说明）
+    pub fn add_observer(&mut self, observer: SharedObserver) {
+        self.observers.0.push(observer);
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.0.clear();
+    }
+
+    pub(crate) fn notify_applied(&self, applied: &AppliedDelta) {
+        for observer in &self.observers.0 {
+            observer.on_delta_applied(self, applied);
+        }
+    }
+
+    pub(crate) fn notify_maintenance(&self, reports: &[JobReport]) {
+        for observer in &self.observers.0 {
+            observer.on_maintenance(self, reports);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{collections::BTreeMap, string::ToString, vec};
+    use std::sync::Mutex;
+
+    use crate::models::delta::{DeltaBatch, DeltaOperation};
+
+    #[derive(Debug, Default)]
+    struct Recording(Mutex<Vec<AppliedDelta>>);
+
+    impl PlaybookObserver for Recording {
+        fn on_delta_applied(&self, _playbook: &Playbook, applied: &AppliedDelta) {
+            self.0.lock().unwrap().push(applied.clone());
+        }
+    }
+
+    fn batch(operations: Vec<DeltaOperation>) -> DeltaBatch {
+        DeltaBatch { reasoning: "curate".to_string(), operations }
+    }
+
+    #[test]
+    fn test_observers_see_committed_deltas_only() {
+        let recording = Arc::new(Recording::default());
+        let mut pb = Playbook::new();
+        pb.add_observer(recording.clone());
+
+        pb.apply_delta(batch(vec![DeltaOperation::add("api", "先分页")])).unwrap();
+        pb.apply_delta(batch(vec![])).unwrap();
+        let tag_missing = DeltaOperation::tag("api", "x", BTreeMap::from([("helpful".into(), 1)]));
+        let failing = batch(vec![DeltaOperation::add("api", "b"), tag_missing]);
+        assert!(pb.apply_delta_transactional(failing).is_err());
+        // 副本不继承观察者
+        let mut preview = pb.clone();
+        preview.apply_delta(batch(vec![DeltaOperation::add("api", "c")])).unwrap();
+        pb.apply_delta_transactional(batch(vec![DeltaOperation::add("api", "d")])).unwrap();
+
+        let seen = recording.0.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!((seen[0].operations, seen[0].impact.added), (1, 1));
+        assert_eq!((seen[1].reasoning.as_str(), seen[1].impact.added), ("curate", 1));
+    }
+}
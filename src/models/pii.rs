@@ -0,0 +1,276 @@
+//! PII脱敏：识别邮箱、电话、密钥等敏感片段并替换为占位符
+//!
+//! [`PiiRedactor`] 实现了 [`ContentPolicy`]，通过 [`Playbook::set_content_policy`]
+//! 挂到apply_delta上即可在入库前脱敏（reflection经常原文引用用户消息）。
+//!
+//! [`Playbook::set_content_policy`]: crate::models::playbook::Playbook::set_content_policy
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{fmt::Debug, ops::Range};
+
+use crate::models::policy::{ContentPolicy, PolicyDecision};
+
+/// 可插拔的敏感信息检测器
+pub trait PiiDetector: Debug + Send + Sync {
+    /// 替换用的占位符，如 `[EMAIL]`
+    fn placeholder(&self) -> &str;
+    /// 命中的字节范围
+    fn find(&self, text: &str) -> Vec<Range<usize>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PiiRedactor {
+    detectors: Vec<Arc<dyn PiiDetector>>,
+}
+
+impl Default for PiiRedactor {
+    /// 内置邮箱、电话、密钥检测
+    fn default() -> Self {
+        Self::empty()
+            .with(ApiKeyDetector)
+            .with(EmailDetector)
+            .with(PhoneDetector)
+    }
+}
+
+impl PiiRedactor {
+    /// 不含任何检测器
+    pub fn empty() -> Self {
+        Self {
+            detectors: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, detector: impl PiiDetector + 'static) -> Self {
+        self.detectors.push(Arc::new(detector));
+        self
+    }
+
+    /// 返回脱敏后的文本；没有命中时返回`None`
+    ///
+    /// 多个检测器的命中区间重叠时，先注册的检测器优先。
+    pub fn redact(&self, text: &str) -> Option<String> {
+        let mut hits: Vec<(Range<usize>, &str)> = Vec::new();
+        for detector in &self.detectors {
+            for range in detector.find(text) {
+                if !hits
+                    .iter()
+                    .any(|(r, _)| r.start < range.end && range.start < r.end)
+                {
+                    hits.push((range, detector.placeholder()));
+                }
+            }
+        }
+        if hits.is_empty() {
+            return None;
+        }
+        hits.sort_by_key(|(r, _)| r.start);
+
+        let mut out = String::with_capacity(text.len());
+        let mut pos = 0;
+        for (range, placeholder) in hits {
+            out.push_str(&text[pos..range.start]);
+            out.push_str(placeholder);
+            pos = range.end;
+        }
+        out.push_str(&text[pos..]);
+        Some(out)
+    }
+}
+
+impl ContentPolicy for PiiRedactor {
+    fn check(&self, _section: &str, content: &str) -> PolicyDecision {
+        self.redact(content)
+            .map_or(PolicyDecision::Allow, PolicyDecision::Redact)
+    }
+}
+
+// --------------------------
+// 内置检测器
+// --------------------------
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmailDetector;
+
+impl PiiDetector for EmailDetector {
+    fn placeholder(&self) -> &str {
+        "[EMAIL]"
+    }
+
+    fn find(&self, text: &str) -> Vec<Range<usize>> {
+        let bytes = text.as_bytes();
+        let is_local = |b: u8| b.is_ascii_alphanumeric() || b"._%+-".contains(&b);
+        let is_domain = |b: u8| b.is_ascii_alphanumeric() || b".-".contains(&b);
+
+        let mut hits = Vec::new();
+        for at in memchr_all(bytes, b'@') {
+            let start = (0..at)
+                .rev()
+                .take_while(|&i| is_local(bytes[i]))
+                .last()
+                .unwrap_or(at);
+            let mut end = (at + 1..bytes.len())
+                .take_while(|&i| is_domain(bytes[i]))
+                .last()
+                .map_or(at, |i| i + 1);
+            while end > at && bytes[end - 1] == b'.' {
+                end -= 1;
+            }
+            let domain = &text[at + 1..end];
+            let tld_ok = domain.rsplit_once('.').is_some_and(|(host, tld)| {
+                !host.is_empty() && tld.len() >= 2 && tld.bytes().all(|b| b.is_ascii_alphabetic())
+            });
+            if start < at && tld_ok && hits.last().is_none_or(|r: &Range<usize>| r.end <= start) {
+                hits.push(start..end);
+            }
+        }
+        hits
+    }
+}
+
+/// 10~15位数字的电话号码；纯数字串只有带`+`前缀时才算（避免误伤时间戳、订单号）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhoneDetector;
+
+impl PiiDetector for PhoneDetector {
+    fn placeholder(&self) -> &str {
+        "[PHONE]"
+    }
+
+    fn find(&self, text: &str) -> Vec<Range<usize>> {
+        let bytes = text.as_bytes();
+        let is_part = |b: u8| b.is_ascii_digit() || b" -().".contains(&b);
+        let mut hits = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let plus = bytes[i] == b'+';
+            let starts = (plus || bytes[i].is_ascii_digit() || bytes[i] == b'(')
+                && (i == 0 || !bytes[i - 1].is_ascii_alphanumeric());
+            if !starts {
+                i += 1;
+                continue;
+            }
+            let body_start = i + usize::from(plus);
+            let mut end = (body_start..bytes.len())
+                .take_while(|&j| is_part(bytes[j]))
+                .last()
+                .map_or(body_start, |j| j + 1);
+            // 去掉结尾的分隔符
+            while end > body_start && !bytes[end - 1].is_ascii_digit() {
+                end -= 1;
+            }
+            let body = &bytes[body_start..end];
+            let digits = body.iter().filter(|b| b.is_ascii_digit()).count();
+            let separated = body.iter().any(|b| !b.is_ascii_digit());
+            let followed_ok = end == bytes.len() || !bytes[end].is_ascii_alphanumeric();
+            if (10..=15).contains(&digits) && (plus || separated) && followed_ok {
+                hits.push(i..end);
+                i = end;
+            } else {
+                i = end.max(i + 1);
+            }
+        }
+        hits
+    }
+}
+
+/// 常见服务商的密钥前缀，以及长度≥32、同时含字母和数字的长串
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiKeyDetector;
+
+impl ApiKeyDetector {
+    const PREFIXES: &'static [&'static str] = &[
+        "sk-",
+        "sk_live_",
+        "sk_test_",
+        "pk_live_",
+        "rk_live_",
+        "ghp_",
+        "gho_",
+        "ghs_",
+        "github_pat_",
+        "xoxb-",
+        "xoxp-",
+        "AKIA",
+        "AIza",
+        "glpat-",
+    ];
+}
+
+impl PiiDetector for ApiKeyDetector {
+    fn placeholder(&self) -> &str {
+        "[KEY]"
+    }
+
+    fn find(&self, text: &str) -> Vec<Range<usize>> {
+        let is_token = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+        let mut hits = Vec::new();
+        let mut offset = 0;
+        for token in text.split(|c: char| !is_token(c)) {
+            let range = offset..offset + token.len();
+            offset = range.end + text[range.end..].chars().next().map_or(0, char::len_utf8);
+
+            let prefixed = token.len() >= 16 && Self::PREFIXES.iter().any(|p| token.starts_with(p));
+            let opaque = token.len() >= 32
+                && token.bytes().any(|b| b.is_ascii_digit())
+                && token.bytes().any(|b| b.is_ascii_alphabetic());
+            if prefixed || opaque {
+                hits.push(range);
+            }
+        }
+        hits
+    }
+}
+
+fn memchr_all(bytes: &[u8], needle: u8) -> impl Iterator<Item = usize> + '_ {
+    bytes
+        .iter()
+        .enumerate()
+        .filter(move |(_, b)| **b == needle)
+        .map(|(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_detectors() {
+        let redactor = PiiRedactor::default();
+        let text = "Mail bob.smith+x@mail.example.com. or call +1 (415) 555-0100, \
+                    key sk-proj-abcdef1234567890XYZ; order 2023-05-18 #1700000000 done";
+        assert_eq!(
+            redactor.redact(text).unwrap(),
+            "Mail [EMAIL]. or call [PHONE], key [KEY]; order 2023-05-18 #1700000000 done"
+        );
+        assert_eq!(redactor.redact("用户说：分页时带上cursor"), None);
+        assert_eq!(
+            redactor.redact("张三 138-0013-8000 反馈").unwrap(),
+            "张三 [PHONE] 反馈"
+        );
+    }
+
+    #[test]
+    fn test_custom_detector_and_policy() {
+        #[derive(Debug)]
+        struct OrderId;
+        impl PiiDetector for OrderId {
+            fn placeholder(&self) -> &str {
+                "[ORDER]"
+            }
+            fn find(&self, text: &str) -> Vec<Range<usize>> {
+                text.match_indices("ORD-").map(|(i, _)| i..i + 10).collect()
+            }
+        }
+
+        let redactor = PiiRedactor::empty().with(OrderId);
+        assert_eq!(
+            redactor.check("support", "refund ORD-123456 now"),
+            PolicyDecision::Redact("refund [ORDER] now".into())
+        );
+        assert_eq!(
+            redactor.check("support", "nothing here"),
+            PolicyDecision::Allow
+        );
+    }
+}
@@ -1,18 +1,44 @@
 //! ACE的知识存储系统，让代理能持久化学习到策略，并在生成任务时作为上下文注入 LLM 提示
 
-use std::{
-    collections::{BTreeMap, HashMap},
-    fmt,
-    fs::{self, File},
-    io::Read,
-    path::Path,
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Write as _},
+    sync::atomic::{AtomicU64, Ordering},
 };
 
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, TimeDelta, Utc};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize, Serializer};
 use thiserror::Error;
 
-use crate::models::delta::{DeltaBatch, DeltaOperation, OperationType};
+#[cfg(feature = "std")]
+use crate::clock::{Clock as _, SystemClock};
+use crate::clock::{self, SharedClock};
+use crate::models::delta::{
+    DeltaBatch, DeltaError, DeltaLimits, DeltaOperation, MergeStrategy, OperationType,
+};
+use crate::models::eviction::Capacity;
+use crate::models::example::{Example, ExampleId};
+use crate::models::failure::{Failure, FailureId};
+use crate::models::line_format::LineFormat;
+use crate::models::observer::{AppliedDelta, Observers};
+use crate::models::policy::{PolicyDecision, SharedContentPolicy};
+use crate::models::scoring::{self, SharedScorer};
+use crate::models::sections::SectionNormalizer;
+use crate::models::skill::Skill;
+use crate::models::tag::{Tag, TagCounts};
+use crate::models::timestamp::{self, TimestampFormat};
+use crate::models::undo::{InverseBuilder, Journal, Prior};
+use crate::models::vars::{self, TemplateVars};
+use crate::progress::{ProgressSink, ProgressTracker};
 
 #[derive(Debug, Error)]
 pub enum PlaybookError {
@@ -22,6 +48,7 @@ pub enum PlaybookError {
     #[error("Invalid tag: {0}. Supported tags: helpful, harmful, neutral")]
     InvalidTag(String),
 
+    #[cfg(feature = "std")]
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -33,70 +60,477 @@ pub enum PlaybookError {
 
     #[error("Delta operation missing required field: {0}")]
     DeltaMissingField(String),
+
+    #[error("Unknown template: {0}")]
+    UnknownTemplate(String),
+
+    #[error("Delta limit exceeded: {0}")]
+    DeltaLimitExceeded(String),
+
+    #[error("Content rejected by policy: {0}")]
+    ContentRejected(String),
+
+    #[error("Playbook is frozen: {0}")]
+    Frozen(String),
+
+    #[error("Invalid delta: {0}")]
+    InvalidDelta(#[from] DeltaError),
+
+    /// DeltaBatch中第`index`个操作（从0开始）出错
+    #[error("Delta operation #{index}: {source}")]
+    Operation {
+        index: usize,
+        #[source]
+        source: Box<PlaybookError>,
+    },
+}
+
+impl PlaybookError {
+    /// 稳定的错误码，供服务端/命令行映射错误（不随错误信息的措辞变化）
+    pub fn code(&self) -> &'static str {
+        match self {
+            PlaybookError::BulletNotFound(_) => "bullet_not_found",
+            PlaybookError::InvalidTag(_) => "invalid_tag",
+            #[cfg(feature = "std")]
+            PlaybookError::IoError(_) => "io",
+            PlaybookError::JsonError(_) => "json",
+            PlaybookError::InvalidData(_) => "invalid_data",
+            PlaybookError::DeltaMissingField(_) => "delta_missing_field",
+            PlaybookError::UnknownTemplate(_) => "unknown_template",
+            PlaybookError::DeltaLimitExceeded(_) => "delta_limit_exceeded",
+            PlaybookError::ContentRejected(_) => "content_rejected",
+            PlaybookError::Frozen(_) => "frozen",
+            PlaybookError::InvalidDelta(_) => "invalid_delta",
+            PlaybookError::Operation { source, .. } => source.code(),
+        }
+    }
+
+    /// 原样重试可能成功（暂时性的IO错误）；其余错误重试前需要修改输入或Playbook
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(feature = "std")]
+            PlaybookError::IoError(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::WouldBlock
+                    | std::io::ErrorKind::TimedOut
+            ),
+            PlaybookError::Operation { source, .. } => source.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// 出错的Delta操作下标
+    pub fn op_index(&self) -> Option<usize> {
+        match self {
+            PlaybookError::Operation { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// 去掉操作下标包装后的错误
+    pub fn root(&self) -> &PlaybookError {
+        match self {
+            PlaybookError::Operation { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    fn at_operation(index: usize) -> impl FnOnce(PlaybookError) -> PlaybookError {
+        move |e| PlaybookError::Operation { index, source: Box::new(e) }
+    }
 }
 
+/// 子弹ID（如 `api-00012`）
+pub type BulletId = String;
+
+/// 章节名。同一章节的所有子弹与`sections`索引共享同一份字符串
+pub type SectionName = Arc<str>;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Bullet {
     pub id: String,
-    pub section: String,
+    pub section: SectionName,
     pub content: String,
     pub helpful: u32,
     pub harmful: u32,
     pub neutral: u32,
+    /// 自定义标签的计数（见 [`Tag::Custom`]）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, u32>,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "timestamp::schema"))]
     pub created_at: DateTime<Utc>,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "timestamp::schema"))]
     pub updated_at: DateTime<Utc>,
+    /// 最近一次被生成过程使用（引用）的时间，从未使用时为空
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "timestamp::deserialize_option"
+    )]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "timestamp::option_schema"))]
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// 过期时间：之后不再渲染进提示词，由维护任务归档（见 [`crate::models::expiry`]）
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "timestamp::deserialize_option"
+    )]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "timestamp::option_schema"))]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 其他语言的内容版本（语言标签如`en`、`zh-CN` → 内容）；`content`为默认语言
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<String, String>,
+    /// `content`的语言（ISO 639-3代码，如`eng`、`cmn`），见 [`crate::models::lang`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// 激活条件（上下文键 → 要求的值），见 [`crate::models::activation`]；为空时总是激活
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub conditions: BTreeMap<String, String>,
+    /// 固定在提示词稳定前缀中（见 [`Playbook::as_prompt_cache_aware`]）
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    pub pinned: bool,
+    /// 工具使用经验（见 [`crate::models::skill`]）；为空时是普通策略子弹
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill: Option<Skill>,
+}
+
+impl Bullet {
+    /// 序列化时受 [`TimestampFormat`] 控制的字段
+    pub const TIMESTAMP_FIELDS: &'static [&'static str] =
+        &["created_at", "updated_at", "last_used_at", "expires_at"];
 }
 
 impl Bullet {
 
-    pub fn new(section: String, content: String) -> Self {
-        let now = Utc::now();
+    /// 读取系统时钟，绕过Playbook注入的时钟；在Playbook之外构造子弹时用 [`Bullet::new_at`]
+    #[cfg(feature = "std")]
+    #[deprecated(note = "reads the system clock; use `new_at`")]
+    pub fn new(section: impl Into<SectionName>, content: String) -> Self {
+        Self::new_at(section, content, SystemClock.now())
+    }
+
+    /// 以给定时间戳创建子弹（批量操作共用同一时间戳）
+    pub fn new_at(section: impl Into<SectionName>, content: String, now: DateTime<Utc>) -> Self {
         Self {
             id: String::new(),
-            section,
+            section: section.into(),
             content,
             helpful: 0,
             harmful: 0,
             neutral: 0,
+            custom: BTreeMap::new(),
             created_at: now,
             updated_at: now,
+            last_used_at: None,
+            expires_at: None,
+            variants: BTreeMap::new(),
+            lang: None,
+            conditions: BTreeMap::new(),
+            pinned: false,
+            skill: None,
         }
     }
 
+    /// 指定语言的内容：先精确匹配语言标签，再匹配主语言（`zh-CN` → `zh`），都没有时用默认内容
+    pub fn content_for(&self, lang: &str) -> &str {
+        let primary = lang.split(['-', '_']).next().unwrap_or(lang);
+        self.variants
+            .get(lang)
+            .or_else(|| self.variants.get(primary))
+            .unwrap_or(&self.content)
+    }
+
+    /// 读取系统时钟，见 [`Bullet::new`]
+    #[cfg(feature = "std")]
+    #[deprecated(note = "reads the system clock; use `apply_metadata_at`")]
     pub fn apply_metadata(&mut self, metadata: BTreeMap<String, u32>) {
+        self.apply_metadata_at(metadata, SystemClock.now());
+    }
+
+    pub fn apply_metadata_at(&mut self, metadata: BTreeMap<String, u32>, now: DateTime<Utc>) {
         for (key, value) in metadata {
-            match key.as_str() {
-                "helpful" => self.helpful = value,
-                "harmful" => self.harmful = value,
-                "neutral" => self.neutral = value,
-                _ => continue,
+            if let Some(tag) = Tag::parse(&key) {
+                *self.count_mut(tag) = value;
             }
         }
-        self.updated_at = Utc::now();
+        self.updated_at = now;
     }
 
-    /// 给子弹打标签（增量修改，支持正负值）
+    /// 给子弹打标签（增量修改，支持正负值）；读取系统时钟，见 [`Bullet::new`]
+    #[cfg(feature = "std")]
+    #[deprecated(note = "reads the system clock; use `tag_at`")]
     pub fn tag(&mut self, tag: &str, increment: i32) -> Result<(), PlaybookError> {
+        self.tag_at(tag, increment, SystemClock.now())
+    }
+
+    pub fn tag_at(
+        &mut self,
+        tag: &str,
+        increment: i32,
+        now: DateTime<Utc>,
+    ) -> Result<(), PlaybookError> {
+        let count = self.count_mut(tag.parse()?);
         // 用saturating_add_signed避免溢出（u32不能为负，最小到0）
+        *count = count.saturating_add_signed(increment);
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// 把给出的标签设为指定计数（负数截断为0）
+    pub fn set_counts_at(&mut self, counts: &TagCounts, now: DateTime<Utc>) {
+        for (tag, value) in counts.iter() {
+            *self.count_mut(tag) = value.max(0) as u32;
+        }
+        self.updated_at = now;
+    }
+
+    /// 按给出的增量修改计数，同`tag_at`但标签已经解析过
+    pub fn add_counts_at(&mut self, counts: &TagCounts, now: DateTime<Utc>) {
+        for (tag, increment) in counts.iter() {
+            let count = self.count_mut(tag);
+            *count = count.saturating_add_signed(increment);
+        }
+        self.updated_at = now;
+    }
+
+    /// 某个标签的计数
+    pub fn count(&self, tag: &Tag) -> u32 {
         match tag {
-            "helpful" => self.helpful = self.helpful.saturating_add_signed(increment),
-            "harmful" => self.harmful = self.harmful.saturating_add_signed(increment),
-            "neutral" => self.neutral = self.neutral.saturating_add_signed(increment),
-            _ => return Err(PlaybookError::InvalidTag(tag.to_string())),
+            Tag::Helpful => self.helpful,
+            Tag::Harmful => self.harmful,
+            Tag::Neutral => self.neutral,
+            Tag::Custom(name) => self.custom.get(name).copied().unwrap_or(0),
+        }
+    }
+
+    /// 内置标签与有计数记录的自定义标签
+    pub fn tags(&self) -> impl Iterator<Item = Tag> + '_ {
+        Tag::ALL.into_iter().chain(self.custom.keys().cloned().map(Tag::Custom))
+    }
+
+    fn count_mut(&mut self, tag: Tag) -> &mut u32 {
+        match tag {
+            Tag::Helpful => &mut self.helpful,
+            Tag::Harmful => &mut self.harmful,
+            Tag::Neutral => &mut self.neutral,
+            Tag::Custom(name) => self.custom.entry(name).or_default(),
+        }
+    }
+}
+
+/// 提示词中的章节标题行（非首行时先换行）
+pub(crate) fn write_section_header(out: &mut String, section: &str) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    let _ = write!(out, "## {}", section);
+}
+
+/// 默认格式的子弹行，计数顺序为 helpful / harmful / neutral；Playbook的渲染按
+/// [`Playbook::line_format`]，这里用于没有Playbook实例的只读视图
+#[cfg(feature = "persist")]
+pub(crate) fn write_bullet_line(out: &mut String, id: &str, content: &str, counters: [u32; 3]) {
+    let [helpful, harmful, neutral] = counters;
+    let _ = write!(
+        out,
+        "\n- [{}] {} (helpful={}, harmful={}, neutral={})",
+        id, content, helpful, harmful, neutral
+    );
+}
+
+/// 章节级聚合计数，随增删改增量维护，统计时不必扫描全部子弹
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SectionRollup {
+    pub bullets: usize,
+    pub helpful: u64,
+    pub harmful: u64,
+    pub neutral: u64,
+    /// 章节内子弹`updated_at`的最大值（删除子弹不会使其回退）
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+impl SectionRollup {
+    fn add(&mut self, bullet: &Bullet) {
+        self.bullets += 1;
+        self.helpful += bullet.helpful as u64;
+        self.harmful += bullet.harmful as u64;
+        self.neutral += bullet.neutral as u64;
+        self.last_updated = self.last_updated.max(Some(bullet.updated_at));
+    }
+
+    fn remove(&mut self, bullet: &Bullet) {
+        self.bullets = self.bullets.saturating_sub(1);
+        self.helpful = self.helpful.saturating_sub(bullet.helpful as u64);
+        self.harmful = self.harmful.saturating_sub(bullet.harmful as u64);
+        self.neutral = self.neutral.saturating_sub(bullet.neutral as u64);
+    }
+
+    /// 某个标签的章节合计；自定义标签不做聚合，总是0
+    pub fn count(&self, tag: &Tag) -> u64 {
+        match tag {
+            Tag::Helpful => self.helpful,
+            Tag::Harmful => self.harmful,
+            Tag::Neutral => self.neutral,
+            Tag::Custom(_) => 0,
         }
-        self.updated_at = Utc::now();
-        Ok(())
     }
 }
 
+/// [`Playbook::merge_bullets`]合并后的内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeContent {
+    /// 保留第一个子弹的内容
+    Keep,
+    /// 按顺序拼接各子弹的内容（以空格分隔，跳过重复的内容）
+    Concatenate,
+    /// 替换为新内容
+    Replace(String),
+}
+
 // --------------------------
 // 核心存储结构（Playbook）
 // --------------------------
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Playbook {
-    pub bullets: HashMap<String, Bullet>,
-    pub sections: HashMap<String, Vec<String>>,
+    /// 子弹ID → 子弹；迭代顺序不确定，需要稳定顺序时用 [`Playbook::bullets`]
+    // 子弹以`Arc`共享：克隆Playbook（快照、预览）只复制指针，修改时由`Arc::make_mut`
+    // 复制被改的那一个子弹（写时复制），未修改的子弹在副本之间共享
+    #[serde(serialize_with = "serialize_sorted")]
+    #[cfg_attr(feature = "schema", schemars(with = "BTreeMap<BulletId, Bullet>"))]
+    pub bullets: HashMap<BulletId, Arc<Bullet>>,
+    /// 章节 → 章节内子弹ID（插入顺序）；迭代顺序不确定，按名称排序用 [`Playbook::sorted_sections`]
+    #[serde(serialize_with = "serialize_sorted")]
+    #[cfg_attr(feature = "schema", schemars(with = "BTreeMap<SectionName, Vec<BulletId>>"))]
+    pub sections: HashMap<SectionName, Vec<BulletId>>,
     pub next_id: u64,
+    /// few-shot示例（见 [`crate::models::example`]），与子弹分开存储
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub examples: BTreeMap<ExampleId, Example>,
+    /// 错误模式记录（见 [`crate::models::failure`]）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failures: BTreeMap<FailureId, Failure>,
+    /// 章节别名 → 规范章节名（见 [`Playbook::add_section_alias`]）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub section_aliases: BTreeMap<String, String>,
+    /// 已过期并归档的子弹（见 [`Playbook::archive_expired`]），不参与渲染
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub archived: BTreeMap<BulletId, Bullet>,
+    /// 时间戳来源（不序列化，加载后为默认时钟，可用 [`Playbook::set_clock`] 替换）
+    #[serde(skip, default = "clock::default_clock")]
+    clock: SharedClock,
+    /// apply_delta时检查的规模限制（不序列化，默认不限制）
+    #[serde(skip)]
+    limits: DeltaLimits,
+    /// 冻结整个Playbook（不序列化），见 [`Playbook::set_frozen`]
+    #[serde(skip)]
+    frozen: bool,
+    /// 冻结的章节（不序列化），见 [`Playbook::freeze_section`]
+    #[serde(skip)]
+    frozen_sections: BTreeSet<String>,
+    /// 章节名规范化规则（不序列化，默认不规范化），见 [`Playbook::set_section_normalizer`]
+    #[serde(skip)]
+    section_normalizer: SectionNormalizer,
+    /// 容量限制与淘汰策略（不序列化，默认不限制），apply_delta后执行
+    #[serde(skip)]
+    capacity: Capacity,
+    /// apply_delta中ADD/UPDATE内容的审核策略（不序列化）
+    #[serde(skip)]
+    content_policy: Option<SharedContentPolicy>,
+    /// 排名用的评分（不序列化，默认 [`scoring::NetCount`]）
+    #[serde(skip, default = "scoring::default_scorer")]
+    scorer: SharedScorer,
+    /// 子弹行的渲染格式（不序列化，默认 [`DEFAULT_LINE_FORMAT`](crate::models::line_format::DEFAULT_LINE_FORMAT)）
+    #[serde(skip)]
+    line_format: LineFormat,
+    /// 章节聚合缓存（不序列化，加载时重建）。直接修改`bullets`/`sections`后需调用
+    /// [`Playbook::rebuild_rollups`]
+    #[serde(skip)]
+    pub(crate) rollups: BTreeMap<SectionName, SectionRollup>,
+    /// 修订号（不序列化），见 [`Playbook::revision`]
+    #[serde(skip, default = "next_revision")]
+    pub(crate) revision: u64,
+    /// 应用成功后通知的观察者（不序列化，不随克隆复制），见 [`crate::models::observer`]
+    #[serde(skip)]
+    pub(crate) observers: Observers,
+}
+
+/// 修订号来源，进程内全局递增，不同Playbook的修订号互不相同
+static REVISIONS: AtomicU64 = AtomicU64::new(1);
+
+/// 去掉列表行首的符号与编号（可重复，如`- 1. xxx`）
+fn strip_list_marker(line: &str) -> &str {
+    let mut rest = line.trim();
+    loop {
+        let stripped = rest
+            .strip_prefix(['-', '*', '+', '•', '·', '>'])
+            .or_else(|| strip_numbering(rest))
+            .map(str::trim_start);
+        match stripped {
+            Some(s) if s.len() < rest.len() => rest = s,
+            _ => return rest,
+        }
+    }
+}
+
+/// `1.`、`2)`、`(3)`、`4、`形式的编号
+fn strip_numbering(line: &str) -> Option<&str> {
+    let (open, body) = match line.strip_prefix('(') {
+        Some(body) => (true, body),
+        None => (false, line),
+    };
+    let digits = body.len() - body.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    let after = &body[digits..];
+    if open {
+        return after.strip_prefix(')');
+    }
+    // `3.14`不是编号：`.`和`)`后需要空白；中文的`、`后通常直接跟正文
+    after.strip_prefix('、').or_else(|| {
+        after
+            .strip_prefix(['.', ')'])
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+    })
+}
+
+/// 拆出子弹行末尾的`(helpful=1, harmful=0, neutral=2)`；没有或格式不符时整行都是内容
+fn split_counters(line: &str) -> (&str, Option<[u32; 3]>) {
+    let parsed = line.rsplit_once(" (helpful=").and_then(|(content, tail)| {
+        let tail = tail.strip_suffix(')')?;
+        let (helpful, tail) = tail.split_once(", harmful=")?;
+        let (harmful, neutral) = tail.split_once(", neutral=")?;
+        Some((content, [helpful.parse().ok()?, harmful.parse().ok()?, neutral.parse().ok()?]))
+    });
+    match parsed {
+        Some((content, counters)) => (content, Some(counters)),
+        None => (line, None),
+    }
+}
+
+/// 按键排序写出哈希表，序列化结果与插入顺序、哈希种子无关
+fn serialize_sorted<K: Ord + Serialize, V: Serialize, S: Serializer>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut entries: Vec<(&K, &V)> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    serializer.collect_map(entries)
+}
+
+fn next_revision() -> u64 {
+    REVISIONS.fetch_add(1, Ordering::Relaxed)
+}
+
+impl Default for Playbook {
+    fn default() -> Self {
+        Self::with_clock(clock::default_clock())
+    }
 }
 
 impl fmt::Display for Playbook {
@@ -108,296 +542,1261 @@ impl fmt::Display for Playbook {
     }
 }
 
-impl Default for Playbook {
-    fn default() -> Self {
+impl Playbook {
+    /// 创建空的Playbook实例
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用指定时钟创建空Playbook（测试/回放注入固定时钟得到确定的时间戳）
+    pub fn with_clock(clock: SharedClock) -> Self {
         Self {
             bullets: HashMap::new(),
             sections: HashMap::new(),
             next_id: 0,
+            examples: BTreeMap::new(),
+            failures: BTreeMap::new(),
+            section_aliases: BTreeMap::new(),
+            archived: BTreeMap::new(),
+            clock,
+            limits: DeltaLimits::default(),
+            frozen: false,
+            frozen_sections: BTreeSet::new(),
+            section_normalizer: SectionNormalizer::default(),
+            capacity: Capacity::default(),
+            content_policy: None,
+            scorer: scoring::default_scorer(),
+            line_format: LineFormat::default(),
+            rollups: BTreeMap::new(),
+            revision: next_revision(),
+            observers: Observers::default(),
         }
     }
-}
 
-impl Playbook {
-    /// 创建空的Playbook实例
-    pub fn new() -> Self {
-        Self::default()
+    /// 按预计子弹数预分配容量（大批量导入时避免反复rehash）
+    pub fn with_capacity(bullets: usize) -> Self {
+        let mut playbook = Self::new();
+        playbook.bullets.reserve(bullets);
+        playbook
     }
 
-    // --------------------------
-    // 核心CRUD方法
-    // --------------------------
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
 
-    pub fn add_bullet(
-        &mut self,
-        section: String,
-        content: String,
-        bullet_id: Option<String>,
-        metadata: Option<BTreeMap<String, u32>>,
-    ) -> &Bullet {
-        let bullet_id = bullet_id.unwrap_or_else(|| self.generate_id(&section));
-        let mut bullet = Bullet::new(section.clone(), content);
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.clock = clock;
+    }
 
-        if let Some(meta) = metadata {
-            bullet.apply_metadata(meta);
-        }
+    pub fn section_normalizer(&self) -> &SectionNormalizer {
+        &self.section_normalizer
+    }
 
-        self.bullets.insert(bullet_id.clone(), bullet);
-        self.sections.entry(section).or_default().push(bullet_id.clone());
+    /// 替换规范化规则，已有章节与别名按新规则合并，返回被移动的子弹数
+    pub fn set_section_normalizer(&mut self, normalizer: SectionNormalizer) -> usize {
+        self.section_normalizer = normalizer;
+        self.renormalize_sections()
+    }
 
-        self.bullets.get(&bullet_id).unwrap()
+    pub fn limits(&self) -> &DeltaLimits {
+        &self.limits
     }
 
-    pub fn update_bullet(
-        &mut self,
-        bullet_id: &str,
-        content: Option<String>,
-        metadata: Option<BTreeMap<String, u32>>,
-    ) -> Result<&Bullet, PlaybookError> {
-        let bullet = self
-            .bullets
-            .get_mut(bullet_id)
-            .ok_or_else(|| PlaybookError::BulletNotFound(bullet_id.to_string()))?;
+    pub fn set_limits(&mut self, limits: DeltaLimits) {
+        self.limits = limits;
+    }
 
-        if let Some(c) = content {
-            bullet.content = c;
-        }
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
 
-        if let Some(meta) = metadata {
-            bullet.apply_metadata(meta);
-        }
+    /// 冻结后apply_delta拒绝ADD/UPDATE/REMOVE与示例操作，也不再按容量淘汰；
+    /// TAG操作与 [`Playbook::tag_bullet`] 照常生效，评测轮次可以在固定的上下文上累积反馈
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
 
-        bullet.updated_at = Utc::now();
+    /// 只冻结一个章节（可用别名），规则同 [`Playbook::set_frozen`]
+    pub fn freeze_section(&mut self, section: &str) {
+        let section = self.resolve_section(section).into_owned();
+        self.frozen_sections.insert(section);
+    }
 
-        Ok(bullet)
+    pub fn unfreeze_section(&mut self, section: &str) {
+        let section = self.resolve_section(section).into_owned();
+        self.frozen_sections.remove(&section);
     }
 
-    pub fn tag_bullet(
-        &mut self,
-        bullet_id: &str,
-        tag: &str,
-        increment: i32,
-    ) -> Result<&Bullet, PlaybookError> {
-        let bullet = self
-            .bullets
-            .get_mut(bullet_id)
-            .ok_or_else(|| PlaybookError::BulletNotFound(bullet_id.to_string()))?;
+    pub fn is_section_frozen(&self, section: &str) -> bool {
+        self.frozen || self.frozen_sections.contains(&*self.resolve_section(section))
+    }
 
-        bullet.tag(tag, increment)?;
-        Ok(bullet)
+    pub fn capacity(&self) -> &Capacity {
+        &self.capacity
     }
 
-    pub fn remove_bullet(&mut self, bullet_id: &str) -> Option<Bullet> {
-        let bullet = self.bullets.remove(bullet_id)?;
+    /// 设置容量（不会立即淘汰，下次apply_delta或调用 [`Playbook::compact`] 时执行）
+    pub fn set_capacity(&mut self, capacity: Capacity) {
+        self.capacity = capacity;
+    }
 
-        if let Some(section_ids) = self.sections.get_mut(&bullet.section) {
-            section_ids.retain(|id| id != bullet_id);
-            if section_ids.is_empty() {
-                self.sections.remove(&bullet.section);
-            }
-        }
-        Some(bullet)
+    pub fn content_policy(&self) -> Option<&SharedContentPolicy> {
+        self.content_policy.as_ref()
     }
 
-    pub fn get_bullet(&self, bullet_id: &str) -> Option<&Bullet> {
-        self.bullets.get(bullet_id)
+    pub fn set_content_policy(&mut self, policy: Option<SharedContentPolicy>) {
+        self.content_policy = policy;
     }
 
-    pub fn bullets(&self) -> Vec<&Bullet> {
-        self.bullets.values().collect()
+    pub fn scorer(&self) -> &SharedScorer {
+        &self.scorer
     }
 
-    // --------------------------
-    // Delta批量操作（对齐Python功能）
-    // --------------------------
+    pub fn set_scorer(&mut self, scorer: SharedScorer) {
+        self.scorer = scorer;
+    }
 
-    /// 应用Delta批量操作（添加/更新/标签/删除）
-    pub fn apply_delta(&mut self, delta: DeltaBatch) -> Result<(), PlaybookError> {
-        for operation in delta.operations {
-            self._apply_operation(operation)?;
-        }
-        Ok(())
+    pub fn line_format(&self) -> &LineFormat {
+        &self.line_format
     }
 
-    /// 执行单个Delta操作
-    fn _apply_operation(&mut self, op: DeltaOperation) -> Result<(), PlaybookError> {
+    /// 替换子弹行的渲染格式；非默认格式的输出不能再用 [`Playbook::parse_prompt`] 解析
+    pub fn set_line_format(&mut self, format: LineFormat) {
+        self.line_format = format;
+    }
 
-        match op.type_ {
-            OperationType::Add => {
-                let metadata = if op.metadata.is_empty() {
-                    None
-                } else {
-                    Some(
-                        op.metadata
-                            .into_iter()
-                            .map(|(k, v)| (k, v.max(0) as u32))
-                            .collect::<BTreeMap<_,_>>(),
-                    )
-                };
+    /// 按当前格式写一行子弹（各种提示词渲染共用）
+    pub(crate) fn write_bullet(&self, out: &mut String, bullet: &Bullet, content: &str) {
+        let counters = [bullet.helpful, bullet.harmful, bullet.neutral];
+        self.line_format.write(out, &bullet.section, &bullet.id, content, counters);
+    }
 
-                self.add_bullet(
-                    op.section,
-                    op.content.unwrap_or_default(),
-                    op.bullet_id,
-                    metadata,
-                );
-                Ok(())
-            }
+    /// 用当前评分给子弹打分
+    pub fn score(&self, bullet: &Bullet) -> f64 {
+        self.scorer.score(bullet, self.clock.now())
+    }
 
-            OperationType::Update => {
-                let bullet_id = op.bullet_id.ok_or_else(|| {
-                    PlaybookError::DeltaMissingField("bullet_id required for UPDATE".to_string())
-                })?;
+    /// 全部子弹按分数从高到低排列（同分按ID）
+    pub fn ranked_bullets(&self) -> Vec<&Bullet> {
+        let now = self.clock.now();
+        let mut scored: Vec<(f64, &Bullet)> =
+            self.bullets.values().map(|b| (self.scorer.score(b, now), &**b)).collect();
+        scored.sort_by(|(a, x), (b, y)| b.total_cmp(a).then_with(|| x.id.cmp(&y.id)));
+        scored.into_iter().map(|(_, b)| b).collect()
+    }
 
-                let metadata = if op.metadata.is_empty() {
-                    None
-                } else {
-                    Some(
-                        op.metadata
-                            .into_iter()
-                            .map(|(k, v)| (k, v.max(0) as u32))
-                            .collect::<BTreeMap<_, _>>(),
-                    )
-                };
+    /// 分数最低的`n`个子弹（最低的在前），剪枝与淘汰的候选
+    pub fn lowest_scored(&self, n: usize) -> Vec<&Bullet> {
+        let mut ranked = self.ranked_bullets();
+        ranked.reverse();
+        ranked.truncate(n);
+        ranked
+    }
 
-                self.update_bullet(&bullet_id, op.content, metadata)?;
-                Ok(())
-            }
+    // --------------------------
+    // 核心CRUD方法
+    // --------------------------
 
-            OperationType::Tag => {
-                let bullet_id = op.bullet_id.ok_or_else(|| {
-                    PlaybookError::DeltaMissingField("bullet_id required for TAG".to_string())
-                })?;
+    /// 添加子弹，返回其ID（需要子弹本身时再用 [`Playbook::get_bullet`] 获取）
+    ///
+    /// 指定的ID已存在时，旧子弹会被替换。
+    pub fn add_bullet(
+        &mut self,
+        section: impl Into<String>,
+        content: impl Into<String>,
+        bullet_id: Option<BulletId>,
+        metadata: Option<BTreeMap<String, u32>>,
+    ) -> BulletId {
+        let now = self.clock.now();
+        self.add_bullet_at(section, content, bullet_id, metadata, now)
+    }
 
-                // 批量应用标签增量
-                for (tag, increment) in op.metadata {
-                    self.tag_bullet(&bullet_id, &tag, increment)?;
-                }
-                Ok(())
+    /// 以给定时间戳添加子弹
+    pub fn add_bullet_at(
+        &mut self,
+        section: impl Into<String>,
+        content: impl Into<String>,
+        bullet_id: Option<BulletId>,
+        metadata: Option<BTreeMap<String, u32>>,
+        now: DateTime<Utc>,
+    ) -> BulletId {
+        self.add_bullet_with(&section.into(), content.into(), bullet_id, now, |bullet| {
+            if let Some(meta) = metadata {
+                bullet.apply_metadata_at(meta, now);
             }
+        })
+    }
 
-            OperationType::Remove => {
-                let bullet_id = op.bullet_id.ok_or_else(|| {
-                    PlaybookError::DeltaMissingField("bullet_id required for REMOVE".to_string())
-                })?;
-
-                self.remove_bullet(&bullet_id);
-                Ok(())
+    /// 添加子弹，`init`在计入章节聚合之前修改新子弹（计数、技能等）
+    fn add_bullet_with(
+        &mut self,
+        section: &str,
+        content: String,
+        bullet_id: Option<BulletId>,
+        now: DateTime<Utc>,
+        init: impl FnOnce(&mut Bullet),
+    ) -> BulletId {
+        let section = self.intern_section(&self.resolve_section(section));
+        let bullet_id = match bullet_id {
+            Some(id) => {
+                self.reserve_id(&id);
+                id
             }
-
+            None => self.generate_id(&section),
+        };
+        if self.bullets.contains_key(&bullet_id) {
+            self.remove_bullet(&bullet_id);
         }
-    }
 
-    // --------------------------
-    // 序列化/反序列化（对齐Python）
-    // --------------------------
+        self.sections
+            .entry(section.clone())
+            .or_default()
+            .push(bullet_id.clone());
 
-    /// 转换为JSON字符串（带格式化，易读）
-    pub fn to_json(&self) -> Result<String, PlaybookError> {
-        Ok(serde_json::to_string_pretty(self)?)
-    }
+        let mut bullet = Bullet::new_at(section, content, now);
+        bullet.id = bullet_id.clone();
+        init(&mut bullet);
+        self.rollups.entry(bullet.section.clone()).or_default().add(&bullet);
+        self.bullets.insert(bullet_id.clone(), Arc::new(bullet));
+        self.revision = next_revision();
 
-    /// 从JSON字符串解析Playbook
-    pub fn from_json(data: &str) -> Result<Self, PlaybookError> {
-        serde_json::from_str(data)
-            .map_err(|e| PlaybookError::InvalidData(format!("Failed to parse JSON: {}", e)))
+        bullet_id
     }
 
-    /// 保存到文件（自动创建父目录）
-    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), PlaybookError> {
-        let path = path.as_ref();
-        // 自动创建父目录（避免文件路径不存在报错）
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+    /// 批量载入已构造好的子弹（反序列化、导入用），保留其ID、章节名与时间戳
+    ///
+    /// 不逐个处理章节别名、替换同ID子弹或更新修订号：先整体校验（ID非空且与已有子弹
+    /// 及批内其他子弹不重复、章节名非空），任一失败时不做任何修改；通过后一次性建立章节索引
+    /// 与聚合，修订号只更新一次，`next_id`推进到已载入ID的最大序号之后。
+    pub fn bulk_load(
+        &mut self,
+        bullets: impl IntoIterator<Item = Bullet>,
+    ) -> Result<(), PlaybookError> {
+        let bullets: Vec<Bullet> = bullets.into_iter().collect();
+        let mut ids = BTreeSet::new();
+        for bullet in &bullets {
+            let invalid = |msg: &str| {
+                PlaybookError::InvalidData(format!("bullet '{}': {}", bullet.id, msg))
+            };
+            if bullet.id.is_empty() {
+                return Err(invalid("empty id"));
+            }
+            if bullet.section.trim().is_empty() {
+                return Err(invalid("empty section"));
+            }
+            if self.bullets.contains_key(&bullet.id) || !ids.insert(bullet.id.as_str()) {
+                return Err(invalid("duplicate bullet id"));
+            }
         }
 
-        let json = self.to_json()?;
-        fs::write(path, json)?;
+        let mut section: Option<SectionName> = None;
+        for mut bullet in bullets {
+            // 输入通常按章节分组，章节不变时沿用上一份章节名
+            let name = match &section {
+                Some(name) if **name == *bullet.section => name.clone(),
+                _ => section.insert(self.intern_section(&bullet.section)).clone(),
+            };
+            bullet.section = name.clone();
+            self.reserve_id(&bullet.id);
+            self.rollups.entry(name.clone()).or_default().add(&bullet);
+            self.sections.entry(name).or_default().push(bullet.id.clone());
+            self.bullets.insert(bullet.id.clone(), Arc::new(bullet));
+        }
+        self.touch();
         Ok(())
     }
 
-    /// 从文件加载（处理文件不存在的情况）
-    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, PlaybookError> {
-        let path = path.as_ref();
-        if !path.exists() {
-            return Err(PlaybookError::IoError(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("Playbook file not found: {}", path.display()),
-            )));
-        }
-
-        let mut file = File::open(path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
+    /// 导入纯文本列表（会议记录、复盘等）：每个非空行一个子弹，去掉行首的`-`、`*`、`•`
+    /// 等符号和`1.`、`2)`、`(3)`之类的编号；返回新子弹的ID（同一时间戳）
+    pub fn import_text(&mut self, section: &str, text: &str) -> Vec<BulletId> {
+        self.import_text_with_progress(section, text, &mut ())
+    }
 
-        self::Playbook::from_json(&contents)
+    /// 同`import_text`，每导入一行报告一次进度
+    pub fn import_text_with_progress(
+        &mut self,
+        section: &str,
+        text: &str,
+        sink: &mut dyn ProgressSink,
+    ) -> Vec<BulletId> {
+        let now = self.clock.now();
+        let lines: Vec<&str> =
+            text.lines().map(strip_list_marker).filter(|line| !line.is_empty()).collect();
+        let mut progress =
+            ProgressTracker::new(sink, self.clock.clone(), "import", Some(lines.len()));
+        lines
+            .into_iter()
+            .map(|line| {
+                let id = self.add_bullet_at(section, line, None, None, now);
+                progress.advance(1);
+                id
+            })
+            .collect()
     }
 
-    // --------------------------
-    // 辅助方法（对齐Python）
-    // --------------------------
+    pub fn update_bullet(
+        &mut self,
+        bullet_id: &str,
+        content: Option<String>,
+        metadata: Option<BTreeMap<String, u32>>,
+    ) -> Result<&Bullet, PlaybookError> {
+        let now = self.clock.now();
+        self.update_bullet_at(bullet_id, content, metadata, now)
+    }
 
-    /// 转换为LLM提示词格式（有序输出章节和子弹）
-    pub fn as_prompt(&self) -> String {
-        let mut parts = Vec::new();
+    /// 以给定时间戳更新子弹
+    pub fn update_bullet_at(
+        &mut self,
+        bullet_id: &str,
+        content: Option<String>,
+        metadata: Option<BTreeMap<String, u32>>,
+        now: DateTime<Utc>,
+    ) -> Result<&Bullet, PlaybookError> {
+        self.modify_bullet(bullet_id, |bullet| {
+            if let Some(c) = content {
+                bullet.content = c;
+            }
 
-        // 章节按字母排序（保证输出一致性，对齐Python的sorted）
-        let mut sorted_sections: Vec<_> = self.sections.keys().collect();
-        sorted_sections.sort();
-
-        for section in sorted_sections {
-            parts.push(format!("## {}", section));
-
-            // 子弹按插入顺序输出（HashMap的values顺序不保证，但章节内的ID列表是插入顺序）
-            if let Some(bullet_ids) = self.sections.get(section) {
-                for bullet_id in bullet_ids {
-                    if let Some(bullet) = self.bullets.get(bullet_id) {
-                        let counters = format!(
-                            "(helpful={}, harmful={}, neutral={})",
-                            bullet.helpful, bullet.harmful, bullet.neutral
-                        );
-                        parts.push(format!("- [{}] {} {}", bullet.id, bullet.content, counters));
-                    }
-                }
+            if let Some(meta) = metadata {
+                bullet.apply_metadata_at(meta, now);
             }
-        }
 
-        parts.join("\n")
+            bullet.updated_at = now;
+            Ok(())
+        })
     }
 
-    /// 获取统计信息（有序输出，用BTreeMap保证JSON字段顺序）
-    pub fn stats(&self) -> BTreeMap<String, serde_json::Value> {
-        let mut tags = BTreeMap::new();
-        tags.insert(
-            "helpful".to_string(),
-            serde_json::Value::Number(
-                self.bullets
-                    .values()
-                    .map(|b| b.helpful as u64)
-                    .sum::<u64>()
-                    .into(),
-            ),
-        );
-        tags.insert(
-            "harmful".to_string(),
-            serde_json::Value::Number(
-                self.bullets
-                    .values()
-                    .map(|b| b.harmful as u64)
-                    .sum::<u64>()
-                    .into(),
-            ),
-        );
-        tags.insert(
-            "neutral".to_string(),
-            serde_json::Value::Number(
-                self.bullets
-                    .values()
-                    .map(|b| b.neutral as u64)
-                    .sum::<u64>()
-                    .into(),
-            ),
-        );
+    pub fn tag_bullet(
+        &mut self,
+        bullet_id: &str,
+        tag: &str,
+        increment: i32,
+    ) -> Result<&Bullet, PlaybookError> {
+        let now = self.clock.now();
+        self.tag_bullet_at(bullet_id, tag, increment, now)
+    }
+
+    /// 以给定时间戳给子弹打标签
+    pub fn tag_bullet_at(
+        &mut self,
+        bullet_id: &str,
+        tag: &str,
+        increment: i32,
+        now: DateTime<Utc>,
+    ) -> Result<&Bullet, PlaybookError> {
+        self.modify_bullet(bullet_id, |bullet| bullet.tag_at(tag, increment, now))
+    }
+
+    /// 设置子弹的某个语言版本，内容为空时删除该版本
+    pub fn set_variant(
+        &mut self,
+        bullet_id: &str,
+        lang: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Result<(), PlaybookError> {
+        let now = self.clock.now();
+        let (lang, content) = (lang.into(), content.into());
+        self.modify_bullet(bullet_id, |bullet| {
+            if content.is_empty() {
+                bullet.variants.remove(&lang);
+            } else {
+                bullet.variants.insert(lang, content);
+            }
+            bullet.updated_at = now;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// 固定或取消固定子弹（不改变`updated_at`）
+    pub fn set_pinned(&mut self, bullet_id: &str, pinned: bool) -> Result<(), PlaybookError> {
+        self.modify_bullet(bullet_id, |bullet| {
+            bullet.pinned = pinned;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// 记录子弹被使用（例如生成结果归因到的子弹），不存在的ID忽略
+    pub fn mark_used(&mut self, bullet_ids: &[BulletId]) {
+        let now = self.clock.now();
+        self.mark_used_at(bullet_ids, now);
+    }
+
+    pub fn mark_used_at(&mut self, bullet_ids: &[BulletId], now: DateTime<Utc>) {
+        for id in bullet_ids {
+            if let Some(bullet) = self.bullets.get_mut(id) {
+                Arc::make_mut(bullet).last_used_at = Some(now);
+            }
+        }
+    }
+
+    /// 窗口期内既没被使用、也没被打标签或修改过的子弹（创建不足一个窗口的不算）
+    pub fn dead_bullets(&self, window: TimeDelta) -> Vec<&Bullet> {
+        self.dead_bullets_at(window, self.clock.now())
+    }
+
+    pub fn dead_bullets_at(&self, window: TimeDelta, now: DateTime<Utc>) -> Vec<&Bullet> {
+        let cutoff = now - window;
+        self.bullets()
+            .into_iter()
+            .filter(|b| {
+                b.created_at <= cutoff
+                    && b.updated_at < cutoff
+                    && b.last_used_at.is_none_or(|used| used < cutoff)
+            })
+            .collect()
+    }
+
+    /// 删除窗口期内死子弹的DeltaBatch
+    pub fn prune_dead_delta(&self, window: TimeDelta) -> DeltaBatch {
+        let operations: Vec<DeltaOperation> = self
+            .dead_bullets(window)
+            .into_iter()
+            .map(|b| DeltaOperation::remove(&*b.section, &b.id))
+            .collect();
+        DeltaBatch {
+            reasoning: format!("prune {} dead bullets", operations.len()),
+            operations,
+        }
+    }
+
+    /// 删除子弹及关联到它的示例
+    pub fn remove_bullet(&mut self, bullet_id: &str) -> Option<Bullet> {
+        let bullet = self.bullets.remove(bullet_id)?;
+        self.revision = next_revision();
+        self.examples.retain(|_, e| e.bullet_id.as_deref() != Some(bullet_id));
+
+        if let Some(rollup) = self.rollups.get_mut(&*bullet.section) {
+            rollup.remove(&bullet);
+            if rollup.bullets == 0 {
+                self.rollups.remove(&*bullet.section);
+            }
+        }
+
+        if let Some(section_ids) = self.sections.get_mut(&*bullet.section) {
+            section_ids.retain(|id| id != bullet_id);
+            if section_ids.is_empty() {
+                self.sections.remove(&*bullet.section);
+            }
+        }
+        Some(Arc::unwrap_or_clone(bullet))
+    }
+
+    /// 把子弹移到`section`章节末尾（按别名解析），保留ID、计数与时间戳，关联的示例随之移动；
+    /// 已在该章节时不做修改
+    pub fn move_bullet(
+        &mut self,
+        bullet_id: &str,
+        section: &str,
+    ) -> Result<&Bullet, PlaybookError> {
+        let target = self.intern_section(&self.resolve_section(section));
+        let from = self
+            .bullets
+            .get(bullet_id)
+            .map(|b| b.section.clone())
+            .ok_or_else(|| PlaybookError::BulletNotFound(bullet_id.to_string()))?;
+        if from == target {
+            return Ok(&self.bullets[bullet_id]);
+        }
+
+        if let Some(section_ids) = self.sections.get_mut(&from) {
+            section_ids.retain(|id| id != bullet_id);
+            if section_ids.is_empty() {
+                self.sections.remove(&from);
+            }
+        }
+        self.sections.entry(target.clone()).or_default().push(bullet_id.to_string());
+        for example in self.examples.values_mut() {
+            if example.bullet_id.as_deref() == Some(bullet_id) {
+                example.section = target.clone();
+            }
+        }
+
+        let bullet = Arc::make_mut(self.bullets.get_mut(bullet_id).expect("checked above"));
+        if let Some(rollup) = self.rollups.get_mut(&from) {
+            rollup.remove(bullet);
+            if rollup.bullets == 0 {
+                self.rollups.remove(&from);
+            }
+        }
+        bullet.section = target;
+        self.rollups.entry(bullet.section.clone()).or_default().add(bullet);
+        self.revision = next_revision();
+        Ok(bullet)
+    }
+
+    /// 把`bullet_ids`中其余子弹并入第一个子弹并删除它们，返回合并后的子弹
+    ///
+    /// 计数相加，`created_at`取最早、`last_used_at`取最晚，任一子弹固定时结果也固定；
+    /// 被并入子弹的示例改为关联到保留的子弹。章节、技能、语言版本与激活条件沿用第一个子弹。
+    /// 少于两个子弹、ID重复或任一子弹不存在时报错，Playbook保持不变。
+    pub fn merge_bullets(
+        &mut self,
+        bullet_ids: &[BulletId],
+        content: MergeContent,
+    ) -> Result<&Bullet, PlaybookError> {
+        let now = self.clock.now();
+        self.merge_bullets_at(bullet_ids, content, now)
+    }
+
+    pub fn merge_bullets_at(
+        &mut self,
+        bullet_ids: &[BulletId],
+        content: MergeContent,
+        now: DateTime<Utc>,
+    ) -> Result<&Bullet, PlaybookError> {
+        let Some((target, merged_ids)) = bullet_ids.split_first().filter(|(_, m)| !m.is_empty())
+        else {
+            return Err(PlaybookError::InvalidData("merge needs at least two bullets".to_string()));
+        };
+        for (i, id) in bullet_ids.iter().enumerate() {
+            if !self.bullets.contains_key(id) {
+                return Err(PlaybookError::BulletNotFound(id.clone()));
+            }
+            if bullet_ids[..i].contains(id) {
+                return Err(PlaybookError::InvalidData(format!("bullet '{}' merged twice", id)));
+            }
+        }
+
+        let section = self.bullets[target].section.clone();
+        for example in self.examples.values_mut() {
+            if example.bullet_id.as_ref().is_some_and(|id| merged_ids.contains(id)) {
+                example.bullet_id = Some(target.clone());
+                example.section = section.clone();
+            }
+        }
+        let merged: Vec<Bullet> =
+            merged_ids.iter().filter_map(|id| self.remove_bullet(id)).collect();
+
+        let content = match content {
+            MergeContent::Keep => None,
+            MergeContent::Concatenate => {
+                let mut contents: Vec<&str> = Vec::from([self.bullets[target].content.as_str()]);
+                for other in &merged {
+                    if !contents.contains(&other.content.as_str()) {
+                        contents.push(&other.content);
+                    }
+                }
+                Some(contents.join(" "))
+            }
+            MergeContent::Replace(content) => Some(content),
+        };
+        self.modify_bullet(target, |bullet| {
+            for other in &merged {
+                for tag in other.tags() {
+                    let count = bullet.count_mut(tag.clone());
+                    *count = count.saturating_add(other.count(&tag));
+                }
+                bullet.created_at = bullet.created_at.min(other.created_at);
+                bullet.last_used_at = bullet.last_used_at.max(other.last_used_at);
+                bullet.pinned |= other.pinned;
+            }
+            if let Some(content) = content {
+                bullet.content = content;
+            }
+            bullet.updated_at = now;
+            Ok(())
+        })
+    }
+
+    /// 修改单个子弹并同步章节聚合（`f`出错时已做的修改同样计入聚合）
+    pub(crate) fn modify_bullet(
+        &mut self,
+        bullet_id: &str,
+        f: impl FnOnce(&mut Bullet) -> Result<(), PlaybookError>,
+    ) -> Result<&Bullet, PlaybookError> {
+        let bullet = self
+            .bullets
+            .get_mut(bullet_id)
+            .map(Arc::make_mut)
+            .ok_or_else(|| PlaybookError::BulletNotFound(bullet_id.to_string()))?;
+        let rollup = self.rollups.entry(bullet.section.clone()).or_default();
+        rollup.remove(bullet);
+        let result = f(bullet);
+        rollup.add(bullet);
+        self.revision = next_revision();
+        result.map(|()| &*bullet)
+    }
+
+    pub fn get_bullet(&self, bullet_id: &str) -> Option<&Bullet> {
+        self.bullets.get(bullet_id).map(|b| &**b)
+    }
+
+    /// 全部子弹，按ID排序
+    pub fn bullets(&self) -> Vec<&Bullet> {
+        let mut bullets: Vec<&Bullet> = self.bullets.values().map(|b| &**b).collect();
+        bullets.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+        bullets
+    }
+
+    /// 全部章节及其子弹ID，按章节名排序
+    pub fn sorted_sections(&self) -> Vec<(&SectionName, &Vec<BulletId>)> {
+        let mut sections: Vec<_> = self.sections.iter().collect();
+        sections.sort_unstable_by_key(|(section, _)| *section);
+        sections
+    }
+
+    /// 找出文本中以`[id]`形式引用、且确实存在的子弹（按首次出现顺序去重）
+    pub fn cited_bullets(&self, text: &str) -> Vec<BulletId> {
+        let mut cited: Vec<BulletId> = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('[') {
+            rest = &rest[start + 1..];
+            let Some(end) = rest.find(']') else { break };
+            let candidate = rest[..end].trim();
+            if self.bullets.contains_key(candidate) && !cited.iter().any(|id| id == candidate) {
+                cited.push(candidate.to_string());
+            }
+        }
+        cited
+    }
+
+    // --------------------------
+    // Delta批量操作（对齐Python功能）
+    // --------------------------
+
+    /// 应用Delta批量操作（添加/更新/标签/删除）
+    ///
+    /// 整批操作共用一个时间戳。
+    pub fn apply_delta(&mut self, delta: DeltaBatch) -> Result<(), PlaybookError> {
+        let now = self.clock.now();
+        self.apply_delta_at(delta, now)
+    }
+
+    /// 以给定时间戳应用Delta批量操作
+    ///
+    /// 超出 [`DeltaLimits`] 或任一内容被策略拒绝时整批拒绝，不应用任何操作；
+    /// 应用后超出 [`Capacity`] 的子弹按淘汰策略删除。某个操作失败时，它之前的操作已经生效；
+    /// 需要整批回滚时用 [`Playbook::apply_delta_transactional`]。
+    pub fn apply_delta_at(
+        &mut self,
+        delta: DeltaBatch,
+        now: DateTime<Utc>,
+    ) -> Result<(), PlaybookError> {
+        self.apply_delta_inner(delta, now, None, None, false)
+    }
+
+    /// 全部成功或全部不生效地应用Delta，返回实际产生的变化（格式同 [`Playbook::diff`]）
+    ///
+    /// `apply_delta`在某个操作失败时已应用的操作不会撤回；这里在每个对象首次被修改前记录
+    /// 它的原状态（见 [`crate::models::undo`]），失败时原样写回，Playbook保持不变。
+    /// 记录与返回的变化都只涉及被操作触及的子弹、示例与章节，不复制整个Playbook。
+    pub fn apply_delta_transactional(
+        &mut self,
+        delta: DeltaBatch,
+    ) -> Result<DeltaBatch, PlaybookError> {
+        let now = self.clock.now();
+        self.apply_delta_transactional_at(delta, now)
+    }
+
+    pub fn apply_delta_transactional_at(
+        &mut self,
+        delta: DeltaBatch,
+        now: DateTime<Utc>,
+    ) -> Result<DeltaBatch, PlaybookError> {
+        let mut journal = self.apply_delta_journaled_at(delta, now)?;
+        journal.commit(self);
+        Ok(journal.changes(self))
+    }
+
+    /// `inverse`不为空时记录每个操作的原状态，用于生成反向批次（见 [`crate::models::undo`]）；
+    /// `journal`不为空时记录被修改对象的完整原状态，用于整批回滚，观察者的通知留给
+    /// [`Journal::commit`]，否则成功后立即通知。`restore`表示批次恢复的是之前已通过检查的
+    /// 状态（撤销/重做），跳过 [`DeltaLimits`]、内容策略与容量淘汰，只检查冻结
+    pub(crate) fn apply_delta_inner(
+        &mut self,
+        mut delta: DeltaBatch,
+        now: DateTime<Utc>,
+        mut inverse: Option<&mut InverseBuilder>,
+        mut journal: Option<&mut Journal>,
+        restore: bool,
+    ) -> Result<(), PlaybookError> {
+        for op in &mut delta.operations {
+            let canonical = self.resolve_section(&op.section);
+            if canonical != op.section.as_str() {
+                op.section = canonical.into_owned();
+            }
+        }
+        self.check_frozen(&delta)?;
+        if !restore {
+            self.check_limits(&delta)?;
+            self.moderate(&mut delta.operations)?;
+        }
+        let notify = !self.observers.is_empty() && !delta.operations.is_empty();
+        let applied = notify.then(|| AppliedDelta {
+            reasoning: delta.reasoning.clone(),
+            operations: delta.operations.len(),
+            impact: self.delta_impact(&delta),
+            at: now,
+        });
+        let adds = delta.operations.iter().filter(|op| op.type_ == OperationType::Add).count();
+        self.bullets.reserve(adds);
+        for (i, operation) in delta.operations.into_iter().enumerate() {
+            if let Some(journal) = journal.as_deref_mut() {
+                journal.capture(self, &operation);
+            }
+            let Some(inverse) = inverse.as_deref_mut() else {
+                self._apply_operation(operation, now).map_err(PlaybookError::at_operation(i))?;
+                continue;
+            };
+            let prior = Prior::capture(self, &operation);
+            let undo = operation.clone();
+            let created =
+                self._apply_operation(operation, now).map_err(PlaybookError::at_operation(i))?;
+            inverse.record(self, prior, &undo, created);
+        }
+        if !self.frozen && !restore {
+            let policy = self.capacity.eviction;
+            for id in self.eviction_victims(&policy) {
+                if let Some(journal) = journal.as_deref_mut() {
+                    journal.bullet(self, &id);
+                }
+                let examples: Vec<Example> = match inverse {
+                    Some(_) => self.bullet_examples(&id).cloned().collect(),
+                    None => Vec::new(),
+                };
+                if let (Some(bullet), Some(inverse)) = (self.remove_bullet(&id), &mut inverse) {
+                    inverse.evicted(&bullet, &examples);
+                }
+            }
+        }
+        match journal {
+            Some(journal) => journal.applied = applied,
+            None => {
+                if let Some(applied) = &applied {
+                    self.notify_applied(applied);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 冻结时整批拒绝，只放行TAG操作
+    fn check_frozen(&self, delta: &DeltaBatch) -> Result<(), PlaybookError> {
+        for (i, op) in delta.operations.iter().enumerate() {
+            if let Some(section) = self.frozen_section_for(op, &op.section) {
+                let e = PlaybookError::Frozen(format!("{} in section '{}'", op.type_, section));
+                return Err(PlaybookError::at_operation(i)(e));
+            }
+        }
+        Ok(())
+    }
+
+    /// 操作会修改的冻结章节：除TAG外检查`section`（已解析别名的操作章节），以及操作指名的
+    /// 子弹（`bullet_id`、MERGE的`merged_ids`）与示例（`example_id`）当前所在的章节——
+    /// UPDATE、REMOVE等按ID修改对象，不能靠填写别的章节名绕过冻结
+    pub(crate) fn frozen_section_for<'a>(
+        &'a self,
+        op: &DeltaOperation,
+        section: &'a str,
+    ) -> Option<&'a str> {
+        if op.type_ == OperationType::Tag {
+            return None;
+        }
+        let bullets = op.bullet_id.iter().chain(&op.merged_ids);
+        let example = op.example_id.as_deref().and_then(|id| self.examples.get(id));
+        core::iter::once(section)
+            .chain(bullets.filter_map(|id| self.bullets.get(id)).map(|b| &*b.section))
+            .chain(example.map(|e| &*e.section))
+            .find(|section| self.is_section_frozen(section))
+    }
+
+    fn check_limits(&self, delta: &DeltaBatch) -> Result<(), PlaybookError> {
+        match self.limit_violations(delta).into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// 违反 [`DeltaLimits`] 的全部情况，逐操作的违规带操作下标
+    pub(crate) fn limit_violations(&self, delta: &DeltaBatch) -> Vec<PlaybookError> {
+        let limits = &self.limits;
+        let mut violations = Vec::new();
+        let mut exceeded_at = |i, msg: String| {
+            violations.push(PlaybookError::at_operation(i)(PlaybookError::DeltaLimitExceeded(msg)))
+        };
+
+        let mut new_per_section: BTreeMap<&str, usize> = BTreeMap::new();
+        for (i, op) in delta.operations.iter().enumerate() {
+            if let Some(max) = limits.max_content_chars {
+                for (field, text) in Self::moderated_fields(op) {
+                    let chars = text.chars().count();
+                    if chars > max {
+                        exceeded_at(i, format!(
+                            "{} {} in section '{}' has {} chars (max {})",
+                            op.type_, field, op.section, chars, max
+                        ));
+                    }
+                }
+            }
+            let is_new = op.type_ == OperationType::Add
+                && op.bullet_id.as_ref().is_none_or(|id| !self.bullets.contains_key(id));
+            if let (Some(max), true) = (limits.max_new_per_section, is_new) {
+                let count = new_per_section.entry(&op.section).or_default();
+                *count += 1;
+                if *count == max + 1 {
+                    exceeded_at(
+                        i,
+                        format!("more than {} new bullets in section '{}'", max, op.section),
+                    );
+                }
+            }
+        }
+
+        if let Some(max) = limits.max_operations
+            && delta.operations.len() > max
+        {
+            let msg = format!("{} operations in batch (max {})", delta.operations.len(), max);
+            violations.insert(0, PlaybookError::DeltaLimitExceeded(msg));
+        }
+        violations
+    }
+
+    /// 操作中写入Playbook的文本：ADD/UPDATE/MERGE的内容及技能说明，ADD_EXAMPLE的输入和输出
+    pub(crate) fn moderated_fields(op: &DeltaOperation) -> Vec<(&'static str, &String)> {
+        let mut fields: Vec<(&str, &String)> = match op.type_ {
+            OperationType::Add | OperationType::Update | OperationType::Merge => {
+                op.content.iter().map(|c| ("content", c)).collect()
+            }
+            OperationType::AddExample => [("input", &op.input), ("output", &op.output)]
+                .into_iter()
+                .filter_map(|(field, text)| Some((field, text.as_ref()?)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        if let Some(skill) = &op.skill {
+            fields.push(("skill arguments", &skill.arguments));
+            fields.extend(skill.pitfalls.iter().map(|p| ("skill pitfall", p)));
+        }
+        fields
+    }
+
+    /// 用内容策略检查写入Playbook的文本（见`moderated_fields`），脱敏结果直接写回操作
+    fn moderate(&self, operations: &mut [DeltaOperation]) -> Result<(), PlaybookError> {
+        let Some(policy) = &self.content_policy else {
+            return Ok(());
+        };
+        for (i, op) in operations.iter_mut().enumerate() {
+            // UPDATE与MERGE改写已有子弹：按子弹当前所在的章节检查，不信任操作填写的章节
+            let target = match op.type_ {
+                OperationType::Update | OperationType::Merge => op.bullet_id.as_deref(),
+                _ => None,
+            };
+            let target = target.and_then(|id| self.bullets.get(id)).map(|b| b.section.clone());
+            let section = target.as_deref().unwrap_or(&op.section);
+            let mut texts: Vec<&mut String> = match op.type_ {
+                OperationType::Add | OperationType::Update | OperationType::Merge => {
+                    op.content.iter_mut().collect()
+                }
+                OperationType::AddExample => op.input.iter_mut().chain(&mut op.output).collect(),
+                _ => continue,
+            };
+            if let Some(skill) = &mut op.skill {
+                texts.push(&mut skill.arguments);
+                texts.extend(&mut skill.pitfalls);
+            }
+            for text in texts {
+                match policy.check(section, text) {
+                    PolicyDecision::Allow => {}
+                    PolicyDecision::Redact(redacted) => *text = redacted,
+                    PolicyDecision::Reject(reason) => {
+                        let e = PlaybookError::ContentRejected(format!(
+                            "{} in section '{}': {}",
+                            op.type_, section, reason
+                        ));
+                        return Err(PlaybookError::at_operation(i)(e));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 执行单个Delta操作，返回ADD/ADD_EXAMPLE生成（或指定）的ID
+    fn _apply_operation(
+        &mut self,
+        op: DeltaOperation,
+        now: DateTime<Utc>,
+    ) -> Result<Option<String>, PlaybookError> {
+
+        match op.type_ {
+            OperationType::Add => {
+                let counts = TagCounts::known(&op.metadata);
+                let content = op.content.unwrap_or_default();
+                let id = self.add_bullet_with(&op.section, content, op.bullet_id, now, |bullet| {
+                    if !counts.is_empty() {
+                        bullet.set_counts_at(&counts, now);
+                    }
+                    bullet.skill = op.skill;
+                });
+                Ok(Some(id))
+            }
+
+            OperationType::Update => {
+                let bullet_id = op.bullet_id.ok_or_else(|| {
+                    PlaybookError::DeltaMissingField("bullet_id required for UPDATE".to_string())
+                })?;
+
+                let counts = TagCounts::known(&op.metadata);
+                self.modify_bullet(&bullet_id, |bullet| {
+                    if let Some(content) = op.content {
+                        bullet.content = content;
+                    }
+                    if let Some(skill) = op.skill {
+                        bullet.skill = Some(skill);
+                    }
+                    bullet.set_counts_at(&counts, now);
+                    Ok(())
+                })?;
+                Ok(None)
+            }
+
+            OperationType::Tag => {
+                let bullet_id = op.bullet_id.ok_or_else(|| {
+                    PlaybookError::DeltaMissingField("bullet_id required for TAG".to_string())
+                })?;
+
+                // 先解析全部标签，再一次修改子弹（未知标签时子弹不变）
+                let counts = TagCounts::parse(&op.metadata)?;
+                self.modify_bullet(&bullet_id, |bullet| {
+                    if !counts.is_empty() {
+                        bullet.add_counts_at(&counts, now);
+                    }
+                    Ok(())
+                })?;
+                Ok(None)
+            }
+
+            OperationType::Remove => {
+                let bullet_id = op.bullet_id.ok_or_else(|| {
+                    PlaybookError::DeltaMissingField("bullet_id required for REMOVE".to_string())
+                })?;
+
+                self.remove_bullet(&bullet_id);
+                Ok(None)
+            }
+
+            OperationType::Move => {
+                let bullet_id = op.bullet_id.ok_or_else(|| {
+                    PlaybookError::DeltaMissingField("bullet_id required for MOVE".to_string())
+                })?;
+
+                self.move_bullet(&bullet_id, &op.section)?;
+                Ok(None)
+            }
+
+            OperationType::Merge => {
+                let bullet_id = op.bullet_id.ok_or_else(|| {
+                    PlaybookError::DeltaMissingField("bullet_id required for MERGE".to_string())
+                })?;
+                if op.merged_ids.is_empty() {
+                    return Err(PlaybookError::DeltaMissingField(
+                        "merged_ids required for MERGE".to_string(),
+                    ));
+                }
+
+                let content = match (op.content, op.merge_strategy) {
+                    (Some(content), None) => MergeContent::Replace(content),
+                    (None, None | Some(MergeStrategy::Keep)) => MergeContent::Keep,
+                    (None, Some(MergeStrategy::Concatenate)) => MergeContent::Concatenate,
+                    (Some(_), Some(_)) => {
+                        return Err(PlaybookError::InvalidData(
+                            "content and merge_strategy are exclusive for MERGE".to_string(),
+                        ));
+                    }
+                };
+                let mut bullet_ids = op.merged_ids;
+                bullet_ids.insert(0, bullet_id);
+                self.merge_bullets_at(&bullet_ids, content, now)?;
+                Ok(None)
+            }
+
+            OperationType::AddExample => {
+                let missing = |field: &str| {
+                    PlaybookError::DeltaMissingField(format!("{} required for ADD_EXAMPLE", field))
+                };
+                let input = op.input.ok_or_else(|| missing("input"))?;
+                let output = op.output.ok_or_else(|| missing("output"))?;
+
+                let (bullet_id, example_id) = (op.bullet_id, op.example_id);
+                let id =
+                    self.add_example_at(&op.section, input, output, bullet_id, example_id, now)?;
+                Ok(Some(id))
+            }
+
+            OperationType::RemoveExample => {
+                let example_id = op.example_id.ok_or_else(|| {
+                    PlaybookError::DeltaMissingField(
+                        "example_id required for REMOVE_EXAMPLE".to_string(),
+                    )
+                })?;
+
+                self.remove_example(&example_id);
+                Ok(None)
+            }
+
+        }
+    }
+
+    // --------------------------
+    // 序列化/反序列化（对齐Python）
+    // --------------------------
+
+    /// 转换为JSON字符串（带格式化，易读）
+    pub fn to_json(&self) -> Result<String, PlaybookError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// 按指定时间戳格式转换为JSON字符串（读取时两种格式都接受）
+    pub fn to_json_with(&self, format: TimestampFormat) -> Result<String, PlaybookError> {
+        if format == TimestampFormat::default() {
+            return self.to_json();
+        }
+        Ok(serde_json::to_string_pretty(&self.to_value_with(format)?)?)
+    }
+
+    /// 序列化为JSON值，并把子弹的时间戳字段改写为指定格式
+    pub fn to_value_with(&self, format: TimestampFormat) -> Result<serde_json::Value, PlaybookError> {
+        let mut value = serde_json::to_value(self)?;
+        if format == TimestampFormat::default() {
+            return Ok(value);
+        }
+        let bullets = value.get_mut("bullets").and_then(|b| b.as_object_mut());
+        for (id, bullet_value) in bullets.into_iter().flatten() {
+            let Some(bullet) = self.bullets.get(id) else { continue };
+            let times = [
+                Some(&bullet.created_at),
+                Some(&bullet.updated_at),
+                bullet.last_used_at.as_ref(),
+                bullet.expires_at.as_ref(),
+            ];
+            for (field, time) in Bullet::TIMESTAMP_FIELDS.iter().zip(times) {
+                if let Some(time) = time {
+                    bullet_value[*field] = format.to_value(time);
+                }
+            }
+        }
+        let examples = value.get_mut("examples").and_then(|e| e.as_object_mut());
+        for (id, example_value) in examples.into_iter().flatten() {
+            if let Some(example) = self.examples.get(id) {
+                example_value["created_at"] = format.to_value(&example.created_at);
+            }
+        }
+        let failures = value.get_mut("failures").and_then(|f| f.as_object_mut());
+        for (id, failure_value) in failures.into_iter().flatten() {
+            if let Some(failure) = self.failures.get(id) {
+                failure_value["created_at"] = format.to_value(&failure.created_at);
+            }
+        }
+        Ok(value)
+    }
+
+    /// 从JSON字符串解析Playbook
+    ///
+    /// 文件读写见 `persist` feature 下的 `save_to_file` / `load_from_file`。
+    pub fn from_json(data: &str) -> Result<Self, PlaybookError> {
+        let mut playbook: Self = serde_json::from_str(data)
+            .map_err(|e| PlaybookError::InvalidData(format!("Failed to parse JSON: {}", e)))?;
+        playbook.reintern_sections();
+        playbook.rebuild_rollups();
+        Ok(playbook)
+    }
+
+    /// 把 [`as_prompt`](Self::as_prompt) 的输出（`## 章节` / `- [id] 内容 (计数)`）解析回Playbook，
+    /// 人工编辑渲染结果后可据此还原，再与原Playbook比较
+    ///
+    /// 计数后缀可省略（计数为0）；省略`[id]`的行按章节生成新ID；不以`- `开头的非空行
+    /// 视为上一条子弹内容的续行。
+    pub fn parse_prompt(text: &str) -> Result<Self, PlaybookError> {
+        struct Entry<'a> {
+            section: &'a str,
+            id: Option<&'a str>,
+            content: String,
+            counters: Option<[u32; 3]>,
+        }
+
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut seen = BTreeSet::new();
+        let mut section = None;
+        for (i, line) in text.lines().enumerate() {
+            let invalid =
+                |msg: &str| PlaybookError::InvalidData(format!("line {}: {}", i + 1, msg));
+            if let Some(name) = line.strip_prefix("## ") {
+                section = Some(name.trim());
+                continue;
+            }
+            let Some(rest) = line.strip_prefix("- ") else {
+                match entries.last_mut() {
+                    // 多行内容的计数后缀在最后一行
+                    Some(entry) if entry.counters.is_none() => {
+                        let (content, counters) = split_counters(line);
+                        entry.content.push('\n');
+                        entry.content.push_str(content);
+                        entry.counters = counters;
+                    }
+                    _ if line.trim().is_empty() => {}
+                    _ => return Err(invalid("expected `## section` or `- [id] content`")),
+                }
+                continue;
+            };
+            let section = section.ok_or_else(|| invalid("bullet before any section"))?;
+            let (id, rest) = match rest.strip_prefix('[').and_then(|r| r.split_once("] ")) {
+                Some((id, rest)) => (Some(id.trim()), rest),
+                None => (None, rest),
+            };
+            let (content, counters) = split_counters(rest);
+            if let Some(id) = id
+                && !seen.insert(id)
+            {
+                return Err(invalid("duplicate bullet id"));
+            }
+            entries.push(Entry { section, id, content: content.to_string(), counters });
+        }
+
+        // 生成的新ID从已有ID的最大序号之后开始，避免与后面的显式ID冲突
+        let mut playbook = Self::new();
+        playbook.next_id = entries
+            .iter()
+            .filter_map(|e| id_sequence(e.id?))
+            .max()
+            .unwrap_or(0);
+        let now = playbook.clock.now();
+        let bullets: Vec<Bullet> = entries
+            .into_iter()
+            .map(|entry| {
+                let mut bullet = Bullet::new_at(entry.section, entry.content, now);
+                bullet.id = match entry.id {
+                    Some(id) => id.to_string(),
+                    None => playbook.generate_id(entry.section),
+                };
+                let counters = entry.counters.unwrap_or_default();
+                for (tag, count) in Tag::ALL.into_iter().zip(counters) {
+                    *bullet.count_mut(tag) = count;
+                }
+                bullet
+            })
+            .collect();
+        playbook.bulk_load(bullets)?;
+        Ok(playbook)
+    }
+
+    // --------------------------
+    // 辅助方法（对齐Python）
+    // --------------------------
+
+    /// 转换为LLM提示词格式（有序输出章节和子弹）
+    pub fn as_prompt(&self) -> String {
+        self.render_sections(|_| true, |b| b.content.as_str().into())
+    }
+
+    /// 只渲染指定章节（可用别名，不存在的章节忽略），顺序与`as_prompt`一致
+    pub fn as_prompt_for(&self, sections: &[&str]) -> String {
+        let sections: Vec<Cow<str>> = sections.iter().map(|s| self.resolve_section(s)).collect();
+        self.render_sections(
+            |section| sections.iter().any(|s| s == section),
+            |b| b.content.as_str().into(),
+        )
+    }
+
+    /// 只渲染指定子弹（不存在的ID忽略），章节和子弹顺序与`as_prompt`一致；
+    /// 没有选中子弹的章节不输出
+    pub fn as_prompt_for_bullets(&self, bullet_ids: &[BulletId]) -> String {
+        let now = self.clock.now();
+        let mut out = String::new();
+        for (section, ids) in self.sorted_sections() {
+            let mut selected = ids
+                .iter()
+                .filter(|id| bullet_ids.contains(id))
+                .filter_map(|id| self.bullets.get(id))
+                .filter(|b| !b.is_expired_at(now))
+                .peekable();
+            if selected.peek().is_none() {
+                continue;
+            }
+            write_section_header(&mut out, section);
+            for bullet in selected {
+                self.write_bullet(&mut out, bullet, &bullet.content);
+            }
+        }
+        out
+    }
+
+    /// 渲染时把内容中的`{{变量}}`替换为`vars`中的值（未提供的变量原样保留）
+    pub fn as_prompt_with_vars(&self, vars: &TemplateVars) -> String {
+        self.render_sections(|_| true, |b| vars::substitute(&b.content, vars))
+    }
+
+    /// 章节内按分数从高到低渲染（章节顺序不变），重要的策略排在前面
+    pub fn as_prompt_ranked(&self) -> String {
+        let now = self.clock.now();
+        let mut out = String::new();
+        for (section, bullet_ids) in self.sorted_sections() {
+            write_section_header(&mut out, section);
+            let mut bullets: Vec<(f64, &Bullet)> = bullet_ids
+                .iter()
+                .filter_map(|id| self.get_bullet(id))
+                .filter(|b| !b.is_expired_at(now))
+                .map(|b| (self.scorer.score(b, now), b))
+                .collect();
+            // 稳定排序：同分保持插入顺序
+            bullets.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+            for (_, bullet) in bullets {
+                self.write_bullet(&mut out, bullet, &bullet.content);
+            }
+        }
+        out
+    }
+
+    /// 用指定语言渲染（没有该语言版本的子弹使用默认内容）
+    pub fn as_prompt_in(&self, lang: &str) -> String {
+        self.render_sections(|_| true, |b| b.content_for(lang).into())
+    }
+
+    /// 所有子弹引用到的变量名
+    pub fn template_vars(&self) -> BTreeSet<String> {
+        self.bullets
+            .values()
+            .flat_map(|b| vars::placeholders(&b.content))
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    pub(crate) fn render_sections<'a>(
+        &'a self,
+        mut include: impl FnMut(&str) -> bool,
+        content: impl Fn(&'a Bullet) -> Cow<'a, str>,
+    ) -> String {
+        // 直接写入单个缓冲区，避免每行一次format!分配再join
+        let mut out = String::new();
+        let now = self.clock.now();
+
+        // 章节按字母排序（保证输出一致性，对齐Python的sorted）
+        let sections = self.sorted_sections();
+        for (section, bullet_ids) in sections.into_iter().filter(|(s, _)| include(s)) {
+            write_section_header(&mut out, section);
+
+            // 子弹按插入顺序输出（章节内的ID列表是插入顺序）
+            for bullet_id in bullet_ids {
+                if let Some(bullet) = self.bullets.get(bullet_id)
+                    && !bullet.is_expired_at(now)
+                {
+                    self.write_bullet(&mut out, bullet, &content(bullet));
+                }
+            }
+        }
+
+        out
+    }
+
+    /// 获取统计信息（有序输出，用BTreeMap保证JSON字段顺序）
+    pub fn stats(&self) -> BTreeMap<String, serde_json::Value> {
+        // 直接汇总章节聚合，不扫描子弹
+        let mut tags = BTreeMap::new();
+        for tag in Tag::ALL {
+            let total: u64 = self.rollups.values().map(|rollup| rollup.count(&tag)).sum();
+            tags.insert(tag.as_str().to_string(), serde_json::Value::Number(total.into()));
+        }
 
         let mut stats = BTreeMap::new();
         stats.insert(
@@ -416,34 +1815,100 @@ impl Playbook {
         stats
     }
 
-    fn generate_id(&mut self, section: &str) -> String {
+    /// 修订号：每次增删改子弹（含打标签）后变为一个新的、全局唯一的值，
+    /// 用作缓存键；直接修改公开字段后调用 [`Playbook::rebuild_rollups`] 同样会更新
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// 各章节的聚合计数（按章节名有序）
+    pub fn section_rollups(&self) -> &BTreeMap<SectionName, SectionRollup> {
+        &self.rollups
+    }
+
+    pub fn section_rollup(&self, section: &str) -> Option<&SectionRollup> {
+        self.rollups.get(section)
+    }
+
+    /// 从全部子弹重新计算章节聚合（直接修改了`bullets`字段之后调用）
+    pub fn rebuild_rollups(&mut self) {
+        self.revision = next_revision();
+        self.rollups.clear();
+        for bullet in self.bullets.values() {
+            self.rollups.entry(bullet.section.clone()).or_default().add(bullet);
+        }
+    }
+
+    /// 内容变化后更新修订号
+    pub(crate) fn touch(&mut self) {
+        self.revision = next_revision();
+    }
+
+    /// 返回已有章节名的共享引用；新章节才分配一次
+    pub(crate) fn intern_section(&self, section: &str) -> SectionName {
+        match self.sections.get_key_value(section) {
+            Some((name, _)) => name.clone(),
+            None => SectionName::from(section),
+        }
+    }
+
+    /// 反序列化后每个子弹各持一份章节字符串，这里统一指向索引中的那一份
+    pub(crate) fn reintern_sections(&mut self) {
+        for bullet in self.bullets.values_mut() {
+            if let Some((name, _)) = self.sections.get_key_value(&*bullet.section) {
+                Arc::make_mut(bullet).section = name.clone();
+            }
+        }
+    }
+
+    /// 显式指定的ID（同步副本、导入等）占用其序号：`next_id`推进到该序号之后，
+    /// 之后生成的ID不会与之冲突
+    pub(crate) fn reserve_id(&mut self, id: &str) {
+        if let Some(seq) = id_sequence(id) {
+            self.next_id = self.next_id.max(seq);
+        }
+    }
+
+    fn generate_id(&mut self, section: &str) -> BulletId {
+        let id = self.next_bullet_id(section);
         self.next_id += 1;
+        id
+    }
+
+    /// 在`section`中不指定ID添加子弹时将生成的ID
+    pub(crate) fn next_bullet_id(&self, section: &str) -> BulletId {
         let section_prefix = section
             .split_whitespace()
             .next()
             .unwrap_or("default")
             .to_lowercase();
-        format!("{}-{:05}", section_prefix, self.next_id)
+        format!("{}-{:05}", section_prefix, self.next_id + 1)
     }
 
 }
 
+/// ID末尾`-`之后的序号（`api-00042` → 42）
+fn id_sequence(id: &str) -> Option<u64> {
+    id.rsplit('-').next()?.parse().ok()
+}
+
 #[cfg(test)]
 mod tests { 
     use super::*;
+    use alloc::vec;
     #[test]
     fn test_stats() {
         let mut pb = Playbook::new();
 
         pb.add_bullet(
-            "测试章节".to_string(),
-            "测试内容1".to_string(),
+            "测试章节",
+            "测试内容1",
             None,
             Some(BTreeMap::from([("helpful".to_string(), 2), ("harmful".to_string(), 1)])),
         );
         pb.add_bullet(
-            "测试章节2".to_string(),
-            "测试内容2".to_string(),
+            "测试章节2",
+            "测试内容2",
             None,
             Some(BTreeMap::from([("neutral".to_string(), 3)])),
         );
@@ -458,4 +1923,701 @@ mod tests {
         assert_eq!(tags.get("harmful").unwrap(), &serde_json::Value::Number(1.into()));
         assert_eq!(tags.get("neutral").unwrap(), &serde_json::Value::Number(3.into()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_add_bullet_returns_id() {
+        let mut pb = Playbook::new();
+        let id = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        assert_eq!(id, "api-00001");
+
+        let bullet = pb.get_bullet(&id).unwrap();
+        assert_eq!(bullet.id, id);
+        assert_eq!(&*bullet.section, "api usage");
+
+        // 复用ID会替换旧子弹，章节列表中不重复
+        pb.add_bullet("api usage", "新内容", Some(id.clone()), None);
+        assert_eq!(pb.bullets.len(), 1);
+        assert_eq!(pb.sections["api usage"], vec![id.clone()]);
+        assert_eq!(pb.get_bullet(&id).unwrap().content, "新内容");
+    }
+
+    #[test]
+    fn test_sections_are_interned() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "a", None, None);
+        let b = pb.add_bullet("api usage", "b", None, None);
+        let (key, _) = pb.sections.get_key_value("api usage").unwrap();
+        assert!(Arc::ptr_eq(&pb.bullets[&a].section, key));
+        assert!(Arc::ptr_eq(&pb.bullets[&b].section, key));
+
+        // 加载后同样共享
+        let loaded = Playbook::from_json(&pb.to_json().unwrap()).unwrap();
+        let (key, _) = loaded.sections.get_key_value("api usage").unwrap();
+        assert!(Arc::ptr_eq(&loaded.bullets[&a].section, key));
+    }
+
+    #[test]
+    fn test_injected_clock_is_deterministic() {
+        use crate::clock::ManualClock;
+        use chrono::TimeDelta;
+
+        let build = || {
+            let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+            let mut pb = Playbook::with_clock(clock.clone());
+            let id = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+            for i in 0..20 {
+                pb.add_bullet(format!("section {}", i % 7), format!("策略{}", i), None, None);
+            }
+            clock.advance(TimeDelta::seconds(5));
+            pb.tag_bullet(&id, "helpful", 1).unwrap();
+            pb
+        };
+
+        let (a, b) = (build(), build());
+        let json = a.to_json().unwrap();
+        assert_eq!(json, b.to_json().unwrap());
+        // 哈希表按键排序写出
+        let positions: Vec<usize> =
+            a.bullets().iter().map(|b| json.find(&format!("\"{}\":", b.id)).unwrap()).collect();
+        assert!(positions.is_sorted());
+
+        let bullet = a.get_bullet("api-00001").unwrap();
+        assert_eq!(bullet.created_at, DateTime::UNIX_EPOCH);
+        assert_eq!(bullet.updated_at, DateTime::UNIX_EPOCH + TimeDelta::seconds(5));
+    }
+
+    #[test]
+    fn test_unix_seconds_roundtrip() {
+        let mut pb = Playbook::new();
+        let id = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+
+        let json = pb.to_json_with(TimestampFormat::UnixSeconds).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let created = pb.get_bullet(&id).unwrap().created_at;
+        assert_eq!(value["bullets"][&id]["created_at"], created.timestamp());
+
+        let loaded = Playbook::from_json(&json).unwrap();
+        assert_eq!(loaded.get_bullet(&id).unwrap().created_at.timestamp(), created.timestamp());
+    }
+
+    #[test]
+    fn test_delta_limits_reject_whole_batch() {
+        let add = |section: &str, content: &str| DeltaOperation {
+            type_: OperationType::Add,
+            section: section.to_string(),
+            content: Some(content.to_string()),
+            bullet_id: None,
+            merged_ids: Vec::new(),
+            merge_strategy: None,
+            metadata: BTreeMap::new(),
+            example_id: None,
+            input: None,
+            output: None,
+            skill: None,
+        };
+        let batch = |ops: Vec<DeltaOperation>| DeltaBatch {
+            reasoning: String::new(),
+            operations: ops,
+        };
+
+        let mut pb = Playbook::new();
+        pb.set_limits(DeltaLimits {
+            max_operations: Some(3),
+            max_content_chars: Some(10),
+            max_new_per_section: Some(2),
+        });
+
+        let err = pb.apply_delta(batch(vec![add("a", "x"); 4])).unwrap_err();
+        assert!(matches!(err, PlaybookError::DeltaLimitExceeded(_)));
+        assert_eq!(err.op_index(), None);
+        let err = pb.apply_delta(batch(vec![add("a", "分页时带上cursor参数")])).unwrap_err();
+        assert!(err.to_string().contains("max 10"));
+        assert_eq!((err.op_index(), err.code()), (Some(0), "delta_limit_exceeded"));
+        assert!(pb.bullets.is_empty());
+
+        pb.apply_delta(batch(vec![add("a", "x"), add("b", "y"), add("a", "z")])).unwrap();
+        let err = pb.apply_delta(batch(vec![add("a", "x"); 3])).unwrap_err();
+        assert!(err.to_string().contains("section 'a'"));
+        assert_eq!(pb.bullets.len(), 3);
+    }
+
+    #[test]
+    fn test_frozen_rejects_curation_but_allows_tags() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        let b = pb.add_bullet("debugging", "先看日志", None, None);
+        pb.set_frozen(true);
+        let batch = |operations| DeltaBatch { reasoning: String::new(), operations };
+
+        let err = pb
+            .apply_delta(batch(vec![
+                DeltaOperation::tag("api usage", &a, BTreeMap::from([("helpful".to_string(), 1)])),
+                DeltaOperation::add("api usage", "重试要指数退避"),
+            ]))
+            .unwrap_err();
+        assert!(matches!(err.root(), PlaybookError::Frozen(_)));
+        assert_eq!((err.op_index(), err.code()), (Some(1), "frozen"));
+        assert_eq!(pb.bullets.len(), 2);
+        assert_eq!(pb.bullets[&a].helpful, 0);
+
+        let tags = BTreeMap::from([("helpful".to_string(), 1)]);
+        pb.apply_delta(batch(vec![DeltaOperation::tag("api usage", &a, tags)])).unwrap();
+        pb.tag_bullet(&a, "helpful", 1).unwrap();
+        assert_eq!(pb.bullets[&a].helpful, 2);
+
+        pb.set_frozen(false);
+        pb.freeze_section("debugging");
+        assert!(pb.is_section_frozen("debugging") && !pb.is_section_frozen("api usage"));
+        assert!(pb.apply_delta(batch(vec![DeltaOperation::remove("debugging", &b)])).is_err());
+        pb.apply_delta(batch(vec![DeltaOperation::remove("api usage", &a)])).unwrap();
+        pb.unfreeze_section("debugging");
+        pb.apply_delta(batch(vec![DeltaOperation::remove("debugging", &b)])).unwrap();
+        assert!(pb.bullets.is_empty());
+    }
+
+    #[test]
+    fn test_frozen_section_checks_the_named_bullet() {
+        let mut pb = Playbook::new();
+        let rule = pb.add_bullet("policies", "不得承诺退款", None, None);
+        let ex = pb.add_example("policies", "能退款吗", "请联系客服", Some(rule.clone())).unwrap();
+        pb.freeze_section("policies");
+        let batch = |operations| DeltaBatch { reasoning: String::new(), operations };
+
+        let mut update = DeltaOperation::add("scratch", "可以随时退款");
+        update.type_ = OperationType::Update;
+        update.bullet_id = Some(rule.clone());
+        let mut replace = DeltaOperation::add("scratch", "可以随时退款");
+        replace.bullet_id = Some(rule.clone());
+        let bypasses = [
+            update,
+            replace,
+            DeltaOperation::remove("scratch", &rule),
+            DeltaOperation::remove_example("scratch", &ex),
+            DeltaOperation::add_example("scratch", Some(rule.clone()), "q", "a"),
+        ];
+        for op in bypasses {
+            let err = pb.apply_delta(batch(vec![op])).unwrap_err();
+            assert_eq!(err.code(), "frozen");
+        }
+        assert_eq!(pb.bullets[&rule].content, "不得承诺退款");
+        assert!(pb.examples.contains_key(&ex));
+        assert_eq!(pb.examples.len(), 1);
+    }
+
+    #[test]
+    fn test_content_policy_on_delta() {
+        use crate::models::policy::{BannedTermAction, BannedTerms, PolicyChain};
+
+        let add = |content: &str| DeltaOperation {
+            type_: OperationType::Add,
+            section: "support".to_string(),
+            content: Some(content.to_string()),
+            bullet_id: None,
+            merged_ids: Vec::new(),
+            merge_strategy: None,
+            metadata: BTreeMap::new(),
+            example_id: None,
+            input: None,
+            output: None,
+            skill: None,
+        };
+        let mut pb = Playbook::new();
+        pb.set_content_policy(Some(Arc::new(
+            PolicyChain::new()
+                .with(BannedTerms::new(["acme"], BannedTermAction::Redact))
+                .with(BannedTerms::new(["refund everything"], BannedTermAction::Reject)),
+        )));
+
+        let delta = DeltaBatch {
+            reasoning: String::new(),
+            operations: vec![add("Escalate Acme tickets"), add("Refund everything on request")],
+        };
+        let err = pb.apply_delta(delta).unwrap_err();
+        assert!(matches!(err.root(), PlaybookError::ContentRejected(_)));
+        assert_eq!(err.op_index(), Some(1));
+        assert!(pb.bullets.is_empty());
+
+        let delta = DeltaBatch {
+            reasoning: String::new(),
+            operations: vec![add("Escalate Acme tickets")],
+        };
+        pb.apply_delta(delta).unwrap();
+        assert_eq!(pb.bullets().first().unwrap().content, "Escalate [REDACTED] tickets");
+    }
+
+    #[test]
+    fn test_content_policy_checks_target_section() {
+        use crate::models::policy::{ContentPolicy, PolicyDecision};
+
+        /// 只允许在`public`章节写入
+        #[derive(Debug)]
+        struct PublicOnly;
+
+        impl ContentPolicy for PublicOnly {
+            fn check(&self, section: &str, _content: &str) -> PolicyDecision {
+                match section {
+                    "public" => PolicyDecision::Allow,
+                    _ => PolicyDecision::Reject(format!("{} is private", section)),
+                }
+            }
+        }
+
+        let mut pb = Playbook::new();
+        let secret = pb.add_bullet("secrets", "轮换密钥", None, None);
+        let public = pb.add_bullet("public", "先分页", None, None);
+        pb.set_content_policy(Some(Arc::new(PublicOnly)));
+
+        // 操作填写的章节是public，但被改写的子弹在secrets
+        let mut update = DeltaOperation::add("public", "改写");
+        update.type_ = OperationType::Update;
+        update.bullet_id = Some(secret.clone());
+        let mut merge = DeltaOperation::merge("public", &secret, [&public]);
+        merge.content = Some("合并".to_string());
+        for op in [update, merge] {
+            let delta = DeltaBatch { reasoning: String::new(), operations: vec![op] };
+            let err = pb.apply_delta(delta).unwrap_err();
+            assert!(err.to_string().contains("section 'secrets'"), "{}", err);
+        }
+        assert_eq!(pb.get_bullet(&secret).unwrap().content, "轮换密钥");
+    }
+
+    #[test]
+    fn test_error_codes_and_op_index() {
+        let mut pb = Playbook::new();
+        let mut remove = DeltaOperation::remove("api", "api-00001");
+        remove.bullet_id = None;
+        let delta = DeltaBatch {
+            reasoning: String::new(),
+            operations: vec![DeltaOperation::add("api", "先分页"), remove],
+        };
+        let err = pb.apply_delta(delta).unwrap_err();
+        assert_eq!((err.op_index(), err.code()), (Some(1), "delta_missing_field"));
+        assert!(err.to_string().starts_with("Delta operation #1: "));
+        assert!(!err.is_retryable());
+
+        let err: PlaybookError =
+            DeltaOperation::from_json(&serde_json::json!({"type": "MOVE"})).unwrap_err().into();
+        assert_eq!((err.op_index(), err.code()), (None, "invalid_delta"));
+
+        #[cfg(feature = "std")]
+        {
+            let io = std::io::Error::from(std::io::ErrorKind::TimedOut);
+            let err = PlaybookError::at_operation(3)(PlaybookError::IoError(io));
+            assert!(err.is_retryable());
+            assert!(matches!(err.root(), PlaybookError::IoError(_)));
+        }
+    }
+
+    #[test]
+    fn test_render_with_vars() {
+        let mut pb = Playbook::new();
+        pb.add_bullet("api usage", "调用{{api_base_url}}/v1前先刷新{{ token_name }}", None, None);
+        pb.add_bullet("api usage", "分页时带上cursor", None, None);
+
+        let names: Vec<String> = pb.template_vars().into_iter().collect();
+        assert_eq!(names, ["api_base_url", "token_name"]);
+
+        let vars = TemplateVars::from([(
+            "api_base_url".to_string(),
+            "https://staging.example.com".to_string(),
+        )]);
+        let prompt = pb.as_prompt_with_vars(&vars);
+        assert!(prompt.contains("调用https://staging.example.com/v1前先刷新{{ token_name }}"));
+        assert!(pb.as_prompt().contains("{{api_base_url}}"));
+    }
+
+    #[test]
+    fn test_language_variants() {
+        let mut pb = Playbook::new();
+        let id = pb.add_bullet("api usage", "Pass the cursor when paginating", None, None);
+        pb.set_variant(&id, "zh", "分页时带上cursor").unwrap();
+        assert!(pb.set_variant("missing", "zh", "x").is_err());
+
+        assert!(pb.as_prompt_in("zh-CN").contains("分页时带上cursor"));
+        assert!(pb.as_prompt_in("fr").contains("Pass the cursor"));
+        assert!(pb.as_prompt().contains("Pass the cursor"));
+
+        let loaded = Playbook::from_json(&pb.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.get_bullet(&id).unwrap().content_for("zh"), "分页时带上cursor");
+
+        pb.set_variant(&id, "zh", "").unwrap();
+        assert!(!pb.to_json().unwrap().contains("variants"));
+    }
+
+    #[test]
+    fn test_section_rollups_track_mutations() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        let b = pb.add_bullet("api usage", "重试要指数退避", None, None);
+        let c = pb.add_bullet("debugging", "先看日志", None, None);
+        pb.tag_bullet(&a, "helpful", 3).unwrap();
+        pb.update_bullet(&b, None, Some(BTreeMap::from([("harmful".to_string(), 2)])))
+            .unwrap();
+        assert!(pb.tag_bullet(&c, "Bogus", 1).is_err());
+        pb.apply_delta(DeltaBatch {
+            reasoning: String::new(),
+            operations: vec![DeltaOperation::tag(
+                "debugging",
+                &c,
+                BTreeMap::from([("neutral".to_string(), 4)]),
+            )],
+        })
+        .unwrap();
+        // 同ID覆盖添加时旧子弹的计数要扣除
+        pb.add_bullet("debugging", "先看日志和指标", Some(c.clone()), None);
+        pb.remove_bullet(&a);
+
+        let api = pb.section_rollup("api usage").unwrap();
+        assert_eq!((api.bullets, api.helpful, api.harmful), (1, 0, 2));
+        assert_eq!(pb.section_rollup("debugging").unwrap().neutral, 0);
+        assert!(pb.section_rollup("missing").is_none());
+
+        let cached = pb.section_rollups().clone();
+        pb.rebuild_rollups();
+        assert_eq!(pb.section_rollups(), &cached);
+
+        let loaded = Playbook::from_json(&pb.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.section_rollups(), &cached);
+        assert_eq!(loaded.stats()["tags"]["harmful"], 2);
+    }
+
+    #[test]
+    fn test_dead_bullets() {
+        use crate::clock::ManualClock;
+
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let used = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        let tagged = pb.add_bullet("api usage", "重试要指数退避", None, None);
+        let dead = pb.add_bullet("debugging", "先看日志", None, None);
+
+        clock.advance(TimeDelta::days(20));
+        pb.mark_used(core::slice::from_ref(&used));
+        pb.tag_bullet(&tagged, "harmful", 1).unwrap();
+        let fresh = pb.add_bullet("debugging", "看指标", None, None);
+        clock.advance(TimeDelta::days(5));
+
+        let window = TimeDelta::days(7);
+        let ids: Vec<&str> = pb.dead_bullets(window).iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, [dead.as_str()]);
+        assert!(pb.get_bullet(&fresh).is_some());
+
+        let delta = pb.prune_dead_delta(window);
+        pb.apply_delta(delta).unwrap();
+        assert!(pb.get_bullet(&dead).is_none());
+        assert_eq!(pb.bullets.len(), 3);
+
+        let json = pb.to_json_with(TimestampFormat::UnixSeconds).unwrap();
+        let loaded = Playbook::from_json(&json).unwrap();
+        let last_used = |pb: &Playbook, id: &str| pb.get_bullet(id).unwrap().last_used_at;
+        assert_eq!(last_used(&loaded, &used), Some(DateTime::UNIX_EPOCH + TimeDelta::days(20)));
+        assert_eq!(last_used(&loaded, &tagged), None);
+    }
+
+    #[test]
+    fn test_ranked_with_scorer() {
+        let mut pb = Playbook::new();
+        let low = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        let high = pb.add_bullet("api usage", "重试要指数退避", None, None);
+        let bad = pb.add_bullet("debugging", "先看日志", None, None);
+        pb.tag_bullet(&high, "helpful", 3).unwrap();
+        pb.tag_bullet(&bad, "harmful", 2).unwrap();
+
+        let ranked: Vec<&str> = pb.ranked_bullets().iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ranked, [high.as_str(), low.as_str(), bad.as_str()]);
+        assert_eq!(pb.lowest_scored(1)[0].id, bad);
+        let prompt = pb.as_prompt_ranked();
+        assert!(prompt.find(&high).unwrap() < prompt.find(&low).unwrap());
+
+        // 换成只看harmful的评分，排名随之改变
+        #[derive(Debug)]
+        struct FewestHarmful;
+        impl scoring::BulletScorer for FewestHarmful {
+            fn score(&self, bullet: &Bullet, _now: DateTime<Utc>) -> f64 {
+                -(bullet.harmful as f64)
+            }
+        }
+        pb.set_scorer(Arc::new(FewestHarmful));
+        assert_eq!(pb.ranked_bullets()[0].id, low);
+    }
+
+    #[test]
+    fn test_import_text() {
+        let mut pb = Playbook::new();
+        let notes = "会议记录\n\n- 分页时带上cursor\n  * 重试用指数退避\n1. 先看日志\n(2) 再加断点\n3、回滚前先备份\n• - 3.14是常量\n   \n";
+        let ids = pb.import_text("postmortem", notes);
+        let contents: Vec<&str> = ids.iter().map(|id| pb.bullets[id].content.as_str()).collect();
+        assert_eq!(contents[..3], ["会议记录", "分页时带上cursor", "重试用指数退避"]);
+        assert_eq!(contents[3..], ["先看日志", "再加断点", "回滚前先备份", "3.14是常量"]);
+        assert_eq!(pb.sections["postmortem"], ids);
+        assert!(pb.import_text("postmortem", "\n - \n").is_empty());
+    }
+
+    #[test]
+    fn test_move_operation() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api", "先分页", None, None);
+        let b = pb.add_bullet("api", "先看日志", None, None);
+        pb.tag_bullet(&b, "helpful", 3).unwrap();
+        let ex = pb.add_example("api", "500", "tail app.log", Some(b.clone())).unwrap();
+        let created_at = pb.bullets[&b].created_at;
+        pb.add_section_alias("debug", "debugging").unwrap();
+
+        let json = serde_json::json!({"type": "move", "section": "debug", "bullet_id": b});
+        let op: DeltaOperation = serde_json::from_value(json).unwrap();
+        assert_eq!(op.type_, OperationType::Move);
+        pb.apply_delta(DeltaBatch { reasoning: String::new(), operations: vec![op] }).unwrap();
+
+        let moved = &pb.bullets[&b];
+        assert_eq!(&*moved.section, "debugging");
+        assert_eq!((moved.helpful, moved.created_at), (3, created_at));
+        assert!(Arc::ptr_eq(&moved.section, pb.sections.get_key_value("debugging").unwrap().0));
+        assert_eq!(pb.sections["api"], vec![a.clone()]);
+        assert_eq!(pb.section_rollup("debugging").unwrap().count(&Tag::Helpful), 3);
+        assert_eq!(pb.section_rollup("api").unwrap().count(&Tag::Helpful), 0);
+        assert_eq!(&*pb.examples[&ex].section, "debugging");
+
+        // 已在目标章节时不做修改；移出冻结章节同样被拒绝，不存在的子弹报错
+        let revision = pb.revision();
+        pb.move_bullet(&a, "api").unwrap();
+        assert_eq!(pb.revision(), revision);
+        pb.freeze_section("api");
+        let delta = |op| DeltaBatch { reasoning: String::new(), operations: vec![op] };
+        let err = pb.apply_delta(delta(DeltaOperation::move_to("debugging", &a))).unwrap_err();
+        assert_eq!(err.code(), "frozen");
+        let err = pb.apply_delta(delta(DeltaOperation::move_to("debugging", "x"))).unwrap_err();
+        assert_eq!(err.code(), "bullet_not_found");
+    }
+
+    #[test]
+    fn test_merge_operation() {
+        use crate::clock::ManualClock;
+
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let a = pb.add_bullet("api", "先分页", None, None);
+        clock.advance(TimeDelta::seconds(10));
+        let b = pb.add_bullet("api", "分页时带上cursor", None, None);
+        let c = pb.add_bullet("debug", "先分页", None, None);
+        pb.tag_bullet(&a, "helpful", 2).unwrap();
+        pb.tag_bullet(&b, "helpful", 3).unwrap();
+        pb.tag_bullet(&c, "harmful", 1).unwrap();
+        let ex = pb.add_example("debug", "列表太长", "加limit", Some(c.clone())).unwrap();
+
+        let json = serde_json::json!({
+            "type": "merge", "section": "api", "bullet_id": b, "merged_ids": [a, c],
+            "content": "分页时带上cursor和limit"
+        });
+        let op: DeltaOperation = serde_json::from_value(json).unwrap();
+        assert_eq!(op.type_, OperationType::Merge);
+        pb.apply_delta(DeltaBatch { reasoning: String::new(), operations: vec![op] }).unwrap();
+
+        let merged = &pb.bullets[&b];
+        assert_eq!(pb.bullets.len(), 1);
+        assert_eq!(merged.content, "分页时带上cursor和limit");
+        assert_eq!((merged.helpful, merged.harmful), (5, 1));
+        assert_eq!(merged.created_at, DateTime::UNIX_EPOCH);
+        assert_eq!(pb.sections.keys().map(|s| &**s).collect::<Vec<_>>(), ["api"]);
+        assert_eq!(pb.section_rollup("api").unwrap().count(&Tag::Helpful), 5);
+        assert!(pb.section_rollup("debug").is_none());
+        assert_eq!(pb.examples[&ex].bullet_id.as_ref(), Some(&b));
+        assert_eq!(&*pb.examples[&ex].section, "api");
+
+        let d = pb.add_bullet("api", "分页时带上cursor", None, None);
+        let e = pb.add_bullet("api", "按id排序", None, None);
+        let ids = [b.clone(), d, e];
+        pb.merge_bullets(&ids, MergeContent::Concatenate).unwrap();
+        assert_eq!(pb.bullets[&b].content, "分页时带上cursor和limit 分页时带上cursor 按id排序");
+
+        let f = pb.add_bullet("api", "限制每页条数", None, None);
+        let json = serde_json::json!({
+            "type": "merge", "section": "api", "bullet_id": b, "merged_ids": [f],
+            "merge_strategy": "concatenate"
+        });
+        let op: DeltaOperation = serde_json::from_value(json).unwrap();
+        assert_eq!(op.merge_strategy, Some(MergeStrategy::Concatenate));
+        pb.apply_delta(DeltaBatch { reasoning: String::new(), operations: vec![op] }).unwrap();
+        assert!(pb.bullets[&b].content.ends_with("按id排序 限制每页条数"));
+
+        // 出错时不做任何修改
+        let revision = pb.revision();
+        let err = pb.merge_bullets(&[b.clone(), b.clone()], MergeContent::Keep).unwrap_err();
+        assert_eq!(err.code(), "invalid_data");
+        let err = pb.merge_bullets(&[b.clone(), "x".into()], MergeContent::Keep).unwrap_err();
+        assert_eq!(err.code(), "bullet_not_found");
+        let err = pb.apply_delta(DeltaBatch {
+            reasoning: String::new(),
+            operations: vec![DeltaOperation::merge("api", &b, Vec::<String>::new())],
+        });
+        assert_eq!(err.unwrap_err().code(), "delta_missing_field");
+        assert_eq!(pb.revision(), revision);
+        let g = pb.add_bullet("api", "按时间排序", None, None);
+        let revision = pb.revision();
+        let mut conflicting = DeltaOperation::merge("api", &b, [&g]);
+        conflicting.content = Some("二选一".into());
+        conflicting.merge_strategy = Some(MergeStrategy::Keep);
+        let err = pb.apply_delta(DeltaBatch {
+            reasoning: String::new(),
+            operations: vec![conflicting],
+        });
+        assert_eq!(err.unwrap_err().code(), "invalid_data");
+        assert_eq!(pb.revision(), revision);
+    }
+
+    #[test]
+    fn test_apply_delta_transactional() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api", "先分页", None, None);
+        let helpful = BTreeMap::from([("helpful".to_string(), 1)]);
+        let mut update = DeltaOperation::add("api", "改写");
+        update.type_ = OperationType::Update;
+        update.bullet_id = Some("missing".into());
+        let failing = DeltaBatch {
+            reasoning: String::new(),
+            operations: vec![
+                DeltaOperation::add("api", "带上cursor"),
+                DeltaOperation::tag("api", &a, helpful.clone()),
+                update,
+            ],
+        };
+
+        let revision = pb.revision();
+        let before = pb.to_json().unwrap();
+        let err = pb.apply_delta_transactional(failing.clone()).unwrap_err();
+        assert_eq!(err.op_index(), Some(2));
+        assert_eq!(pb.to_json().unwrap(), before);
+        assert_eq!(pb.revision(), revision);
+        assert_eq!(pb.section_rollup("api").unwrap().bullets, 1);
+
+        // 非事务模式下前两个操作已经生效
+        let mut partial = pb.clone();
+        assert!(partial.apply_delta(failing).is_err());
+        assert_eq!(partial.bullets.len(), 2);
+
+        let ok = DeltaBatch {
+            reasoning: String::new(),
+            operations: vec![
+                DeltaOperation::add("api", "带上cursor"),
+                DeltaOperation::tag("api", &a, helpful),
+            ],
+        };
+        let changes = pb.apply_delta_transactional(ok).unwrap();
+        assert_eq!(changes.operations.len(), 2);
+        assert_eq!(changes.operations[0].type_, OperationType::Tag);
+        assert_eq!(changes.operations[0].bullet_id.as_ref(), Some(&a));
+        assert_eq!(changes.operations[1].type_, OperationType::Add);
+        assert_eq!(pb.bullets.len(), 2);
+    }
+
+    #[test]
+    fn test_apply_delta_transactional_restores_order_and_examples() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api", "先分页", None, None);
+        let b = pb.add_bullet("api", "重试要退避", None, None);
+        let c = pb.add_bullet("db", "连接池要设上限", None, None);
+        pb.add_example("api", "q", "a", Some(b.clone())).unwrap();
+        let mut replace = DeltaOperation::add("db", "索引要覆盖查询");
+        replace.bullet_id = Some(a.clone());
+        let failing = DeltaBatch {
+            reasoning: String::new(),
+            operations: vec![
+                replace,
+                DeltaOperation::merge("db", &c, [&b]),
+                DeltaOperation::add_example("cache", None, "q2", "a2"),
+                DeltaOperation::remove("missing", "missing"),
+                DeltaOperation::move_to("db", "missing"),
+            ],
+        };
+        let (before, next_id) = (pb.to_json().unwrap(), pb.next_id);
+        let rollups = pb.section_rollups().clone();
+        assert_eq!(pb.apply_delta_transactional(failing).unwrap_err().op_index(), Some(4));
+        assert_eq!(pb.to_json().unwrap(), before);
+        assert_eq!(pb.next_id, next_id);
+        assert_eq!(pb.section_rollups(), &rollups);
+        assert_eq!(pb.sections["api"], [a.clone(), b.clone()]);
+
+        // 成功时返回的变化与整体比较的结果一致
+        let ops = vec![
+            DeltaOperation::add("db", "索引要覆盖查询"),
+            DeltaOperation::merge("db", &c, [&b]),
+            DeltaOperation::move_to("cache", &a),
+            DeltaOperation::add_example("cache", None, "q2", "a2"),
+        ];
+        let old = pb.clone();
+        let batch = DeltaBatch { reasoning: String::new(), operations: ops };
+        let changes = pb.apply_delta_transactional(batch).unwrap();
+        let full = old.diff(&pb);
+        assert_eq!(changes.reasoning, full.reasoning);
+        assert_eq!(serde_json::to_value(&changes).unwrap(), serde_json::to_value(&full).unwrap());
+    }
+
+    #[test]
+    fn test_bulk_load() {
+        let mut source = Playbook::new();
+        let a = source.add_bullet("api", "先分页", None, None);
+        source.add_bullet("debug", "先看日志", None, None);
+        source.add_bullet("api", "带上cursor", None, None);
+        source.tag_bullet(&a, "helpful", 2).unwrap();
+
+        let mut pb = Playbook::new();
+        let existing = pb.add_bullet("api", "已有", Some("api-old".into()), None);
+        let revision = pb.revision();
+        let loaded: Vec<Bullet> = source.bullets().into_iter().cloned().collect();
+        pb.bulk_load(loaded.clone()).unwrap();
+        assert!(pb.revision() > revision);
+        assert_eq!(pb.bullets[&a].updated_at, source.bullets[&a].updated_at);
+        assert_eq!(pb.sections["api"].len(), 3);
+        assert_eq!(pb.sections["api"][0], existing);
+        assert!(Arc::ptr_eq(&pb.bullets[&a].section, pb.sections.get_key_value("api").unwrap().0));
+        assert_eq!(pb.section_rollup("api").unwrap().count(&Tag::Helpful), 2);
+        assert_eq!(pb.next_id, 3);
+
+        // 校验失败时不做任何修改
+        let revision = pb.revision();
+        let mut unnamed = Bullet::new_at("api", "无ID".into(), pb.clock().now());
+        assert!(pb.bulk_load(vec![unnamed.clone()]).is_err());
+        unnamed.id = "api-00099".into();
+        assert!(pb.bulk_load(vec![unnamed.clone(), loaded[0].clone()]).is_err());
+        assert!(pb.bulk_load(vec![unnamed.clone(), unnamed.clone()]).is_err());
+        assert_eq!(pb.revision(), revision);
+        assert_eq!(pb.bullets.len(), 4);
+        pb.bulk_load(vec![unnamed]).unwrap();
+        assert_eq!(pb.add_bullet("api", "新", None, None), "api-00100");
+    }
+
+    #[test]
+    fn test_parse_prompt_round_trip() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        pb.add_bullet("api usage", "限流时读取Retry-After", None, None);
+        let c = pb.add_bullet("debugging", "先看日志 (见runbook)", None, None);
+        pb.tag_bullet(&a, "helpful", 3).unwrap();
+        pb.tag_bullet(&c, "harmful", 1).unwrap();
+
+        let prompt = pb.as_prompt();
+        let parsed = Playbook::parse_prompt(&prompt).unwrap();
+        assert_eq!(parsed.as_prompt(), prompt);
+        assert_eq!(parsed.sections, pb.sections);
+
+        // 人工编辑：改内容、删计数、加续行和无ID的新子弹
+        let edited = prompt
+            .replace("cursor (helpful=3, harmful=0, neutral=0)", "cursor和limit")
+            .replace("## debugging", "## debugging\n- 复现后再修");
+        let parsed = Playbook::parse_prompt(&edited).unwrap();
+        assert_eq!(parsed.bullets[&a].content, "分页时带上cursor和limit");
+        assert_eq!(parsed.bullets[&a].helpful, 0);
+        assert_eq!(parsed.bullets[&c].content, "先看日志 (见runbook)");
+        let cmp = parsed.compare_stats(&pb);
+        assert_eq!(cmp.added.len(), 1);
+        assert_eq!(parsed.bullets[&cmp.added[0]].content, "复现后再修");
+        assert!(!pb.bullets.contains_key(&cmp.added[0]));
+
+        let mut multi = Playbook::new();
+        multi.add_bullet("a", "第一行\n  第二行", None, None);
+        let parsed = Playbook::parse_prompt(&multi.as_prompt()).unwrap();
+        assert_eq!(parsed.bullets()[0].content, "第一行\n  第二行");
+        assert!(Playbook::parse_prompt(&(multi.as_prompt() + "\n多余的行")).is_err());
+        assert!(Playbook::parse_prompt("- [x-1] 没有章节").is_err());
+        assert!(Playbook::parse_prompt("## a\n- [x-1] 一\n- [x-1] 二").is_err());
+    }
+}
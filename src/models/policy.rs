@@ -0,0 +1,366 @@
+//! 内容策略：每个ADD/UPDATE在写入Playbook前都要经过检查，可以放行、脱敏改写或整批拒绝
+
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    Allow,
+    /// 用改写后的内容替换原内容
+    Redact(String),
+    /// 拒绝写入，附原因
+    Reject(String),
+}
+
+pub trait ContentPolicy: Debug + Send + Sync {
+    fn check(&self, section: &str, content: &str) -> PolicyDecision;
+}
+
+pub type SharedContentPolicy = Arc<dyn ContentPolicy>;
+
+/// 按顺序执行多个策略：前一个策略的改写结果交给下一个，任一拒绝即拒绝
+#[derive(Debug, Clone, Default)]
+pub struct PolicyChain {
+    policies: Vec<SharedContentPolicy>,
+}
+
+impl PolicyChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, policy: impl ContentPolicy + 'static) -> Self {
+        self.policies.push(Arc::new(policy));
+        self
+    }
+}
+
+impl ContentPolicy for PolicyChain {
+    fn check(&self, section: &str, content: &str) -> PolicyDecision {
+        let mut current: Option<String> = None;
+        for policy in &self.policies {
+            match policy.check(section, current.as_deref().unwrap_or(content)) {
+                PolicyDecision::Allow => {}
+                PolicyDecision::Redact(redacted) => current = Some(redacted),
+                reject @ PolicyDecision::Reject(_) => return reject,
+            }
+        }
+        current.map_or(PolicyDecision::Allow, PolicyDecision::Redact)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BannedTermAction {
+    #[default]
+    Reject,
+    /// 把命中的词替换为 [`BannedTerms::REPLACEMENT`]
+    Redact,
+}
+
+/// 禁用词列表（忽略ASCII大小写）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BannedTerms {
+    pub terms: Vec<String>,
+    #[serde(default)]
+    pub action: BannedTermAction,
+}
+
+impl BannedTerms {
+    pub const REPLACEMENT: &'static str = "[REDACTED]";
+
+    pub fn new<I, S>(terms: I, action: BannedTermAction) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            terms: terms.into_iter().map(Into::into).filter(|t: &String| !t.is_empty()).collect(),
+            action,
+        }
+    }
+}
+
+impl ContentPolicy for BannedTerms {
+    fn check(&self, _section: &str, content: &str) -> PolicyDecision {
+        let Some(hit) = self.terms.iter().find(|t| find_ignore_ascii_case(content, t, 0).is_some()) else {
+            return PolicyDecision::Allow;
+        };
+        match self.action {
+            BannedTermAction::Reject => PolicyDecision::Reject(alloc::format!("banned term: {}", hit)),
+            BannedTermAction::Redact => {
+                let mut redacted = content.to_string();
+                for term in &self.terms {
+                    redacted = replace_ignore_ascii_case(&redacted, term, Self::REPLACEMENT);
+                }
+                PolicyDecision::Redact(redacted)
+            }
+        }
+    }
+}
+
+/// 把超长内容压缩到给定字符数以内（通常是LLM摘要）
+pub trait Summarizer: Debug + Send + Sync {
+    fn summarize(&self, section: &str, content: &str, max_chars: usize) -> Result<String, String>;
+}
+
+pub type SharedSummarizer = Arc<dyn Summarizer>;
+
+/// 内容长度上限：超长时交给摘要器改写，没有摘要器或摘要仍超长时拒绝
+///
+/// 与 [`DeltaLimits::max_content_chars`](crate::models::delta::DeltaLimits::max_content_chars)
+/// 不同，后者在策略之前检查、超长即整批拒绝；需要摘要回退时只用本策略。
+#[derive(Debug, Clone)]
+pub struct MaxLength {
+    pub max_chars: usize,
+    summarizer: Option<SharedSummarizer>,
+}
+
+impl MaxLength {
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            summarizer: None,
+        }
+    }
+
+    pub fn with_summarizer(mut self, summarizer: impl Summarizer + 'static) -> Self {
+        self.summarizer = Some(Arc::new(summarizer));
+        self
+    }
+}
+
+impl ContentPolicy for MaxLength {
+    fn check(&self, section: &str, content: &str) -> PolicyDecision {
+        let chars = content.chars().count();
+        if chars <= self.max_chars {
+            return PolicyDecision::Allow;
+        }
+        let Some(summarizer) = &self.summarizer else {
+            return PolicyDecision::Reject(alloc::format!(
+                "content has {} chars (max {})",
+                chars,
+                self.max_chars
+            ));
+        };
+        let summary = match summarizer.summarize(section, content, self.max_chars) {
+            Ok(summary) => summary.trim().to_string(),
+            Err(err) => {
+                return PolicyDecision::Reject(alloc::format!("summarization failed: {}", err));
+            }
+        };
+        let chars = summary.chars().count();
+        if summary.is_empty() || chars > self.max_chars {
+            return PolicyDecision::Reject(alloc::format!(
+                "summary has {} chars (max {})",
+                chars,
+                self.max_chars
+            ));
+        }
+        PolicyDecision::Redact(summary)
+    }
+}
+
+#[cfg(feature = "llm")]
+pub use llm_policy::{LlmContentPolicy, LlmSummarizer};
+
+#[cfg(feature = "llm")]
+mod llm_policy {
+    use alloc::format;
+
+    use super::*;
+    use crate::llm::{LlmClient, extract_json};
+
+    /// 让LLM审核内容；调用失败或输出无法解析时按拒绝处理（fail closed）
+    #[derive(Debug)]
+    pub struct LlmContentPolicy<C> {
+        client: C,
+        guidelines: String,
+    }
+
+    impl<C: LlmClient + Debug> LlmContentPolicy<C> {
+        pub fn new(client: C, guidelines: impl Into<String>) -> Self {
+            Self {
+                client,
+                guidelines: guidelines.into(),
+            }
+        }
+    }
+
+    impl<C: LlmClient + Debug> ContentPolicy for LlmContentPolicy<C> {
+        fn check(&self, section: &str, content: &str) -> PolicyDecision {
+            let prompt = format!(
+                "You moderate strategies before they are added to an agent playbook.\n\n\
+                 Guidelines:\n{}\n\nSection: {}\nContent: {}\n\n\
+                 Reply with JSON only: {{\"decision\": \"allow\" | \"redact\" | \"reject\", \
+                 \"content\": \"<rewritten content when redacting>\", \"reason\": \"...\"}}",
+                self.guidelines, section, content
+            );
+            let value = match self.client.complete(&prompt) {
+                Ok(response) => extract_json(&response.text),
+                Err(err) => return PolicyDecision::Reject(format!("moderation failed: {}", err)),
+            };
+            let Some(value) = value else {
+                return PolicyDecision::Reject("moderation output is not JSON".to_string());
+            };
+            let reason = value["reason"].as_str().unwrap_or_default().to_string();
+            match (value["decision"].as_str(), value["content"].as_str()) {
+                (Some("allow"), _) => PolicyDecision::Allow,
+                (Some("redact"), Some(rewritten)) => PolicyDecision::Redact(rewritten.to_string()),
+                (Some("reject"), _) => PolicyDecision::Reject(reason),
+                _ => PolicyDecision::Reject(format!("invalid moderation output: {}", value)),
+            }
+        }
+    }
+
+    /// 让LLM把超长内容（如整段堆栈）压缩成一条简短的策略
+    #[derive(Debug)]
+    pub struct LlmSummarizer<C> {
+        client: C,
+    }
+
+    impl<C: LlmClient + Debug> LlmSummarizer<C> {
+        pub fn new(client: C) -> Self {
+            Self { client }
+        }
+    }
+
+    impl<C: LlmClient + Debug> Summarizer for LlmSummarizer<C> {
+        fn summarize(
+            &self,
+            section: &str,
+            content: &str,
+            max_chars: usize,
+        ) -> Result<String, String> {
+            let prompt = format!(
+                "Condense this playbook strategy into at most {} characters. Keep the actionable \
+                 advice and any identifiers it depends on; drop logs, stack traces and filler.\n\n\
+                 Section: {}\nContent:\n{}\n\nReply with the condensed strategy only.",
+                max_chars, section, content
+            );
+            let response = self.client.complete(&prompt).map_err(|err| err.to_string())?;
+            Ok(response.text)
+        }
+    }
+}
+
+// --------------------------
+// 辅助函数
+// --------------------------
+
+/// 从`from`开始查找`needle`（忽略ASCII大小写），返回字节偏移
+fn find_ignore_ascii_case(haystack: &str, needle: &str, from: usize) -> Option<usize> {
+    let (hay, pat) = (haystack.as_bytes(), needle.as_bytes());
+    if pat.is_empty() || hay.len() < pat.len() {
+        return None;
+    }
+    (from..=hay.len() - pat.len())
+        .filter(|&i| haystack.is_char_boundary(i))
+        .find(|&i| hay[i..i + pat.len()].eq_ignore_ascii_case(pat))
+}
+
+fn replace_ignore_ascii_case(haystack: &str, needle: &str, replacement: &str) -> String {
+    let mut out = String::with_capacity(haystack.len());
+    let mut pos = 0;
+    while let Some(start) = find_ignore_ascii_case(haystack, needle, pos) {
+        out.push_str(&haystack[pos..start]);
+        out.push_str(replacement);
+        pos = start + needle.len();
+    }
+    out.push_str(&haystack[pos..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_banned_terms() {
+        let reject = BannedTerms::new(["internal-only"], BannedTermAction::Reject);
+        assert_eq!(reject.check("s", "safe content"), PolicyDecision::Allow);
+        assert!(matches!(reject.check("s", "see INTERNAL-ONLY docs"), PolicyDecision::Reject(_)));
+
+        let redact = BannedTerms::new(["acme", "秘密"], BannedTermAction::Redact);
+        assert_eq!(
+            redact.check("s", "Ask Acme about 秘密 ACME things"),
+            PolicyDecision::Redact("Ask [REDACTED] about [REDACTED] [REDACTED] things".to_string())
+        );
+    }
+
+    #[test]
+    fn test_chain_feeds_redactions_forward() {
+        let chain = PolicyChain::new()
+            .with(BannedTerms::new(["acme"], BannedTermAction::Redact))
+            .with(BannedTerms::new(["[redacted] secret"], BannedTermAction::Reject));
+        assert_eq!(
+            chain.check("s", "acme rocks"),
+            PolicyDecision::Redact("[REDACTED] rocks".to_string())
+        );
+        assert!(matches!(chain.check("s", "acme secret"), PolicyDecision::Reject(_)));
+        assert_eq!(chain.check("s", "fine"), PolicyDecision::Allow);
+    }
+
+    #[derive(Debug)]
+    struct FirstLine;
+
+    impl Summarizer for FirstLine {
+        fn summarize(&self, _section: &str, content: &str, _max: usize) -> Result<String, String> {
+            Ok(content.lines().next().unwrap_or_default().to_string())
+        }
+    }
+
+    #[test]
+    fn test_max_length_summarizes_over_length_content() {
+        let trace = "空指针时先检查配置是否加载\nat Foo.bar(Foo.java:10)\nat Baz.qux(Baz.java:20)";
+        let strict = MaxLength::new(20);
+        assert_eq!(strict.check("s", "短内容"), PolicyDecision::Allow);
+        assert!(matches!(strict.check("s", trace), PolicyDecision::Reject(_)));
+
+        let lenient = MaxLength::new(20).with_summarizer(FirstLine);
+        assert_eq!(
+            lenient.check("s", trace),
+            PolicyDecision::Redact("空指针时先检查配置是否加载".to_string())
+        );
+        let long_first_line = "x".repeat(30) + "\ny";
+        assert!(matches!(lenient.check("s", &long_first_line), PolicyDecision::Reject(_)));
+    }
+
+    #[cfg(feature = "llm")]
+    #[test]
+    fn test_llm_summarizer() {
+        use crate::llm::DummyLlmClient;
+
+        let client = DummyLlmClient::with_responses(["  先检查配置是否加载 "]);
+        let policy = MaxLength::new(20).with_summarizer(LlmSummarizer::new(client));
+        assert_eq!(
+            policy.check("debugging", &"堆栈".repeat(20)),
+            PolicyDecision::Redact("先检查配置是否加载".to_string())
+        );
+    }
+
+    #[cfg(feature = "llm")]
+    #[test]
+    fn test_llm_policy_fails_closed() {
+        use crate::llm::DummyLlmClient;
+
+        let client = DummyLlmClient::with_responses([
+            r#"{"decision": "redact", "content": "Email the customer", "reason": "pii"}"#,
+            "not json",
+        ]);
+        let policy = LlmContentPolicy::new(&client, "No personal data.");
+        assert_eq!(
+            policy.check("s", "Email bob@example.com"),
+            PolicyDecision::Redact("Email the customer".to_string())
+        );
+        assert!(matches!(policy.check("s", "x"), PolicyDecision::Reject(_)));
+        assert!(matches!(policy.check("s", "x"), PolicyDecision::Reject(_)));
+        assert!(client.prompts()[0].contains("No personal data."));
+    }
+}
@@ -0,0 +1,269 @@
+//! 变化速率限制：限制一段时间内Playbook新增、删除的子弹数与token净增长，
+//! 避免线上自适应让agent的行为变化过快
+//!
+//! [`RateGuard::apply`] 先估算Delta的影响（[`Playbook::delta_impact`]），加上窗口内已应用的
+//! 变化后仍在所有限额内才应用；否则整批提交到 [`ReviewQueue`]，由人工决定。
+
+use alloc::{collections::BTreeSet, format, string::String, vec::Vec};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::canary::ApplyReport;
+use crate::models::context::estimate_tokens;
+use crate::models::delta::{DeltaBatch, OperationType};
+use crate::models::playbook::{Playbook, PlaybookError};
+use crate::models::review::{ReviewError, ReviewQueue};
+
+/// 一个时间窗口内的限额（`None`表示不限制）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// 窗口长度（秒），如3600或86400
+    pub window_secs: u64,
+    #[serde(default)]
+    pub max_added: Option<usize>,
+    #[serde(default)]
+    pub max_removed: Option<usize>,
+    /// 渲染后token数的最大净增长
+    #[serde(default)]
+    pub max_token_growth: Option<i64>,
+}
+
+impl RateLimit {
+    pub fn hourly() -> Self {
+        Self {
+            window_secs: 3600,
+            ..Default::default()
+        }
+    }
+
+    pub fn daily() -> Self {
+        Self {
+            window_secs: 24 * 3600,
+            ..Default::default()
+        }
+    }
+
+    fn window(&self) -> TimeDelta {
+        i64::try_from(self.window_secs)
+            .ok()
+            .and_then(TimeDelta::try_seconds)
+            .unwrap_or(TimeDelta::MAX)
+    }
+}
+
+/// 一个DeltaBatch对Playbook的影响
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeltaImpact {
+    /// 新增的子弹数
+    pub added: usize,
+    /// 删除的已有子弹数
+    pub removed: usize,
+    /// 子弹内容的估算token净变化
+    pub token_growth: i64,
+}
+
+impl DeltaImpact {
+    fn accumulate(&mut self, other: &DeltaImpact) {
+        self.added += other.added;
+        self.removed += other.removed;
+        self.token_growth += other.token_growth;
+    }
+}
+
+impl Playbook {
+    /// 估算Delta应用后的变化（不修改Playbook）
+    pub fn delta_impact(&self, delta: &DeltaBatch) -> DeltaImpact {
+        let tokens = |text: &str| estimate_tokens(text) as i64;
+        let mut impact = DeltaImpact::default();
+        let mut removed: BTreeSet<&str> = BTreeSet::new();
+        for op in &delta.operations {
+            let existing = op.bullet_id.as_deref().and_then(|id| self.bullets.get(id));
+            let content = op.content.as_deref().unwrap_or_default();
+            match (op.type_, existing) {
+                (OperationType::Add, None) => {
+                    impact.added += 1;
+                    impact.token_growth += tokens(content);
+                }
+                (OperationType::Add | OperationType::Update, Some(bullet))
+                    if op.content.is_some() =>
+                {
+                    impact.token_growth += tokens(content) - tokens(&bullet.content);
+                }
+                (OperationType::Remove, Some(bullet)) if removed.insert(&bullet.id) => {
+                    impact.removed += 1;
+                    impact.token_growth -= tokens(&bullet.content);
+                }
+                (OperationType::Merge, Some(bullet)) => {
+                    if op.content.is_some() {
+                        impact.token_growth += tokens(content) - tokens(&bullet.content);
+                    }
+                    for merged in op.merged_ids.iter().filter_map(|id| self.bullets.get(id)) {
+                        if removed.insert(&merged.id) {
+                            impact.removed += 1;
+                            impact.token_growth -= tokens(&merged.content);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        impact
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuardDecision {
+    Applied(DeltaImpact),
+    /// 已提交到审核队列，`reason`为超出的限额
+    Queued { review_id: u64, reason: String },
+}
+
+/// 按时间窗口限制变化速率的Delta入口
+#[derive(Debug, Clone, Default)]
+pub struct RateGuard {
+    pub limits: Vec<RateLimit>,
+    /// 已应用的变化（按时间顺序）
+    history: Vec<(DateTime<Utc>, DeltaImpact)>,
+    /// 超出限额的Delta
+    pub review: ReviewQueue,
+}
+
+impl RateGuard {
+    pub fn new(limits: Vec<RateLimit>) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_limit(mut self, limit: RateLimit) -> Self {
+        self.limits.push(limit);
+        self
+    }
+
+    /// 以Playbook的时钟为准应用或排队
+    pub fn apply(
+        &mut self,
+        playbook: &mut Playbook,
+        delta: DeltaBatch,
+    ) -> Result<GuardDecision, PlaybookError> {
+        let now = playbook.clock().now();
+        self.apply_at(playbook, delta, now)
+    }
+
+    /// 加上窗口内已应用的变化仍在所有限额内时应用Delta，否则整批提交审核。
+    /// 应用出错时不计入历史
+    pub fn apply_at(
+        &mut self,
+        playbook: &mut Playbook,
+        delta: DeltaBatch,
+        now: DateTime<Utc>,
+    ) -> Result<GuardDecision, PlaybookError> {
+        let impact = playbook.delta_impact(&delta);
+        if let Some(reason) = self.exceeded(&impact, now) {
+            let review_id = self.review.submit(delta, reason.clone(), playbook);
+            return Ok(GuardDecision::Queued { review_id, reason });
+        }
+        playbook.apply_delta_at(delta, now)?;
+        self.record(impact, now);
+        Ok(GuardDecision::Applied(impact))
+    }
+
+    /// 批准审核队列中的Delta；人工批准不受限额约束，但变化计入窗口
+    pub fn approve(
+        &mut self,
+        review_id: u64,
+        playbook: &mut Playbook,
+        note: Option<String>,
+    ) -> Result<ApplyReport, ReviewError> {
+        let report = self.review.approve(review_id, playbook, note)?;
+        self.record(report.impact, playbook.clock().now());
+        Ok(report)
+    }
+
+    /// 记录在Guard之外应用的变化，使其计入限额
+    pub fn record(&mut self, impact: DeltaImpact, now: DateTime<Utc>) {
+        let longest = self.limits.iter().map(RateLimit::window).max().unwrap_or_default();
+        self.history.retain(|(at, _)| now - *at < longest);
+        self.history.push((now, impact));
+    }
+
+    /// 窗口内已应用的变化总和
+    pub fn used(&self, limit: &RateLimit, now: DateTime<Utc>) -> DeltaImpact {
+        let mut used = DeltaImpact::default();
+        for (_, impact) in self.history.iter().filter(|(at, _)| now - *at < limit.window()) {
+            used.accumulate(impact);
+        }
+        used
+    }
+
+    /// 第一个会被超出的限额
+    fn exceeded(&self, impact: &DeltaImpact, now: DateTime<Utc>) -> Option<String> {
+        self.limits.iter().find_map(|limit| {
+            let mut total = self.used(limit, now);
+            total.accumulate(impact);
+            let window = limit.window_secs;
+            if let Some(max) = limit.max_added.filter(|&max| total.added > max) {
+                return Some(format!("{} bullets added in {}s (max {})", total.added, window, max));
+            }
+            if let Some(max) = limit.max_removed.filter(|&max| total.removed > max) {
+                let removed = total.removed;
+                return Some(format!("{} bullets removed in {}s (max {})", removed, window, max));
+            }
+            let growth = total.token_growth;
+            limit
+                .max_token_growth
+                .filter(|&max| growth > max)
+                .map(|max| format!("{} tokens of growth in {}s (max {})", growth, window, max))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, sync::Arc, vec};
+
+    use crate::clock::{Clock as _, ManualClock};
+    use crate::models::delta::DeltaOperation;
+
+    #[test]
+    fn test_excess_delta_is_queued_until_window_passes() {
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let old = pb.add_bullet("rules", "Never share customer emails", None, None);
+        let mut guard = RateGuard::default().with_limit(RateLimit {
+            max_added: Some(2),
+            max_removed: Some(0),
+            ..RateLimit::hourly()
+        });
+        let adds = |n: usize| DeltaBatch {
+            reasoning: "learned".to_string(),
+            operations: (0..n)
+                .map(|i| DeltaOperation::add("tips", format!("Tip number {}", i)))
+                .collect(),
+        };
+
+        let applied = guard.apply(&mut pb, adds(2)).unwrap();
+        assert!(matches!(applied, GuardDecision::Applied(DeltaImpact { added: 2, .. })));
+        assert!(matches!(guard.apply(&mut pb, adds(1)).unwrap(), GuardDecision::Queued { .. }));
+        let remove = DeltaBatch {
+            reasoning: "cleanup".to_string(),
+            operations: vec![DeltaOperation::remove("rules", &old)],
+        };
+        let GuardDecision::Queued { review_id, reason } = guard.apply(&mut pb, remove).unwrap()
+        else {
+            panic!("remove should be queued");
+        };
+        assert_eq!(reason, "1 bullets removed in 3600s (max 0)");
+        assert_eq!((pb.bullets.len(), guard.review.pending().count()), (3, 2));
+
+        // 人工批准绕过限额，但计入窗口
+        guard.approve(review_id, &mut pb, None).unwrap();
+        assert_eq!(guard.used(&guard.limits[0], clock.now()).removed, 1);
+        clock.advance(TimeDelta::hours(1));
+        assert!(matches!(guard.apply(&mut pb, adds(1)).unwrap(), GuardDecision::Applied(_)));
+        assert_eq!(guard.review.pending().count(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+//! 多个Playbook的注册表，支持跨Playbook引用子弹
+//!
+//! 子弹内容中的`{{playbook::bullet_id}}`在渲染时替换为被引用子弹的内容，
+//! 这样一个共享的“公司政策”Playbook可以被多个代理各自的Playbook引用，而不必复制。
+
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::playbook::{Bullet, Playbook};
+
+/// 被引用的子弹仍含引用时继续展开的最大层数（防止循环引用）
+const MAX_REFERENCE_DEPTH: usize = 4;
+
+/// 跨Playbook引用：`playbook::bullet_id`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CrossRef {
+    pub playbook: String,
+    pub bullet_id: String,
+}
+
+impl CrossRef {
+    /// 解析`playbook::bullet_id`（两边都不能为空或含空白）
+    pub fn parse(text: &str) -> Option<Self> {
+        let (playbook, bullet_id) = text.trim().split_once("::")?;
+        let valid =
+            |s: &str| !s.is_empty() && !s.contains(char::is_whitespace) && !s.contains("::");
+        (valid(playbook) && valid(bullet_id)).then(|| Self {
+            playbook: playbook.to_string(),
+            bullet_id: bullet_id.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for CrossRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}", self.playbook, self.bullet_id)
+    }
+}
+
+/// 内容中所有的跨Playbook引用（按出现顺序）
+pub fn references(content: &str) -> Vec<CrossRef> {
+    let mut refs = Vec::new();
+    scan(content, |inner| {
+        if let Some(r) = CrossRef::parse(inner) {
+            refs.push(r);
+        }
+        None
+    });
+    refs
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlaybookRegistry {
+    playbooks: BTreeMap<String, Playbook>,
+}
+
+impl PlaybookRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册Playbook，返回同名的旧Playbook
+    pub fn register(&mut self, name: impl Into<String>, playbook: Playbook) -> Option<Playbook> {
+        self.playbooks.insert(name.into(), playbook)
+    }
+
+    pub fn unregister(&mut self, name: &str) -> Option<Playbook> {
+        self.playbooks.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Playbook> {
+        self.playbooks.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Playbook> {
+        self.playbooks.get_mut(name)
+    }
+
+    /// 已注册的名称（有序）
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.playbooks.keys().map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Playbook)> {
+        self.playbooks.iter().map(|(name, pb)| (name.as_str(), pb))
+    }
+
+    pub fn len(&self) -> usize {
+        self.playbooks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.playbooks.is_empty()
+    }
+
+    pub fn resolve(&self, reference: &CrossRef) -> Option<&Bullet> {
+        self.playbooks.get(&reference.playbook)?.get_bullet(&reference.bullet_id)
+    }
+
+    /// 渲染指定Playbook并展开其中的跨Playbook引用；无法解析的引用原样保留
+    pub fn as_prompt(&self, name: &str) -> Option<String> {
+        let playbook = self.playbooks.get(name)?;
+        Some(playbook.render_sections(|_| true, |b| {
+            self.expand(&b.content, MAX_REFERENCE_DEPTH)
+        }))
+    }
+
+    /// 指定Playbook中无法解析的引用（目标Playbook未注册或子弹不存在）
+    pub fn dangling_references(&self, name: &str) -> Vec<(String, CrossRef)> {
+        let Some(playbook) = self.playbooks.get(name) else {
+            return Vec::new();
+        };
+        playbook
+            .bullets()
+            .into_iter()
+            .flat_map(|b| references(&b.content).into_iter().map(move |r| (b.id.clone(), r)))
+            .filter(|(_, r)| self.resolve(r).is_none())
+            .collect()
+    }
+
+    fn expand<'a>(&self, content: &'a str, depth: usize) -> Cow<'a, str> {
+        if depth == 0 || !content.contains("::") {
+            return Cow::Borrowed(content);
+        }
+        scan(content, |inner| {
+            let bullet = self.resolve(&CrossRef::parse(inner)?)?;
+            Some(self.expand(&bullet.content, depth - 1).into_owned())
+        })
+    }
+}
+
+/// 扫描`{{...}}`，`replace`返回`Some`时替换整个占位符；没有替换时不分配
+fn scan<'a>(content: &'a str, mut replace: impl FnMut(&str) -> Option<String>) -> Cow<'a, str> {
+    let mut out = String::new();
+    let mut rest = content;
+    let mut replaced = false;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        match replace(&after[..end]) {
+            Some(value) => {
+                out.push_str(&rest[..start]);
+                out.push_str(&value);
+                replaced = true;
+            }
+            None => out.push_str(&rest[..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    if !replaced {
+        return Cow::Borrowed(content);
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> PlaybookRegistry {
+        let mut policies = Playbook::new();
+        policies.add_bullet("privacy", "Never share customer emails", None, None);
+        policies.add_bullet("loop", "see {{support::support-00002}}", None, None);
+
+        let mut support = Playbook::new();
+        support.add_bullet(
+            "support",
+            "Follow {{policies::privacy-00001}}; {{policies::missing-1}} {{ops::x-1}}",
+            None,
+            None,
+        );
+        support.add_bullet("support", "Cycle: {{policies::loop-00002}}", None, None);
+
+        let mut registry = PlaybookRegistry::new();
+        registry.register("policies", policies);
+        registry.register("support", support);
+        registry
+    }
+
+    #[test]
+    fn test_render_resolves_references() {
+        let registry = registry();
+        let prompt = registry.as_prompt("support").unwrap();
+        assert!(prompt.contains(
+            "Follow Never share customer emails; {{policies::missing-1}} {{ops::x-1}}"
+        ));
+        // 循环引用在最大深度处停止展开
+        assert!(prompt.contains("Cycle: see Cycle: see Cycle: {{policies::loop-00002}}"));
+        assert!(registry.as_prompt("unknown").is_none());
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let registry = registry();
+        let dangling: Vec<String> = registry
+            .dangling_references("support")
+            .into_iter()
+            .map(|(_, r)| r.to_string())
+            .collect();
+        assert_eq!(dangling, ["policies::missing-1", "ops::x-1"]);
+
+        assert_eq!(
+            CrossRef::parse(" policies::privacy-00001 "),
+            Some(CrossRef {
+                playbook: "policies".into(),
+                bullet_id: "privacy-00001".into()
+            })
+        );
+        assert!(CrossRef::parse("::x").is_none());
+        assert!(CrossRef::parse("api_base_url").is_none());
+    }
+}
@@ -0,0 +1,212 @@
+//! 按条件重放Delta日志，构造反事实的Playbook（只保留某个章节、只重放某次运行、去掉所有
+//! REMOVE…），用于消融研究：比较各个训练阶段分别贡献了什么
+//!
+//! 被过滤掉的ADD之后，引用这些子弹的UPDATE/TAG/REMOVE/MOVE/MERGE在反事实Playbook中没有对象，
+//! 重放时跳过并计入 [`ReplayReport::dangling`]，而不是让整个重放失败。
+
+use alloc::{
+    collections::BTreeSet,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::delta::{DeltaBatch, DeltaOperation, OperationType};
+use crate::models::playbook::{Playbook, PlaybookError};
+use crate::models::timestamp;
+use crate::progress::{ProgressSink, ProgressTracker};
+
+/// Delta日志中的一条：某次运行在某时刻应用的DeltaBatch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// 产生这批操作的运行（如训练轮次`epoch-2`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<String>,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub applied_at: DateTime<Utc>,
+    pub delta: DeltaBatch,
+}
+
+/// 重放条件；各项为空时不限制
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayFilter {
+    /// 只重放这些章节的操作
+    pub sections: BTreeSet<String>,
+    /// 只重放这些运行的条目（没有`run`的条目视为不匹配）
+    pub runs: BTreeSet<String>,
+    /// 跳过这些类型的操作
+    pub exclude: Vec<OperationType>,
+}
+
+/// 重放统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// 应用了至少一个操作的条目数
+    pub entries: usize,
+    pub applied: usize,
+    /// 被条件过滤掉的操作数
+    pub filtered: usize,
+    /// 引用的子弹在反事实Playbook中不存在而跳过的操作数
+    pub dangling: usize,
+}
+
+impl ReplayFilter {
+    pub fn matches_entry(&self, entry: &JournalEntry) -> bool {
+        self.runs.is_empty() || entry.run.as_ref().is_some_and(|run| self.runs.contains(run))
+    }
+
+    pub fn matches_op(&self, op: &DeltaOperation) -> bool {
+        (self.sections.is_empty() || self.sections.contains(&op.section))
+            && !self.exclude.contains(&op.type_)
+    }
+
+    /// 从空Playbook开始重放
+    pub fn replay<'a>(
+        &self,
+        entries: impl IntoIterator<Item = &'a JournalEntry>,
+    ) -> Result<(Playbook, ReplayReport), PlaybookError> {
+        let mut playbook = Playbook::new();
+        let report = self.replay_onto(&mut playbook, entries)?;
+        Ok((playbook, report))
+    }
+
+    /// 在已有的Playbook（如某个快照）上重放，每条以其`applied_at`为时间戳
+    pub fn replay_onto<'a>(
+        &self,
+        playbook: &mut Playbook,
+        entries: impl IntoIterator<Item = &'a JournalEntry>,
+    ) -> Result<ReplayReport, PlaybookError> {
+        self.replay_onto_with_progress(playbook, entries, &mut ())
+    }
+
+    /// 同`replay_onto`，每处理一条日志报告一次进度
+    pub fn replay_onto_with_progress<'a>(
+        &self,
+        playbook: &mut Playbook,
+        entries: impl IntoIterator<Item = &'a JournalEntry>,
+        sink: &mut dyn ProgressSink,
+    ) -> Result<ReplayReport, PlaybookError> {
+        let entries: Vec<&JournalEntry> = entries.into_iter().collect();
+        let clock = playbook.clock().clone();
+        let mut progress = ProgressTracker::new(sink, clock, "replay", Some(entries.len()));
+        let mut report = ReplayReport::default();
+        for entry in entries {
+            progress.advance(1);
+            let total = entry.delta.operations.len();
+            if !self.matches_entry(entry) {
+                report.filtered += total;
+                continue;
+            }
+            let mut known: BTreeSet<String> = BTreeSet::new();
+            let mut operations = Vec::with_capacity(total);
+            for op in &entry.delta.operations {
+                if !self.matches_op(op) {
+                    report.filtered += 1;
+                    continue;
+                }
+                let target = op.bullet_id.as_deref();
+                let unknown = |id: &str| !playbook.bullets.contains_key(id) && !known.contains(id);
+                let dangling = match op.type_ {
+                    OperationType::Update
+                    | OperationType::Tag
+                    | OperationType::Remove
+                    | OperationType::Move => target.is_some_and(unknown),
+                    OperationType::Merge => {
+                        target.is_some_and(unknown) || op.merged_ids.iter().any(|id| unknown(id))
+                    }
+                    _ => false,
+                };
+                if dangling {
+                    report.dangling += 1;
+                    continue;
+                }
+                if op.type_ == OperationType::Add
+                    && let Some(id) = target
+                {
+                    known.insert(id.to_string());
+                }
+                operations.push(op.clone());
+            }
+            if operations.is_empty() {
+                continue;
+            }
+            report.applied += operations.len();
+            report.entries += 1;
+            let delta = DeltaBatch { reasoning: entry.delta.reasoning.clone(), operations };
+            playbook.apply_delta_at(delta, entry.applied_at)?;
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeMap;
+
+    fn entry(run: &str, operations: Vec<DeltaOperation>) -> JournalEntry {
+        JournalEntry {
+            run: Some(run.to_string()),
+            applied_at: DateTime::UNIX_EPOCH,
+            delta: DeltaBatch { reasoning: String::new(), operations },
+        }
+    }
+
+    fn add(section: &str, id: &str) -> DeltaOperation {
+        let mut op = DeltaOperation::add(section, id);
+        op.bullet_id = Some(id.to_string());
+        op
+    }
+
+    #[test]
+    fn test_filtered_replay() {
+        let helpful = || BTreeMap::from([("helpful".to_string(), 1)]);
+        let journal = [
+            entry("epoch-1", vec![add("api usage", "api-1"), add("debugging", "dbg-2")]),
+            entry(
+                "epoch-2",
+                vec![
+                    DeltaOperation::tag("api usage", "api-1", helpful()),
+                    DeltaOperation::tag("debugging", "dbg-2", helpful()),
+                    add("api usage", "api-3"),
+                ],
+            ),
+            entry("epoch-3", vec![DeltaOperation::remove("api usage", "api-1")]),
+        ];
+
+        let (full, report) = ReplayFilter::default().replay(&journal).unwrap();
+        let ids: Vec<&str> = full.bullets().into_iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, ["api-3", "dbg-2"]);
+        assert_eq!(report, ReplayReport { entries: 3, applied: 6, filtered: 0, dangling: 0 });
+
+        let no_removes =
+            ReplayFilter { exclude: vec![OperationType::Remove], ..ReplayFilter::default() };
+        let (pb, report) = no_removes.replay(&journal).unwrap();
+        assert_eq!(pb.bullets["api-1"].helpful, 1);
+        assert_eq!((report.entries, report.filtered), (2, 1));
+
+        let only_api = ReplayFilter {
+            sections: BTreeSet::from(["api usage".to_string()]),
+            ..ReplayFilter::default()
+        };
+        let (pb, report) = only_api.replay(&journal).unwrap();
+        assert_eq!(pb.sections.len(), 1);
+        assert_eq!(report.filtered, 2);
+
+        // 没有epoch-1的ADD，epoch-2对旧子弹的TAG没有对象
+        let later_runs = ReplayFilter {
+            runs: BTreeSet::from(["epoch-2".to_string(), "epoch-3".to_string()]),
+            ..ReplayFilter::default()
+        };
+        let (pb, report) = later_runs.replay(&journal).unwrap();
+        assert_eq!(pb.bullets.keys().collect::<Vec<_>>(), ["api-3"]);
+        assert_eq!(report, ReplayReport { entries: 1, applied: 1, filtered: 2, dangling: 3 });
+
+        let json = serde_json::to_string(&journal[0]).unwrap();
+        let restored: JournalEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.run.as_deref(), Some("epoch-1"));
+    }
+}
@@ -0,0 +1,249 @@
+//! Delta审核队列：暂不直接应用的DeltaBatch（超出速率限制、需要人工批准等）在这里排队，
+//! 附带理由与金丝雀diff，由人工或外部审核界面批准、拒绝或修改后再批准
+
+use alloc::{string::String, vec::Vec};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::canary::ApplyReport;
+use crate::models::delta::DeltaBatch;
+use crate::models::playbook::{Playbook, PlaybookError};
+use crate::models::timestamp;
+
+#[derive(Debug, Error)]
+pub enum ReviewError {
+    #[error("审核项不存在: {0}")]
+    NotFound(u64),
+    #[error("审核项{0}已处理（{1:?}）")]
+    AlreadyDecided(u64, ReviewStatus),
+    /// 提交后Playbook已变化且金丝雀diff随之改变；审核项已按当前Playbook重新计算，需重新审核
+    #[error("审核项{0}提交后Playbook已变化，金丝雀diff已重新计算")]
+    Stale(u64),
+    #[error(transparent)]
+    Playbook(#[from] PlaybookError),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+    pub id: u64,
+    pub delta: DeltaBatch,
+    /// 排队原因（如超出的限额）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reason: String,
+    /// 提交（或修改）时相对当时Playbook的渲染diff；Delta无法应用时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary_diff: Option<String>,
+    /// 计算`canary_diff`时Playbook的修订号（不序列化，加载后为0，批准时按diff重新核对）
+    #[serde(skip)]
+    pub revision: u64,
+    #[serde(default)]
+    pub status: ReviewStatus,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub submitted_at: DateTime<Utc>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "timestamp::deserialize_option"
+    )]
+    pub decided_at: Option<DateTime<Utc>>,
+    /// 审核者备注（如拒绝原因）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// 审核队列，可整体序列化（见`persist::review`）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReviewQueue {
+    pub items: Vec<ReviewItem>,
+    #[serde(default)]
+    next_id: u64,
+}
+
+impl ReviewQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 提交Delta，以Playbook的当前状态计算金丝雀diff，返回审核项ID
+    pub fn submit(
+        &mut self,
+        delta: DeltaBatch,
+        reason: impl Into<String>,
+        playbook: &Playbook,
+    ) -> u64 {
+        self.next_id += 1;
+        self.items.push(ReviewItem {
+            id: self.next_id,
+            canary_diff: playbook.canary_diff(&delta).ok(),
+            revision: playbook.revision(),
+            delta,
+            reason: reason.into(),
+            status: ReviewStatus::Pending,
+            submitted_at: playbook.clock().now(),
+            decided_at: None,
+            note: None,
+        });
+        self.next_id
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ReviewItem> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    /// 待审核的项（按提交顺序）
+    pub fn pending(&self) -> impl Iterator<Item = &ReviewItem> {
+        self.items.iter().filter(|item| item.status == ReviewStatus::Pending)
+    }
+
+    /// 批准并应用到Playbook。应用失败时审核项保持待审核；
+    /// 提交后Playbook已变化时先重新计算金丝雀diff，diff改变则返回 [`ReviewError::Stale`]，
+    /// 审核项更新为新diff、保持待审核，审核者确认后可再次批准
+    pub fn approve(
+        &mut self,
+        id: u64,
+        playbook: &mut Playbook,
+        note: Option<String>,
+    ) -> Result<ApplyReport, ReviewError> {
+        let item = self.pending_mut(id)?;
+        if item.revision != playbook.revision() {
+            let canary_diff = playbook.canary_diff(&item.delta).ok();
+            item.revision = playbook.revision();
+            if canary_diff != item.canary_diff {
+                item.canary_diff = canary_diff;
+                return Err(ReviewError::Stale(id));
+            }
+        }
+        let report = playbook.apply_delta_with_report(item.delta.clone(), true)?;
+        item.status = ReviewStatus::Approved;
+        item.decided_at = Some(playbook.clock().now());
+        item.note = note;
+        Ok(report)
+    }
+
+    pub fn reject(
+        &mut self,
+        id: u64,
+        note: Option<String>,
+        now: DateTime<Utc>,
+    ) -> Result<(), ReviewError> {
+        let item = self.pending_mut(id)?;
+        item.status = ReviewStatus::Rejected;
+        item.decided_at = Some(now);
+        item.note = note;
+        Ok(())
+    }
+
+    /// 用修改后的Delta替换待审核项的内容，并重新计算金丝雀diff
+    pub fn edit(
+        &mut self,
+        id: u64,
+        delta: DeltaBatch,
+        playbook: &Playbook,
+    ) -> Result<&ReviewItem, ReviewError> {
+        let item = self.pending_mut(id)?;
+        item.canary_diff = playbook.canary_diff(&delta).ok();
+        item.revision = playbook.revision();
+        item.delta = delta;
+        Ok(item)
+    }
+
+    /// 删除早于`before`处理完的项，返回删除数
+    pub fn prune_decided(&mut self, before: DateTime<Utc>) -> usize {
+        let len = self.items.len();
+        self.items.retain(|item| item.decided_at.is_none_or(|at| at >= before));
+        len - self.items.len()
+    }
+
+    fn pending_mut(&mut self, id: u64) -> Result<&mut ReviewItem, ReviewError> {
+        let item = self
+            .items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or(ReviewError::NotFound(id))?;
+        match item.status {
+            ReviewStatus::Pending => Ok(item),
+            status => Err(ReviewError::AlreadyDecided(id, status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    use crate::models::delta::{DeltaOperation, OperationType};
+
+    #[test]
+    fn test_submit_edit_approve_reject() {
+        let mut pb = Playbook::new();
+        let mut queue = ReviewQueue::new();
+        let batch = |content: &str| DeltaBatch {
+            reasoning: "learned".to_string(),
+            operations: vec![DeltaOperation::add("tips", content)],
+        };
+        let first = queue.submit(batch("Retry on 503"), "rate limit", &pb);
+        let second = queue.submit(batch("Always reboot the server"), "", &pb);
+        assert!(queue.get(first).unwrap().canary_diff.as_ref().unwrap().contains("+- ["));
+        assert_eq!(queue.pending().count(), 2);
+
+        let edited = queue.edit(first, batch("Retry on 503 with backoff"), &pb).unwrap();
+        assert!(edited.canary_diff.as_ref().unwrap().contains("with backoff"));
+        let report = queue.approve(first, &mut pb, None).unwrap();
+        assert_eq!(report.impact.added, 1);
+        assert_eq!(pb.bullets.values().next().unwrap().content, "Retry on 503 with backoff");
+
+        let now = pb.clock().now();
+        queue.reject(second, Some("too broad".into()), now).unwrap();
+        assert_eq!(queue.pending().count(), 0);
+        assert!(matches!(
+            queue.approve(second, &mut pb, None),
+            Err(ReviewError::AlreadyDecided(_, ReviewStatus::Rejected))
+        ));
+        assert!(matches!(queue.reject(99, None, now), Err(ReviewError::NotFound(99))));
+    }
+
+    #[test]
+    fn test_approve_rechecks_changed_playbook() {
+        let mut pb = Playbook::new();
+        let id = pb.add_bullet("tips", "Retry on 503", None, None);
+        let mut queue = ReviewQueue::new();
+        let update = DeltaBatch {
+            reasoning: "learned".to_string(),
+            operations: vec![DeltaOperation {
+                type_: OperationType::Update,
+                content: Some("Retry on 503 with backoff".to_string()),
+                ..DeltaOperation::remove("tips", &id)
+            }],
+        };
+        let item = queue.submit(update, "", &pb);
+
+        // 重新加载的队列没有修订号，diff未变时照常批准
+        let json = serde_json::to_string(&queue).unwrap();
+        let mut reloaded: ReviewQueue = serde_json::from_str(&json).unwrap();
+        let mut copy = pb.clone();
+        reloaded.approve(item, &mut copy, None).unwrap();
+
+        pb.update_bullet(&id, Some("Retry on 502".to_string()), None).unwrap();
+        let before = queue.get(item).unwrap().canary_diff.clone();
+        assert!(matches!(queue.approve(item, &mut pb, None), Err(ReviewError::Stale(_))));
+        let stale = queue.get(item).unwrap();
+        assert_eq!(stale.status, ReviewStatus::Pending);
+        assert_ne!(stale.canary_diff, before);
+        assert!(stale.canary_diff.as_ref().unwrap().contains("Retry on 502"));
+
+        queue.approve(item, &mut pb, None).unwrap();
+        assert_eq!(pb.get_bullet(&id).unwrap().content, "Retry on 503 with backoff");
+    }
+}
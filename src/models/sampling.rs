@@ -0,0 +1,151 @@
+//! 按分数加权随机抽样渲染：不总是取分数最高的k条，而是按分数比例不放回地抽k条，
+//! 新子弹和暂时落后的子弹也有机会进入上下文，避免同一批子弹永远占据提示词
+//!
+//! 权重为`分数 - 最低分 + floor`，`floor`越大分布越平均。相同的种子与Playbook内容
+//! 总是抽到相同的子弹，便于复现。
+
+use alloc::{string::String, vec::Vec};
+
+use crate::models::context::{Candidate, ContextError, ContextRequest, ContextStage};
+use crate::models::playbook::{Bullet, BulletId, Playbook};
+
+/// 默认的权重下限
+pub const DEFAULT_FLOOR: f64 = 1.0;
+
+/// 抽样参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sampling {
+    /// 抽取的子弹数
+    pub k: usize,
+    pub seed: u64,
+    /// 最低分子弹的权重（见模块文档）
+    pub floor: f64,
+}
+
+impl Sampling {
+    pub fn new(k: usize, seed: u64) -> Self {
+        Self { k, seed, floor: DEFAULT_FLOOR }
+    }
+
+    /// 按`scores`加权不放回抽样，返回抽中的下标（按抽中顺序）
+    pub fn pick(&self, scores: &[f64]) -> Vec<usize> {
+        let min = scores.iter().copied().filter(|s| s.is_finite()).fold(f64::INFINITY, f64::min);
+        let floor = self.floor.max(f64::MIN_POSITIVE);
+        let mut weights: Vec<f64> =
+            scores.iter().map(|s| if s.is_finite() { s - min + floor } else { floor }).collect();
+        let mut rng = SplitMix64(self.seed);
+        let mut picked = Vec::with_capacity(self.k.min(scores.len()));
+        while picked.len() < self.k {
+            let total: f64 = weights.iter().sum();
+            if total <= 0.0 {
+                break;
+            }
+            let mut target = rng.next_f64() * total;
+            // 浮点误差可能让target略大于最后的累计值，此时取最后一个有权重的
+            let mut chosen = None;
+            for (i, w) in weights.iter().enumerate().filter(|(_, w)| **w > 0.0) {
+                chosen = Some(i);
+                if target < *w {
+                    break;
+                }
+                target -= w;
+            }
+            let Some(i) = chosen else { break };
+            weights[i] = 0.0;
+            picked.push(i);
+        }
+        picked
+    }
+}
+
+/// SplitMix64：小而快、可复现的伪随机数（不用于安全场景）
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1)内均匀分布
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl Playbook {
+    /// 按分数加权抽取子弹（已过期的不参与），按抽中顺序返回
+    pub fn sample_bullets(&self, sampling: &Sampling) -> Vec<&Bullet> {
+        let now = self.clock().now();
+        let mut bullets = self.bullets();
+        bullets.retain(|b| !b.is_expired_at(now));
+        let scores: Vec<f64> = bullets.iter().map(|b| self.score(b)).collect();
+        sampling.pick(&scores).into_iter().map(|i| bullets[i]).collect()
+    }
+
+    /// 渲染抽中的子弹，章节与子弹顺序与`as_prompt`一致
+    pub fn as_prompt_sampled(&self, sampling: &Sampling) -> String {
+        let ids: Vec<BulletId> =
+            self.sample_bullets(sampling).into_iter().map(|b| b.id.clone()).collect();
+        self.as_prompt_for_bullets(&ids)
+    }
+}
+
+/// 上下文流水线中的阶段：按候选分数加权抽取`k`个，结果按抽中顺序排列
+#[derive(Debug, Clone, Copy)]
+pub struct WeightedSample(pub Sampling);
+
+impl ContextStage for WeightedSample {
+    fn apply<'a>(
+        &self,
+        _request: &ContextRequest<'a>,
+        candidates: Vec<Candidate<'a>>,
+    ) -> Result<Vec<Candidate<'a>>, ContextError> {
+        let scores: Vec<f64> = candidates.iter().map(|c| c.score).collect();
+        Ok(self.0.pick(&scores).into_iter().map(|i| candidates[i]).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{collections::BTreeMap, format};
+
+    use crate::models::context::ContextPipeline;
+
+    #[test]
+    fn test_weighted_sampling() {
+        let mut pb = Playbook::new();
+        let ids: Vec<BulletId> =
+            (0..6).map(|i| pb.add_bullet("api usage", format!("策略{}", i), None, None)).collect();
+        pb.tag_bullet(&ids[0], "helpful", 50).unwrap();
+        pb.tag_bullet(&ids[5], "harmful", 3).unwrap();
+
+        let sampling = Sampling::new(3, 42);
+        let first: Vec<&str> = pb.sample_bullets(&sampling).iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(first.len(), 3);
+        let again: Vec<&str> = pb.sample_bullets(&sampling).iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(first, again);
+        assert!(first.windows(2).all(|w| w[0] != w[1]));
+        assert_eq!(pb.as_prompt_sampled(&sampling).lines().count(), 4);
+
+        // 高分子弹几乎总被抽中，但其他子弹也都有机会
+        let mut seen = BTreeMap::<String, usize>::new();
+        for seed in 0..200 {
+            for b in pb.sample_bullets(&Sampling::new(2, seed)) {
+                *seen.entry(b.id.clone()).or_default() += 1;
+            }
+        }
+        assert!(seen[&ids[0]] > 150);
+        assert_eq!(seen.len(), 6);
+
+        assert_eq!(pb.sample_bullets(&Sampling::new(10, 1)).len(), 6);
+        assert!(Sampling::new(3, 1).pick(&[]).is_empty());
+
+        let pipeline = ContextPipeline::new().with_stage(WeightedSample(Sampling::new(2, 7)));
+        assert_eq!(pipeline.select(&pb, "").unwrap().len(), 2);
+    }
+}
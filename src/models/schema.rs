@@ -0,0 +1,43 @@
+//! 文件格式的JSON Schema，供外部校验器、UI和HTTP API在加载前校验
+
+use schemars::schema_for;
+
+use crate::models::{delta::DeltaBatch, playbook::Playbook};
+
+/// 序列化后的Playbook文件的Schema
+pub fn playbook_schema() -> serde_json::Value {
+    serde_json::to_value(schema_for!(Playbook)).unwrap_or_default()
+}
+
+/// DeltaBatch（curator输出）的Schema
+pub fn delta_batch_schema() -> serde_json::Value {
+    serde_json::to_value(schema_for!(DeltaBatch)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playbook_schema_describes_file_format() {
+        let schema = playbook_schema();
+        let required = schema["required"].as_array().unwrap();
+        for field in ["bullets", "sections", "next_id"] {
+            assert!(required.iter().any(|r| r == field), "{}", field);
+        }
+        // 时钟不属于文件格式
+        assert!(schema["properties"].get("clock").is_none());
+
+        let bullet = &schema["definitions"]["Bullet"];
+        assert!(bullet["properties"]["created_at"]["anyOf"].is_array());
+    }
+
+    #[test]
+    fn test_delta_batch_schema_lists_operation_types() {
+        let schema = delta_batch_schema();
+        let ops = &schema["definitions"]["OperationType"]["enum"];
+        assert_eq!(ops, &serde_json::json!(
+            ["add", "update", "tag", "remove", "move", "merge", "add_example", "remove_example"]
+        ));
+    }
+}
@@ -0,0 +1,166 @@
+//! 子弹评分：提示词排序、检索、淘汰与剪枝都通过同一个 [`BulletScorer`] 排名，
+//! 评分策略在Playbook上配置一次，不再各处硬编码公式
+
+use alloc::sync::Arc;
+use core::fmt::Debug;
+
+use chrono::{DateTime, Utc};
+#[cfg(feature = "std")]
+use chrono::TimeDelta;
+
+use crate::models::playbook::Bullet;
+
+/// 分数越高越好；`now`供与时间相关的评分使用
+pub trait BulletScorer: Debug + Send + Sync {
+    fn score(&self, bullet: &Bullet, now: DateTime<Utc>) -> f64;
+}
+
+pub type SharedScorer = Arc<dyn BulletScorer>;
+
+/// Playbook的默认评分：[`NetCount`]
+pub fn default_scorer() -> SharedScorer {
+    Arc::new(NetCount)
+}
+
+/// helpful - harmful
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetCount;
+
+impl BulletScorer for NetCount {
+    fn score(&self, bullet: &Bullet, _now: DateTime<Utc>) -> f64 {
+        bullet.helpful as f64 - bullet.harmful as f64
+    }
+}
+
+/// helpful占比的Wilson区间下界：标记次数少的子弹不会因为一两次helpful排到最前；
+/// 没有helpful/harmful标记时为0
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WilsonScore {
+    /// 正态分位数，默认1.96（95%置信）
+    pub z: f64,
+}
+
+#[cfg(feature = "std")]
+impl Default for WilsonScore {
+    fn default() -> Self {
+        Self { z: 1.96 }
+    }
+}
+
+#[cfg(feature = "std")]
+impl BulletScorer for WilsonScore {
+    fn score(&self, bullet: &Bullet, _now: DateTime<Utc>) -> f64 {
+        let n = (bullet.helpful + bullet.harmful) as f64;
+        if n == 0.0 {
+            return 0.0;
+        }
+        let p = bullet.helpful as f64 / n;
+        let z2 = self.z * self.z;
+        let margin = self.z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+        (p + z2 / (2.0 * n) - margin) / (1.0 + z2 / n)
+    }
+}
+
+/// 按最近活动时间（修改或使用，取较晚者）指数衰减内层分数，每过`half_life`减半
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecencyDecayed<S> {
+    pub inner: S,
+    pub half_life: TimeDelta,
+}
+
+#[cfg(feature = "std")]
+impl<S: BulletScorer> RecencyDecayed<S> {
+    pub fn new(inner: S, half_life: TimeDelta) -> Self {
+        Self { inner, half_life }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: BulletScorer> BulletScorer for RecencyDecayed<S> {
+    fn score(&self, bullet: &Bullet, now: DateTime<Utc>) -> f64 {
+        let score = self.inner.score(bullet, now);
+        let half_life = self.half_life.as_seconds_f64();
+        if half_life <= 0.0 {
+            return score;
+        }
+        let last_active = bullet
+            .last_used_at
+            .map_or(bullet.updated_at, |used| used.max(bullet.updated_at));
+        let age = (now - last_active).as_seconds_f64().max(0.0);
+        score * 0.5f64.powf(age / half_life)
+    }
+}
+
+/// UCB风格的探索加成：在内层分数上加 `c / sqrt(1 + 标记次数)`，并随子弹创建时间在
+/// `window`内线性衰减到0，让新加入、证据还少的子弹先有机会被用到并积累helpful/harmful
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplorationBonus<S> {
+    pub inner: S,
+    /// 加成系数，刚创建、没有任何标记的子弹加`c`分
+    pub c: f64,
+    pub window: TimeDelta,
+}
+
+#[cfg(feature = "std")]
+impl<S: BulletScorer> ExplorationBonus<S> {
+    pub fn new(inner: S, c: f64, window: TimeDelta) -> Self {
+        Self { inner, c, window }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: BulletScorer> BulletScorer for ExplorationBonus<S> {
+    fn score(&self, bullet: &Bullet, now: DateTime<Utc>) -> f64 {
+        let score = self.inner.score(bullet, now);
+        let window = self.window.as_seconds_f64();
+        if window <= 0.0 {
+            return score;
+        }
+        let age = (now - bullet.created_at).as_seconds_f64().max(0.0);
+        let freshness = (1.0 - age / window).max(0.0);
+        let evidence = (bullet.helpful + bullet.harmful + bullet.neutral) as f64;
+        score + self.c * freshness / (1.0 + evidence).sqrt()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn bullet(helpful: u32, harmful: u32) -> Bullet {
+        let content = "分页时带上cursor".to_string();
+        let mut bullet = Bullet::new_at("api usage", content, DateTime::UNIX_EPOCH);
+        bullet.helpful = helpful;
+        bullet.harmful = harmful;
+        bullet
+    }
+
+    #[test]
+    fn test_scorers() {
+        let now = DateTime::UNIX_EPOCH;
+        assert_eq!(NetCount.score(&bullet(5, 2), now), 3.0);
+
+        // 一次helpful不如九十次helpful、十次harmful
+        let wilson = WilsonScore::default();
+        assert!(wilson.score(&bullet(1, 0), now) < wilson.score(&bullet(90, 10), now));
+        assert_eq!(wilson.score(&bullet(0, 0), now), 0.0);
+
+        let decayed = RecencyDecayed::new(NetCount, TimeDelta::days(7));
+        let mut b = bullet(8, 0);
+        assert_eq!(decayed.score(&b, now + TimeDelta::days(14)), 2.0);
+        b.last_used_at = Some(now + TimeDelta::days(7));
+        assert_eq!(decayed.score(&b, now + TimeDelta::days(14)), 4.0);
+
+        let explore = ExplorationBonus::new(NetCount, 4.0, TimeDelta::days(10));
+        assert_eq!(explore.score(&bullet(0, 0), now), 4.0);
+        assert_eq!(explore.score(&bullet(3, 0), now), 5.0);
+        assert_eq!(explore.score(&bullet(0, 0), now + TimeDelta::days(5)), 2.0);
+        assert_eq!(explore.score(&bullet(2, 0), now + TimeDelta::days(10)), 2.0);
+        // 新子弹暂时排在有少量证据的老子弹前面
+        assert!(explore.score(&bullet(0, 0), now) > NetCount.score(&bullet(3, 0), now));
+    }
+}
@@ -0,0 +1,162 @@
+//! Playbook内的全文搜索：按查询词在子弹内容中的出现情况打分排序，不需要预先建立索引
+//!
+//! 面向人工查找（命令行、管理界面）；为生成检索上下文的BM25/向量混合检索见
+//! `crate::retrieval`。分词与检索一致：ASCII按单词，中文等按字。
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::models::context::Candidate;
+use crate::models::playbook::Playbook;
+
+/// 搜索选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// 只搜索这些章节，为空时搜索全部
+    pub sections: Vec<String>,
+    /// 区分大小写（默认不区分）
+    pub case_sensitive: bool,
+    /// 最多返回的结果数，`None`表示不限制
+    pub limit: Option<usize>,
+}
+
+/// 词频饱和参数，与BM25的k1相同
+const K1: f64 = 1.2;
+
+/// ASCII字母数字连成单词，其余字母数字逐字成词；不区分大小写时先转小写
+pub(crate) fn terms(text: &str, case_sensitive: bool) -> Vec<String> {
+    fn push(c: char, word: &mut String, out: &mut Vec<String>) {
+        if c.is_ascii_alphanumeric() {
+            word.push(c);
+            return;
+        }
+        if !word.is_empty() {
+            out.push(core::mem::take(word));
+        }
+        if c.is_alphanumeric() {
+            out.push(c.to_string());
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if case_sensitive {
+            push(c, &mut word, &mut out);
+        } else {
+            c.to_lowercase().for_each(|c| push(c, &mut word, &mut out));
+        }
+    }
+    if !word.is_empty() {
+        out.push(word);
+    }
+    out
+}
+
+impl Playbook {
+    /// 不区分大小写搜索全部章节，见 [`Playbook::search_with`]
+    pub fn search(&self, query: &str) -> Vec<Candidate<'_>> {
+        self.search_with(query, &SearchOptions::default())
+    }
+
+    /// 返回至少包含一个查询词的子弹，按相关度从高到低排序（同分按ID）
+    ///
+    /// 每个命中的查询词贡献`稀有度 × 饱和词频`：出现在越少子弹中的词权重越高，
+    /// 同一个词重复出现的收益递减，长子弹的词频按长度折算。
+    pub fn search_with(&self, query: &str, options: &SearchOptions) -> Vec<Candidate<'_>> {
+        let query: BTreeSet<String> = terms(query, options.case_sensitive).into_iter().collect();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let bullets: Vec<_> = self
+            .bullets
+            .values()
+            .filter(|b| {
+                options.sections.is_empty() || options.sections.iter().any(|s| *s == *b.section)
+            })
+            .map(|b| {
+                let terms = terms(&b.content, options.case_sensitive);
+                let mut tf: BTreeMap<&String, u32> = BTreeMap::new();
+                for term in terms.iter().filter_map(|t| query.get(t)) {
+                    *tf.entry(term).or_default() += 1;
+                }
+                (b, terms.len(), tf)
+            })
+            .filter(|(_, _, tf)| !tf.is_empty())
+            .collect();
+        if bullets.is_empty() {
+            return Vec::new();
+        }
+
+        let mut df: BTreeMap<&String, usize> = BTreeMap::new();
+        for (_, _, tf) in &bullets {
+            for term in tf.keys() {
+                *df.entry(term).or_default() += 1;
+            }
+        }
+        let n = bullets.len() as f64;
+        let avg_len = bullets.iter().map(|(_, len, _)| *len).sum::<usize>() as f64 / n;
+
+        let mut hits: Vec<Candidate<'_>> = bullets
+            .iter()
+            .map(|(bullet, len, tf)| {
+                let norm = K1 * (0.25 + 0.75 * *len as f64 / avg_len.max(1.0));
+                let score = tf
+                    .iter()
+                    .map(|(term, count)| {
+                        let rarity = 1.0 + n / df[term] as f64;
+                        let tf = *count as f64;
+                        rarity * tf * (K1 + 1.0) / (tf + norm)
+                    })
+                    .sum();
+                Candidate { bullet, score }
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score.total_cmp(&a.score).then_with(|| a.bullet.id.cmp(&b.bullet.id))
+        });
+        if let Some(limit) = options.limit {
+            hits.truncate(limit);
+        }
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn ids<'a>(hits: &'a [Candidate<'_>]) -> Vec<&'a str> {
+        hits.iter().map(|hit| hit.bullet.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search_ranks_and_filters() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api", "Retry 429 responses with backoff", None, None);
+        let b = pb.add_bullet("api", "Use cursor pagination, not offset", None, None);
+        let c = pb.add_bullet("db", "Retry deadlocks; retry at most 3 times", None, None);
+        let d = pb.add_bullet("db", "分页查询要带排序键", None, None);
+
+        let hits = pb.search("retry backoff");
+        assert_eq!(ids(&hits), vec![a.as_str(), c.as_str()]);
+        assert!(hits[0].score > hits[1].score);
+        assert_eq!(ids(&pb.search("分页")), vec![d.as_str()]);
+        assert!(pb.search("  ").is_empty());
+
+        let options = SearchOptions { sections: vec!["db".into()], ..Default::default() };
+        assert_eq!(ids(&pb.search_with("retry", &options)), vec![c.as_str()]);
+
+        let options = SearchOptions { case_sensitive: true, ..Default::default() };
+        assert_eq!(ids(&pb.search_with("Use", &options)), vec![b.as_str()]);
+        assert!(pb.search_with("use", &options).is_empty());
+        assert_eq!(ids(&pb.search("use")), vec![b.as_str()]);
+
+        let options = SearchOptions { limit: Some(1), ..Default::default() };
+        assert_eq!(pb.search_with("retry", &options).len(), 1);
+    }
+}
@@ -0,0 +1,312 @@
+//! 章节名的规范化与别名：`API Usage`、`api usage`、`Api-Usage` → `api-usage`，
+//! `auth` → `authentication & authorization`
+//!
+//! 添加子弹和应用Delta时先按 [`SectionNormalizer`] 规范化、再查别名，LLM策展人用了
+//! 略有不同的章节名时仍落到同一章节，而不是悄悄多出一个近似重复的章节。已经产生的
+//! 重复章节用 [`Playbook::merge_sections`] 合并。
+
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::playbook::{BulletId, Playbook, PlaybookError};
+
+/// [`Playbook::merge_sections`] 的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SectionMerge {
+    /// 从其他章节移入的子弹数（含随后被去重删除的）
+    pub moved: usize,
+    /// 因内容重复被删除的子弹
+    pub duplicates_removed: Vec<BulletId>,
+}
+
+/// 章节名规范化规则（默认全部关闭，章节名原样使用）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SectionNormalizer {
+    pub lowercase: bool,
+    /// 去掉首尾空白
+    pub trim: bool,
+    /// 连续空白合并为一个空格
+    pub collapse_whitespace: bool,
+    /// 字母数字以外的连续字符（空白、标点）替换为一个`-`，并去掉首尾的`-`
+    pub slug: bool,
+}
+
+impl SectionNormalizer {
+    /// 小写、去空白、合并空白
+    pub fn standard() -> Self {
+        Self { lowercase: true, trim: true, collapse_whitespace: true, slug: false }
+    }
+
+    /// 在 [`standard`](Self::standard) 基础上转为slug
+    pub fn slugged() -> Self {
+        Self { slug: true, ..Self::standard() }
+    }
+
+    pub fn is_noop(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn normalize<'a>(&self, section: &'a str) -> Cow<'a, str> {
+        if self.is_noop() {
+            return Cow::Borrowed(section);
+        }
+        let mut name = if self.trim { section.trim() } else { section }.to_string();
+        if self.lowercase {
+            name = name.to_lowercase();
+        }
+        if self.slug {
+            let mut slug = String::with_capacity(name.len());
+            for c in name.chars() {
+                if c.is_alphanumeric() {
+                    slug.push(c);
+                } else if !slug.is_empty() && !slug.ends_with('-') {
+                    slug.push('-');
+                }
+            }
+            name = slug.trim_end_matches('-').to_string();
+        } else if self.collapse_whitespace {
+            name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        if name == section { Cow::Borrowed(section) } else { Cow::Owned(name) }
+    }
+}
+
+impl Playbook {
+    /// 规范章节名：先按 [`Playbook::section_normalizer`] 规范化，再查别名
+    pub fn resolve_section<'a>(&'a self, section: &'a str) -> Cow<'a, str> {
+        let normalized = self.section_normalizer().normalize(section);
+        match self.section_aliases.get(&*normalized) {
+            Some(canonical) => Cow::Borrowed(canonical),
+            None => normalized,
+        }
+    }
+
+    /// 登记别名，返回从别名章节并入规范章节的子弹数
+    ///
+    /// 别名按当前规范化规则保存；`canonical`本身是别名时指向其规范名；
+    /// 别名不能是已有别名的目标。已存在以别名命名的章节时，其子弹移到规范章节末尾。
+    pub fn add_section_alias(
+        &mut self,
+        alias: impl Into<String>,
+        canonical: &str,
+    ) -> Result<usize, PlaybookError> {
+        let alias = self.section_normalizer().normalize(&alias.into()).into_owned();
+        let canonical = self.resolve_section(canonical).into_owned();
+        if alias == canonical {
+            return Err(PlaybookError::InvalidData(format!(
+                "section alias '{}' points to itself",
+                alias
+            )));
+        }
+        if self.section_aliases.values().any(|target| *target == alias) {
+            return Err(PlaybookError::InvalidData(format!(
+                "'{}' is already the canonical name of other aliases",
+                alias
+            )));
+        }
+        self.section_aliases.insert(alias.clone(), canonical.clone());
+        Ok(self.merge_section(&alias, &canonical))
+    }
+
+    /// 删除别名，返回其指向的规范名
+    pub fn remove_section_alias(&mut self, alias: &str) -> Option<String> {
+        let alias = self.section_normalizer().normalize(alias).into_owned();
+        self.section_aliases.remove(&alias)
+    }
+
+    /// 按当前规范化规则重写别名并合并已有章节，返回被移动的子弹数
+    pub(crate) fn renormalize_sections(&mut self) -> usize {
+        let normalizer = *self.section_normalizer();
+        let aliases = core::mem::take(&mut self.section_aliases);
+        for (alias, canonical) in aliases {
+            let alias = normalizer.normalize(&alias).into_owned();
+            let canonical = normalizer.normalize(&canonical).into_owned();
+            if alias != canonical {
+                self.section_aliases.insert(alias, canonical);
+            }
+        }
+        let names: Vec<String> =
+            self.sorted_sections().into_iter().map(|(s, _)| s.to_string()).collect();
+        names
+            .iter()
+            .map(|name| {
+                let canonical = self.resolve_section(name).into_owned();
+                if canonical == *name { 0 } else { self.merge_section(name, &canonical) }
+            })
+            .sum()
+    }
+
+    /// 把`from`中各章节的子弹移到`into`末尾并删除这些章节，再按内容去重：
+    /// 内容相同（忽略首尾空白）的子弹只保留最早的一条，重复子弹的计数与关联示例并入保留的子弹
+    ///
+    /// 用于清理规范化之前产生的近似重复章节；`into`按别名解析，不存在时新建。
+    pub fn merge_sections(&mut self, into: &str, from: &[&str]) -> SectionMerge {
+        let into = self.resolve_section(into).into_owned();
+        let mut report = SectionMerge::default();
+        for section in from.iter().filter(|s| **s != into) {
+            report.moved += self.merge_section(section, &into);
+        }
+
+        let ids = self.sections.get(into.as_str()).cloned().unwrap_or_default();
+        let mut kept: BTreeMap<String, BulletId> = BTreeMap::new();
+        for id in ids {
+            let Some(content) = self.bullets.get(&id).map(|b| b.content.trim().to_string()) else {
+                continue;
+            };
+            let Some(keep) = kept.get(&content) else {
+                kept.insert(content, id);
+                continue;
+            };
+            for example in self.examples.values_mut() {
+                if example.bullet_id.as_deref() == Some(id.as_str()) {
+                    example.bullet_id = Some(keep.clone());
+                }
+            }
+            if let Some(dup) = self.remove_bullet(&id) {
+                let _ = self.modify_bullet(keep, |b| {
+                    b.helpful = b.helpful.saturating_add(dup.helpful);
+                    b.harmful = b.harmful.saturating_add(dup.harmful);
+                    b.neutral = b.neutral.saturating_add(dup.neutral);
+                    Ok(())
+                });
+            }
+            report.duplicates_removed.push(id);
+        }
+        report
+    }
+
+    /// 把`from`章节的子弹（及章节的示例）移到`into`章节末尾
+    fn merge_section(&mut self, from: &str, into: &str) -> usize {
+        let target = self.intern_section(into);
+        for example in self.examples.values_mut().filter(|e| &*e.section == from) {
+            example.section = target.clone();
+        }
+        let Some(ids) = self.sections.remove(from) else {
+            return 0;
+        };
+        for id in &ids {
+            if let Some(bullet) = self.bullets.get_mut(id) {
+                Arc::make_mut(bullet).section = target.clone();
+            }
+        }
+        let moved = ids.len();
+        self.sections.entry(target).or_default().extend(ids);
+        self.rebuild_rollups();
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::models::delta::DeltaBatch;
+
+    #[test]
+    fn test_section_aliases() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("authentication & authorization", "token过期先刷新", None, None);
+        let b = pb.add_bullet("auth", "不要在日志里打印token", None, None);
+        pb.tag_bullet(&b, "helpful", 2).unwrap();
+
+        assert_eq!(pb.add_section_alias("auth", "authentication & authorization").unwrap(), 1);
+        assert_eq!(
+            pb.sections.keys().map(|s| &**s).collect::<Vec<_>>(),
+            ["authentication & authorization"]
+        );
+        assert_eq!(pb.sections["authentication & authorization"], [a.clone(), b.clone()]);
+        assert_eq!(&*pb.bullets[&b].section, "authentication & authorization");
+        assert_eq!(pb.section_rollups()["authentication & authorization"].helpful, 2);
+
+        // 别名的别名指向同一规范名；Delta与渲染都按规范名
+        assert_eq!(pb.add_section_alias("authn", "auth").unwrap(), 0);
+        assert_eq!(pb.section_aliases["authn"], "authentication & authorization");
+        let delta = DeltaBatch::from_json(&serde_json::json!({
+            "reasoning": "",
+            "operations": [{"type": "ADD", "section": "authn", "content": "用OAuth"}]
+        }))
+        .unwrap();
+        pb.apply_delta(delta).unwrap();
+        assert_eq!(pb.sections.len(), 1);
+        assert_eq!(pb.sections["authentication & authorization"].len(), 3);
+        assert!(pb.as_prompt_for(&["auth"]).contains("用OAuth"));
+
+        assert!(pb.add_section_alias("x", "x").is_err());
+        assert!(pb.add_section_alias("authentication & authorization", "security").is_err());
+
+        let restored = Playbook::from_json(&pb.to_json().unwrap()).unwrap();
+        assert_eq!(restored.resolve_section("authn"), "authentication & authorization");
+        assert_eq!(
+            pb.remove_section_alias("authn").as_deref(),
+            Some("authentication & authorization")
+        );
+        assert_eq!(pb.resolve_section("authn"), "authn");
+    }
+
+    #[test]
+    fn test_section_normalizer() {
+        let n = SectionNormalizer::standard();
+        assert_eq!(n.normalize("  API   Usage "), "api usage");
+        assert!(matches!(n.normalize("api usage"), Cow::Borrowed(_)));
+        assert_eq!(SectionNormalizer::slugged().normalize("Api-Usage"), "api-usage");
+        assert_eq!(SectionNormalizer::slugged().normalize(" API  usage!"), "api-usage");
+        assert_eq!(SectionNormalizer::default().normalize(" API "), " API ");
+
+        let mut pb = Playbook::new();
+        pb.add_bullet("API Usage", "分页时带上cursor", None, None);
+        pb.add_bullet("api usage", "重试时用指数退避", None, None);
+        pb.add_section_alias("API", "api usage").unwrap();
+        assert_eq!(pb.sections.len(), 2);
+
+        assert_eq!(pb.set_section_normalizer(SectionNormalizer::slugged()), 2);
+        assert_eq!(pb.sections.keys().map(|s| &**s).collect::<Vec<_>>(), ["api-usage"]);
+        assert_eq!(pb.section_aliases["api"], "api-usage");
+
+        let delta = DeltaBatch::from_json(&serde_json::json!({
+            "reasoning": "",
+            "operations": [
+                {"type": "ADD", "section": "Api-Usage", "content": "限流时读取Retry-After"},
+                {"type": "ADD", "section": " API ", "content": "带上User-Agent"}
+            ]
+        }))
+        .unwrap();
+        pb.apply_delta(delta).unwrap();
+        assert_eq!(pb.sections["api-usage"].len(), 4);
+        pb.add_bullet("API usage", "用HTTPS", None, None);
+        assert_eq!(pb.sections.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_sections() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        let b = pb.add_bullet("API Usage", " 分页时带上cursor ", None, None);
+        let c = pb.add_bullet("Api-Usage", "限流时读取Retry-After", None, None);
+        pb.add_bullet("debugging", "先看日志", None, None);
+        pb.tag_bullet(&a, "helpful", 1).unwrap();
+        pb.tag_bullet(&b, "helpful", 2).unwrap();
+        pb.add_example("API Usage", "列出订单", "GET /orders?cursor=", Some(b.clone())).unwrap();
+
+        let report = pb.merge_sections("api usage", &["API Usage", "Api-Usage", "missing"]);
+        assert_eq!(report, SectionMerge { moved: 2, duplicates_removed: vec![b.clone()] });
+        assert_eq!(
+            pb.sorted_sections().into_iter().map(|(s, _)| &**s).collect::<Vec<_>>(),
+            ["api usage", "debugging"]
+        );
+        assert_eq!(pb.sections["api usage"], [a.clone(), c.clone()]);
+        assert_eq!(pb.bullets[&a].helpful, 3);
+        assert_eq!(pb.section_rollups()["api usage"].helpful, 3);
+        let example = pb.examples.values().next().unwrap();
+        assert_eq!((&*example.section, example.bullet_id.as_ref()), ("api usage", Some(&a)));
+        assert!(!pb.bullets.contains_key(&b));
+    }
+}
@@ -0,0 +1,489 @@
+//! 签名的DeltaBatch：共享的Playbook只接受已授权curator身份签名的Delta
+//!
+//! 签名内容为 [`signing_payload`]（固定前缀、签名时间、nonce加Delta的JSON），签名算法为Ed25519
+//! （32字节公钥、64字节签名）。启用`ed25519` feature时由`RingSigner` / `RingVerifier`
+//! （ring）实现；也可通过 [`Ed25519Signer`] / [`Ed25519Verifier`] 接入其他实现（如HSM）。
+//!
+//! 签名本身不防重放：[`ReplayGuard`] 拒绝签名时间超出有效期的信封，并记住有效期内
+//! 已接受过的（身份，nonce），同一信封只能应用一次。
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use core::fmt::Write as _;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::models::delta::DeltaBatch;
+use crate::models::playbook::{Playbook, PlaybookError};
+
+/// 签名载荷的前缀，避免签名被挪作他用
+pub const PAYLOAD_PREFIX: &[u8] = b"ace-rs delta v2\n";
+
+#[derive(Debug, Error)]
+pub enum SigningError {
+    #[error("未授权的curator身份：{0}")]
+    UnknownKey(String),
+    #[error("Delta签名校验失败（curator {0}）")]
+    InvalidSignature(String),
+    #[error("Delta无法序列化：{0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Delta签名已过期或签名时间无效（curator {key_id}，签名于{signed_at}）")]
+    Expired { key_id: String, signed_at: DateTime<Utc> },
+    #[error("Delta已经应用过（curator {0}），拒绝重放")]
+    Replayed(String),
+    #[error(transparent)]
+    Playbook(#[from] PlaybookError),
+    #[cfg(feature = "ed25519")]
+    #[error("Ed25519私钥无效：{0}")]
+    InvalidKey(String),
+    #[cfg(feature = "ed25519")]
+    #[error("系统随机源不可用")]
+    Random,
+}
+
+pub type PublicKey = [u8; 32];
+pub type Signature = [u8; 64];
+/// 每个信封唯一的随机数，与身份一起识别重放
+pub type Nonce = [u8; 16];
+
+/// curator持有的签名私钥
+pub trait Ed25519Signer {
+    /// 身份标识，对应 [`TrustedCurators`] 中的键
+    fn key_id(&self) -> &str;
+    fn sign(&self, message: &[u8]) -> Signature;
+}
+
+/// Ed25519签名校验
+pub trait Ed25519Verifier {
+    fn verify(&self, public_key: &PublicKey, message: &[u8], signature: &Signature) -> bool;
+}
+
+/// 带签名的DeltaBatch，签名、nonce与公钥以十六进制序列化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedDelta {
+    pub delta: DeltaBatch,
+    pub key_id: String,
+    pub signed_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+    pub nonce: Nonce,
+    #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+    pub signature: Signature,
+}
+
+/// 签名载荷：[`PAYLOAD_PREFIX`]、签名时间（Unix毫秒）与nonce各占一行，再加Delta的JSON
+/// （字段顺序固定、metadata有序，结果确定）
+pub fn signing_payload(
+    delta: &DeltaBatch,
+    signed_at: DateTime<Utc>,
+    nonce: &Nonce,
+) -> Result<Vec<u8>, SigningError> {
+    let mut payload = PAYLOAD_PREFIX.to_vec();
+    let mut header = format!("{}\n", signed_at.timestamp_millis());
+    for byte in nonce {
+        let _ = write!(header, "{:02x}", byte);
+    }
+    header.push('\n');
+    payload.extend_from_slice(header.as_bytes());
+    payload.extend(serde_json::to_vec(delta)?);
+    Ok(payload)
+}
+
+impl SignedDelta {
+    /// 以`signed_at`为签名时间签名；`nonce`每个信封必须不同（如`random_nonce`）
+    pub fn sign(
+        delta: DeltaBatch,
+        signer: &dyn Ed25519Signer,
+        signed_at: DateTime<Utc>,
+        nonce: Nonce,
+    ) -> Result<Self, SigningError> {
+        let signature = signer.sign(&signing_payload(&delta, signed_at, &nonce)?);
+        Ok(Self {
+            delta,
+            key_id: signer.key_id().into(),
+            signed_at,
+            nonce,
+            signature,
+        })
+    }
+
+    /// 校验签名后应用到`playbook`，见 [`Playbook::apply_signed_delta`]
+    pub fn verify_and_apply(
+        self,
+        playbook: &mut Playbook,
+        curators: &TrustedCurators,
+        verifier: &dyn Ed25519Verifier,
+        replay: &mut ReplayGuard,
+    ) -> Result<(), SigningError> {
+        playbook.apply_signed_delta(self, curators, verifier, replay)
+    }
+}
+
+/// 重放保护：签名时间与当前时间相差超过`max_age`（过旧或来自未来）的信封直接拒绝，
+/// 有效期内按（身份，nonce）记住已接受的信封；有效期外的记录随之清除，占用有界
+#[derive(Debug, Clone)]
+pub struct ReplayGuard {
+    max_age: TimeDelta,
+    seen: BTreeMap<(String, Nonce), DateTime<Utc>>,
+}
+
+impl Default for ReplayGuard {
+    fn default() -> Self {
+        Self::new(TimeDelta::minutes(5))
+    }
+}
+
+impl ReplayGuard {
+    pub fn new(max_age: TimeDelta) -> Self {
+        Self { max_age, seen: BTreeMap::new() }
+    }
+
+    /// 信封在`now`时是否可以接受（不记录）
+    pub fn check(&self, signed: &SignedDelta, now: DateTime<Utc>) -> Result<(), SigningError> {
+        if (now - signed.signed_at).abs() > self.max_age {
+            return Err(SigningError::Expired {
+                key_id: signed.key_id.clone(),
+                signed_at: signed.signed_at,
+            });
+        }
+        if self.seen.contains_key(&(signed.key_id.clone(), signed.nonce)) {
+            return Err(SigningError::Replayed(signed.key_id.clone()));
+        }
+        Ok(())
+    }
+
+    /// 记录已接受的信封，同时清除有效期外的记录
+    fn accept(
+        &mut self,
+        key_id: String,
+        nonce: Nonce,
+        signed_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) {
+        let max_age = self.max_age;
+        self.seen.retain(|_, signed_at| (now - *signed_at).abs() <= max_age);
+        self.seen.insert((key_id, nonce), signed_at);
+    }
+}
+
+/// 已授权的curator公钥（身份→公钥），可序列化进服务配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TrustedCurators {
+    keys: BTreeMap<String, HexKey>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+struct HexKey(
+    #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")] PublicKey,
+);
+
+impl TrustedCurators {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, key_id: impl Into<String>, public_key: PublicKey) -> Self {
+        self.keys.insert(key_id.into(), HexKey(public_key));
+        self
+    }
+
+    pub fn revoke(&mut self, key_id: &str) -> bool {
+        self.keys.remove(key_id).is_some()
+    }
+
+    /// 身份已授权且签名有效时返回Delta
+    pub fn verify<'a>(
+        &self,
+        signed: &'a SignedDelta,
+        verifier: &dyn Ed25519Verifier,
+    ) -> Result<&'a DeltaBatch, SigningError> {
+        let HexKey(public_key) = self
+            .keys
+            .get(&signed.key_id)
+            .ok_or_else(|| SigningError::UnknownKey(signed.key_id.clone()))?;
+        let payload = signing_payload(&signed.delta, signed.signed_at, &signed.nonce)?;
+        if !verifier.verify(public_key, &payload, &signed.signature) {
+            return Err(SigningError::InvalidSignature(signed.key_id.clone()));
+        }
+        Ok(&signed.delta)
+    }
+}
+
+impl Playbook {
+    /// 校验签名与重放保护后整批应用Delta（见 [`Playbook::apply_delta_transactional`]）
+    ///
+    /// 身份未授权、签名无效、信封过期或已应用过，以及任一操作失败时，Playbook不做任何修改；
+    /// 只有成功应用的信封记入`replay`，失败的可以重新提交。
+    pub fn apply_signed_delta(
+        &mut self,
+        signed: SignedDelta,
+        curators: &TrustedCurators,
+        verifier: &dyn Ed25519Verifier,
+        replay: &mut ReplayGuard,
+    ) -> Result<(), SigningError> {
+        curators.verify(&signed, verifier)?;
+        let now = self.clock().now();
+        replay.check(&signed, now)?;
+        let SignedDelta { delta, key_id, signed_at, nonce, .. } = signed;
+        self.apply_delta_transactional_at(delta, now)?;
+        replay.accept(key_id, nonce, signed_at, now);
+        Ok(())
+    }
+}
+
+// --------------------------
+// ring实现
+// --------------------------
+
+/// 基于ring的Ed25519私钥，以PKCS#8文档保存
+#[cfg(feature = "ed25519")]
+pub struct RingSigner {
+    key_id: String,
+    key_pair: ring::signature::Ed25519KeyPair,
+}
+
+#[cfg(feature = "ed25519")]
+impl RingSigner {
+    /// 从系统随机源生成新私钥，同时返回需要保存的PKCS#8文档
+    pub fn generate(key_id: impl Into<String>) -> Result<(Self, Vec<u8>), SigningError> {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = ring::signature::Ed25519KeyPair::generate_pkcs8(&rng)
+            .map_err(|err| SigningError::InvalidKey(err.to_string()))?;
+        let signer = Self::from_pkcs8(key_id, pkcs8.as_ref())?;
+        Ok((signer, pkcs8.as_ref().to_vec()))
+    }
+
+    pub fn from_pkcs8(key_id: impl Into<String>, pkcs8: &[u8]) -> Result<Self, SigningError> {
+        let key_pair = ring::signature::Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map_err(|err| SigningError::InvalidKey(err.to_string()))?;
+        Ok(Self { key_id: key_id.into(), key_pair })
+    }
+
+    /// 登记到 [`TrustedCurators`] 的公钥
+    pub fn public_key(&self) -> PublicKey {
+        use ring::signature::KeyPair as _;
+
+        let mut public_key = [0u8; 32];
+        public_key.copy_from_slice(self.key_pair.public_key().as_ref());
+        public_key
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl core::fmt::Debug for RingSigner {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RingSigner").field("key_id", &self.key_id).finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl Ed25519Signer for RingSigner {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn sign(&self, message: &[u8]) -> Signature {
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(self.key_pair.sign(message).as_ref());
+        signature
+    }
+}
+
+/// 从系统随机源生成nonce
+#[cfg(feature = "ed25519")]
+pub fn random_nonce() -> Result<Nonce, SigningError> {
+    use ring::rand::SecureRandom as _;
+
+    let mut nonce = [0u8; 16];
+    ring::rand::SystemRandom::new().fill(&mut nonce).map_err(|_| SigningError::Random)?;
+    Ok(nonce)
+}
+
+/// 基于ring的Ed25519签名校验
+#[cfg(feature = "ed25519")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RingVerifier;
+
+#[cfg(feature = "ed25519")]
+impl Ed25519Verifier for RingVerifier {
+    fn verify(&self, public_key: &PublicKey, message: &[u8], signature: &Signature) -> bool {
+        ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+            .verify(message, signature)
+            .is_ok()
+    }
+}
+
+// --------------------------
+// 十六进制序列化
+// --------------------------
+
+fn serialize_hex<S: Serializer, const N: usize>(
+    bytes: &[u8; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let hex = bytes.iter().fold(String::with_capacity(N * 2), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    });
+    serializer.serialize_str(&hex)
+}
+
+fn deserialize_hex<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    use serde::de::Error;
+
+    let hex = String::deserialize(deserializer)?;
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return Err(D::Error::custom(format!("expected {} hex digits", N * 2)));
+    }
+    let mut bytes = [0u8; N];
+    for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        let pair = core::str::from_utf8(pair).map_err(D::Error::custom)?;
+        *byte = u8::from_str_radix(pair, 16).map_err(D::Error::custom)?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, sync::Arc, vec};
+
+    use crate::clock::{Clock, ManualClock};
+    use crate::models::delta::DeltaOperation;
+
+    /// 测试用的假签名：公钥与载荷逐字节异或折叠，只用来检查接线，不具备安全性
+    struct FakeKey(&'static str, PublicKey);
+
+    fn fold(public_key: &PublicKey, message: &[u8]) -> Signature {
+        let mut signature = [0u8; 64];
+        for (i, byte) in message.iter().enumerate() {
+            signature[i % 64] ^= byte ^ public_key[i % 32];
+        }
+        signature
+    }
+
+    impl Ed25519Signer for FakeKey {
+        fn key_id(&self) -> &str {
+            self.0
+        }
+
+        fn sign(&self, message: &[u8]) -> Signature {
+            fold(&self.1, message)
+        }
+    }
+
+    struct FakeVerifier;
+
+    impl Ed25519Verifier for FakeVerifier {
+        fn verify(&self, public_key: &PublicKey, message: &[u8], signature: &Signature) -> bool {
+            fold(public_key, message) == *signature
+        }
+    }
+
+    #[test]
+    fn test_only_authorized_signed_deltas_apply() {
+        let curator = FakeKey("curator-a", [7; 32]);
+        let mut curators = TrustedCurators::new().with("curator-a", [7; 32]);
+        let delta = DeltaBatch {
+            reasoning: "learned".to_string(),
+            operations: vec![DeltaOperation::add("tips", "Retry on 503")],
+        };
+        let signed = SignedDelta::sign(delta, &curator, DateTime::UNIX_EPOCH, [1; 16]).unwrap();
+
+        // 经JSON往返后签名仍然有效
+        let json = serde_json::to_value(&signed).unwrap();
+        assert_eq!(json["signature"].as_str().unwrap().len(), 128);
+        let signed: SignedDelta = serde_json::from_value(json).unwrap();
+        let curators_json = serde_json::to_string(&curators).unwrap();
+        assert_eq!(serde_json::from_str::<TrustedCurators>(&curators_json).unwrap(), curators);
+
+        let mut tampered = signed.clone();
+        tampered.delta.operations[0].content = Some("Delete all logs".into());
+        let mut pb = Playbook::with_clock(Arc::new(ManualClock::new(DateTime::UNIX_EPOCH)));
+        let mut replay = ReplayGuard::default();
+        let err = pb.apply_signed_delta(tampered, &curators, &FakeVerifier, &mut replay);
+        assert!(matches!(err, Err(SigningError::InvalidSignature(_))));
+        // 签名覆盖时间与nonce
+        let mut renonced = signed.clone();
+        renonced.nonce = [2; 16];
+        let err = pb.apply_signed_delta(renonced, &curators, &FakeVerifier, &mut replay);
+        assert!(matches!(err, Err(SigningError::InvalidSignature(_))));
+        pb.apply_signed_delta(signed.clone(), &curators, &FakeVerifier, &mut replay).unwrap();
+        assert_eq!(pb.bullets.len(), 1);
+
+        curators.revoke("curator-a");
+        let err = pb.apply_signed_delta(signed, &curators, &FakeVerifier, &mut replay);
+        assert!(matches!(err, Err(SigningError::UnknownKey(id)) if id == "curator-a"));
+    }
+
+    #[test]
+    fn test_replayed_and_stale_envelopes_are_rejected() {
+        let curator = FakeKey("curator-a", [7; 32]);
+        let curators = TrustedCurators::new().with("curator-a", [7; 32]);
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let mut replay = ReplayGuard::new(TimeDelta::minutes(5));
+        let add = |content: &str| DeltaBatch {
+            reasoning: "learned".to_string(),
+            operations: vec![DeltaOperation::add("tips", content)],
+        };
+        let sign = |delta, nonce| SignedDelta::sign(delta, &curator, clock.now(), nonce).unwrap();
+
+        let first = sign(add("Retry on 503"), [1; 16]);
+        pb.apply_signed_delta(first.clone(), &curators, &FakeVerifier, &mut replay).unwrap();
+        let err = pb.apply_signed_delta(first, &curators, &FakeVerifier, &mut replay);
+        assert!(matches!(err, Err(SigningError::Replayed(_))));
+
+        // 失败的批次整批不生效，nonce也不被占用
+        let tag_missing = DeltaOperation::tag("tips", "x", BTreeMap::from([("helpful".into(), 1)]));
+        let mut failing = add("Paginate");
+        failing.operations.push(tag_missing);
+        let failing = sign(failing, [2; 16]);
+        let err = pb.apply_signed_delta(failing, &curators, &FakeVerifier, &mut replay);
+        assert!(matches!(err, Err(SigningError::Playbook(_))));
+        assert_eq!(pb.bullets.len(), 1);
+        pb.apply_signed_delta(sign(add("Paginate"), [2; 16]), &curators, &FakeVerifier, &mut replay)
+            .unwrap();
+
+        let stale = sign(add("Cache tokens"), [3; 16]);
+        clock.advance(TimeDelta::minutes(6));
+        let err = pb.apply_signed_delta(stale, &curators, &FakeVerifier, &mut replay);
+        assert!(matches!(err, Err(SigningError::Expired { .. })));
+        assert_eq!(pb.bullets.len(), 2);
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_ring_signatures_verify() {
+        let (curator, pkcs8) = RingSigner::generate("curator-a").unwrap();
+        let curators = TrustedCurators::new().with("curator-a", curator.public_key());
+        let delta = DeltaBatch {
+            reasoning: "learned".to_string(),
+            operations: vec![DeltaOperation::add("tips", "Retry on 503")],
+        };
+        let mut pb = Playbook::new();
+        let now = pb.clock().now();
+        let nonce = random_nonce().unwrap();
+        assert_ne!(nonce, random_nonce().unwrap());
+        let signed = SignedDelta::sign(delta, &curator, now, nonce).unwrap();
+
+        // 其他私钥的签名不被接受
+        let (other, _) = RingSigner::generate("curator-a").unwrap();
+        let forged = SignedDelta::sign(signed.delta.clone(), &other, now, nonce).unwrap();
+        let mut replay = ReplayGuard::default();
+        let err = forged.verify_and_apply(&mut pb, &curators, &RingVerifier, &mut replay);
+        assert!(matches!(err, Err(SigningError::InvalidSignature(_))));
+
+        // 从保存的PKCS#8文档恢复的私钥签名一致（Ed25519签名是确定的）
+        let reloaded = RingSigner::from_pkcs8("curator-a", &pkcs8).unwrap();
+        let resigned = SignedDelta::sign(signed.delta.clone(), &reloaded, now, nonce).unwrap();
+        assert_eq!(resigned.signature, signed.signature);
+        signed.verify_and_apply(&mut pb, &curators, &RingVerifier, &mut replay).unwrap();
+        assert_eq!(pb.bullets.len(), 1);
+        assert!(RingSigner::from_pkcs8("curator-a", b"garbage").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! 工具使用经验（技能）：带 [`Skill`] 的子弹记录某个工具怎么调用、参数要注意什么、踩过哪些坑
+//!
+//! 技能仍是普通子弹（计数、归因、检索、淘汰都照常），子弹内容是使用方法；
+//! [`Playbook::as_tool_prompt`] 按工具分组渲染，供Generator的工具调用提示词使用。
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::playbook::{Bullet, Playbook, PlaybookError};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Skill {
+    /// 工具名，与工具定义中的名称一致
+    pub tool: String,
+    /// 参数说明（格式、取值范围、必填项等）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub arguments: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pitfalls: Vec<String>,
+}
+
+impl Playbook {
+    /// 所有技能子弹（按工具名、再按ID）
+    pub fn skills(&self) -> impl Iterator<Item = (&Skill, &Bullet)> {
+        let mut skills: Vec<_> = self
+            .bullets
+            .values()
+            .filter_map(|b| Some((b.skill.as_ref()?, &**b)))
+            .collect();
+        skills.sort_by(|(a, x), (b, y)| a.tool.cmp(&b.tool).then_with(|| x.id.cmp(&y.id)));
+        skills.into_iter()
+    }
+
+    /// 某个工具的技能子弹
+    pub fn skills_for<'a>(&'a self, tool: &'a str) -> impl Iterator<Item = &'a Bullet> {
+        self.skills().filter(move |(s, _)| s.tool == tool).map(|(_, b)| b)
+    }
+
+    /// 设置或清除子弹的技能信息
+    pub fn set_skill(
+        &mut self,
+        bullet_id: &str,
+        skill: Option<Skill>,
+    ) -> Result<(), PlaybookError> {
+        let now = self.clock().now();
+        self.modify_bullet(bullet_id, |bullet| {
+            bullet.skill = skill;
+            bullet.updated_at = now;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// 按工具分组渲染全部技能
+    pub fn as_tool_prompt(&self) -> String {
+        self.as_tool_prompt_for(None)
+    }
+
+    /// 只渲染给定工具（通常是本次请求可用的工具）的技能；`None`表示全部
+    pub fn as_tool_prompt_for(&self, tools: Option<&[&str]>) -> String {
+        let now = self.clock().now();
+        let mut by_tool: BTreeMap<&str, Vec<(&Skill, &Bullet)>> = BTreeMap::new();
+        for (skill, bullet) in self.skills() {
+            if tools.is_none_or(|tools| tools.contains(&skill.tool.as_str()))
+                && !bullet.is_expired_at(now)
+            {
+                by_tool.entry(&skill.tool).or_default().push((skill, bullet));
+            }
+        }
+
+        let mut out = String::new();
+        for (tool, skills) in by_tool {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            let _ = write!(out, "## Tool: {}", tool);
+            for (skill, b) in skills {
+                self.write_bullet(&mut out, b, &b.content);
+                if !skill.arguments.is_empty() {
+                    let _ = write!(out, "\n  Arguments: {}", skill.arguments);
+                }
+                for pitfall in &skill.pitfalls {
+                    let _ = write!(out, "\n  Pitfall: {}", pitfall);
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    use crate::models::delta::{DeltaBatch, DeltaOperation, OperationType};
+
+    #[test]
+    fn test_skills_from_delta_render_by_tool() {
+        let mut pb = Playbook::new();
+        pb.add_bullet("api usage", "Pass the cursor token when paginating", None, None);
+        let op = serde_json::json!({
+            "type": "ADD",
+            "section": "tools",
+            "content": "Look up orders by customer before refunding",
+            "skill": {
+                "tool": "search_orders",
+                "arguments": "customer_id is required; status is open|closed",
+                "pitfalls": ["dates must be ISO-8601"]
+            }
+        });
+        let op = DeltaOperation::from_json(&op).unwrap();
+        assert_eq!(op.type_, OperationType::Add);
+        pb.apply_delta(DeltaBatch {
+            reasoning: "learned tool usage".to_string(),
+            operations: vec![op],
+        })
+        .unwrap();
+        let refund = pb.add_bullet("tools", "Refund only after the order is closed", None, None);
+        let skill = Skill {
+            tool: "refund".into(),
+            ..Default::default()
+        };
+        pb.set_skill(&refund, Some(skill)).unwrap();
+
+        assert_eq!(pb.skills().count(), 2);
+        let search = pb.skills_for("search_orders").next().unwrap();
+        let prompt = pb.as_tool_prompt();
+        assert!(prompt.starts_with("## Tool: refund\n"));
+        assert!(prompt.contains(&format!(
+            "## Tool: search_orders\n- [{}] Look up orders by customer before refunding \
+             (helpful=0, harmful=0, neutral=0)\n  \
+             Arguments: customer_id is required; status is open|closed\n  \
+             Pitfall: dates must be ISO-8601",
+            search.id
+        )));
+        assert!(!pb.as_tool_prompt_for(Some(&["refund"])).contains("search_orders"));
+
+        pb.set_skill(&refund, None).unwrap();
+        assert_eq!(pb.skills().count(), 1);
+    }
+}
@@ -0,0 +1,134 @@
+//! 读写隔离的只读快照：服务路径上的提示词渲染读一个一致的视图，
+//! curator在另一份Playbook上应用大批量Delta，渲染期间不必持有写锁
+//!
+//! 快照在写入方完成一批修改后生成一次，之后读者只复制`Arc`；修订号不变时
+//! [`SnapshotCell::publish`] 不会重新生成。生成快照不深拷贝子弹：子弹以`Arc`与
+//! Playbook共享，只复制索引，写入方之后修改某个子弹时才复制那一个（写时复制）。
+
+use alloc::sync::Arc;
+use core::ops::Deref;
+
+use chrono::{DateTime, Utc};
+
+use crate::models::playbook::Playbook;
+
+/// 某一修订的Playbook只读副本，可通过`Deref`调用所有只读方法（`as_prompt`等）
+#[derive(Debug, Clone)]
+pub struct PlaybookSnapshot {
+    playbook: Playbook,
+    taken_at: DateTime<Utc>,
+}
+
+impl PlaybookSnapshot {
+    /// 与生成快照时的 [`Playbook::revision`] 相同，可作缓存键
+    pub fn revision(&self) -> u64 {
+        self.playbook.revision()
+    }
+
+    pub fn taken_at(&self) -> DateTime<Utc> {
+        self.taken_at
+    }
+
+    /// 复制出可修改的Playbook（如在快照基础上试验一批Delta）
+    pub fn to_playbook(&self) -> Playbook {
+        self.playbook.clone()
+    }
+}
+
+impl Deref for PlaybookSnapshot {
+    type Target = Playbook;
+
+    fn deref(&self) -> &Playbook {
+        &self.playbook
+    }
+}
+
+impl Playbook {
+    /// 当前状态的只读快照，之后对Playbook的修改不影响快照
+    pub fn snapshot(&self) -> Arc<PlaybookSnapshot> {
+        Arc::new(PlaybookSnapshot { playbook: self.clone(), taken_at: self.clock().now() })
+    }
+}
+
+/// 发布最新快照的位置：写入方每批修改后`publish`，读者`load`得到当前快照
+///
+/// 锁只在替换或复制`Arc`时短暂持有，读者渲染期间不占用任何锁。
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct SnapshotCell {
+    current: std::sync::RwLock<Arc<PlaybookSnapshot>>,
+}
+
+#[cfg(feature = "std")]
+impl SnapshotCell {
+    pub fn new(playbook: &Playbook) -> Self {
+        Self { current: std::sync::RwLock::new(playbook.snapshot()) }
+    }
+
+    pub fn load(&self) -> Arc<PlaybookSnapshot> {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 修订号变化时生成并发布新快照，返回是否发布了新快照
+    pub fn publish(&self, playbook: &Playbook) -> bool {
+        if self.load().revision() == playbook.revision() {
+            return false;
+        }
+        // 在锁外复制，替换时只短暂持有写锁
+        let snapshot = playbook.snapshot();
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = snapshot;
+        true
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    use crate::models::delta::{DeltaBatch, DeltaOperation};
+
+    #[test]
+    fn test_snapshot_is_isolated_from_writes() {
+        let mut pb = Playbook::new();
+        pb.add_bullet("api", "先分页", None, None);
+        let cell = SnapshotCell::new(&pb);
+        let before = cell.load();
+        let prompt = before.as_prompt();
+        assert!(!cell.publish(&pb));
+        assert!(Arc::ptr_eq(&before, &cell.load()));
+
+        let writer = std::thread::scope(|scope| {
+            let reader = scope.spawn(|| cell.load().as_prompt());
+            let operations = (0..100).map(|i| DeltaOperation::add("api", alloc::format!("{i}")));
+            let delta = DeltaBatch { reasoning: "bulk".into(), operations: operations.collect() };
+            pb.apply_delta(delta).unwrap();
+            assert_eq!(reader.join().unwrap(), prompt);
+            cell.publish(&pb)
+        });
+        assert!(writer);
+
+        // 旧快照不受影响，新快照看到整批修改
+        assert_eq!(before.as_prompt(), prompt);
+        assert_eq!(before.bullets.len(), 1);
+        let after = cell.load();
+        assert_eq!(after.bullets.len(), 101);
+        assert_eq!(after.revision(), pb.revision());
+        let ids: Vec<_> = after.to_playbook().bullets.into_keys().collect();
+        assert_eq!(ids.len(), 101);
+    }
+
+    #[test]
+    fn test_snapshot_shares_unchanged_bullets() {
+        let mut pb = Playbook::new();
+        let kept = pb.add_bullet("api", "先分页", None, None);
+        let edited = pb.add_bullet("api", "重试三次", None, None);
+        let snapshot = pb.snapshot();
+        assert!(Arc::ptr_eq(&snapshot.bullets[&kept], &pb.bullets[&kept]));
+
+        pb.update_bullet(&edited, Some("重试五次".into()), None).unwrap();
+        assert!(Arc::ptr_eq(&snapshot.bullets[&kept], &pb.bullets[&kept]));
+        assert!(!Arc::ptr_eq(&snapshot.bullets[&edited], &pb.bullets[&edited]));
+        assert_eq!(snapshot.bullets[&edited].content, "重试三次");
+    }
+}
@@ -0,0 +1,237 @@
+//! Playbook统计快照：训练过程中每个epoch记录一次，用来画规模与有用度的变化曲线
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::playbook::{Bullet, BulletId, Playbook};
+use crate::models::tag::Tag;
+use crate::models::timestamp;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionStats {
+    pub bullets: usize,
+    pub helpful: u64,
+    pub harmful: u64,
+    pub neutral: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub taken_at: DateTime<Utc>,
+    /// 调用方的标记，如 `epoch-3`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub bullets: usize,
+    pub sections: usize,
+    pub helpful: u64,
+    pub harmful: u64,
+    pub neutral: u64,
+    #[serde(default)]
+    pub per_section: BTreeMap<String, SectionStats>,
+}
+
+impl Playbook {
+    /// 基于章节聚合生成当前统计快照（不扫描子弹）
+    pub fn snapshot_stats(&self) -> StatsSnapshot {
+        let now = self.clock().now();
+        let per_section: BTreeMap<String, SectionStats> = self
+            .section_rollups()
+            .iter()
+            .map(|(name, r)| {
+                let stats = SectionStats {
+                    bullets: r.bullets,
+                    helpful: r.helpful,
+                    harmful: r.harmful,
+                    neutral: r.neutral,
+                };
+                (name.to_string(), stats)
+            })
+            .collect();
+        let sum = |f: fn(&SectionStats) -> u64| per_section.values().map(f).sum();
+        StatsSnapshot {
+            taken_at: now,
+            label: None,
+            bullets: self.bullets.len(),
+            sections: self.sections.len(),
+            helpful: sum(|s| s.helpful),
+            harmful: sum(|s| s.harmful),
+            neutral: sum(|s| s.neutral),
+            per_section,
+        }
+    }
+}
+
+impl StatsSnapshot {
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// helpful占全部标签的比例；没有任何标签时为0
+    pub fn helpful_ratio(&self) -> f64 {
+        let total = self.helpful + self.harmful + self.neutral;
+        if total == 0 {
+            return 0.0;
+        }
+        self.helpful as f64 / total as f64
+    }
+}
+
+/// 计数的变化量（新减旧）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterDrift {
+    pub helpful: i64,
+    pub harmful: i64,
+    pub neutral: i64,
+}
+
+impl CounterDrift {
+    fn between(old: Option<&Bullet>, new: Option<&Bullet>) -> Self {
+        let counts = |b: Option<&Bullet>| {
+            b.map_or([0; 3], |b| Tag::ALL.map(|tag| i64::from(b.count(&tag))))
+        };
+        let ([h0, x0, n0], [h1, x1, n1]) = (counts(old), counts(new));
+        Self { helpful: h1 - h0, harmful: x1 - x0, neutral: n1 - n0 }
+    }
+
+    fn add(&mut self, other: Self) {
+        self.helpful += other.helpful;
+        self.harmful += other.harmful;
+        self.neutral += other.neutral;
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 两个版本中都存在、但有变化的子弹
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulletChange {
+    pub id: BulletId,
+    /// 新版本中的章节
+    pub section: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moved_from: Option<String>,
+    pub content_changed: bool,
+    pub drift: CounterDrift,
+}
+
+/// 两个版本的比较结果（"这周改了什么"）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaybookComparison {
+    pub added: Vec<BulletId>,
+    pub removed: Vec<BulletId>,
+    pub changed: Vec<BulletChange>,
+    /// 全部子弹（含新增、删除的）的计数变化
+    pub drift: CounterDrift,
+    /// 按章节的计数变化（只含有变化的章节）
+    pub per_section: BTreeMap<String, CounterDrift>,
+}
+
+impl Playbook {
+    /// 与旧版本比较：新增、删除、有变化的子弹（按ID排序）及计数变化
+    pub fn compare_stats(&self, old: &Playbook) -> PlaybookComparison {
+        let mut comparison = PlaybookComparison::default();
+        let mut drift_in = |section: &str, drift: CounterDrift| {
+            if !drift.is_zero() {
+                comparison.per_section.entry(section.to_string()).or_default().add(drift);
+            }
+        };
+        for new in self.bullets() {
+            let id = &new.id;
+            let Some(prev) = old.bullets.get(id) else {
+                let drift = CounterDrift::between(None, Some(new));
+                drift_in(&new.section, drift);
+                comparison.drift.add(drift);
+                comparison.added.push(id.clone());
+                continue;
+            };
+            let drift = CounterDrift::between(Some(prev), Some(new));
+            let moved_from = (prev.section != new.section).then(|| prev.section.to_string());
+            if moved_from.is_some() {
+                // 跨章节移动：计数从旧章节转到新章节
+                drift_in(&prev.section, CounterDrift::between(Some(prev), None));
+                drift_in(&new.section, CounterDrift::between(None, Some(new)));
+            } else {
+                drift_in(&new.section, drift);
+            }
+            comparison.drift.add(drift);
+            let content_changed = prev.content != new.content;
+            if content_changed || moved_from.is_some() || !drift.is_zero() {
+                comparison.changed.push(BulletChange {
+                    id: id.clone(),
+                    section: new.section.to_string(),
+                    moved_from,
+                    content_changed,
+                    drift,
+                });
+            }
+        }
+        for prev in old.bullets().into_iter().filter(|b| !self.bullets.contains_key(&b.id)) {
+            let drift = CounterDrift::between(Some(prev), None);
+            drift_in(&prev.section, drift);
+            comparison.drift.add(drift);
+            comparison.removed.push(prev.id.clone());
+        }
+        comparison
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_matches_playbook() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        pb.add_bullet("debugging", "先看日志", None, None);
+        pb.tag_bullet(&a, "helpful", 3).unwrap();
+        pb.tag_bullet(&a, "harmful", 1).unwrap();
+
+        let snapshot = pb.snapshot_stats().with_label("epoch-1");
+        assert_eq!((snapshot.bullets, snapshot.sections), (2, 2));
+        assert_eq!(snapshot.per_section["api usage"].helpful, 3);
+        assert_eq!(snapshot.helpful_ratio(), 0.75);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<StatsSnapshot>(&json).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_compare_stats() {
+        let mut old = Playbook::new();
+        let a = old.add_bullet("api usage", "分页时带上cursor", None, None);
+        let b = old.add_bullet("debugging", "先看日志", None, None);
+        let c = old.add_bullet("debugging", "打断点", None, None);
+        old.tag_bullet(&b, "helpful", 2).unwrap();
+
+        let mut new = old.clone();
+        new.tag_bullet(&a, "helpful", 3).unwrap();
+        new.update_bullet(&b, Some("先看错误日志".to_string()), None).unwrap();
+        new.remove_bullet(&c);
+        let d = new.add_bullet("api usage", "重试时用指数退避", None, None);
+        new.tag_bullet(&d, "harmful", 1).unwrap();
+
+        let cmp = new.compare_stats(&old);
+        assert_eq!((cmp.added, cmp.removed), (vec![d], vec![c]));
+        let changed: Vec<(&str, bool)> =
+            cmp.changed.iter().map(|c| (c.id.as_str(), c.content_changed)).collect();
+        assert_eq!(changed, [(a.as_str(), false), (b.as_str(), true)]);
+        assert_eq!(cmp.changed[0].drift.helpful, 3);
+        assert_eq!((cmp.drift.helpful, cmp.drift.harmful), (3, 1));
+        assert_eq!(cmp.per_section["api usage"].harmful, 1);
+        assert!(!cmp.per_section.contains_key("debugging"));
+
+        let json = serde_json::to_string(&new.compare_stats(&new)).unwrap();
+        assert_eq!(serde_json::from_str::<PlaybookComparison>(&json).unwrap(), Default::default());
+    }
+}
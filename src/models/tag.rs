@@ -0,0 +1,226 @@
+//! 子弹的计数标签：`Bullet::tag`、Delta解析与统计共用这一份标签名，
+//! 不再各自维护`["helpful", "harmful", "neutral"]`列表
+//!
+//! 除三个内置标签外，启用 `custom-tags` feature 时其他合法的标签名解析为
+//! [`Tag::Custom`]，计数记在 [`Bullet::custom`](crate::models::playbook::Bullet::custom) 中；
+//! 自定义标签不参与渲染、评分与章节聚合。
+
+use alloc::{collections::BTreeMap, string::String};
+use core::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::models::playbook::PlaybookError;
+
+/// 自定义标签名的最大长度（字节）
+pub const MAX_CUSTOM_TAG_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tag {
+    Helpful,
+    Harmful,
+    Neutral,
+    /// 自定义计数（小写ASCII字母、数字、`_`、`-`，以字母开头），见模块说明
+    Custom(String),
+}
+
+impl Tag {
+    /// 内置标签，顺序与渲染计数的顺序一致
+    pub const ALL: [Tag; 3] = [Tag::Helpful, Tag::Harmful, Tag::Neutral];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Tag::Helpful => "helpful",
+            Tag::Harmful => "harmful",
+            Tag::Neutral => "neutral",
+            Tag::Custom(name) => name,
+        }
+    }
+
+    /// 按名字查找（区分大小写），未知的名字返回空；启用 `custom-tags` 时合法的
+    /// 其他名字返回 [`Tag::Custom`]
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tag| tag.as_str() == name).or_else(|| {
+            (cfg!(feature = "custom-tags") && is_custom_name(name))
+                .then(|| Tag::Custom(name.into()))
+        })
+    }
+
+    /// 内置标签在 [`Tag::ALL`] 中的下标
+    fn index(&self) -> Option<usize> {
+        match self {
+            Tag::Helpful => Some(0),
+            Tag::Harmful => Some(1),
+            Tag::Neutral => Some(2),
+            Tag::Custom(_) => None,
+        }
+    }
+}
+
+fn is_custom_name(name: &str) -> bool {
+    name.len() <= MAX_CUSTOM_TAG_LEN
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.bytes().all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-'))
+}
+
+impl core::fmt::Display for Tag {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Tag {
+    type Err = PlaybookError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::parse(name).ok_or_else(|| PlaybookError::InvalidTag(name.into()))
+    }
+}
+
+impl Serialize for Tag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Tag {
+    fn schema_name() -> String {
+        "Tag".into()
+    }
+
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(generator)
+    }
+}
+
+/// 各标签的计数（ADD/UPDATE的初始值或TAG的增量），未给出的标签为空
+///
+/// 内置标签用定长数组，应用Delta时每个操作只解析一次标签名，不必为计数重建`BTreeMap`；
+/// 自定义标签另存一个`BTreeMap`。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagCounts {
+    builtin: [Option<i32>; Tag::ALL.len()],
+    custom: BTreeMap<String, i32>,
+}
+
+impl TagCounts {
+    /// 忽略未知标签（与`Bullet::apply_metadata_at`一致）
+    pub fn known(metadata: &BTreeMap<String, i32>) -> Self {
+        let mut counts = Self::default();
+        for (name, value) in metadata {
+            if let Some(tag) = Tag::parse(name) {
+                counts.set(tag, *value);
+            }
+        }
+        counts
+    }
+
+    /// 遇到未知标签时报错（与`Bullet::tag_at`一致）
+    pub fn parse(metadata: &BTreeMap<String, i32>) -> Result<Self, PlaybookError> {
+        let mut counts = Self::default();
+        for (name, value) in metadata {
+            counts.set(name.parse()?, *value);
+        }
+        Ok(counts)
+    }
+
+    pub fn get(&self, tag: &Tag) -> Option<i32> {
+        match (tag.index(), tag) {
+            (Some(i), _) => self.builtin[i],
+            (None, tag) => self.custom.get(tag.as_str()).copied(),
+        }
+    }
+
+    pub fn set(&mut self, tag: Tag, value: i32) {
+        match (tag.index(), tag) {
+            (Some(i), _) => self.builtin[i] = Some(value),
+            (None, tag) => {
+                self.custom.insert(tag.as_str().into(), value);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.builtin.iter().all(Option::is_none) && self.custom.is_empty()
+    }
+
+    /// 给出的标签及其计数，先按 [`Tag::ALL`] 的顺序，再按名字排列自定义标签
+    pub fn iter(&self) -> impl Iterator<Item = (Tag, i32)> + '_ {
+        let builtin = Tag::ALL.into_iter().filter_map(|tag| Some((tag.clone(), self.get(&tag)?)));
+        let custom = self.custom.iter().map(|(name, value)| (Tag::Custom(name.clone()), *value));
+        builtin.chain(custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_parse_and_serde_agree() {
+        for tag in Tag::ALL {
+            assert_eq!(tag.to_string().parse::<Tag>().unwrap(), tag);
+            let json = serde_json::to_string(&tag).unwrap();
+            assert_eq!(json, alloc::format!("\"{}\"", tag));
+        }
+        assert_eq!(Tag::parse("Helpful"), None);
+        let err = "Useful".parse::<Tag>().unwrap_err();
+        assert_eq!(err.code(), "invalid_tag");
+    }
+
+    #[test]
+    fn test_custom_tags_follow_feature() {
+        let parsed = Tag::parse("cited");
+        let expected = cfg!(feature = "custom-tags").then(|| Tag::Custom("cited".into()));
+        assert_eq!(parsed, expected);
+        for name in ["", "1st", "Cited", "has space", "x".repeat(MAX_CUSTOM_TAG_LEN + 1).as_str()] {
+            assert_eq!(Tag::parse(name), None, "{}", name);
+        }
+
+        let custom = Tag::Custom("cited".into());
+        assert_eq!(serde_json::to_string(&custom).unwrap(), "\"cited\"");
+        let mut counts = TagCounts::default();
+        counts.set(custom.clone(), 2);
+        counts.set(Tag::Harmful, 1);
+        assert_eq!(counts.iter().collect::<alloc::vec::Vec<_>>(), [(Tag::Harmful, 1), (custom, 2)]);
+    }
+
+    #[test]
+    fn test_tag_counts_from_metadata() {
+        let metadata = BTreeMap::from([("neutral".to_string(), 2), ("Bogus".to_string(), 1)]);
+        let counts = TagCounts::known(&metadata);
+        assert_eq!(counts.iter().collect::<alloc::vec::Vec<_>>(), [(Tag::Neutral, 2)]);
+        assert_eq!(counts.get(&Tag::Helpful), None);
+        assert!(TagCounts::parse(&metadata).is_err());
+        assert!(TagCounts::known(&BTreeMap::new()).is_empty());
+    }
+
+    #[cfg(feature = "custom-tags")]
+    #[test]
+    fn test_custom_counts_through_delta_and_diff() {
+        use crate::models::delta::{DeltaBatch, DeltaOperation};
+        use crate::models::playbook::Playbook;
+
+        let mut pb = Playbook::new();
+        let id = pb.add_bullet("api", "先分页", None, None);
+        let before = pb.clone();
+        let counts = BTreeMap::from([("cited".to_string(), 2), ("helpful".to_string(), 1)]);
+        let operations = alloc::vec![DeltaOperation::tag("api", &id, counts)];
+        pb.apply_delta(DeltaBatch { reasoning: String::new(), operations }).unwrap();
+
+        let bullet = pb.get_bullet(&id).unwrap();
+        assert_eq!(bullet.count(&Tag::Custom("cited".into())), 2);
+        assert_eq!(serde_json::to_value(bullet).unwrap()["custom"]["cited"], 2);
+        let mut restored = pb.clone();
+        restored.apply_delta(pb.diff(&before)).unwrap();
+        assert_eq!(restored.get_bullet(&id).unwrap().count(&Tag::Custom("cited".into())), 0);
+    }
+}
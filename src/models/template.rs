@@ -0,0 +1,228 @@
+//! Playbook模板：新代理从预置的章节和子弹起步，而不是从空Playbook冷启动
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::playbook::{Playbook, PlaybookError};
+
+/// 要实例化的模板：内置模板或已注册的自定义模板名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Template {
+    CodingAgent,
+    CustomerSupport,
+    ResearchAssistant,
+    Custom(String),
+}
+
+impl Template {
+    pub fn name(&self) -> &str {
+        match self {
+            Template::CodingAgent => "coding_agent",
+            Template::CustomerSupport => "customer_support",
+            Template::ResearchAssistant => "research_assistant",
+            Template::Custom(name) => name,
+        }
+    }
+}
+
+/// 模板内容，可从JSON加载后注册
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateSpec {
+    pub name: String,
+    pub sections: Vec<TemplateSection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateSection {
+    pub name: String,
+    pub bullets: Vec<String>,
+}
+
+impl TemplateSpec {
+    fn builtin(name: &str, sections: &[(&str, &[&str])]) -> Self {
+        Self {
+            name: name.to_string(),
+            sections: sections
+                .iter()
+                .map(|(section, bullets)| TemplateSection {
+                    name: section.to_string(),
+                    bullets: bullets.iter().map(|b| b.to_string()).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 模板注册表，默认包含全部内置模板
+#[derive(Debug, Clone)]
+pub struct TemplateRegistry {
+    templates: BTreeMap<String, TemplateSpec>,
+}
+
+impl Default for TemplateRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            templates: BTreeMap::new(),
+        };
+        for spec in builtin_templates() {
+            registry.register(spec);
+        }
+        registry
+    }
+}
+
+impl TemplateRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册模板，同名模板（包括内置模板）会被覆盖
+    pub fn register(&mut self, spec: TemplateSpec) {
+        self.templates.insert(spec.name.clone(), spec);
+    }
+
+    pub fn get(&self, template: &Template) -> Option<&TemplateSpec> {
+        self.templates.get(template.name())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    pub fn instantiate(&self, template: &Template) -> Result<Playbook, PlaybookError> {
+        let spec = self
+            .get(template)
+            .ok_or_else(|| PlaybookError::UnknownTemplate(template.name().to_string()))?;
+        let mut playbook = Playbook::new();
+        playbook.seed_from(spec);
+        Ok(playbook)
+    }
+}
+
+impl Playbook {
+    /// 从内置模板创建Playbook；自定义模板请通过 [`TemplateRegistry::instantiate`]
+    pub fn from_template(template: Template) -> Result<Self, PlaybookError> {
+        TemplateRegistry::default().instantiate(&template)
+    }
+
+    /// 将模板中的章节与子弹追加到当前Playbook
+    pub fn seed_from(&mut self, spec: &TemplateSpec) {
+        for section in &spec.sections {
+            for content in &section.bullets {
+                self.add_bullet(section.name.as_str(), content.as_str(), None, None);
+            }
+        }
+    }
+}
+
+fn builtin_templates() -> [TemplateSpec; 3] {
+    [
+        TemplateSpec::builtin(
+            Template::CodingAgent.name(),
+            &[
+                (
+                    "coding workflow",
+                    &[
+                        "Read the surrounding code before editing and match its conventions.",
+                        "Run the build and tests after every change; fix failures before moving on.",
+                        "Prefer small, focused changes over broad rewrites.",
+                    ],
+                ),
+                (
+                    "debugging",
+                    &[
+                        "Reproduce the failure first, then narrow it down with the smallest failing input.",
+                        "Read the full error message and stack trace before guessing at a fix.",
+                    ],
+                ),
+                (
+                    "tool usage",
+                    &["Search the codebase for existing helpers before writing new ones."],
+                ),
+            ],
+        ),
+        TemplateSpec::builtin(
+            Template::CustomerSupport.name(),
+            &[
+                (
+                    "communication",
+                    &[
+                        "Acknowledge the customer's problem before proposing a solution.",
+                        "Keep answers short and give concrete next steps.",
+                    ],
+                ),
+                (
+                    "escalation",
+                    &[
+                        "Escalate billing disputes and account security issues to a human agent.",
+                        "Never promise refunds or timelines you cannot verify.",
+                    ],
+                ),
+                (
+                    "troubleshooting",
+                    &["Ask for the exact error message and the steps that led to it."],
+                ),
+            ],
+        ),
+        TemplateSpec::builtin(
+            Template::ResearchAssistant.name(),
+            &[
+                (
+                    "sources",
+                    &[
+                        "Cite the source for every factual claim.",
+                        "Prefer primary sources over summaries and secondary reporting.",
+                    ],
+                ),
+                (
+                    "reasoning",
+                    &[
+                        "Break multi-hop questions into sub-questions and answer each explicitly.",
+                        "State uncertainty instead of guessing when evidence is missing.",
+                    ],
+                ),
+            ],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_builtin_template() {
+        let pb = Playbook::from_template(Template::CodingAgent).unwrap();
+        assert_eq!(pb.sections.len(), 3);
+        assert_eq!(pb.sections["debugging"].len(), 2);
+        assert!(pb.as_prompt().contains("## coding workflow"));
+
+        assert!(matches!(
+            Playbook::from_template(Template::Custom("missing".into())),
+            Err(PlaybookError::UnknownTemplate(_))
+        ));
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let mut registry = TemplateRegistry::new();
+        registry.register(TemplateSpec {
+            name: "sql".into(),
+            sections: vec![TemplateSection {
+                name: "queries".into(),
+                bullets: vec!["Always add a LIMIT when exploring tables.".into()],
+            }],
+        });
+        assert!(registry.names().any(|n| n == "sql"));
+
+        let pb = registry.instantiate(&Template::Custom("sql".into())).unwrap();
+        assert_eq!(pb.bullets.len(), 1);
+        assert_eq!(pb.sections["queries"].len(), 1);
+    }
+}
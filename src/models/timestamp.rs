@@ -0,0 +1,181 @@
+//! 时间戳的序列化格式
+//!
+//! 默认按RFC3339字符串写出（chrono的格式）；下游系统解析不了时可选UNIX秒。
+//! 读取时两种格式都接受，无需知道文件是用哪种格式保存的。
+
+use alloc::string::String;
+use core::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// `2024-05-01T12:00:00.123456789Z`
+    #[default]
+    Rfc3339,
+    /// 整数秒（丢弃亚秒精度）
+    UnixSeconds,
+}
+
+impl TimestampFormat {
+    pub fn to_value(self, time: &DateTime<Utc>) -> serde_json::Value {
+        match self {
+            TimestampFormat::Rfc3339 => serde_json::Value::String(rfc3339_string(time)),
+            TimestampFormat::UnixSeconds => serde_json::Value::from(time.timestamp()),
+        }
+    }
+}
+
+fn rfc3339_string(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+}
+
+/// `#[serde(with = "timestamp::rfc3339")]`
+pub mod rfc3339 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&rfc3339_string(time))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        super::deserialize(deserializer)
+    }
+}
+
+/// `#[serde(with = "timestamp::unix_seconds")]`
+pub mod unix_seconds {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(time.timestamp())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        super::deserialize(deserializer)
+    }
+}
+
+/// 同时接受RFC3339字符串与UNIX秒（整数或小数）
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    deserializer.deserialize_any(TimestampVisitor)
+}
+
+/// 可选时间戳：`null`或缺省为`None`，其余同 [`deserialize`]
+pub fn deserialize_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    #[derive(Deserialize)]
+    struct Timestamp(#[serde(deserialize_with = "deserialize")] DateTime<Utc>);
+
+    Ok(Option::<Timestamp>::deserialize(deserializer)?.map(|t| t.0))
+}
+
+struct TimestampVisitor;
+
+impl de::Visitor<'_> for TimestampVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an RFC3339 string or unix timestamp in seconds")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        DateTime::parse_from_rfc3339(v)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        DateTime::from_timestamp(v, 0).ok_or_else(|| E::custom("timestamp out of range"))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        let v = i64::try_from(v).map_err(|_| E::custom("timestamp out of range"))?;
+        self.visit_i64(v)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        // f64::floor需要std，用整数截断后对负数向下修正
+        let mut secs = v as i64;
+        if secs as f64 > v {
+            secs -= 1;
+        }
+        let nanos = ((v - secs as f64) * 1e9) as u32;
+        DateTime::from_timestamp(secs, nanos.min(999_999_999))
+            .ok_or_else(|| E::custom("timestamp out of range"))
+    }
+}
+
+/// JSON Schema：RFC3339字符串、UNIX秒或null
+#[cfg(feature = "schema")]
+pub fn option_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+    use schemars::schema::{InstanceType, Schema, SchemaObject};
+
+    let mut schema = schema(generator).into_object();
+    let null = SchemaObject {
+        instance_type: Some(InstanceType::Null.into()),
+        ..Default::default()
+    };
+    if let Some(any_of) = schema.subschemas().any_of.as_mut() {
+        any_of.push(null.into());
+    }
+    Schema::Object(schema)
+}
+
+/// JSON Schema：RFC3339字符串或UNIX秒
+#[cfg(feature = "schema")]
+pub fn schema(_: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+    use schemars::schema::{InstanceType, Schema, SchemaObject, SubschemaValidation};
+
+    let string = SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        format: Some("date-time".into()),
+        ..Default::default()
+    };
+    let number = SchemaObject {
+        instance_type: Some(InstanceType::Number.into()),
+        ..Default::default()
+    };
+    Schema::Object(SchemaObject {
+        subschemas: Some(alloc::boxed::Box::new(SubschemaValidation {
+            any_of: Some(alloc::vec![string.into(), number.into()]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Stamped {
+        #[serde(with = "unix_seconds")]
+        at: DateTime<Utc>,
+    }
+
+    #[test]
+    fn test_both_formats_deserialize() {
+        let expected = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        for payload in [
+            r#"{"at": 1700000000}"#,
+            r#"{"at": 1700000000.0}"#,
+            r#"{"at": "2023-11-14T22:13:20Z"}"#,
+            r#"{"at": "2023-11-15T06:13:20+08:00"}"#,
+        ] {
+            let parsed: Stamped = serde_json::from_str(payload).unwrap();
+            assert_eq!(parsed.at, expected, "{}", payload);
+        }
+
+        // 负的小数秒向下取整：-1.5 → -2秒 + 0.5秒
+        let parsed: Stamped = serde_json::from_str(r#"{"at": -1.5}"#).unwrap();
+        assert_eq!(parsed.at, DateTime::from_timestamp(-2, 500_000_000).unwrap());
+
+        let json = serde_json::to_string(&Stamped { at: expected }).unwrap();
+        assert_eq!(json, r#"{"at":1700000000}"#);
+    }
+}
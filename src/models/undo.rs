@@ -0,0 +1,628 @@
+//! 反向Delta与撤销/重做：应用DeltaBatch时记录被修改对象的原状态，生成能恢复原状的反向批次
+//!
+//! 反向批次本身也是普通的DeltaBatch，可以记入日志或交给其他副本；撤销时应用反向批次，
+//! 同时得到它的反向（即重做批次），所以生成的ID在撤销/重做之间保持不变。
+//!
+//! 被REMOVE、被同ID的ADD替换或被容量淘汰的子弹以ADD恢复内容、计数、技能及其示例，
+//! Delta操作无法表达的字段（语言版本、激活条件、固定、过期时间、时间戳）不会恢复。
+//! MOVE以反向MOVE撤销，子弹回到原章节的末尾而不是原来的位置。
+//! MERGE以UPDATE恢复保留子弹的内容与计数，被并入的子弹及其示例以ADD恢复（位于章节末尾）。
+//!
+//! 整批回滚（[`Playbook::apply_delta_transactional`]）不经过反向批次，而是由`Journal`
+//! 在每个对象首次被修改前记录它的完整原状态（包括章节内的位置与章节聚合），失败时原样写回。
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet, btree_map::Entry},
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::models::delta::{DeltaBatch, DeltaOperation, OperationType};
+use crate::models::example::{Example, ExampleId};
+use crate::models::observer::AppliedDelta;
+use crate::models::playbook::{
+    Bullet, BulletId, Playbook, PlaybookError, SectionName, SectionRollup,
+};
+use crate::models::tag::Tag;
+
+/// 单个操作应用前被它影响的对象
+pub(crate) enum Prior {
+    Bullet(Option<(Bullet, Vec<Example>)>),
+    Example(Option<Example>),
+    /// MERGE并入的子弹及其示例（保留的子弹记录在`Bullet`中）
+    Merge(Option<(Bullet, Vec<Example>)>, Vec<(Bullet, Vec<Example>)>),
+}
+
+/// 应用过程中逐操作累积的反向操作
+#[derive(Default)]
+pub(crate) struct InverseBuilder {
+    /// 每个操作的反向操作，按应用顺序
+    groups: Vec<Vec<DeltaOperation>>,
+    /// 容量淘汰的子弹，需在其他反向操作之前恢复
+    evicted: Vec<DeltaOperation>,
+}
+
+/// 恢复子弹及其示例的ADD/ADD_EXAMPLE操作
+fn restore(bullet: &Bullet, examples: &[Example]) -> Vec<DeltaOperation> {
+    let mut add = DeltaOperation::add(&*bullet.section, bullet.content.clone());
+    add.bullet_id = Some(bullet.id.clone());
+    add.metadata = absolute_counts(bullet);
+    add.skill = bullet.skill.clone();
+    let mut ops = Vec::with_capacity(examples.len() + 1);
+    ops.push(add);
+    ops.extend(examples.iter().map(restore_example));
+    ops
+}
+
+fn restore_example(example: &Example) -> DeltaOperation {
+    let mut op = DeltaOperation::add_example(
+        &*example.section,
+        example.bullet_id.clone(),
+        example.input.clone(),
+        example.output.clone(),
+    );
+    op.example_id = Some(example.id.clone());
+    op
+}
+
+/// 以UPDATE恢复子弹原来的内容、计数与技能
+fn restore_update(old: Bullet) -> DeltaOperation {
+    let mut update = DeltaOperation::add(&*old.section, old.content.clone());
+    update.type_ = OperationType::Update;
+    update.bullet_id = Some(old.id.clone());
+    update.metadata = absolute_counts(&old);
+    update.skill = old.skill;
+    update
+}
+
+fn absolute_counts(bullet: &Bullet) -> BTreeMap<String, i32> {
+    bullet.tags().map(|tag| (tag.as_str().into(), bullet.count(&tag) as i32)).collect()
+}
+
+impl Prior {
+    /// 记录`op`将要影响的对象（在应用之前调用）
+    pub(crate) fn capture(playbook: &Playbook, op: &DeltaOperation) -> Self {
+        let bullet = |id: &str| {
+            let bullet = playbook.get_bullet(id)?.clone();
+            Some((bullet, playbook.bullet_examples(id).cloned().collect()))
+        };
+        match op.type_ {
+            OperationType::AddExample | OperationType::RemoveExample => {
+                let id = op.example_id.as_deref();
+                Prior::Example(id.and_then(|id| playbook.examples.get(id)).cloned())
+            }
+            OperationType::Merge => Prior::Merge(
+                op.bullet_id.as_deref().and_then(bullet),
+                op.merged_ids.iter().filter_map(|id| bullet(id)).collect(),
+            ),
+            _ => Prior::Bullet(op.bullet_id.as_deref().and_then(bullet)),
+        }
+    }
+
+    /// 撤销`op`所需的操作（在应用之后调用）；`created`为ADD/ADD_EXAMPLE生成或使用的ID
+    fn undo(
+        self,
+        playbook: &Playbook,
+        op: &DeltaOperation,
+        created: Option<String>,
+    ) -> Vec<DeltaOperation> {
+        let section = op.section.as_str();
+        match (self, op.type_) {
+            (Prior::Bullet(None), OperationType::Add) => {
+                created.map(|id| DeltaOperation::remove(section, id)).into_iter().collect()
+            }
+            (Prior::Bullet(Some((old, _))), OperationType::Tag) => {
+                let Some(new) = playbook.bullets.get(&old.id) else {
+                    return Vec::new();
+                };
+                let tags: BTreeSet<Tag> = old.tags().chain(new.tags()).collect();
+                let increments: BTreeMap<String, i32> = tags
+                    .into_iter()
+                    .map(|tag| (old.count(&tag) as i64 - new.count(&tag) as i64, tag))
+                    .filter(|(diff, _)| *diff != 0)
+                    .map(|(diff, tag)| (tag.as_str().into(), diff as i32))
+                    .collect();
+                if increments.is_empty() {
+                    return Vec::new();
+                }
+                Vec::from([DeltaOperation::tag(&*old.section, old.id, increments)])
+            }
+            // UPDATE不能清除技能，原来没有技能而这次加上了时整体恢复
+            (Prior::Bullet(Some((old, _))), OperationType::Update)
+                if old.skill.is_some() || op.skill.is_none() =>
+            {
+                Vec::from([restore_update(old)])
+            }
+            (Prior::Bullet(Some((old, _))), OperationType::Move) => {
+                Vec::from([DeltaOperation::move_to(&*old.section, old.id)])
+            }
+            (Prior::Bullet(Some((old, examples))), _) => restore(&old, &examples),
+            (Prior::Merge(Some((old, _)), merged), _) => {
+                let mut ops = Vec::from([restore_update(old)]);
+                for (bullet, examples) in &merged {
+                    ops.extend(restore(bullet, examples));
+                }
+                ops
+            }
+            (Prior::Example(None), OperationType::AddExample) => {
+                created.map(|id| DeltaOperation::remove_example(section, id)).into_iter().collect()
+            }
+            (Prior::Example(Some(old)), _) => Vec::from([restore_example(&old)]),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl InverseBuilder {
+    pub(crate) fn record(
+        &mut self,
+        playbook: &Playbook,
+        prior: Prior,
+        op: &DeltaOperation,
+        created: Option<String>,
+    ) {
+        self.groups.push(prior.undo(playbook, op, created));
+    }
+
+    /// 记录容量淘汰的子弹及淘汰前关联到它的示例
+    pub(crate) fn evicted(&mut self, bullet: &Bullet, examples: &[Example]) {
+        self.evicted.extend(restore(bullet, examples));
+    }
+
+    pub(crate) fn finish(self, reasoning: &str) -> DeltaBatch {
+        let mut operations = self.evicted;
+        operations.extend(self.groups.into_iter().rev().flatten());
+        DeltaBatch { reasoning: alloc::format!("undo: {}", reasoning), operations }
+    }
+}
+
+/// 章节索引及聚合的原状态（`None`表示原来没有该章节）
+type SectionState = (Option<Vec<BulletId>>, Option<SectionRollup>);
+
+/// 事务应用的原状态日志：每个子弹、示例与章节在首次被修改前记录一次
+pub(crate) struct Journal {
+    next_id: u64,
+    revision: u64,
+    bullets: BTreeMap<BulletId, Option<Arc<Bullet>>>,
+    examples: BTreeMap<ExampleId, Option<Example>>,
+    sections: BTreeMap<SectionName, SectionState>,
+    /// 提交时通知观察者的内容（没有观察者或批次为空时为`None`）
+    pub(crate) applied: Option<AppliedDelta>,
+}
+
+impl Journal {
+    pub(crate) fn new(playbook: &Playbook) -> Self {
+        Self {
+            next_id: playbook.next_id,
+            revision: playbook.revision,
+            bullets: BTreeMap::new(),
+            examples: BTreeMap::new(),
+            sections: BTreeMap::new(),
+            applied: None,
+        }
+    }
+
+    /// 整批确定生效后调用：通知Playbook的观察者
+    pub(crate) fn commit(&mut self, playbook: &Playbook) {
+        if let Some(applied) = self.applied.take() {
+            playbook.notify_applied(&applied);
+        }
+    }
+
+    /// 记录`op`将要修改的对象（在应用之前调用）；不指定ID的ADD/ADD_EXAMPLE按将生成的ID记录
+    pub(crate) fn capture(&mut self, playbook: &Playbook, op: &DeltaOperation) {
+        self.section(playbook, &op.section);
+        let generated = match op.type_ {
+            OperationType::Add if op.bullet_id.is_none() => {
+                Some(playbook.next_bullet_id(&op.section))
+            }
+            _ => None,
+        };
+        let ids = op.bullet_id.iter().chain(&op.merged_ids).chain(&generated);
+        for id in ids {
+            self.bullet(playbook, id);
+        }
+        let example_id = match op.type_ {
+            OperationType::AddExample => {
+                Some(op.example_id.clone().unwrap_or_else(|| playbook.next_example_id()))
+            }
+            OperationType::RemoveExample => op.example_id.clone(),
+            _ => None,
+        };
+        if let Some(id) = example_id {
+            let prior = playbook.examples.get(&id).cloned();
+            self.examples.entry(id).or_insert(prior);
+        }
+    }
+
+    /// 记录子弹、它所在的章节及关联到它的示例
+    pub(crate) fn bullet(&mut self, playbook: &Playbook, id: &str) {
+        let Entry::Vacant(entry) = self.bullets.entry(id.into()) else {
+            return;
+        };
+        let bullet = playbook.bullets.get(id);
+        entry.insert(bullet.cloned());
+        let Some(bullet) = bullet else {
+            return;
+        };
+        self.section(playbook, &bullet.section);
+        for example in playbook.bullet_examples(id) {
+            self.examples.entry(example.id.clone()).or_insert_with(|| Some(example.clone()));
+        }
+    }
+
+    fn section(&mut self, playbook: &Playbook, name: &str) {
+        if !self.sections.contains_key(name) {
+            let state = (playbook.sections.get(name).cloned(), playbook.rollups.get(name).copied());
+            self.sections.insert(playbook.intern_section(name), state);
+        }
+    }
+
+    /// 记录过的子弹ID（按ID排序）及其原状态
+    #[cfg(feature = "server")]
+    pub(crate) fn bullets(&self) -> impl Iterator<Item = (&BulletId, Option<&Bullet>)> {
+        self.bullets.iter().map(|(id, prior)| (id, prior.as_deref()))
+    }
+
+    /// 把记录的对象写回原状态，修订号与ID序号一并恢复
+    pub(crate) fn rollback(self, playbook: &mut Playbook) {
+        for (id, prior) in self.bullets {
+            match prior {
+                Some(bullet) => playbook.bullets.insert(id, bullet),
+                None => playbook.bullets.remove(&id),
+            };
+        }
+        for (id, prior) in self.examples {
+            match prior {
+                Some(example) => playbook.examples.insert(id, example),
+                None => playbook.examples.remove(&id),
+            };
+        }
+        for (name, (ids, rollup)) in self.sections {
+            match rollup {
+                Some(rollup) => playbook.rollups.insert(name.clone(), rollup),
+                None => playbook.rollups.remove(&name),
+            };
+            match ids {
+                Some(ids) => playbook.sections.insert(name, ids),
+                None => playbook.sections.remove(&name),
+            };
+        }
+        playbook.next_id = self.next_id;
+        playbook.revision = self.revision;
+    }
+
+    /// 实际产生的变化：只比较记录过的对象，格式同 [`Playbook::diff`]
+    pub(crate) fn changes(self, playbook: &Playbook) -> DeltaBatch {
+        let (mut before, mut after) = (Playbook::new(), Playbook::new());
+        for (id, prior) in self.bullets {
+            if let Some(bullet) = playbook.bullets.get(&id) {
+                after.bullets.insert(id.clone(), bullet.clone());
+            }
+            if let Some(bullet) = prior {
+                before.bullets.insert(id, bullet);
+            }
+        }
+        for (id, prior) in self.examples {
+            if let Some(example) = playbook.examples.get(&id) {
+                after.examples.insert(id.clone(), example.clone());
+            }
+            if let Some(example) = prior {
+                before.examples.insert(id, example);
+            }
+        }
+        // 新增的子弹按所在章节中的顺序排列
+        let sections: BTreeMap<&str, SectionName> =
+            after.bullets.values().map(|b| (&*b.section, b.section.clone())).collect();
+        let mut ordered = BTreeMap::new();
+        for (name, section) in sections {
+            let ids = playbook.sections.get(name).into_iter().flatten();
+            let ids = ids.filter(|id| after.bullets.contains_key(*id)).cloned().collect();
+            ordered.insert(section, ids);
+        }
+        after.sections.extend(ordered);
+        before.diff(&after)
+    }
+}
+
+impl Playbook {
+    /// 应用Delta并返回原状态日志；任一操作失败时已写回原状态
+    pub(crate) fn apply_delta_journaled_at(
+        &mut self,
+        delta: DeltaBatch,
+        now: DateTime<Utc>,
+    ) -> Result<Journal, PlaybookError> {
+        self.apply_journaled(delta, now, None, false)
+    }
+
+    fn apply_journaled(
+        &mut self,
+        delta: DeltaBatch,
+        now: DateTime<Utc>,
+        inverse: Option<&mut InverseBuilder>,
+        restore: bool,
+    ) -> Result<Journal, PlaybookError> {
+        let mut journal = Journal::new(self);
+        match self.apply_delta_inner(delta, now, inverse, Some(&mut journal), restore) {
+            Ok(()) => Ok(journal),
+            Err(e) => {
+                journal.rollback(self);
+                Err(e)
+            }
+        }
+    }
+
+    /// 应用Delta并返回反向批次：对结果应用反向批次即恢复原状（见模块说明的限制）
+    ///
+    /// 与 [`Playbook::apply_delta_transactional`] 一样整批生效或整批不生效。
+    pub fn apply_delta_with_inverse(
+        &mut self,
+        delta: DeltaBatch,
+    ) -> Result<DeltaBatch, PlaybookError> {
+        let now = self.clock().now();
+        self.apply_delta_with_inverse_at(delta, now)
+    }
+
+    pub fn apply_delta_with_inverse_at(
+        &mut self,
+        delta: DeltaBatch,
+        now: DateTime<Utc>,
+    ) -> Result<DeltaBatch, PlaybookError> {
+        self.apply_inverting(delta, now, false)
+    }
+
+    fn apply_inverting(
+        &mut self,
+        delta: DeltaBatch,
+        now: DateTime<Utc>,
+        restore: bool,
+    ) -> Result<DeltaBatch, PlaybookError> {
+        let reasoning = delta.reasoning.clone();
+        let mut inverse = InverseBuilder::default();
+        let mut journal = self.apply_journaled(delta, now, Some(&mut inverse), restore)?;
+        journal.commit(self);
+        Ok(inverse.finish(&reasoning))
+    }
+
+    /// 应用撤销/重做栈中的批次：恢复的是之前已通过检查的状态，不再经过
+    /// [`DeltaLimits`](crate::models::delta::DeltaLimits)、内容策略与容量淘汰
+    fn restore_with_inverse(&mut self, delta: DeltaBatch) -> Result<DeltaBatch, PlaybookError> {
+        let now = self.clock().now();
+        self.apply_inverting(delta, now, true)
+    }
+}
+
+/// 撤销/重做栈：保存已应用批次的反向批次
+///
+/// 每一步都整批生效或整批不生效。撤销与重做恢复的是之前已通过检查的状态，只检查冻结，
+/// 不再经过 [`DeltaLimits`](crate::models::delta::DeltaLimits)、内容策略与容量淘汰；
+/// 失败时返回错误，Playbook与栈都保持不变。
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    undo: Vec<DeltaBatch>,
+    redo: Vec<DeltaBatch>,
+    /// 最多保留的撤销步数，`None`表示不限制
+    limit: Option<usize>,
+}
+
+impl UndoStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 最多保留`limit`步，超出时丢弃最早的
+    pub fn with_limit(limit: usize) -> Self {
+        Self { limit: Some(limit), ..Self::default() }
+    }
+
+    /// 应用一批操作并记录其反向批次；清空重做栈
+    pub fn apply(
+        &mut self,
+        playbook: &mut Playbook,
+        delta: DeltaBatch,
+    ) -> Result<(), PlaybookError> {
+        let inverse = playbook.apply_delta_with_inverse(delta)?;
+        self.push_undo(inverse);
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// 撤销最近一批，没有可撤销的返回`false`
+    pub fn undo(&mut self, playbook: &mut Playbook) -> Result<bool, PlaybookError> {
+        let Some(inverse) = self.undo.pop() else {
+            return Ok(false);
+        };
+        match playbook.restore_with_inverse(inverse.clone()) {
+            Ok(redo) => {
+                self.redo.push(redo);
+                Ok(true)
+            }
+            Err(e) => {
+                self.undo.push(inverse);
+                Err(e)
+            }
+        }
+    }
+
+    /// 重做最近撤销的一批，没有可重做的返回`false`
+    pub fn redo(&mut self, playbook: &mut Playbook) -> Result<bool, PlaybookError> {
+        let Some(redo) = self.redo.pop() else {
+            return Ok(false);
+        };
+        match playbook.restore_with_inverse(redo.clone()) {
+            Ok(inverse) => {
+                self.push_undo(inverse);
+                Ok(true)
+            }
+            Err(e) => {
+                self.redo.push(redo);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// 下一次撤销将应用的反向批次
+    pub fn peek_undo(&self) -> Option<&DeltaBatch> {
+        self.undo.last()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    fn push_undo(&mut self, inverse: DeltaBatch) {
+        self.undo.push(inverse);
+        if let Some(limit) = self.limit
+            && self.undo.len() > limit
+        {
+            self.undo.drain(..self.undo.len() - limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, sync::Arc, vec};
+
+    use crate::clock::ManualClock;
+    use crate::models::delta::DeltaLimits;
+    use crate::models::eviction::Capacity;
+    use crate::models::policy::{BannedTermAction, BannedTerms};
+    use crate::models::skill::Skill;
+
+    fn batch(operations: Vec<DeltaOperation>) -> DeltaBatch {
+        DeltaBatch { reasoning: "curate".to_string(), operations }
+    }
+
+    /// 比较可由Delta恢复的状态
+    fn state(pb: &Playbook) -> Vec<(String, String, String, [u32; 3], bool)> {
+        pb.bullets()
+            .into_iter()
+            .map(|b| {
+                let counts = Tag::ALL.map(|tag| b.count(&tag));
+                (b.id.clone(), b.section.to_string(), b.content.clone(), counts, b.skill.is_some())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_inverse_restores_every_operation_type() {
+        let mut pb = Playbook::with_clock(Arc::new(ManualClock::new(DateTime::UNIX_EPOCH)));
+        let a = pb.add_bullet("api", "先分页", None, None);
+        let b = pb.add_bullet("api", "重试要退避", None, None);
+        pb.tag_bullet(&b, "helpful", 2).unwrap();
+        let ex = pb.add_example("api", "q", "a", Some(b.clone())).unwrap();
+        let c = pb.add_bullet("db", "连接池要设上限", None, None);
+        let ex_c = pb.add_example("db", "q3", "a3", Some(c.clone())).unwrap();
+        let before = state(&pb);
+        let examples = pb.examples.clone();
+
+        let mut update = DeltaOperation::add("api", "先分页，再排序");
+        update.type_ = OperationType::Update;
+        update.bullet_id = Some(a.clone());
+        update.skill = Some(Skill::default());
+        let mut replace = DeltaOperation::add("db", "索引要覆盖查询");
+        replace.bullet_id = Some(a.clone());
+        let ops = vec![
+            DeltaOperation::add("api", "带上User-Agent"),
+            update,
+            DeltaOperation::tag("api", &b, BTreeMap::from([("helpful".into(), -5)])),
+            DeltaOperation::move_to("db", &b),
+            DeltaOperation::merge("db", &b, [&c]),
+            DeltaOperation::remove("db", &b),
+            DeltaOperation::add_example("api", None, "q2", "a2"),
+            replace,
+        ];
+        let inverse = pb.apply_delta_with_inverse(batch(ops)).unwrap();
+        assert_eq!(pb.bullets.len(), 2);
+        assert!(!pb.examples.contains_key(&ex));
+        assert_eq!(inverse.reasoning, "undo: curate");
+
+        let redo = pb.apply_delta_with_inverse(inverse).unwrap();
+        assert_eq!(state(&pb), before);
+        assert_eq!(pb.examples.keys().collect::<Vec<_>>(), examples.keys().collect::<Vec<_>>());
+        assert_eq!(pb.examples[&ex].bullet_id.as_ref(), Some(&b));
+        assert_eq!(pb.examples[&ex_c].bullet_id.as_ref(), Some(&c));
+
+        pb.apply_delta(redo).unwrap();
+        assert_eq!(pb.bullets.len(), 2);
+        assert_eq!(pb.bullets[&a].section.as_ref(), "db");
+    }
+
+    #[test]
+    fn test_undo_stack_round_trip_with_eviction() {
+        let mut pb = Playbook::with_clock(Arc::new(ManualClock::new(DateTime::UNIX_EPOCH)));
+        pb.set_capacity(Capacity { max_bullets: Some(2), ..Default::default() });
+        let mut stack = UndoStack::with_limit(2);
+        assert!(!stack.undo(&mut pb).unwrap());
+
+        stack.apply(&mut pb, batch(vec![DeltaOperation::add("api", "a")])).unwrap();
+        stack.apply(&mut pb, batch(vec![DeltaOperation::add("api", "b")])).unwrap();
+        let two = state(&pb);
+        // 超出容量，淘汰一个旧子弹
+        stack.apply(&mut pb, batch(vec![DeltaOperation::add("api", "c")])).unwrap();
+        assert_eq!(pb.bullets.len(), 2);
+        let three = state(&pb);
+
+        assert!(stack.undo(&mut pb).unwrap());
+        assert_eq!(state(&pb), two);
+        assert!(stack.can_redo());
+        assert!(stack.redo(&mut pb).unwrap());
+        assert_eq!(state(&pb), three);
+
+        assert!(stack.undo(&mut pb).unwrap());
+        assert!(stack.undo(&mut pb).unwrap());
+        assert_eq!(pb.bullets.len(), 1);
+        // 只保留两步
+        assert!(!stack.can_undo());
+        stack.apply(&mut pb, batch(vec![DeltaOperation::add("api", "d")])).unwrap();
+        assert!(!stack.can_redo());
+    }
+
+    #[test]
+    fn test_inverse_batches_apply_atomically_and_skip_checks() {
+        let mut pb = Playbook::with_clock(Arc::new(ManualClock::new(DateTime::UNIX_EPOCH)));
+        let a = pb.add_bullet("api", "先分页", None, None);
+        let before = state(&pb);
+
+        // 失败的批次不留下任何修改
+        let tag_missing = DeltaOperation::tag("api", "x", BTreeMap::from([("helpful".into(), 1)]));
+        let failing = batch(vec![DeltaOperation::remove("api", &a), tag_missing]);
+        assert!(pb.apply_delta_with_inverse(failing).is_err());
+        assert_eq!(state(&pb), before);
+
+        let mut stack = UndoStack::new();
+        let ops = vec![DeltaOperation::remove("api", &a), DeltaOperation::add("api", "b")];
+        stack.apply(&mut pb, batch(ops)).unwrap();
+        // 之后收紧的限制、策略与容量不妨碍恢复之前接受过的内容
+        pb.set_limits(DeltaLimits { max_operations: Some(1), ..Default::default() });
+        pb.set_content_policy(Some(Arc::new(BannedTerms::new(["分页"], BannedTermAction::Reject))));
+        pb.set_capacity(Capacity { max_bullets: Some(1), ..Default::default() });
+        assert!(stack.undo(&mut pb).unwrap());
+        assert_eq!(state(&pb), before);
+        assert!(stack.redo(&mut pb).unwrap());
+        assert!(stack.undo(&mut pb).unwrap());
+        assert_eq!(state(&pb), before);
+
+        // 冻结仍然拦截撤销，栈保持不变
+        assert!(stack.redo(&mut pb).unwrap());
+        let after = state(&pb);
+        pb.freeze_section("api");
+        assert!(stack.undo(&mut pb).is_err());
+        assert_eq!(state(&pb), after);
+        assert!(stack.can_undo());
+    }
+}
@@ -0,0 +1,236 @@
+//! DeltaBatch的试运行校验：不修改Playbook，一次列出整批操作的全部问题
+//!
+//! curator经常输出部分无效的批次，`apply_delta`遇到第一个错误就整批失败；
+//! 先用 [`Playbook::validate_delta`] 得到完整的问题列表，便于反馈给模型一次改正。
+
+use alloc::{
+    collections::BTreeSet,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use serde::Serialize;
+
+use crate::models::delta::{DeltaBatch, OperationType};
+use crate::models::playbook::{Playbook, PlaybookError};
+use crate::models::policy::PolicyDecision;
+use crate::models::tag::Tag;
+
+/// 一个问题；`code`与 [`PlaybookError::code`] 相同
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationIssue {
+    /// 出问题的操作下标，整批的问题（如操作数超限）为空
+    pub index: Option<usize>,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl From<&PlaybookError> for ValidationIssue {
+    fn from(error: &PlaybookError) -> Self {
+        Self { index: error.op_index(), code: error.code(), message: error.root().to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    /// 批次中的操作数
+    pub operations: usize,
+    /// 按操作顺序排列，整批的问题在最前
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// 某个操作的问题
+    pub fn for_operation(&self, index: usize) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(move |issue| issue.index == Some(index))
+    }
+
+    /// 没有问题的操作下标
+    pub fn valid_operations(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.operations).filter(|i| self.for_operation(*i).next().is_none())
+    }
+}
+
+impl Playbook {
+    /// 检查整批操作而不应用：冻结章节、[`DeltaLimits`](crate::models::delta::DeltaLimits)、
+    /// 内容策略的拒绝，以及缺少的字段、不存在的子弹、无效的标签与空的ADD内容
+    ///
+    /// 按顺序模拟批内的引用：先ADD（指定ID）再TAG同一子弹有效，REMOVE或被MERGE并入之后
+    /// 再UPDATE无效。
+    /// REMOVE不存在的子弹在应用时会被忽略，这里仍然报告，因为通常是模型编造的ID。
+    pub fn validate_delta(&self, delta: &DeltaBatch) -> ValidationReport {
+        let mut issues: Vec<ValidationIssue> =
+            self.limit_violations(delta).iter().map(ValidationIssue::from).collect();
+
+        let mut added: BTreeSet<&str> = BTreeSet::new();
+        let mut removed: BTreeSet<&str> = BTreeSet::new();
+        for (index, op) in delta.operations.iter().enumerate() {
+            let mut report = |error: PlaybookError| {
+                issues.push(ValidationIssue {
+                    index: Some(index),
+                    code: error.code(),
+                    message: error.to_string(),
+                })
+            };
+            let section = self.resolve_section(&op.section);
+            if let Some(frozen) = self.frozen_section_for(op, &section) {
+                report(PlaybookError::Frozen(format!("{} in section '{}'", op.type_, frozen)));
+            }
+            if let Some(policy) = self.content_policy() {
+                for (field, text) in Self::moderated_fields(op) {
+                    if let PolicyDecision::Reject(reason) = policy.check(&section, text) {
+                        let message =
+                            format!("{} {} in section '{}': {}", op.type_, field, section, reason);
+                        report(PlaybookError::ContentRejected(message));
+                    }
+                }
+            }
+            let exists = |id: &str| {
+                !removed.contains(id) && (added.contains(id) || self.bullets.contains_key(id))
+            };
+            let missing = |field: &str| {
+                PlaybookError::DeltaMissingField(format!("{} required for {}", field, op.type_))
+            };
+
+            match op.type_ {
+                OperationType::Add => {
+                    if op.content.as_deref().is_none_or(|c| c.trim().is_empty()) {
+                        report(missing("content"));
+                    }
+                    if let Some(id) = op.bullet_id.as_deref() {
+                        removed.remove(id);
+                        added.insert(id);
+                    }
+                }
+                OperationType::Update
+                | OperationType::Tag
+                | OperationType::Remove
+                | OperationType::Move => {
+                    match op.bullet_id.as_deref() {
+                        None => report(missing("bullet_id")),
+                        Some(id) if !exists(id) => {
+                            report(PlaybookError::BulletNotFound(id.to_string()))
+                        }
+                        Some(id) if op.type_ == OperationType::Remove => {
+                            removed.insert(id);
+                        }
+                        Some(_) => {}
+                    }
+                    if op.type_ == OperationType::Tag {
+                        for tag in op.metadata.keys() {
+                            if let Err(e) = tag.parse::<Tag>() {
+                                report(e);
+                            }
+                        }
+                    }
+                }
+                OperationType::Merge => {
+                    if op.bullet_id.is_none() {
+                        report(missing("bullet_id"));
+                    }
+                    if op.merged_ids.is_empty() {
+                        report(missing("merged_ids"));
+                    }
+                    if op.content.is_some() && op.merge_strategy.is_some() {
+                        report(PlaybookError::InvalidData(
+                            "content and merge_strategy are exclusive for MERGE".to_string(),
+                        ));
+                    }
+                    let ids: Vec<&str> =
+                        op.bullet_id.iter().chain(&op.merged_ids).map(String::as_str).collect();
+                    for (i, id) in ids.iter().enumerate() {
+                        if !exists(id) {
+                            report(PlaybookError::BulletNotFound(id.to_string()));
+                        } else if ids[..i].contains(id) {
+                            report(PlaybookError::InvalidData(format!(
+                                "bullet '{}' merged twice",
+                                id
+                            )));
+                        }
+                    }
+                    removed.extend(op.merged_ids.iter().map(String::as_str));
+                }
+                OperationType::AddExample => {
+                    for (field, value) in [("input", &op.input), ("output", &op.output)] {
+                        if value.is_none() {
+                            report(missing(field));
+                        }
+                    }
+                    if let Some(id) = op.bullet_id.as_deref().filter(|id| !exists(id)) {
+                        report(PlaybookError::BulletNotFound(id.to_string()));
+                    }
+                }
+                OperationType::RemoveExample => {
+                    if op.example_id.is_none() {
+                        report(missing("example_id"));
+                    }
+                }
+            }
+        }
+        // 整批的问题在前，其余按操作下标（同一操作内保持发现顺序）
+        issues.sort_by_key(|issue| issue.index.map_or(0, |i| i + 1));
+        ValidationReport { operations: delta.operations.len(), issues }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{collections::BTreeMap, vec};
+
+    use crate::models::delta::{DeltaLimits, DeltaOperation};
+
+    fn batch(operations: Vec<DeltaOperation>) -> DeltaBatch {
+        DeltaBatch { reasoning: String::new(), operations }
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem_without_mutating() {
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api", "先分页", None, None);
+        pb.set_limits(DeltaLimits { max_operations: Some(6), ..Default::default() });
+        pb.freeze_section("frozen");
+
+        let mut new = DeltaOperation::add("api", "带上cursor");
+        new.bullet_id = Some("api-custom".into());
+        let mut no_id = DeltaOperation::remove("api", "x");
+        no_id.bullet_id = None;
+        let delta = batch(vec![
+            new,
+            DeltaOperation::tag("api", "api-custom", BTreeMap::from([("helpful".into(), 1)])),
+            DeltaOperation::add("api", "  "),
+            DeltaOperation::tag("api", &a, BTreeMap::from([("Useful".into(), 1)])),
+            DeltaOperation::remove("api", &a),
+            DeltaOperation::tag("api", &a, BTreeMap::new()),
+            DeltaOperation::add("frozen", "x"),
+            no_id,
+        ]);
+        let report = pb.validate_delta(&delta);
+        let codes: Vec<(Option<usize>, &str)> =
+            report.issues.iter().map(|issue| (issue.index, issue.code)).collect();
+        assert_eq!(
+            codes,
+            vec![
+                (None, "delta_limit_exceeded"),
+                (Some(2), "delta_missing_field"),
+                (Some(3), "invalid_tag"),
+                (Some(5), "bullet_not_found"),
+                (Some(6), "frozen"),
+                (Some(7), "delta_missing_field"),
+            ]
+        );
+        assert!(!report.is_valid());
+        assert_eq!(report.valid_operations().collect::<Vec<_>>(), vec![0, 1, 4]);
+        assert!(report.for_operation(5).next().unwrap().message.contains(&a));
+        assert_eq!(pb.bullets.len(), 1);
+
+        let ok =
+            batch(vec![DeltaOperation::tag("api", &a, BTreeMap::from([("helpful".into(), 1)]))]);
+        assert!(pb.validate_delta(&ok).is_valid());
+    }
+}
@@ -0,0 +1,87 @@
+//! 子弹内容中的占位变量（`{{api_base_url}}`），渲染时从上下文表中取值
+//!
+//! 环境相关的值不写死在学到的策略里；未提供的变量原样保留。
+
+use alloc::{borrow::Cow, collections::BTreeMap, string::String};
+
+/// 变量名 → 值
+pub type TemplateVars = BTreeMap<String, String>;
+
+/// 内容中出现的变量名（去掉首尾空白，按出现顺序，可能重复）
+pub fn placeholders(content: &str) -> impl Iterator<Item = &str> {
+    let mut rest = content;
+    core::iter::from_fn(move || {
+        loop {
+            let start = rest.find("{{")?;
+            let after = &rest[start + 2..];
+            let end = after.find("}}")?;
+            rest = &after[end + 2..];
+            let name = after[..end].trim();
+            if is_name(name) {
+                return Some(name);
+            }
+        }
+    })
+}
+
+/// 替换内容中的变量；没有可替换的变量时不分配
+pub fn substitute<'a>(content: &'a str, vars: &TemplateVars) -> Cow<'a, str> {
+    if vars.is_empty() || !content.contains("{{") {
+        return Cow::Borrowed(content);
+    }
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    let mut replaced = false;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = after[..end].trim();
+        match vars.get(name).filter(|_| is_name(name)) {
+            Some(value) => {
+                out.push_str(&rest[..start]);
+                out.push_str(value);
+                replaced = true;
+            }
+            None => out.push_str(&rest[..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    if !replaced {
+        return Cow::Borrowed(content);
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec::Vec};
+
+    #[test]
+    fn test_substitute() {
+        let vars = TemplateVars::from([
+            ("api_base_url".to_string(), "https://api.example.com".to_string()),
+            ("team".to_string(), "支付组".to_string()),
+        ]);
+        let content = "GET {{api_base_url}}/v1/items, ask {{ team }}; keep {{missing}} and {{not a var}}";
+        assert_eq!(
+            substitute(content, &vars),
+            "GET https://api.example.com/v1/items, ask 支付组; keep {{missing}} and {{not a var}}"
+        );
+        assert!(matches!(substitute("no vars {{missing}}", &vars), Cow::Borrowed(_)));
+        assert_eq!(substitute("dangling {{api_base_url", &vars), "dangling {{api_base_url");
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let names: Vec<&str> = placeholders("{{a}} {{ b.c }} {{}} {{a}} {{x y}}").collect();
+        assert_eq!(names, ["a", "b.c", "a"]);
+    }
+}
@@ -0,0 +1,269 @@
+//! 只追加的Delta日志（JSONL，每行一个 [`JournalEntry`]）：每个成功应用的DeltaBatch追加一行，
+//! 可从空Playbook或某个快照重放日志重建Playbook，也可只重放到某个时刻（时间点恢复）
+//!
+//! 每次只追加一行并`fsync`，崩溃时最多丢失正在写入的那一行，打开日志时截掉不完整的末行。
+//! 重放以各条的`applied_at`为时间戳依次应用，生成的ID与记录时一致；不序列化的设置
+//! （规模限制、容量、内容策略、章节规范化）需在重放前设置成与记录时相同。
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::delta::DeltaBatch;
+use crate::models::playbook::{Playbook, PlaybookError};
+use crate::models::replay::{JournalEntry, ReplayFilter};
+use crate::persist::indexed;
+
+/// 打开的Delta日志
+#[derive(Debug)]
+pub struct DeltaLog {
+    path: PathBuf,
+    file: File,
+    /// 日志中的条目数
+    entries: usize,
+}
+
+/// 日志快照：Playbook及其已包含的日志条目数，从快照恢复时只重放之后的条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogSnapshot {
+    pub entries: usize,
+    pub playbook: Playbook,
+}
+
+impl LogSnapshot {
+    /// 保存到文件（自动创建父目录）；先写临时文件再改名，崩溃时旧快照保持完整
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PlaybookError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        indexed::save_atomically(path, |writer| Ok(serde_json::to_writer(writer, self)?))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PlaybookError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut snapshot: Self = serde_json::from_reader(reader)
+            .map_err(|e| PlaybookError::InvalidData(format!("Failed to parse snapshot: {}", e)))?;
+        snapshot.playbook.reintern_sections();
+        snapshot.playbook.rebuild_rollups();
+        Ok(snapshot)
+    }
+}
+
+impl DeltaLog {
+    /// 打开日志（文件与父目录不存在时创建）；末尾不完整的一行（写入时崩溃）被截掉
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, PlaybookError> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        let complete = data.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        if complete < data.len() {
+            file.set_len(complete as u64)?;
+        }
+        let entries = data[..complete]
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.trim_ascii().is_empty())
+            .count();
+        Ok(Self { path, file, entries })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries == 0
+    }
+
+    /// 追加一条并同步到磁盘；写入失败时截回原长度，不留下写了一半的行
+    pub fn append(&mut self, entry: &JournalEntry) -> Result<(), PlaybookError> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let len = self.file.metadata()?.len();
+        // 单次write_all写入整行，并发追加时行不会交错
+        if let Err(e) = self.file.write_all(&line).and_then(|()| self.file.sync_data()) {
+            let _ = self.file.set_len(len);
+            return Err(e.into());
+        }
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// 全部成功或全部不生效地应用`delta`并追加到日志，返回实际产生的变化
+    ///
+    /// 与 [`Playbook::apply_delta_transactional`] 一样记录被修改对象的原状态，
+    /// 应用或写入日志失败时写回原状态：Playbook与日志都不变，也不通知观察者。
+    pub fn apply(
+        &mut self,
+        playbook: &mut Playbook,
+        delta: DeltaBatch,
+        run: Option<String>,
+    ) -> Result<DeltaBatch, PlaybookError> {
+        let applied_at = playbook.clock().now();
+        let mut journal = playbook.apply_delta_journaled_at(delta.clone(), applied_at)?;
+        if let Err(e) = self.append(&JournalEntry { run, applied_at, delta }) {
+            journal.rollback(playbook);
+            return Err(e);
+        }
+        journal.commit(playbook);
+        Ok(journal.changes(playbook))
+    }
+
+    /// 按写入顺序逐行读取全部条目，空行跳过
+    pub fn entries(&self) -> Result<Vec<JournalEntry>, PlaybookError> {
+        let mut entries = Vec::with_capacity(self.entries);
+        for (i, line) in BufReader::new(File::open(&self.path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line).map_err(|err| {
+                PlaybookError::InvalidData(format!("{} line {}: {}", self.path.display(), i + 1, err))
+            })?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// 当前Playbook的快照，记录它已包含的条目数
+    pub fn snapshot(&self, playbook: &Playbook) -> LogSnapshot {
+        LogSnapshot { entries: self.entries, playbook: playbook.clone() }
+    }
+
+    /// 从空Playbook重放全部日志
+    pub fn replay(&self) -> Result<Playbook, PlaybookError> {
+        self.restore(None, None)
+    }
+
+    /// 在快照（为空时从空Playbook）上重放之后的条目；`until`不为空时只重放不晚于该时刻的条目
+    pub fn restore(
+        &self,
+        snapshot: Option<LogSnapshot>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<Playbook, PlaybookError> {
+        let (mut playbook, skip) = match snapshot {
+            Some(snapshot) => (snapshot.playbook, snapshot.entries),
+            None => (Playbook::new(), 0),
+        };
+        let entries = self.entries()?;
+        if skip > entries.len() {
+            return Err(PlaybookError::InvalidData(format!(
+                "snapshot covers {} entries but the log has {}",
+                skip,
+                entries.len()
+            )));
+        }
+        let entries = entries[skip..]
+            .iter()
+            .take_while(|entry| until.is_none_or(|until| entry.applied_at <= until));
+        ReplayFilter::default().replay_onto(&mut playbook, entries)?;
+        Ok(playbook)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use chrono::TimeDelta;
+
+    use crate::clock::{Clock as _, ManualClock};
+    use crate::models::delta::{DeltaOperation, OperationType};
+
+    fn batch(operations: Vec<DeltaOperation>) -> DeltaBatch {
+        DeltaBatch { reasoning: "curate".to_string(), operations }
+    }
+
+    #[test]
+    fn test_append_replay_and_restore() {
+        let dir = std::env::temp_dir().join(format!("ace-delta-log-{}", std::process::id()));
+        let path = dir.join("delta.jsonl");
+        let _ = fs::remove_dir_all(&dir);
+
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut pb = Playbook::with_clock(clock.clone());
+        let mut log = DeltaLog::open(&path).unwrap();
+        assert!(log.is_empty());
+        log.apply(&mut pb, batch(vec![DeltaOperation::add("api", "先分页")]), None).unwrap();
+        let a = pb.bullets.keys().next().unwrap().clone();
+        let snapshot = log.snapshot(&pb);
+
+        clock.advance(TimeDelta::seconds(10));
+        let middle = clock.now();
+        let ops = vec![
+            DeltaOperation::add("api", "带上User-Agent"),
+            DeltaOperation::tag("api", &a, [("helpful".to_string(), 2)].into()),
+        ];
+        let changes = log.apply(&mut pb, batch(ops), Some("epoch-2".into())).unwrap();
+        let types: Vec<_> = changes.operations.iter().map(|op| op.type_).collect();
+        assert_eq!(types, [OperationType::Tag, OperationType::Add]);
+
+        // 失败的批次既不生效也不写入日志
+        let (revision, before) = (pb.revision(), pb.to_json().unwrap());
+        let tag_missing = DeltaOperation::tag("api", "nope", [("helpful".to_string(), 1)].into());
+        let bad = batch(vec![DeltaOperation::add("api", "x"), tag_missing]);
+        assert!(log.apply(&mut pb, bad, None).is_err());
+        assert_eq!((pb.revision(), log.len()), (revision, 2));
+        assert_eq!(pb.to_json().unwrap(), before);
+
+        clock.advance(TimeDelta::seconds(10));
+        log.apply(&mut pb, batch(vec![DeltaOperation::remove("api", &a)]), None).unwrap();
+
+        let replayed = log.replay().unwrap();
+        assert_eq!(replayed.to_json().unwrap(), pb.to_json().unwrap());
+        let restored = log.restore(Some(snapshot.clone()), None).unwrap();
+        assert_eq!(restored.to_json().unwrap(), pb.to_json().unwrap());
+
+        // 时间点恢复：第三批之前A还在
+        let earlier = log.restore(None, Some(middle)).unwrap();
+        assert_eq!(earlier.bullets.len(), 2);
+        assert_eq!(earlier.bullets[&a].helpful, 2);
+
+        // 快照写入文件后照样可用；快照比日志新时报错
+        let snapshot_path = dir.join("snapshot.json");
+        snapshot.save(&snapshot_path).unwrap();
+        assert!(!dir.join("snapshot.json.tmp").exists());
+        let loaded = LogSnapshot::load(&snapshot_path).unwrap();
+        assert_eq!(loaded.playbook.section_rollup("api").unwrap().bullets, 1);
+        let future = LogSnapshot { entries: 9, ..loaded };
+        assert_eq!(log.restore(Some(future), None).unwrap_err().code(), "invalid_data");
+
+        // 写入时崩溃留下的半行在重新打开时被截掉
+        drop(log);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"applied_at\":").unwrap();
+        let log = DeltaLog::open(&path).unwrap();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.entries().unwrap()[1].run.as_deref(), Some("epoch-2"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// `/dev/full`上的写入总是失败（ENOSPC）：条目数不变，错误交给调用方
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_failed_append_is_not_counted() {
+        let file = OpenOptions::new().append(true).open("/dev/full").unwrap();
+        let mut log = DeltaLog { path: PathBuf::from("/dev/full"), file, entries: 0 };
+        let entry = JournalEntry {
+            run: None,
+            applied_at: DateTime::UNIX_EPOCH,
+            delta: batch(vec![DeltaOperation::add("api", "先分页")]),
+        };
+        assert_eq!(log.append(&entry).unwrap_err().code(), "io");
+        assert!(log.is_empty());
+    }
+}
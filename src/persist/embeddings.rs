@@ -0,0 +1,144 @@
+//! 向量缓存文件，放在Playbook旁边（`<playbook>.embeddings`），按内容哈希存储
+//!
+//! 布局（全部小端序）：
+//!
+//! ```text
+//! header   28B  magic[8] | version u32 | dimensions u32 | count u64 | model_len u32
+//! model         模型标识的UTF-8字节
+//! entries       content_hash u64 | f32 × dimensions   （按哈希排序）
+//! ```
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::embedding::{Embedder, EmbeddingStore};
+use crate::models::playbook::PlaybookError;
+use crate::persist::indexed;
+
+pub const MAGIC: &[u8; 8] = b"ACEEMB\0\0";
+pub const FORMAT_VERSION: u32 = 1;
+
+const HEADER_LEN: usize = 28;
+const EXTENSION: &str = "embeddings";
+
+impl EmbeddingStore {
+    /// Playbook文件对应的向量缓存路径
+    pub fn path_for(playbook_path: impl AsRef<Path>) -> PathBuf {
+        playbook_path.as_ref().with_extension(EXTENSION)
+    }
+
+    /// 原子写入（临时文件+rename）
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PlaybookError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut hashes: Vec<u64> = self.vectors.keys().copied().collect();
+        hashes.sort_unstable();
+
+        indexed::save_atomically(path, |writer| {
+            writer.write_all(MAGIC)?;
+            writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+            writer.write_all(&(self.dimensions as u32).to_le_bytes())?;
+            writer.write_all(&(hashes.len() as u64).to_le_bytes())?;
+            writer.write_all(&(self.model.len() as u32).to_le_bytes())?;
+            writer.write_all(self.model.as_bytes())?;
+            for hash in hashes {
+                writer.write_all(&hash.to_le_bytes())?;
+                for value in &self.vectors[&hash] {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// 加载缓存；文件不存在时返回空缓存。模型与`embedder`不同的缓存在下次`refresh`时整体失效
+    pub fn load(
+        path: impl AsRef<Path>,
+        embedder: Arc<dyn Embedder>,
+    ) -> Result<Self, PlaybookError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new(embedder));
+        }
+        let data = std::fs::read(path)?;
+        let invalid = |msg: &str| {
+            PlaybookError::InvalidData(format!("{}: embeddings {}", path.display(), msg))
+        };
+        if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+            return Err(invalid("bad header"));
+        }
+        let u32_at = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        if u32_at(8) != FORMAT_VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        let dimensions = u32_at(12) as usize;
+        let count = u64_at(16) as usize;
+        let model_end = HEADER_LEN + u32_at(24) as usize;
+        let entry_len = 8 + dimensions * 4;
+        let expected = count.checked_mul(entry_len).and_then(|n| n.checked_add(model_end));
+        if expected != Some(data.len()) {
+            return Err(invalid("truncated or corrupted"));
+        }
+        let model = std::str::from_utf8(&data[HEADER_LEN..model_end])
+            .map_err(|_| invalid("model is not UTF-8"))?
+            .to_string();
+
+        let mut vectors = HashMap::with_capacity(count);
+        for entry in data[model_end..].chunks_exact(entry_len) {
+            let hash = u64::from_le_bytes(entry[..8].try_into().unwrap());
+            let vector = entry[8..]
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect();
+            vectors.insert(hash, vector);
+        }
+        Ok(Self::with_vectors(embedder, model, dimensions, vectors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+
+    use crate::embedding::tests::CountingEmbedder;
+    use crate::models::playbook::Playbook;
+
+    #[test]
+    fn test_cache_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("ace-embeddings-{}", std::process::id()));
+        let path = EmbeddingStore::path_for(dir.join("support.json"));
+        let mut pb = Playbook::new();
+        pb.add_bullet("support", "Escalate refund requests over $500", None, None);
+        pb.add_bullet("support", "Never share customer emails", None, None);
+
+        let embedder = Arc::new(CountingEmbedder::default());
+        let mut store = EmbeddingStore::load(&path, embedder.clone()).unwrap();
+        store.refresh(&pb).unwrap();
+        store.save(&path).unwrap();
+
+        let mut reloaded = EmbeddingStore::load(&path, embedder.clone()).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.refresh(&pb).unwrap().computed, 0);
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), 2);
+
+        // 换模型后缓存失效
+        let other = Arc::new(CountingEmbedder {
+            model: "other".into(),
+            ..Default::default()
+        });
+        let mut stale = EmbeddingStore::load(&path, other).unwrap();
+        assert_eq!(stale.refresh(&pb).unwrap().computed, 2);
+
+        std::fs::write(&path, b"ACEEMB\0\0garbage").unwrap();
+        assert!(EmbeddingStore::load(&path, embedder).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
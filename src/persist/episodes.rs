@@ -0,0 +1,56 @@
+//! 情景记录文件（JSON，整体原子写入）
+
+use std::path::Path;
+
+use crate::episodes::EpisodeStore;
+use crate::models::playbook::PlaybookError;
+use crate::persist::indexed;
+
+impl EpisodeStore {
+    /// 原子写入（临时文件+rename）
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PlaybookError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        indexed::save_atomically(path, |writer| Ok(serde_json::to_writer(writer, self)?))
+    }
+
+    /// 加载；文件不存在时返回空记录
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PlaybookError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        let data = std::fs::read(path)?;
+        serde_json::from_slice(&data).map_err(|err| {
+            PlaybookError::InvalidData(format!("{}: episodes {}", path.display(), err))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    use crate::feedback::Outcome;
+
+    #[test]
+    fn test_episodes_roundtrip() {
+        let path = std::env::temp_dir().join(format!("ace-episodes-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(EpisodeStore::load(&path).unwrap().is_empty());
+
+        let mut store = EpisodeStore::new();
+        let at = DateTime::UNIX_EPOCH;
+        store.record("refund order 42", Outcome::ToolErrored, "503", vec!["api-00001".into()], at);
+        store.record("list users", Outcome::Custom("slow".into()), "", vec![], at);
+        store.save(&path).unwrap();
+
+        let loaded = EpisodeStore::load(&path).unwrap();
+        assert_eq!(loaded, store);
+        assert_eq!(loaded.pending().len(), 2);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+//! Playbook的文件读写（JSON与索引格式）
+
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::models::playbook::{Playbook, PlaybookError};
+use crate::models::timestamp::TimestampFormat;
+use crate::persist::indexed;
+
+impl Playbook {
+    /// 保存到文件（自动创建父目录）
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), PlaybookError> {
+        self.save_to_file_with(path, TimestampFormat::default())
+    }
+
+    /// 按指定时间戳格式保存到文件
+    pub fn save_to_file_with(
+        &self,
+        path: impl AsRef<Path>,
+        format: TimestampFormat,
+    ) -> Result<(), PlaybookError> {
+        let path = path.as_ref();
+        // 自动创建父目录（避免文件路径不存在报错）
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // 直接流式写入文件，不生成中间字符串
+        let mut writer = BufWriter::new(File::create(path)?);
+        if format == TimestampFormat::default() {
+            serde_json::to_writer_pretty(&mut writer, self)?;
+        } else {
+            serde_json::to_writer_pretty(&mut writer, &self.to_value_with(format)?)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 从文件加载（处理文件不存在的情况）
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, PlaybookError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(PlaybookError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Playbook file not found: {}", path.display()),
+            )));
+        }
+
+        Playbook::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// 从reader流式解析JSON，不先把整个文件读入字符串，大Playbook的峰值内存约减半
+    pub fn from_reader(reader: impl Read) -> Result<Self, PlaybookError> {
+        let mut playbook: Self = serde_json::from_reader(reader)
+            .map_err(|e| PlaybookError::InvalidData(format!("Failed to parse JSON: {}", e)))?;
+        playbook.reintern_sections();
+        playbook.rebuild_rollups();
+        Ok(playbook)
+    }
+
+    /// 保存为带索引的二进制格式（见 [`crate::persist::indexed`]），可用 `MappedPlaybook` 只读打开
+    pub fn save_indexed(&self, path: impl AsRef<Path>) -> Result<(), PlaybookError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        indexed::save_atomically(path, |writer| indexed::write_indexed(self, writer))
+    }
+
+    /// 加载带索引的二进制格式
+    pub fn load_indexed(path: impl AsRef<Path>) -> Result<Self, PlaybookError> {
+        indexed::MappedPlaybook::open(path)?.view().to_playbook()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_streams_from_file() {
+        let path = std::env::temp_dir().join(format!("ace-load-{}.json", std::process::id()));
+        let mut pb = Playbook::new();
+        let id = pb.add_bullet("api", "先分页", None, None);
+        pb.tag_bullet(&id, "helpful", 2).unwrap();
+        pb.save_to_file(&path).unwrap();
+
+        let loaded = Playbook::load_from_file(&path).unwrap();
+        assert_eq!(loaded.to_json().unwrap(), pb.to_json().unwrap());
+        assert_eq!(loaded.section_rollup("api").unwrap().helpful, 2);
+        let (key, _) = loaded.sections.get_key_value("api").unwrap();
+        assert!(std::sync::Arc::ptr_eq(key, &loaded.bullets[&id].section));
+
+        fs::write(&path, "{\"bullets\": ").unwrap();
+        assert_eq!(Playbook::load_from_file(&path).unwrap_err().code(), "invalid_data");
+        fs::remove_file(path).unwrap();
+    }
+}
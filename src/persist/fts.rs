@@ -0,0 +1,455 @@
+//! BM25倒排索引的持久化格式：与Playbook一起保存，mmap打开后直接查询，冷启动时不必重新分词
+//!
+//! 布局（全部小端序）：
+//!
+//! ```text
+//! header    48B  magic[8] | version u32 | doc_count u32 | term_count u32 | posting_count u32
+//!                | k1 f64 | b f64 | total_len u64
+//! docs      24B  id_off u64 | id_len u32 | length u32 | content_hash u64   （按ID排序）
+//! terms     20B  term_off u64 | term_len u32 | first_posting u32 | posting_count u32   （按词排序）
+//! postings   8B  doc u32 | tf u32
+//! heap           所有字符串的UTF-8字节，偏移量相对heap起点
+//! ```
+//!
+//! 查询词按二分查找定位；用 [`FtsView::is_current`] 检查索引是否与Playbook一致，
+//! 不一致时 [`FtsView::to_index`] 载入内存后 [`Bm25Index::refresh`] 只重新索引变化的子弹。
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::Write,
+    path::Path,
+};
+
+use memmap2::Mmap;
+
+use crate::embedding::content_hash;
+use crate::models::playbook::{BulletId, Playbook, PlaybookError};
+use crate::persist::indexed::save_atomically;
+use crate::retrieval::{Bm25Index, idf, query_terms, tf_weight};
+
+pub const MAGIC: &[u8; 8] = b"ACEFTS\0\0";
+pub const FORMAT_VERSION: u32 = 1;
+
+const HEADER_LEN: usize = 48;
+const DOC_LEN: usize = 24;
+const TERM_LEN: usize = 20;
+const POSTING_LEN: usize = 8;
+
+// --------------------------
+// 写入
+// --------------------------
+
+/// 将BM25索引编码为持久化格式
+pub fn write_fts(index: &Bm25Index, mut writer: impl Write) -> Result<(), PlaybookError> {
+    let mut heap = Vec::new();
+    let mut push_str = |s: &str| -> (u64, u32) {
+        let off = heap.len() as u64;
+        heap.extend_from_slice(s.as_bytes());
+        (off, s.len() as u32)
+    };
+
+    // lengths按ID有序，下标即文档号
+    let mut docs = Vec::with_capacity(index.lengths.len() * DOC_LEN);
+    let mut doc_numbers: HashMap<&str, u32> = HashMap::with_capacity(index.lengths.len());
+    let mut total_len = 0u64;
+    for (i, (id, len)) in index.lengths.iter().enumerate() {
+        let (id_off, id_len) = push_str(id);
+        docs.extend_from_slice(&id_off.to_le_bytes());
+        docs.extend_from_slice(&id_len.to_le_bytes());
+        docs.extend_from_slice(&(*len as u32).to_le_bytes());
+        docs.extend_from_slice(&index.hashes.get(id).copied().unwrap_or_default().to_le_bytes());
+        doc_numbers.insert(id, i as u32);
+        total_len += *len as u64;
+    }
+
+    let mut terms: Vec<_> = index.postings.iter().collect();
+    terms.sort_unstable_by_key(|(term, _)| *term);
+    let mut term_table = Vec::with_capacity(terms.len() * TERM_LEN);
+    let mut postings = Vec::new();
+    let mut posting_count = 0u32;
+    for (term, list) in terms {
+        let mut entries: Vec<(u32, u32)> = list
+            .iter()
+            .filter_map(|(id, tf)| Some((*doc_numbers.get(id.as_str())?, *tf)))
+            .collect();
+        entries.sort_unstable();
+        let (term_off, term_len) = push_str(term);
+        term_table.extend_from_slice(&term_off.to_le_bytes());
+        term_table.extend_from_slice(&term_len.to_le_bytes());
+        term_table.extend_from_slice(&posting_count.to_le_bytes());
+        term_table.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for (doc, tf) in entries {
+            postings.extend_from_slice(&doc.to_le_bytes());
+            postings.extend_from_slice(&tf.to_le_bytes());
+            posting_count += 1;
+        }
+    }
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    header.extend_from_slice(&(index.lengths.len() as u32).to_le_bytes());
+    header.extend_from_slice(&(index.postings.len() as u32).to_le_bytes());
+    header.extend_from_slice(&posting_count.to_le_bytes());
+    header.extend_from_slice(&index.k1.to_le_bytes());
+    header.extend_from_slice(&index.b.to_le_bytes());
+    header.extend_from_slice(&total_len.to_le_bytes());
+
+    writer.write_all(&header)?;
+    writer.write_all(&docs)?;
+    writer.write_all(&term_table)?;
+    writer.write_all(&postings)?;
+    writer.write_all(&heap)?;
+    writer.flush()?;
+    Ok(())
+}
+
+impl Bm25Index {
+    /// 保存为持久化格式（临时文件+rename，已mmap打开的读者继续看到旧内容）
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PlaybookError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        save_atomically(path, |writer| write_fts(self, writer))
+    }
+}
+
+// --------------------------
+// 读取
+// --------------------------
+
+/// 持久化索引的只读视图，直接在字节上查询
+#[derive(Debug, Clone, Copy)]
+pub struct FtsView<'a> {
+    data: &'a [u8],
+    doc_count: usize,
+    term_count: usize,
+    posting_count: usize,
+}
+
+impl<'a> FtsView<'a> {
+    /// 校验并打开一段持久化索引字节
+    pub fn open(data: &'a [u8]) -> Result<Self, PlaybookError> {
+        if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+            return Err(invalid("not a full-text index"));
+        }
+        let version = read_u32(data, 8);
+        if version != FORMAT_VERSION {
+            return Err(invalid(&format!("unsupported format version {}", version)));
+        }
+        let view = Self {
+            data,
+            doc_count: read_u32(data, 12) as usize,
+            term_count: read_u32(data, 16) as usize,
+            posting_count: read_u32(data, 20) as usize,
+        };
+        view.validate()?;
+        Ok(view)
+    }
+
+    fn validate(&self) -> Result<(), PlaybookError> {
+        let tables = self
+            .doc_count
+            .checked_mul(DOC_LEN)
+            .zip(self.term_count.checked_mul(TERM_LEN))
+            .zip(self.posting_count.checked_mul(POSTING_LEN))
+            .and_then(|((a, b), c)| a.checked_add(b)?.checked_add(c)?.checked_add(HEADER_LEN));
+        if tables.is_none_or(|end| end > self.data.len()) {
+            return Err(invalid("truncated tables"));
+        }
+
+        let heap = &self.data[self.heap_start()..];
+        let check_str = |off: u64, len: u32| {
+            usize::try_from(off)
+                .ok()
+                .and_then(|off| heap.get(off..off.checked_add(len as usize)?))
+                .is_some_and(|bytes| std::str::from_utf8(bytes).is_ok())
+        };
+        for i in 0..self.doc_count {
+            let at = self.doc_offset(i);
+            if !check_str(read_u64(self.data, at), read_u32(self.data, at + 8))
+                || (i > 0 && self.doc_id(i - 1) >= self.doc_id(i))
+            {
+                return Err(invalid("corrupted document table"));
+            }
+        }
+        let mut next_posting = 0;
+        for i in 0..self.term_count {
+            let at = self.term_offset(i);
+            let (first, count) = self.posting_range(i);
+            if !check_str(read_u64(self.data, at), read_u32(self.data, at + 8))
+                || first != next_posting
+                || count == 0
+                || (i > 0 && self.term(i - 1) >= self.term(i))
+            {
+                return Err(invalid("corrupted term table"));
+            }
+            next_posting = first + count;
+        }
+        if next_posting != self.posting_count
+            || (0..self.posting_count)
+                .any(|i| read_u32(self.data, self.posting_offset(i)) as usize >= self.doc_count)
+        {
+            return Err(invalid("corrupted postings"));
+        }
+        Ok(())
+    }
+
+    /// 已建索引的子弹数
+    pub fn len(&self) -> usize {
+        self.doc_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doc_count == 0
+    }
+
+    /// 与Playbook当前内容一致（ID与内容哈希逐一相同）
+    pub fn is_current(&self, playbook: &Playbook) -> bool {
+        self.doc_count == playbook.bullets.len()
+            && playbook.bullets().into_iter().enumerate().all(|(i, bullet)| {
+                self.doc_id(i) == bullet.id && self.doc_hash(i) == content_hash(&bullet.content)
+            })
+    }
+
+    /// 与 [`Bm25Index::scores`] 结果一致
+    pub fn scores(&self, query: &str) -> BTreeMap<BulletId, f64> {
+        let (k1, b) = (read_f64(self.data, 24), read_f64(self.data, 32));
+        let n = self.doc_count as f64;
+        let avg_len = self.avg_len();
+        let mut scores = BTreeMap::new();
+        for term in query_terms(query) {
+            let Some(i) = self.find_term(&term) else {
+                continue;
+            };
+            let (first, count) = self.posting_range(i);
+            let idf = idf(n, count as f64);
+            for posting in first..first + count {
+                let at = self.posting_offset(posting);
+                let doc = read_u32(self.data, at) as usize;
+                let tf = read_u32(self.data, at + 4) as f64;
+                let score = idf * tf_weight(k1, b, tf, self.doc_len(doc) as f64, avg_len);
+                *scores.entry(self.doc_id(doc).to_string()).or_insert(0.0) += score;
+            }
+        }
+        scores
+    }
+
+    /// 载入为内存中的索引（之后可 [`Bm25Index::refresh`]）
+    pub fn to_index(&self) -> Bm25Index {
+        let mut postings: HashMap<String, Vec<(BulletId, u32)>> =
+            HashMap::with_capacity(self.term_count);
+        for i in 0..self.term_count {
+            let (first, count) = self.posting_range(i);
+            let list = (first..first + count)
+                .map(|posting| {
+                    let at = self.posting_offset(posting);
+                    let doc = read_u32(self.data, at) as usize;
+                    (self.doc_id(doc).to_string(), read_u32(self.data, at + 4))
+                })
+                .collect();
+            postings.insert(self.term(i).to_string(), list);
+        }
+        let docs = (0..self.doc_count).map(|i| (self.doc_id(i).to_string(), i));
+        let (lengths, hashes) =
+            docs.map(|(id, i)| ((id.clone(), self.doc_len(i)), (id, self.doc_hash(i)))).unzip();
+        Bm25Index {
+            k1: read_f64(self.data, 24),
+            b: read_f64(self.data, 32),
+            postings,
+            lengths,
+            hashes,
+            avg_len: self.avg_len(),
+        }
+    }
+
+    fn avg_len(&self) -> f64 {
+        if self.doc_count == 0 {
+            return 0.0;
+        }
+        read_u64(self.data, 40) as f64 / self.doc_count as f64
+    }
+
+    fn find_term(&self, term: &str) -> Option<usize> {
+        let (mut lo, mut hi) = (0, self.term_count);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.term(mid).cmp(term) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+
+    fn doc_id(&self, i: usize) -> &'a str {
+        let at = self.doc_offset(i);
+        self.str_at(read_u64(self.data, at), read_u32(self.data, at + 8))
+    }
+
+    fn doc_len(&self, i: usize) -> usize {
+        read_u32(self.data, self.doc_offset(i) + 12) as usize
+    }
+
+    fn doc_hash(&self, i: usize) -> u64 {
+        read_u64(self.data, self.doc_offset(i) + 16)
+    }
+
+    fn term(&self, i: usize) -> &'a str {
+        let at = self.term_offset(i);
+        self.str_at(read_u64(self.data, at), read_u32(self.data, at + 8))
+    }
+
+    fn posting_range(&self, i: usize) -> (usize, usize) {
+        let at = self.term_offset(i);
+        (read_u32(self.data, at + 12) as usize, read_u32(self.data, at + 16) as usize)
+    }
+
+    fn str_at(&self, off: u64, len: u32) -> &'a str {
+        // 打开时已校验过范围与UTF-8
+        let start = self.heap_start() + off as usize;
+        std::str::from_utf8(&self.data[start..start + len as usize]).unwrap_or_default()
+    }
+
+    fn doc_offset(&self, i: usize) -> usize {
+        HEADER_LEN + i * DOC_LEN
+    }
+
+    fn term_offset(&self, i: usize) -> usize {
+        self.doc_offset(self.doc_count) + i * TERM_LEN
+    }
+
+    fn posting_offset(&self, i: usize) -> usize {
+        self.term_offset(self.term_count) + i * POSTING_LEN
+    }
+
+    fn heap_start(&self) -> usize {
+        self.posting_offset(self.posting_count)
+    }
+}
+
+/// mmap打开的持久化索引
+///
+/// 文件被映射期间不应被原地改写；[`Bm25Index::save`] 通过临时文件+rename替换。
+#[derive(Debug)]
+pub struct MappedBm25Index {
+    map: Mmap,
+    doc_count: usize,
+    term_count: usize,
+    posting_count: usize,
+}
+
+impl MappedBm25Index {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PlaybookError> {
+        let file = File::open(path.as_ref())?;
+        // SAFETY: 映射只读；文件由save原子替换而不是原地修改
+        let map = unsafe { Mmap::map(&file)? };
+        let view = FtsView::open(&map)?;
+        let (doc_count, term_count, posting_count) =
+            (view.doc_count, view.term_count, view.posting_count);
+        Ok(Self { map, doc_count, term_count, posting_count })
+    }
+
+    /// 底层视图（已在open时校验）
+    pub fn view(&self) -> FtsView<'_> {
+        FtsView {
+            data: &self.map,
+            doc_count: self.doc_count,
+            term_count: self.term_count,
+            posting_count: self.posting_count,
+        }
+    }
+
+    pub fn is_current(&self, playbook: &Playbook) -> bool {
+        self.view().is_current(playbook)
+    }
+
+    pub fn scores(&self, query: &str) -> BTreeMap<BulletId, f64> {
+        self.view().scores(query)
+    }
+
+    pub fn to_index(&self) -> Bm25Index {
+        self.view().to_index()
+    }
+}
+
+fn invalid(msg: &str) -> PlaybookError {
+    PlaybookError::InvalidData(format!("Full-text index: {}", msg))
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+}
+
+fn read_f64(data: &[u8], at: usize) -> f64 {
+    f64::from_bits(read_u64(data, at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Playbook {
+        let mut pb = Playbook::new();
+        pb.add_bullet("api usage", "paginate with a cursor", None, None);
+        pb.add_bullet("api usage", "retry with exponential backoff", None, None);
+        pb.add_bullet("debugging", "先看日志 then check the cursor", None, None);
+        pb
+    }
+
+    #[test]
+    fn test_view_scores_match_in_memory_index() {
+        let pb = sample();
+        let index = Bm25Index::build(&pb);
+        let mut bytes = Vec::new();
+        write_fts(&index, &mut bytes).unwrap();
+
+        let view = FtsView::open(&bytes).unwrap();
+        assert_eq!(view.len(), 3);
+        assert!(view.is_current(&pb));
+        for query in ["cursor", "retry 日志", "missing", ""] {
+            assert_eq!(view.scores(query), index.scores(query));
+        }
+        let loaded = view.to_index();
+        assert_eq!(loaded.scores("cursor backoff"), index.scores("cursor backoff"));
+        assert!(loaded.is_current(&pb));
+
+        assert!(FtsView::open(&bytes[..bytes.len() - 1]).is_err());
+        assert!(FtsView::open(b"not an index").is_err());
+    }
+
+    #[test]
+    fn test_refresh_reindexes_only_changed_bullets() {
+        let mut pb = sample();
+        let path = std::env::temp_dir().join(format!("ace-fts-{}.fts", std::process::id()));
+        Bm25Index::build(&pb).save(&path).unwrap();
+
+        let ids: Vec<BulletId> = pb.bullets().into_iter().map(|b| b.id.clone()).collect();
+        pb.update_bullet(&ids[0], Some("paginate with offsets".into()), None).unwrap();
+        pb.remove_bullet(&ids[1]);
+        pb.add_bullet("debugging", "bisect the cursor bug", None, None);
+
+        let mapped = MappedBm25Index::open(&path).unwrap();
+        assert!(!mapped.is_current(&pb));
+        let mut index = mapped.to_index();
+        drop(mapped);
+        assert_eq!(index.refresh(&pb), 3);
+        assert_eq!(index.refresh(&pb), 0);
+        assert!(index.is_current(&pb));
+        assert_eq!(index.scores("cursor offsets"), Bm25Index::build(&pb).scores("cursor offsets"));
+        assert!(!index.postings.contains_key("backoff"));
+
+        index.save(&path).unwrap();
+        let mapped = MappedBm25Index::open(&path).unwrap();
+        assert!(mapped.is_current(&pb));
+        assert_eq!(mapped.scores("cursor"), index.scores("cursor"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,972 @@
+//! 带索引的只读二进制格式，可直接mmap后按需读取，不做整体反序列化
+//!
+//! 布局（全部小端序）：
+//!
+//! ```text
+//! header      68B  magic[8] | version u32 | section_count u32 | bullet_count u32 | index_slots u32 | next_id u64
+//!                  | examples_off u64 | examples_len u32   （few-shot示例，JSON数组；没有时长度为0）
+//!                  | failures_off u64 | failures_len u32   （错误模式记录，JSON数组；没有时长度为0）
+//!                  | archived_off u64 | archived_len u32   （已归档的子弹，JSON数组；没有时长度为0）
+//! sections    20B  name_off u64 | name_len u32 | first_record u32 | record_count u32   （按章节名排序）
+//! records    152B  id_off u64 | id_len u32 | content_off u64 | content_len u32 | section u32
+//!                  | helpful u32 | harmful u32 | neutral u32
+//!                  | created_secs i64 | created_nanos u32 | updated_secs i64 | updated_nanos u32
+//!                  | variants_off u64 | variants_len u32   （多语言版本，JSON对象；没有时长度为0）
+//!                  | last_used_secs i64 | last_used_nanos u32   （从未使用时nanos为u32::MAX）
+//!                  | flags u32   （bit 0: pinned）
+//!                  | skill_off u64 | skill_len u32   （工具使用信息，JSON对象；没有时长度为0）
+//!                  | lang_off u64 | lang_len u32   （内容语言代码；未知时长度为0）
+//!                  | expires_secs i64 | expires_nanos u32   （不过期时nanos为u32::MAX）
+//!                  | conditions_off u64 | conditions_len u32   （激活条件，JSON对象；没有时长度为0）
+//!                  | custom_off u64 | custom_len u32   （自定义标签的计数，JSON对象；没有时长度为0）
+//! id index     4B  开放寻址哈希表（FNV-1a + 线性探测），槽位存record下标，空槽为u32::MAX
+//! heap             所有字符串的UTF-8字节，偏移量相对heap起点
+//! ```
+//!
+//! 同一章节的记录连续存放，顺序即章节内的插入顺序，因此渲染提示词只需顺序扫描；
+//! 按ID查找O(1)，按章节加载只需读取该章节的记录区间。
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    sync::OnceLock,
+};
+
+use chrono::{DateTime, Utc};
+use memmap2::Mmap;
+
+use crate::models::example::Example;
+use crate::models::failure::Failure;
+use crate::models::skill::Skill;
+use crate::models::playbook::{
+    Bullet, Playbook, PlaybookError, write_bullet_line, write_section_header,
+};
+
+pub const MAGIC: &[u8; 8] = b"ACEPB\0\0\0";
+pub const FORMAT_VERSION: u32 = 12;
+
+const HEADER_LEN: usize = 68;
+const SECTION_LEN: usize = 20;
+const RECORD_LEN: usize = 152;
+const INDEX_LEN: usize = 4;
+const EMPTY_SLOT: u32 = u32::MAX;
+/// last_used_nanos的哨兵值：从未使用
+const NEVER_USED: u32 = u32::MAX;
+/// expires_nanos的哨兵值：不过期
+const NO_EXPIRY: u32 = u32::MAX;
+const FLAG_PINNED: u32 = 1;
+
+// --------------------------
+// 写入
+// --------------------------
+
+/// 将Playbook编码为索引格式
+pub fn write_indexed(playbook: &Playbook, mut writer: impl Write) -> Result<(), PlaybookError> {
+    let mut sections: Vec<_> = playbook.sections.iter().collect();
+    sections.sort_unstable_by_key(|(name, _)| *name);
+
+    let mut heap = Vec::new();
+    let mut push_str = |s: &str| -> (u64, u32) {
+        let off = heap.len() as u64;
+        heap.extend_from_slice(s.as_bytes());
+        (off, s.len() as u32)
+    };
+
+    let mut section_table = Vec::with_capacity(sections.len() * SECTION_LEN);
+    let mut records = Vec::with_capacity(playbook.bullets.len() * RECORD_LEN);
+    let mut ids: Vec<(&str, u32)> = Vec::with_capacity(playbook.bullets.len());
+
+    for (section_idx, (name, bullet_ids)) in sections.iter().enumerate() {
+        let first = ids.len() as u32;
+        for bullet in bullet_ids.iter().filter_map(|id| playbook.bullets.get(id)) {
+            let (id_off, id_len) = push_str(&bullet.id);
+            let (content_off, content_len) = push_str(&bullet.content);
+            records.extend_from_slice(&id_off.to_le_bytes());
+            records.extend_from_slice(&id_len.to_le_bytes());
+            records.extend_from_slice(&content_off.to_le_bytes());
+            records.extend_from_slice(&content_len.to_le_bytes());
+            records.extend_from_slice(&(section_idx as u32).to_le_bytes());
+            records.extend_from_slice(&bullet.helpful.to_le_bytes());
+            records.extend_from_slice(&bullet.harmful.to_le_bytes());
+            records.extend_from_slice(&bullet.neutral.to_le_bytes());
+            for ts in [bullet.created_at, bullet.updated_at] {
+                records.extend_from_slice(&ts.timestamp().to_le_bytes());
+                records.extend_from_slice(&ts.timestamp_subsec_nanos().to_le_bytes());
+            }
+            let (variants_off, variants_len) = if bullet.variants.is_empty() {
+                (0, 0)
+            } else {
+                push_str(&serde_json::to_string(&bullet.variants)?)
+            };
+            records.extend_from_slice(&variants_off.to_le_bytes());
+            records.extend_from_slice(&variants_len.to_le_bytes());
+            let (used_secs, used_nanos) = bullet
+                .last_used_at
+                .map_or((0, NEVER_USED), |ts| (ts.timestamp(), ts.timestamp_subsec_nanos()));
+            records.extend_from_slice(&used_secs.to_le_bytes());
+            records.extend_from_slice(&used_nanos.to_le_bytes());
+            let flags = if bullet.pinned { FLAG_PINNED } else { 0 };
+            records.extend_from_slice(&flags.to_le_bytes());
+            let (skill_off, skill_len) = match &bullet.skill {
+                Some(skill) => push_str(&serde_json::to_string(skill)?),
+                None => (0, 0),
+            };
+            records.extend_from_slice(&skill_off.to_le_bytes());
+            records.extend_from_slice(&skill_len.to_le_bytes());
+            let (lang_off, lang_len) = bullet.lang.as_deref().map_or((0, 0), &mut push_str);
+            records.extend_from_slice(&lang_off.to_le_bytes());
+            records.extend_from_slice(&lang_len.to_le_bytes());
+            let (expires_secs, expires_nanos) = bullet
+                .expires_at
+                .map_or((0, NO_EXPIRY), |ts| (ts.timestamp(), ts.timestamp_subsec_nanos()));
+            records.extend_from_slice(&expires_secs.to_le_bytes());
+            records.extend_from_slice(&expires_nanos.to_le_bytes());
+            let (conditions_off, conditions_len) = if bullet.conditions.is_empty() {
+                (0, 0)
+            } else {
+                push_str(&serde_json::to_string(&bullet.conditions)?)
+            };
+            records.extend_from_slice(&conditions_off.to_le_bytes());
+            records.extend_from_slice(&conditions_len.to_le_bytes());
+            let (custom_off, custom_len) = if bullet.custom.is_empty() {
+                (0, 0)
+            } else {
+                push_str(&serde_json::to_string(&bullet.custom)?)
+            };
+            records.extend_from_slice(&custom_off.to_le_bytes());
+            records.extend_from_slice(&custom_len.to_le_bytes());
+            ids.push((&bullet.id, ids.len() as u32));
+        }
+
+        let (name_off, name_len) = push_str(name);
+        section_table.extend_from_slice(&name_off.to_le_bytes());
+        section_table.extend_from_slice(&name_len.to_le_bytes());
+        section_table.extend_from_slice(&first.to_le_bytes());
+        section_table.extend_from_slice(&(ids.len() as u32 - first).to_le_bytes());
+    }
+
+    // 装载因子不超过1/2，探测链保持很短
+    let slots = (ids.len() * 2).next_power_of_two();
+    let mut index = vec![EMPTY_SLOT; slots];
+    for (id, record) in &ids {
+        let mut slot = hash_id(id) as usize & (slots - 1);
+        while index[slot] != EMPTY_SLOT {
+            slot = (slot + 1) & (slots - 1);
+        }
+        index[slot] = *record;
+    }
+
+    let (examples_off, examples_len) = if playbook.examples.is_empty() {
+        (0, 0)
+    } else {
+        let examples: Vec<&Example> = playbook.examples.values().collect();
+        push_str(&serde_json::to_string(&examples)?)
+    };
+    let (failures_off, failures_len) = if playbook.failures.is_empty() {
+        (0, 0)
+    } else {
+        let failures: Vec<&Failure> = playbook.failures.values().collect();
+        push_str(&serde_json::to_string(&failures)?)
+    };
+    let (archived_off, archived_len) = if playbook.archived.is_empty() {
+        (0, 0)
+    } else {
+        let archived: Vec<&Bullet> = playbook.archived.values().collect();
+        push_str(&serde_json::to_string(&archived)?)
+    };
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    header.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+    header.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+    header.extend_from_slice(&(slots as u32).to_le_bytes());
+    header.extend_from_slice(&playbook.next_id.to_le_bytes());
+    header.extend_from_slice(&examples_off.to_le_bytes());
+    header.extend_from_slice(&examples_len.to_le_bytes());
+    header.extend_from_slice(&failures_off.to_le_bytes());
+    header.extend_from_slice(&failures_len.to_le_bytes());
+    header.extend_from_slice(&archived_off.to_le_bytes());
+    header.extend_from_slice(&archived_len.to_le_bytes());
+
+    writer.write_all(&header)?;
+    writer.write_all(&section_table)?;
+    writer.write_all(&records)?;
+    for record in &index {
+        writer.write_all(&record.to_le_bytes())?;
+    }
+    writer.write_all(&heap)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// --------------------------
+// 读取
+// --------------------------
+
+/// 索引格式中的一条子弹，字符串直接借用底层字节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulletRef<'a> {
+    pub id: &'a str,
+    pub section: &'a str,
+    pub content: &'a str,
+    pub helpful: u32,
+    pub harmful: u32,
+    pub neutral: u32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub pinned: bool,
+    /// 多语言版本的原始JSON对象；没有时为空串
+    pub variants: &'a str,
+    /// 技能信息的原始JSON对象；没有时为空串
+    pub skill: &'a str,
+    pub lang: Option<&'a str>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 激活条件的原始JSON对象；没有时为空串
+    pub conditions: &'a str,
+    /// 自定义标签计数的原始JSON对象；没有时为空串
+    pub custom: &'a str,
+}
+
+impl BulletRef<'_> {
+    /// 解析多语言版本（语言标签 → 内容）
+    pub fn variants(&self) -> BTreeMap<String, String> {
+        // 打开时已校验过JSON
+        if self.variants.is_empty() {
+            return BTreeMap::new();
+        }
+        serde_json::from_str(self.variants).unwrap_or_default()
+    }
+
+    /// 解析激活条件
+    pub fn conditions(&self) -> BTreeMap<String, String> {
+        // 打开时已校验过JSON
+        if self.conditions.is_empty() {
+            return BTreeMap::new();
+        }
+        serde_json::from_str(self.conditions).unwrap_or_default()
+    }
+
+    /// 解析自定义标签的计数
+    pub fn custom(&self) -> BTreeMap<String, u32> {
+        // 打开时已校验过JSON
+        if self.custom.is_empty() {
+            return BTreeMap::new();
+        }
+        serde_json::from_str(self.custom).unwrap_or_default()
+    }
+
+    /// 解析技能信息
+    pub fn skill(&self) -> Option<Skill> {
+        // 打开时已校验过JSON
+        if self.skill.is_empty() {
+            return None;
+        }
+        serde_json::from_str(self.skill).ok()
+    }
+
+    /// 复制为拥有所有权的Bullet
+    pub fn to_bullet(&self) -> Bullet {
+        let mut bullet = Bullet::new_at(self.section, self.content.to_string(), self.created_at);
+        bullet.id = self.id.to_string();
+        bullet.helpful = self.helpful;
+        bullet.harmful = self.harmful;
+        bullet.neutral = self.neutral;
+        bullet.updated_at = self.updated_at;
+        bullet.last_used_at = self.last_used_at;
+        bullet.pinned = self.pinned;
+        bullet.variants = self.variants();
+        bullet.skill = self.skill();
+        bullet.lang = self.lang.map(str::to_string);
+        bullet.expires_at = self.expires_at;
+        bullet.conditions = self.conditions();
+        bullet.custom = self.custom();
+        bullet
+    }
+}
+
+/// 索引格式字节上的只读视图（打开时完整校验一次，之后的访问不再分配）
+#[derive(Debug, Clone, Copy)]
+pub struct IndexedView<'a> {
+    data: &'a [u8],
+    section_count: usize,
+    bullet_count: usize,
+    index_slots: usize,
+    next_id: u64,
+}
+
+impl<'a> IndexedView<'a> {
+    /// 校验并打开一段索引格式字节
+    pub fn open(data: &'a [u8]) -> Result<Self, PlaybookError> {
+        if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+            return Err(invalid("not an indexed playbook"));
+        }
+        let version = read_u32(data, 8);
+        if version != FORMAT_VERSION {
+            return Err(invalid(&format!("unsupported format version {}", version)));
+        }
+
+        let view = Self {
+            data,
+            section_count: read_u32(data, 12) as usize,
+            bullet_count: read_u32(data, 16) as usize,
+            index_slots: read_u32(data, 20) as usize,
+            next_id: read_u64(data, 24),
+        };
+        view.validate()?;
+        Ok(view)
+    }
+
+    fn validate(&self) -> Result<(), PlaybookError> {
+        if !self.index_slots.is_power_of_two() || self.index_slots <= self.bullet_count {
+            return Err(invalid("corrupted id index"));
+        }
+        let tables = self
+            .section_count
+            .checked_mul(SECTION_LEN)
+            .zip(self.bullet_count.checked_mul(RECORD_LEN))
+            .zip(self.index_slots.checked_mul(INDEX_LEN))
+            .and_then(|((a, b), c)| a.checked_add(b)?.checked_add(c)?.checked_add(HEADER_LEN));
+        if tables.is_none_or(|end| end > self.data.len()) {
+            return Err(invalid("truncated tables"));
+        }
+
+        let heap = &self.data[self.heap_start()..];
+        let check_str = |off: u64, len: u32| {
+            usize::try_from(off)
+                .ok()
+                .and_then(|off| heap.get(off..off.checked_add(len as usize)?))
+                .is_some_and(|bytes| std::str::from_utf8(bytes).is_ok())
+        };
+        let check_variants = |off: u64, len: u32| {
+            len == 0
+                || (check_str(off, len)
+                    && serde_json::from_str::<BTreeMap<String, String>>(self.str_at(off, len)).is_ok())
+        };
+
+        let check_custom = |off: u64, len: u32| {
+            len == 0
+                || (check_str(off, len)
+                    && serde_json::from_str::<BTreeMap<String, u32>>(self.str_at(off, len)).is_ok())
+        };
+
+        let check_skill = |off: u64, len: u32| {
+            len == 0
+                || (check_str(off, len)
+                    && serde_json::from_str::<Skill>(self.str_at(off, len)).is_ok())
+        };
+
+        for i in 0..self.section_count {
+            let at = self.section_offset(i);
+            let (first, count) = (read_u32(self.data, at + 12), read_u32(self.data, at + 16));
+            if !check_str(read_u64(self.data, at), read_u32(self.data, at + 8))
+                || first as usize + count as usize > self.bullet_count
+            {
+                return Err(invalid("corrupted section table"));
+            }
+        }
+        for i in 0..self.bullet_count {
+            let at = self.record_offset(i);
+            if !check_str(read_u64(self.data, at), read_u32(self.data, at + 8))
+                || !check_str(read_u64(self.data, at + 12), read_u32(self.data, at + 20))
+                || read_u32(self.data, at + 24) as usize >= self.section_count
+                || self.timestamp(at + 40).is_none()
+                || self.timestamp(at + 52).is_none()
+                || !check_variants(read_u64(self.data, at + 64), read_u32(self.data, at + 72))
+                || (read_u32(self.data, at + 84) != NEVER_USED && self.timestamp(at + 76).is_none())
+                || !check_skill(read_u64(self.data, at + 92), read_u32(self.data, at + 100))
+                || !check_str(read_u64(self.data, at + 104), read_u32(self.data, at + 112))
+                || (read_u32(self.data, at + 124) != NO_EXPIRY
+                    && self.timestamp(at + 116).is_none())
+                || !check_variants(read_u64(self.data, at + 128), read_u32(self.data, at + 136))
+                || !check_custom(read_u64(self.data, at + 140), read_u32(self.data, at + 148))
+            {
+                return Err(invalid("corrupted bullet record"));
+            }
+        }
+        let (examples_off, examples_len) = (read_u64(self.data, 32), read_u32(self.data, 40));
+        if examples_len != 0
+            && (!check_str(examples_off, examples_len)
+                || serde_json::from_str::<Vec<Example>>(self.examples_json()).is_err())
+        {
+            return Err(invalid("corrupted examples"));
+        }
+        let (failures_off, failures_len) = (read_u64(self.data, 44), read_u32(self.data, 52));
+        if failures_len != 0
+            && (!check_str(failures_off, failures_len)
+                || serde_json::from_str::<Vec<Failure>>(self.failures_json()).is_err())
+        {
+            return Err(invalid("corrupted failures"));
+        }
+        let (archived_off, archived_len) = (read_u64(self.data, 56), read_u32(self.data, 64));
+        if archived_len != 0
+            && (!check_str(archived_off, archived_len)
+                || serde_json::from_str::<Vec<Bullet>>(self.archived_json()).is_err())
+        {
+            return Err(invalid("corrupted archive"));
+        }
+        let mut occupied = 0;
+        for slot in 0..self.index_slots {
+            match read_u32(self.data, self.index_offset(slot)) {
+                EMPTY_SLOT => {}
+                record if (record as usize) < self.bullet_count => occupied += 1,
+                _ => return Err(invalid("corrupted id index")),
+            }
+        }
+        // 至少留一个空槽，保证查找的探测一定终止
+        if occupied != self.bullet_count {
+            return Err(invalid("corrupted id index"));
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.bullet_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bullet_count == 0
+    }
+
+    pub fn next_id(&self) -> u64 {
+        self.next_id
+    }
+
+    /// 全部few-shot示例
+    pub fn examples(&self) -> Vec<Example> {
+        // 打开时已校验过JSON
+        let json = self.examples_json();
+        if json.is_empty() {
+            return Vec::new();
+        }
+        serde_json::from_str(json).unwrap_or_default()
+    }
+
+    fn examples_json(&self) -> &'a str {
+        self.str_at(read_u64(self.data, 32), read_u32(self.data, 40))
+    }
+
+    /// 全部错误模式记录
+    pub fn failures(&self) -> Vec<Failure> {
+        // 打开时已校验过JSON
+        let json = self.failures_json();
+        if json.is_empty() {
+            return Vec::new();
+        }
+        serde_json::from_str(json).unwrap_or_default()
+    }
+
+    fn failures_json(&self) -> &'a str {
+        self.str_at(read_u64(self.data, 44), read_u32(self.data, 52))
+    }
+
+    /// 已归档的子弹
+    pub fn archived(&self) -> Vec<Bullet> {
+        // 打开时已校验过JSON
+        let json = self.archived_json();
+        if json.is_empty() {
+            return Vec::new();
+        }
+        serde_json::from_str(json).unwrap_or_default()
+    }
+
+    fn archived_json(&self) -> &'a str {
+        self.str_at(read_u64(self.data, 56), read_u32(self.data, 64))
+    }
+
+    /// 所有章节名（已排序）
+    pub fn sections(&self) -> impl Iterator<Item = &'a str> + '_ {
+        (0..self.section_count).map(|i| self.section_name(i))
+    }
+
+    /// 按ID查找子弹（哈希索引，O(1)）
+    pub fn get_bullet(&self, bullet_id: &str) -> Option<BulletRef<'a>> {
+        self.find_record(bullet_id).map(|record| self.record(record))
+    }
+
+    fn find_record(&self, bullet_id: &str) -> Option<usize> {
+        let mask = self.index_slots - 1;
+        let mut slot = hash_id(bullet_id) as usize & mask;
+        loop {
+            match read_u32(self.data, self.index_offset(slot)) {
+                EMPTY_SLOT => return None,
+                record => {
+                    let record = record as usize;
+                    if self.record(record).id == bullet_id {
+                        return Some(record);
+                    }
+                }
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    /// 某章节内的子弹（插入顺序）
+    pub fn section_bullets(&self, section: &str) -> impl Iterator<Item = BulletRef<'a>> + '_ {
+        let range = self
+            .find_section(section)
+            .map(|i| self.section_range(i))
+            .unwrap_or(0..0);
+        range.map(|i| self.record(i))
+    }
+
+    /// 完整物化为可修改的Playbook（用 [`Playbook::bulk_load`] 一次建立索引）
+    pub fn to_playbook(&self) -> Result<Playbook, PlaybookError> {
+        self.materialize(0..self.section_count, |_| true)
+    }
+
+    /// 只加载指定章节（不存在的章节忽略），其余章节的记录不会被读取
+    pub fn load_sections(&self, sections: &[&str]) -> Result<Playbook, PlaybookError> {
+        self.materialize(
+            sections.iter().filter_map(|s| self.find_section(s)),
+            |section| sections.contains(&section),
+        )
+    }
+
+    fn materialize(
+        &self,
+        sections: impl Iterator<Item = usize>,
+        keep_section: impl Fn(&str) -> bool,
+    ) -> Result<Playbook, PlaybookError> {
+        let mut playbook = Playbook::new();
+        let records = sections.flat_map(|i| self.section_range(i));
+        playbook.bulk_load(records.map(|record| self.record(record).to_bullet()))?;
+        for mut example in self.examples().into_iter().filter(|e| keep_section(&e.section)) {
+            if let Some((name, _)) = playbook.sections.get_key_value(&*example.section) {
+                example.section = name.clone();
+            }
+            playbook.examples.insert(example.id.clone(), example);
+        }
+        for bullet in self.archived().into_iter().filter(|b| keep_section(&b.section)) {
+            playbook.archived.insert(bullet.id.clone(), bullet);
+        }
+        // 错误模式记录不属于任何章节，按章节加载时也全部带上
+        for failure in self.failures() {
+            playbook.failures.insert(failure.id.clone(), failure);
+        }
+        playbook.next_id = playbook.next_id.max(self.next_id);
+        Ok(playbook)
+    }
+
+    /// 与 [`Playbook::as_prompt`] 输出一致
+    pub fn as_prompt(&self) -> String {
+        self.render((0..self.section_count).collect())
+    }
+
+    /// 与 [`Playbook::as_prompt_for`] 输出一致
+    pub fn as_prompt_for(&self, sections: &[&str]) -> String {
+        let mut wanted: Vec<_> = sections.iter().filter_map(|s| self.find_section(s)).collect();
+        wanted.sort_unstable();
+        wanted.dedup();
+        self.render(wanted)
+    }
+
+    fn render(&self, sections: Vec<usize>) -> String {
+        let now = Utc::now();
+        let mut out = String::new();
+        for i in sections {
+            write_section_header(&mut out, self.section_name(i));
+            for record in self.section_range(i) {
+                let b = self.record(record);
+                if b.expires_at.is_some_and(|at| at <= now) {
+                    continue;
+                }
+                write_bullet_line(&mut out, b.id, b.content, [b.helpful, b.harmful, b.neutral]);
+            }
+        }
+        out
+    }
+
+    fn find_section(&self, section: &str) -> Option<usize> {
+        let (mut lo, mut hi) = (0, self.section_count);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.section_name(mid).cmp(section) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+
+    fn section_name(&self, i: usize) -> &'a str {
+        let at = self.section_offset(i);
+        self.str_at(read_u64(self.data, at), read_u32(self.data, at + 8))
+    }
+
+    fn section_range(&self, i: usize) -> std::ops::Range<usize> {
+        let at = self.section_offset(i);
+        let first = read_u32(self.data, at + 12) as usize;
+        first..first + read_u32(self.data, at + 16) as usize
+    }
+
+    fn record(&self, i: usize) -> BulletRef<'a> {
+        let at = self.record_offset(i);
+        let d = self.data;
+        BulletRef {
+            id: self.str_at(read_u64(d, at), read_u32(d, at + 8)),
+            content: self.str_at(read_u64(d, at + 12), read_u32(d, at + 20)),
+            section: self.section_name(read_u32(d, at + 24) as usize),
+            helpful: read_u32(d, at + 28),
+            harmful: read_u32(d, at + 32),
+            neutral: read_u32(d, at + 36),
+            created_at: self.timestamp(at + 40).unwrap_or_default(),
+            updated_at: self.timestamp(at + 52).unwrap_or_default(),
+            variants: self.str_at(read_u64(d, at + 64), read_u32(d, at + 72)),
+            last_used_at: self.timestamp(at + 76).filter(|_| read_u32(d, at + 84) != NEVER_USED),
+            pinned: read_u32(d, at + 88) & FLAG_PINNED != 0,
+            skill: self.str_at(read_u64(d, at + 92), read_u32(d, at + 100)),
+            lang: Some(self.str_at(read_u64(d, at + 104), read_u32(d, at + 112)))
+                .filter(|lang| !lang.is_empty()),
+            expires_at: self.timestamp(at + 116).filter(|_| read_u32(d, at + 124) != NO_EXPIRY),
+            conditions: self.str_at(read_u64(d, at + 128), read_u32(d, at + 136)),
+            custom: self.str_at(read_u64(d, at + 140), read_u32(d, at + 148)),
+        }
+    }
+
+    fn timestamp(&self, at: usize) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(read_u64(self.data, at) as i64, read_u32(self.data, at + 8))
+    }
+
+    fn str_at(&self, off: u64, len: u32) -> &'a str {
+        // 打开时已校验过范围与UTF-8
+        let start = self.heap_start() + off as usize;
+        std::str::from_utf8(&self.data[start..start + len as usize]).unwrap_or_default()
+    }
+
+    fn section_offset(&self, i: usize) -> usize {
+        HEADER_LEN + i * SECTION_LEN
+    }
+
+    fn record_offset(&self, i: usize) -> usize {
+        HEADER_LEN + self.section_count * SECTION_LEN + i * RECORD_LEN
+    }
+
+    fn index_offset(&self, i: usize) -> usize {
+        self.record_offset(self.bullet_count) + i * INDEX_LEN
+    }
+
+    fn heap_start(&self) -> usize {
+        self.index_offset(self.index_slots)
+    }
+}
+
+/// mmap打开的只读Playbook，适合只渲染不修改的推理服务
+///
+/// 文件被映射期间不应被原地改写；[`Playbook::save_indexed`] 通过临时文件+rename替换，
+/// 已打开的映射继续看到旧内容。
+#[derive(Debug)]
+pub struct MappedPlaybook {
+    map: Mmap,
+    section_count: usize,
+    bullet_count: usize,
+    index_slots: usize,
+    next_id: u64,
+}
+
+impl MappedPlaybook {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PlaybookError> {
+        let file = File::open(path.as_ref())?;
+        // SAFETY: 映射只读；文件由save_indexed原子替换而不是原地修改
+        let map = unsafe { Mmap::map(&file)? };
+        let view = IndexedView::open(&map)?;
+        let (section_count, bullet_count, index_slots, next_id) = (
+            view.section_count,
+            view.bullet_count,
+            view.index_slots,
+            view.next_id,
+        );
+        Ok(Self {
+            map,
+            section_count,
+            bullet_count,
+            index_slots,
+            next_id,
+        })
+    }
+
+    /// 底层视图（已在open时校验）
+    pub fn view(&self) -> IndexedView<'_> {
+        IndexedView {
+            data: &self.map,
+            section_count: self.section_count,
+            bullet_count: self.bullet_count,
+            index_slots: self.index_slots,
+            next_id: self.next_id,
+        }
+    }
+
+    pub fn get_bullet(&self, bullet_id: &str) -> Option<BulletRef<'_>> {
+        self.view().get_bullet(bullet_id)
+    }
+
+    pub fn as_prompt(&self) -> String {
+        self.view().as_prompt()
+    }
+
+    pub fn as_prompt_for(&self, sections: &[&str]) -> String {
+        self.view().as_prompt_for(sections)
+    }
+}
+
+/// 按章节延迟物化的Playbook：打开时只读取章节元数据（名称、子弹数），
+/// 某章节的子弹在第一次访问时才解码为 [`Bullet`] 并缓存
+///
+/// 适合只用到少数章节的服务：四十个章节只渲染其中两个时，其余章节的记录不会被解码。
+/// 只读；需要修改时用 [`LazyPlaybook::to_playbook`] 得到所访问章节的Playbook。
+#[derive(Debug)]
+pub struct LazyPlaybook {
+    mapped: MappedPlaybook,
+    /// 按章节下标（与文件中的章节顺序一致），未访问的为空
+    sections: Vec<OnceLock<Vec<Bullet>>>,
+}
+
+impl LazyPlaybook {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PlaybookError> {
+        let mapped = MappedPlaybook::open(path)?;
+        let sections = (0..mapped.section_count).map(|_| OnceLock::new()).collect();
+        Ok(Self { mapped, sections })
+    }
+
+    /// 所有章节名及其子弹数（已排序），不物化任何章节
+    pub fn sections(&self) -> impl Iterator<Item = (&str, usize)> + '_ {
+        let view = self.mapped.view();
+        (0..self.sections.len()).map(move |i| (view.section_name(i), view.section_range(i).len()))
+    }
+
+    /// 某章节的子弹（插入顺序），第一次访问时物化；章节不存在时为空
+    pub fn section(&self, section: &str) -> Option<&[Bullet]> {
+        let i = self.mapped.view().find_section(section)?;
+        Some(self.materialize(i))
+    }
+
+    /// 按ID查找子弹，物化其所在章节
+    pub fn get_bullet(&self, bullet_id: &str) -> Option<&Bullet> {
+        let view = self.mapped.view();
+        let record = view.find_record(bullet_id)?;
+        let section = read_u32(view.data, view.record_offset(record) + 24) as usize;
+        self.materialize(section).get(record - view.section_range(section).start)
+    }
+
+    pub fn is_materialized(&self, section: &str) -> bool {
+        let i = self.mapped.view().find_section(section);
+        i.is_some_and(|i| self.sections[i].get().is_some())
+    }
+
+    /// 与 [`Playbook::as_prompt_for`] 输出一致，只物化用到的章节
+    pub fn as_prompt_for(&self, sections: &[&str]) -> String {
+        let view = self.mapped.view();
+        let mut wanted: Vec<_> = sections.iter().filter_map(|s| view.find_section(s)).collect();
+        wanted.sort_unstable();
+        wanted.dedup();
+        let now = Utc::now();
+        let mut out = String::new();
+        for i in wanted {
+            write_section_header(&mut out, view.section_name(i));
+            for b in self.materialize(i).iter().filter(|b| !b.is_expired_at(now)) {
+                write_bullet_line(&mut out, &b.id, &b.content, [b.helpful, b.harmful, b.neutral]);
+            }
+        }
+        out
+    }
+
+    /// 已物化章节组成的Playbook（含这些章节的示例、归档子弹与全部错误模式记录）
+    pub fn to_playbook(&self) -> Result<Playbook, PlaybookError> {
+        let view = self.mapped.view();
+        let loaded: Vec<&str> = (0..self.sections.len())
+            .filter(|i| self.sections[*i].get().is_some())
+            .map(|i| view.section_name(i))
+            .collect();
+        view.load_sections(&loaded)
+    }
+
+    fn materialize(&self, i: usize) -> &[Bullet] {
+        self.sections[i].get_or_init(|| {
+            let view = self.mapped.view();
+            view.section_range(i).map(|record| view.record(record).to_bullet()).collect()
+        })
+    }
+}
+
+/// 写入同目录临时文件后rename，保证读者不会看到写了一半的文件
+pub(crate) fn save_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut io::BufWriter<File>) -> Result<(), PlaybookError>,
+) -> Result<(), PlaybookError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = Path::new(&tmp);
+    let mut writer = io::BufWriter::new(File::create(tmp)?);
+    write(&mut writer)?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// FNV-1a，跨平台稳定（不能用std的随机化哈希写入文件）
+fn hash_id(id: &str) -> u64 {
+    id.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn invalid(msg: &str) -> PlaybookError {
+    PlaybookError::InvalidData(format!("Indexed playbook: {}", msg))
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn sample() -> Playbook {
+        let mut pb = Playbook::new();
+        pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        pb.add_bullet("api usage", "重试要指数退避", None, None);
+        let id = pb.add_bullet("debugging", "先看日志", None, None);
+        pb.tag_bullet(&id, "helpful", 3).unwrap();
+        pb.set_variant(&id, "en", "Read the logs first").unwrap();
+        pb.set_pinned(&id, true).unwrap();
+        pb.set_bullet_lang(&id, Some("cmn".to_string())).unwrap();
+        pb.set_condition(&id, "env", "prod|staging").unwrap();
+        Arc::make_mut(pb.bullets.get_mut(&id).unwrap()).custom.insert("cited".into(), 2);
+        let skill = Skill {
+            tool: "grep".into(),
+            arguments: "pattern first, then path".into(),
+            pitfalls: vec!["quote the pattern".into()],
+        };
+        pb.set_skill(&id, Some(skill)).unwrap();
+        pb.add_example("debugging", "500 error", "tail app.log", Some(id.clone())).unwrap();
+        let archived = pb.add_bullet("api usage", "v0接口已下线", None, None);
+        pb.set_expires_at(&archived, Some(DateTime::UNIX_EPOCH)).unwrap();
+        pb.archive_expired_at(DateTime::UNIX_EPOCH);
+        let expired = pb.add_bullet("api usage", "v1接口用offset分页", None, None);
+        pb.set_expires_at(&expired, Some(DateTime::UNIX_EPOCH)).unwrap();
+        pb.set_expires_at(&id, Some(DateTime::<Utc>::MAX_UTC)).unwrap();
+        pb.mark_used(&[id]);
+        pb.add_failure("connection refused", "server not started", "start the server first");
+        pb
+    }
+
+    #[test]
+    fn test_view_matches_playbook() {
+        let pb = sample();
+        let mut bytes = Vec::new();
+        write_indexed(&pb, &mut bytes).unwrap();
+
+        let view = IndexedView::open(&bytes).unwrap();
+        assert_eq!(view.len(), 4);
+        assert_eq!(view.next_id(), pb.next_id);
+        assert_eq!(view.as_prompt(), pb.as_prompt());
+        assert_eq!(view.as_prompt_for(&["debugging"]), pb.as_prompt_for(&["debugging"]));
+
+        for bullet in pb.bullets.values() {
+            let found = view.get_bullet(&bullet.id).unwrap();
+            assert_eq!(found.content, bullet.content);
+            assert_eq!(found.section, &*bullet.section);
+            assert_eq!(found.helpful, bullet.helpful);
+            assert_eq!(found.updated_at, bullet.updated_at);
+            assert_eq!(found.last_used_at, bullet.last_used_at);
+            assert_eq!(found.pinned, bullet.pinned);
+            assert_eq!(found.skill(), bullet.skill);
+            assert_eq!(found.lang, bullet.lang.as_deref());
+            assert_eq!(found.expires_at, bullet.expires_at);
+            assert_eq!(found.conditions(), bullet.conditions);
+            assert_eq!(found.custom(), bullet.custom);
+        }
+        assert!(view.get_bullet("missing-00001").is_none());
+    }
+
+    #[test]
+    fn test_mapped_playbook_roundtrip() {
+        let pb = sample();
+        let path = std::env::temp_dir().join(format!("ace-indexed-{}.acepb", std::process::id()));
+        pb.save_indexed(&path).unwrap();
+
+        let mapped = MappedPlaybook::open(&path).unwrap();
+        assert_eq!(mapped.as_prompt(), pb.as_prompt());
+        assert_eq!(mapped.get_bullet("debugging-00003").unwrap().helpful, 3);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_lazy_playbook_materializes_on_access() {
+        let pb = sample();
+        let path = std::env::temp_dir().join(format!("ace-lazy-{}.acepb", std::process::id()));
+        pb.save_indexed(&path).unwrap();
+
+        let lazy = LazyPlaybook::open(&path).unwrap();
+        assert_eq!(lazy.sections().collect::<Vec<_>>(), [("api usage", 3), ("debugging", 1)]);
+        assert!(!lazy.is_materialized("api usage"));
+
+        assert_eq!(lazy.as_prompt_for(&["debugging"]), pb.as_prompt_for(&["debugging"]));
+        assert!(lazy.is_materialized("debugging"));
+        assert!(!lazy.is_materialized("api usage"));
+        assert_eq!(lazy.get_bullet("debugging-00003").unwrap().helpful, 3);
+        assert!(lazy.section("missing").is_none());
+
+        let expired = &lazy.section("api usage").unwrap()[2];
+        assert_eq!(lazy.get_bullet(&expired.id).unwrap().content, "v1接口用offset分页");
+        assert!(lazy.get_bullet("missing-00001").is_none());
+
+        let loaded = lazy.to_playbook().unwrap();
+        assert_eq!(loaded.as_prompt(), pb.as_prompt());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_corrupted_data() {
+        let mut bytes = Vec::new();
+        write_indexed(&sample(), &mut bytes).unwrap();
+
+        assert!(IndexedView::open(&bytes[..bytes.len() - 1]).is_err());
+        assert!(IndexedView::open(b"not a playbook at all, definitely").is_err());
+    }
+
+    #[test]
+    fn test_partial_section_load() {
+        let pb = sample();
+        let mut bytes = Vec::new();
+        write_indexed(&pb, &mut bytes).unwrap();
+        let view = IndexedView::open(&bytes).unwrap();
+
+        let partial = view.load_sections(&["api usage", "missing"]).unwrap();
+        assert_eq!(partial.bullets.len(), 3);
+        assert_eq!(partial.sections.len(), 1);
+        assert_eq!(partial.next_id, pb.next_id);
+        assert_eq!(partial.as_prompt(), pb.as_prompt_for(&["api usage"]));
+        assert!(partial.examples.is_empty());
+        assert_eq!(partial.failures, pb.failures);
+
+        let full = view.to_playbook().unwrap();
+        assert_eq!(full.as_prompt(), pb.as_prompt());
+        assert_eq!(full.as_prompt_in("en"), pb.as_prompt_in("en"));
+        assert_eq!(full.examples, pb.examples);
+        assert_eq!(
+            full.archived.keys().collect::<Vec<_>>(),
+            pb.archived.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(view.get_bullet("api-00001").unwrap().variants, "");
+    }
+
+    #[test]
+    fn test_empty_playbook() {
+        let mut bytes = Vec::new();
+        write_indexed(&Playbook::new(), &mut bytes).unwrap();
+        let view = IndexedView::open(&bytes).unwrap();
+        assert!(view.is_empty());
+        assert!(view.get_bullet("x").is_none());
+    }
+}
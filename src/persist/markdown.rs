@@ -0,0 +1,149 @@
+//! 导出为Markdown目录（每个章节一个文件，另有索引页），可放进文档仓库或Obsidian库，
+//! 像普通文档一样审阅学到的策略
+//!
+//! 每个文件以YAML front-matter开头，记录章节名、子弹ID与计数；正文每个子弹一个二级标题。
+//! 重复导出会覆盖同名文件，已不存在的章节的旧文件不会删除。
+
+use std::{
+    collections::BTreeSet,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::models::playbook::{Bullet, Playbook, PlaybookError};
+
+/// 索引页文件名
+pub const INDEX_FILE: &str = "index.md";
+
+impl Playbook {
+    /// 导出到目录（不存在时创建），返回写入的文件（索引页在最后）
+    pub fn export_markdown_tree(
+        &self,
+        dir: impl AsRef<Path>,
+    ) -> Result<Vec<PathBuf>, PlaybookError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let mut used = BTreeSet::from(["index".to_string()]);
+        let mut written = Vec::new();
+        let mut index = String::from("---\n");
+        let [helpful, harmful, neutral] = self.bullets.values().fold([0u64; 3], |acc, b| {
+            [acc[0] + b.helpful as u64, acc[1] + b.harmful as u64, acc[2] + b.neutral as u64]
+        });
+        let _ = write!(
+            index,
+            "sections: {}\nbullets: {}\nhelpful: {}\nharmful: {}\nneutral: {}\n---\n\n# Playbook\n\n",
+            self.sections.len(),
+            self.bullets.len(),
+            helpful,
+            harmful,
+            neutral
+        );
+
+        for (section, ids) in self.sorted_sections() {
+            let bullets: Vec<&Bullet> = ids.iter().filter_map(|id| self.get_bullet(id)).collect();
+            let stem = unique_stem(&mut used, section);
+            let path = dir.join(format!("{}.md", stem));
+            fs::write(&path, render_section(section, &bullets))?;
+            written.push(path);
+            let _ = writeln!(index, "- [{}]({}.md) ({})", section, stem, bullets.len());
+        }
+
+        let path = dir.join(INDEX_FILE);
+        fs::write(&path, index)?;
+        written.push(path);
+        Ok(written)
+    }
+}
+
+fn render_section(section: &str, bullets: &[&Bullet]) -> String {
+    let sum = |f: fn(&Bullet) -> u32| bullets.iter().map(|b| f(b) as u64).sum::<u64>();
+    let mut out = String::from("---\n");
+    let _ = writeln!(out, "section: {}", yaml_str(section));
+    let _ = writeln!(
+        out,
+        "helpful: {}\nharmful: {}\nneutral: {}",
+        sum(|b| b.helpful),
+        sum(|b| b.harmful),
+        sum(|b| b.neutral)
+    );
+    out.push_str(if bullets.is_empty() { "bullets: []\n" } else { "bullets:\n" });
+    for b in bullets {
+        let _ = writeln!(
+            out,
+            "  - id: {}\n    helpful: {}\n    harmful: {}\n    neutral: {}",
+            yaml_str(&b.id),
+            b.helpful,
+            b.harmful,
+            b.neutral
+        );
+    }
+    let _ = write!(out, "---\n\n# {}\n", section);
+    for b in bullets {
+        let _ = write!(out, "\n## {}\n\n{}\n", b.id, b.content.trim_end());
+    }
+    out
+}
+
+/// 用JSON字符串作为YAML标量，免去YAML自己的转义规则
+fn yaml_str(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_default()
+}
+
+/// 章节名转文件名：保留字母数字（含中文），其余字符折叠为`-`；重名时追加序号
+fn unique_stem(used: &mut BTreeSet<String>, section: &str) -> String {
+    let mut base = String::new();
+    for c in section.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            base.push(c);
+        } else if !base.is_empty() && !base.ends_with('-') {
+            base.push('-');
+        }
+    }
+    let base = match base.trim_end_matches('-') {
+        "" => "section".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    let mut stem = base.clone();
+    let mut n = 2;
+    while !used.insert(stem.clone()) {
+        stem = format!("{}-{}", base, n);
+        n += 1;
+    }
+    stem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_markdown_tree() {
+        let dir = std::env::temp_dir().join(format!("ace-markdown-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("API usage", "分页时带上cursor", None, None);
+        pb.add_bullet("api/usage", "重试时用指数退避", None, None);
+        pb.add_bullet("调试", "先看日志\n\n再加断点", None, None);
+        pb.add_bullet("index", "\"quoted\" content", None, None);
+        pb.tag_bullet(&a, "helpful", 3).unwrap();
+
+        let files = pb.export_markdown_tree(&dir).unwrap();
+        let names: Vec<&str> =
+            files.iter().map(|p| p.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, ["api-usage.md", "api-usage-2.md", "index-2.md", "调试.md", "index.md"]);
+
+        let section = fs::read_to_string(&files[0]).unwrap();
+        assert!(section.starts_with("---\nsection: \"API usage\"\nhelpful: 3\n"));
+        assert!(section.contains(&format!("  - id: \"{}\"\n    helpful: 3\n", a)));
+        assert!(section.contains(&format!("---\n\n# API usage\n\n## {}\n\n分页时带上cursor\n", a)));
+        assert!(fs::read_to_string(&files[3]).unwrap().contains("先看日志\n\n再加断点\n"));
+
+        let index = fs::read_to_string(&files[4]).unwrap();
+        assert!(index.starts_with("---\nsections: 4\nbullets: 4\nhelpful: 3\n"));
+        assert!(index.contains("- [api/usage](api-usage-2.md) (1)\n"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
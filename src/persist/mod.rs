@@ -0,0 +1,12 @@
+//! Playbook的持久化（文件读写与JSON之外的存储格式），需要 `persist` feature
+
+pub mod delta_log;
+pub mod embeddings;
+pub mod episodes;
+mod file;
+pub mod fts;
+pub mod indexed;
+pub mod markdown;
+pub mod review;
+pub mod stats;
+pub mod workspace;
@@ -0,0 +1,59 @@
+//! 审核队列文件（JSON，整体原子写入），供审核接口与命令行共享
+
+use std::path::Path;
+
+use crate::models::playbook::PlaybookError;
+use crate::models::review::ReviewQueue;
+use crate::persist::indexed;
+
+impl ReviewQueue {
+    /// 原子写入（临时文件+rename）
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PlaybookError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        indexed::save_atomically(path, |writer| Ok(serde_json::to_writer(writer, self)?))
+    }
+
+    /// 加载；文件不存在时返回空队列
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PlaybookError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        let data = std::fs::read(path)?;
+        serde_json::from_slice(&data).map_err(|err| {
+            PlaybookError::InvalidData(format!("{}: review queue {}", path.display(), err))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::models::delta::{DeltaBatch, DeltaOperation};
+    use crate::models::playbook::Playbook;
+
+    #[test]
+    fn test_review_queue_roundtrip() {
+        let path = std::env::temp_dir().join(format!("ace-review-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(ReviewQueue::load(&path).unwrap().items.is_empty());
+
+        let pb = Playbook::new();
+        let mut queue = ReviewQueue::new();
+        let delta = DeltaBatch {
+            reasoning: "learned".into(),
+            operations: vec![DeltaOperation::add("tips", "Retry on 503")],
+        };
+        let first = queue.submit(delta.clone(), "rate limit", &pb);
+        queue.save(&path).unwrap();
+
+        let mut loaded = ReviewQueue::load(&path).unwrap();
+        assert_eq!(loaded.get(first).unwrap().reason, "rate limit");
+        assert_eq!(loaded.submit(delta, "", &pb), first + 1);
+        std::fs::remove_file(path).unwrap();
+    }
+}
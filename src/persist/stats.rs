@@ -0,0 +1,70 @@
+//! 统计快照的历史文件（JSONL，每行一个 [`StatsSnapshot`]，只追加）
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+use crate::models::playbook::PlaybookError;
+use crate::models::stats::StatsSnapshot;
+
+/// 追加一条快照（文件与父目录不存在时创建）
+pub fn append_stats(path: impl AsRef<Path>, snapshot: &StatsSnapshot) -> Result<(), PlaybookError> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_vec(snapshot)?;
+    line.push(b'\n');
+    // 单次write_all写入整行，并发追加时行不会交错
+    OpenOptions::new().create(true).append(true).open(path)?.write_all(&line)?;
+    Ok(())
+}
+
+/// 按写入顺序加载全部快照；文件不存在时返回空序列，空行跳过
+pub fn load_stats(path: impl AsRef<Path>) -> Result<Vec<StatsSnapshot>, PlaybookError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut series = Vec::new();
+    for (i, line) in BufReader::new(fs::File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let snapshot = serde_json::from_str(&line).map_err(|err| {
+            PlaybookError::InvalidData(format!("{} line {}: {}", path.display(), i + 1, err))
+        })?;
+        series.push(snapshot);
+    }
+    Ok(series)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::playbook::Playbook;
+
+    #[test]
+    fn test_append_and_load_series() {
+        let path = std::env::temp_dir().join(format!("ace-stats-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(load_stats(&path).unwrap().is_empty());
+
+        let mut pb = Playbook::new();
+        for epoch in 0..3 {
+            pb.add_bullet("api usage", "分页时带上cursor", None, None);
+            append_stats(&path, &pb.snapshot_stats().with_label(format!("epoch-{}", epoch))).unwrap();
+        }
+        let series = load_stats(&path).unwrap();
+        let sizes: Vec<usize> = series.iter().map(|s| s.bullets).collect();
+        assert_eq!(sizes, [1, 2, 3]);
+        assert_eq!(series[2].label.as_deref(), Some("epoch-2"));
+
+        fs::write(&path, "{\"bad\": 1}\n").unwrap();
+        assert!(matches!(load_stats(&path), Err(PlaybookError::InvalidData(_))));
+        fs::remove_file(path).unwrap();
+    }
+}
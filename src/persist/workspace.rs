@@ -0,0 +1,244 @@
+//! 工作区：管理一个目录下的多个Playbook（每个代理一个），提供跨Playbook检索和共享服务
+//!
+//! 目录中的`<name>.json`与`<name>.acepb`（索引格式）都会被发现，同名时以JSON为准；
+//! 保存统一写为`<name>.json`。
+
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::models::playbook::{BulletId, Playbook, PlaybookError};
+use crate::models::registry::PlaybookRegistry;
+
+const JSON_EXT: &str = "json";
+const INDEXED_EXT: &str = "acepb";
+
+/// 检索命中
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub playbook: String,
+    pub bullet_id: BulletId,
+    /// 命中的查询词占比 (0, 1]
+    pub score: f64,
+    /// 所在Playbook的评分给出的子弹分数，命中率相同时分数高的在前
+    pub bullet_score: f64,
+}
+
+#[derive(Debug)]
+pub struct AceWorkspace {
+    root: PathBuf,
+    registry: PlaybookRegistry,
+    services: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl AceWorkspace {
+    /// 打开目录（不存在时创建）并加载其中所有Playbook
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, PlaybookError> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+
+        let mut found: BTreeMap<String, PathBuf> = BTreeMap::new();
+        for entry in fs::read_dir(&root)? {
+            let path = entry?.path();
+            let (Some(name), Some(ext)) = (
+                path.file_stem().and_then(|s| s.to_str()),
+                path.extension().and_then(|s| s.to_str()),
+            ) else {
+                continue;
+            };
+            let replace = match found.get(name) {
+                None => ext == JSON_EXT || ext == INDEXED_EXT,
+                Some(_) => ext == JSON_EXT,
+            };
+            if replace {
+                found.insert(name.to_string(), path);
+            }
+        }
+
+        let mut registry = PlaybookRegistry::new();
+        for (name, path) in found {
+            registry.register(name, load(&path)?);
+        }
+        Ok(Self {
+            root,
+            registry,
+            services: HashMap::new(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 全部Playbook（可用于跨Playbook引用渲染）
+    pub fn registry(&self) -> &PlaybookRegistry {
+        &self.registry
+    }
+
+    pub fn registry_mut(&mut self) -> &mut PlaybookRegistry {
+        &mut self.registry
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Playbook> {
+        self.registry.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Playbook> {
+        self.registry.get_mut(name)
+    }
+
+    /// 新建空Playbook（已存在时返回已有的）
+    pub fn create(&mut self, name: &str) -> &mut Playbook {
+        if self.registry.get(name).is_none() {
+            self.registry.register(name, Playbook::new());
+        }
+        self.registry.get_mut(name).expect("registered above")
+    }
+
+    /// 从工作区移除并删除其文件
+    pub fn remove(&mut self, name: &str) -> Result<Option<Playbook>, PlaybookError> {
+        for ext in [JSON_EXT, INDEXED_EXT] {
+            let path = self.path_of(name, ext);
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(self.registry.unregister(name))
+    }
+
+    pub fn save(&self, name: &str) -> Result<(), PlaybookError> {
+        let playbook = self
+            .registry
+            .get(name)
+            .ok_or_else(|| PlaybookError::InvalidData(format!("no playbook named {}", name)))?;
+        playbook.save_to_file(self.path_of(name, JSON_EXT))
+    }
+
+    pub fn save_all(&self) -> Result<(), PlaybookError> {
+        self.registry.names().try_for_each(|name| self.save(name))
+    }
+
+    /// 跨所有Playbook检索：按命中查询词的比例排序（忽略大小写），其次按各Playbook
+    /// 评分给出的子弹分数，再按名称和ID排序
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let mut hits: Vec<SearchHit> = self
+            .registry
+            .iter()
+            .flat_map(|(name, playbook)| {
+                playbook.bullets.values().filter_map(|bullet| {
+                    let content = bullet.content.to_lowercase();
+                    let matched = terms.iter().filter(|t| content.contains(t.as_str())).count();
+                    (matched > 0).then(|| SearchHit {
+                        playbook: name.to_string(),
+                        bullet_id: bullet.id.clone(),
+                        score: matched as f64 / terms.len() as f64,
+                        bullet_score: playbook.score(bullet),
+                    })
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.bullet_score.total_cmp(&a.bullet_score))
+                .then_with(|| a.playbook.cmp(&b.playbook))
+                .then_with(|| a.bullet_id.cmp(&b.bullet_id))
+        });
+        hits.truncate(limit);
+        hits
+    }
+
+    // --------------------------
+    // 共享服务（embedder、缓存等），按类型存取，所有代理共用一份
+    // --------------------------
+
+    pub fn insert_service<T: Any + Send + Sync>(&mut self, service: Arc<T>) -> Option<Arc<T>> {
+        self.services
+            .insert(TypeId::of::<T>(), service)
+            .and_then(|old| old.downcast().ok())
+    }
+
+    pub fn service<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.services.get(&TypeId::of::<T>())?.clone().downcast().ok()
+    }
+
+    fn path_of(&self, name: &str, ext: &str) -> PathBuf {
+        self.root.join(format!("{}.{}", name, ext))
+    }
+}
+
+fn load(path: &Path) -> Result<Playbook, PlaybookError> {
+    let result = match path.extension().and_then(|s| s.to_str()) {
+        Some(INDEXED_EXT) => Playbook::load_indexed(path),
+        _ => Playbook::load_from_file(path),
+    };
+    result.map_err(|err| match err {
+        PlaybookError::IoError(err) => PlaybookError::IoError(err),
+        other => PlaybookError::InvalidData(format!("{}: {}", path.display(), other)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ace-workspace-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_discover_save_and_search() {
+        let dir = temp_dir("discover");
+        let mut support = Playbook::new();
+        support.add_bullet("support", "Escalate refund requests over $500", None, None);
+        support.save_to_file(dir.join("support.json")).unwrap();
+        let mut coding = Playbook::new();
+        coding.add_bullet("api usage", "Retry refund API calls with backoff", None, None);
+        coding.save_indexed(dir.join("coding.acepb")).unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let mut ws = AceWorkspace::open(&dir).unwrap();
+        assert_eq!(ws.registry().names().collect::<Vec<_>>(), ["coding", "support"]);
+
+        let hits = ws.search("refund backoff", 10);
+        assert_eq!(hits[0].playbook, "coding");
+        assert_eq!(hits[0].score, 1.0);
+        assert_eq!(hits[1].bullet_id, "support-00001");
+        assert!(ws.search("  ", 10).is_empty());
+
+        ws.create("research").add_bullet("sources", "Cite primary sources", None, None);
+        ws.save_all().unwrap();
+        let reopened = AceWorkspace::open(&dir).unwrap();
+        assert_eq!(reopened.registry().len(), 3);
+        assert_eq!(reopened.get("coding").unwrap().bullets.len(), 1);
+
+        let mut ws = reopened;
+        assert!(ws.remove("research").unwrap().is_some());
+        assert!(!dir.join("research.json").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_shared_services() {
+        #[derive(Debug, PartialEq)]
+        struct Cache(usize);
+
+        let dir = temp_dir("services");
+        let mut ws = AceWorkspace::open(&dir).unwrap();
+        assert!(ws.service::<Cache>().is_none());
+        assert!(ws.insert_service(Arc::new(Cache(1))).is_none());
+        assert_eq!(ws.insert_service(Arc::new(Cache(2))).unwrap().0, 1);
+        assert_eq!(*ws.service::<Cache>().unwrap(), Cache(2));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+//! 长时间操作（去重、大批量导入、日志重放……）的进度报告：操作每处理一项就把
+//! [`Progress`]（当前阶段、已完成/总数、预计剩余时间）交给 [`ProgressSink`]
+//!
+//! 命令行用 [`Progress::bar`] 画进度条；Web仪表盘可把`Progress`序列化为JSON推送，
+//! 或用`mpsc::Sender<Progress>`作为sink在另一个线程中消费。
+
+use alloc::{format, string::String};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::SharedClock;
+use crate::models::timestamp;
+
+/// 某一时刻的进度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    pub phase: String,
+    pub done: usize,
+    /// 总数未知时为空
+    pub total: Option<usize>,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub started_at: DateTime<Utc>,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Progress {
+    /// 完成比例，总数未知时为空；总数为0时为1
+    pub fn fraction(&self) -> Option<f64> {
+        self.total.map(|total| if total == 0 { 1.0 } else { self.done as f64 / total as f64 })
+    }
+
+    /// 按当前阶段的平均速度估算剩余时间，尚无完成项或总数未知时为空
+    pub fn eta(&self) -> Option<TimeDelta> {
+        let total = self.total?;
+        if self.done == 0 {
+            return None;
+        }
+        let elapsed = (self.updated_at - self.started_at).num_milliseconds().max(0);
+        let remaining = total.saturating_sub(self.done) as i64;
+        Some(TimeDelta::milliseconds(elapsed * remaining / self.done as i64))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.total.is_some_and(|total| self.done >= total)
+    }
+
+    /// 单行文本进度条，如`dedup [#####-----] 50/100 ETA 12s`
+    pub fn bar(&self, width: usize) -> String {
+        let Some(fraction) = self.fraction() else {
+            return format!("{} {}", self.phase, self.done);
+        };
+        // f64::round需要std；值非负，加0.5截断即四舍五入
+        let filled = ((fraction.clamp(0.0, 1.0) * width as f64 + 0.5) as usize).min(width);
+        let mut line = format!(
+            "{} [{}{}] {}/{}",
+            self.phase,
+            "#".repeat(filled),
+            "-".repeat(width - filled),
+            self.done,
+            self.total.unwrap_or_default()
+        );
+        if let Some(eta) = self.eta().filter(|_| !self.is_finished()) {
+            line.push_str(&format!(" ETA {}s", eta.num_seconds()));
+        }
+        line
+    }
+}
+
+/// 进度的接收方
+pub trait ProgressSink {
+    fn report(&mut self, progress: &Progress);
+
+    /// 为false时不读时钟也不报告，大批量操作不必为无人接收的进度付出开销
+    fn is_listening(&self) -> bool {
+        true
+    }
+}
+
+/// 不关心进度
+impl ProgressSink for () {
+    fn report(&mut self, _: &Progress) {}
+
+    fn is_listening(&self) -> bool {
+        false
+    }
+}
+
+impl<F: FnMut(&Progress)> ProgressSink for F {
+    fn report(&mut self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ProgressSink for std::sync::mpsc::Sender<Progress> {
+    fn report(&mut self, progress: &Progress) {
+        // 接收方已退出时忽略，进度不影响操作本身
+        let _ = self.send(progress.clone());
+    }
+}
+
+/// 操作内部使用：维护当前阶段的计数并通知sink
+pub struct ProgressTracker<'a> {
+    sink: &'a mut dyn ProgressSink,
+    clock: SharedClock,
+    progress: Progress,
+}
+
+impl<'a> ProgressTracker<'a> {
+    /// 开始第一个阶段并立即报告一次
+    pub fn new(
+        sink: &'a mut dyn ProgressSink,
+        clock: SharedClock,
+        phase: impl Into<String>,
+        total: Option<usize>,
+    ) -> Self {
+        let now = clock.now();
+        let progress =
+            Progress { phase: phase.into(), done: 0, total, started_at: now, updated_at: now };
+        sink.report(&progress);
+        Self { sink, clock, progress }
+    }
+
+    /// 切换到新阶段，计数与ETA重新开始
+    pub fn phase(&mut self, phase: impl Into<String>, total: Option<usize>) {
+        let now = self.clock.now();
+        self.progress =
+            Progress { phase: phase.into(), done: 0, total, started_at: now, updated_at: now };
+        self.sink.report(&self.progress);
+    }
+
+    pub fn advance(&mut self, items: usize) {
+        self.progress.done += items;
+        if !self.sink.is_listening() {
+            return;
+        }
+        self.progress.updated_at = self.clock.now();
+        self.sink.report(&self.progress);
+    }
+
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{sync::Arc, vec::Vec};
+
+    use crate::clock::ManualClock;
+    use crate::models::playbook::Playbook;
+
+    #[test]
+    fn test_tracker_reports_eta_and_bar() {
+        let clock = Arc::new(ManualClock::new(DateTime::UNIX_EPOCH));
+        let mut seen: Vec<Progress> = Vec::new();
+        let mut sink = |p: &Progress| seen.push(p.clone());
+        let mut tracker = ProgressTracker::new(&mut sink, clock.clone(), "import", Some(4));
+        clock.advance(TimeDelta::seconds(2));
+        tracker.advance(1);
+        assert_eq!(tracker.progress().eta(), Some(TimeDelta::seconds(6)));
+        assert_eq!(tracker.progress().bar(8), "import [##------] 1/4 ETA 6s");
+        tracker.advance(3);
+        assert_eq!(tracker.progress().bar(4), "import [####] 4/4");
+        tracker.phase("index", None);
+        tracker.advance(7);
+        assert_eq!(tracker.progress().bar(4), "index 7");
+
+        assert_eq!(seen.len(), 5);
+        assert_eq!(seen[0].fraction(), Some(0.0));
+        assert!(seen[2].is_finished());
+        let json = serde_json::to_string(&seen[1]).unwrap();
+        assert_eq!(serde_json::from_str::<Progress>(&json).unwrap(), seen[1]);
+
+        let mut pb = Playbook::with_clock(clock.clone());
+        let mut phases: Vec<(String, usize)> = Vec::new();
+        let mut sink = |p: &Progress| phases.push((p.phase.clone(), p.done));
+        pb.import_text_with_progress("notes", "- 先看日志\n\n- 先看日志\n- 再重试", &mut sink);
+        pb.dedup_delta_with_progress(0.9, &mut sink);
+        assert_eq!(phases.iter().filter(|(phase, _)| phase == "import").count(), 4);
+        assert_eq!(phases.last().unwrap(), &("dedup".to_string(), 3));
+
+        #[cfg(feature = "std")]
+        {
+            let (mut tx, rx) = std::sync::mpsc::channel();
+            ProgressTracker::new(&mut tx, clock, "dedup", Some(0)).advance(0);
+            assert_eq!(rx.try_iter().count(), 2);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! 预发/生产双槽位：在线适应只写staging，经人工或评估把关后再`promote()`到production
+//!
+//! 每次晋升前自动给production拍快照，`demote()`回滚到上一个快照。
+
+use alloc::vec::Vec;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::playbook::Playbook;
+
+#[derive(Debug, Error)]
+pub enum PromotionError {
+    #[error("没有可回滚的生产快照")]
+    NoSnapshot,
+}
+
+/// production在某次晋升前的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// 快照时production的版本号
+    pub version: u64,
+    pub taken_at: DateTime<Utc>,
+    pub playbook: Playbook,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlaybookSlots {
+    staging: Playbook,
+    production: Playbook,
+    version: u64,
+    snapshots: Vec<Snapshot>,
+    max_snapshots: usize,
+}
+
+impl PlaybookSlots {
+    /// 默认保留的快照数
+    pub const DEFAULT_MAX_SNAPSHOTS: usize = 10;
+
+    /// 以现有Playbook作为production（版本0），staging从它复制
+    pub fn new(production: Playbook) -> Self {
+        Self {
+            staging: production.clone(),
+            production,
+            version: 0,
+            snapshots: Vec::new(),
+            max_snapshots: Self::DEFAULT_MAX_SNAPSHOTS,
+        }
+    }
+
+    /// 设置保留的快照数，超出时丢弃最旧的（至少保留1个）
+    pub fn with_max_snapshots(mut self, max: usize) -> Self {
+        self.max_snapshots = max.max(1);
+        self.trim_snapshots();
+        self
+    }
+
+    pub fn staging(&self) -> &Playbook {
+        &self.staging
+    }
+
+    /// 在线适应写入的槽位
+    pub fn staging_mut(&mut self) -> &mut Playbook {
+        &mut self.staging
+    }
+
+    pub fn production(&self) -> &Playbook {
+        &self.production
+    }
+
+    /// production的版本号，每次晋升加1，回滚时恢复为快照的版本号
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// 从旧到新排列
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// 把staging晋升为production，返回新的版本号
+    pub fn promote(&mut self) -> u64 {
+        let snapshot = Snapshot {
+            version: self.version,
+            taken_at: self.production.clock().now(),
+            playbook: core::mem::replace(&mut self.production, self.staging.clone()),
+        };
+        self.snapshots.push(snapshot);
+        self.trim_snapshots();
+        self.version += 1;
+        self.version
+    }
+
+    /// 仅当`gate(staging, production)`通过时晋升（例如评估分数不低于线上版本）
+    pub fn promote_if(&mut self, gate: impl FnOnce(&Playbook, &Playbook) -> bool) -> Option<u64> {
+        gate(&self.staging, &self.production).then(|| self.promote())
+    }
+
+    /// 把production回滚到最近的快照，返回回滚后的版本号；staging不受影响
+    pub fn demote(&mut self) -> Result<u64, PromotionError> {
+        let snapshot = self.snapshots.pop().ok_or(PromotionError::NoSnapshot)?;
+        self.production = snapshot.playbook;
+        self.version = snapshot.version;
+        Ok(self.version)
+    }
+
+    /// 丢弃staging上未晋升的改动
+    pub fn reset_staging(&mut self) {
+        self.staging = self.production.clone();
+    }
+
+    fn trim_snapshots(&mut self) {
+        let excess = self.snapshots.len().saturating_sub(self.max_snapshots);
+        self.snapshots.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promote_and_demote() {
+        let mut slots = PlaybookSlots::new(Playbook::new());
+        let id = slots.staging_mut().add_bullet("api usage", "分页时带上cursor", None, None);
+        assert!(slots.production().get_bullet(&id).is_none());
+
+        assert_eq!(slots.promote_if(|staging, _| staging.bullets().len() > 5), None);
+        assert_eq!(slots.promote(), 1);
+        assert!(slots.production().get_bullet(&id).is_some());
+        assert_eq!(slots.snapshots()[0].version, 0);
+
+        slots.staging_mut().remove_bullet(&id);
+        assert_eq!(slots.promote_if(|_, _| true), Some(2));
+        assert!(slots.production().get_bullet(&id).is_none());
+
+        assert_eq!(slots.demote().unwrap(), 1);
+        assert!(slots.production().get_bullet(&id).is_some());
+        assert!(slots.staging().get_bullet(&id).is_none());
+        assert_eq!(slots.demote().unwrap(), 0);
+        assert!(matches!(slots.demote(), Err(PromotionError::NoSnapshot)));
+
+        slots.reset_staging();
+        assert!(slots.staging().bullets().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_retention() {
+        let mut slots = PlaybookSlots::new(Playbook::new()).with_max_snapshots(2);
+        for _ in 0..5 {
+            slots.promote();
+        }
+        let versions: Vec<u64> = slots.snapshots().iter().map(|s| s.version).collect();
+        assert_eq!(versions, [3, 4]);
+    }
+}
@@ -0,0 +1,271 @@
+//! reflector与curator的提示词模板：内置模板编译进二进制，用户可以替换为自己的版本
+//! （本地化、调整措辞），加载时检查必需的占位符，而不是等到运行中才发现模板缺了Playbook
+//!
+//! 占位符写作`{name}`，只识别 [`PLACEHOLDERS`] 中的名字，拼错的名字（如`{playbok}`）会报错；
+//! 其他花括号（如JSON示例）原样保留，需要字面的`{playbook}`时写作`{{playbook}}`。
+//! 渲染是单遍替换，填入的Playbook或轨迹中即使含有占位符也不会再被替换。
+
+use alloc::string::{String, ToString};
+
+use thiserror::Error;
+
+/// 可用的占位符，每个模板都必须包含全部
+pub const PLACEHOLDERS: [&str; 3] = ["playbook", "trajectory", "schema"];
+
+/// reflector输出的格式说明，填入`{schema}`
+pub const REFLECTOR_SCHEMA: &str = "{\"insights\": [{\"content\": \"<lesson>\", \
+    \"section\": \"<section, optional>\", \"confidence\": <0..1, optional>}]}";
+
+/// curator输出的格式说明（DeltaBatch），填入`{schema}`
+pub const CURATOR_SCHEMA: &str = "{\"reasoning\": \"...\", \"operations\": [{\"type\": \
+    \"ADD|UPDATE|TAG|REMOVE|MOVE|MERGE\", \"section\": \"<section; target for MOVE>\", \
+    \"content\": \"<ADD/UPDATE; optional for MERGE>\", \
+    \"bullet_id\": \"<UPDATE/TAG/REMOVE/MOVE; kept bullet for MERGE>\", \
+    \"merged_ids\": [\"<MERGE: bullets folded into bullet_id>\"], \
+    \"merge_strategy\": \"<MERGE without content: keep|concatenate>\", \
+    \"metadata\": {\"helpful\": 1}}]}";
+
+#[derive(Debug, Error)]
+pub enum PromptError {
+    #[error("{role}模板缺少占位符{{{placeholder}}}")]
+    MissingPlaceholder { role: PromptRole, placeholder: &'static str },
+    #[error("{role}模板中有未知的占位符{{{placeholder}}}")]
+    UnknownPlaceholder { role: PromptRole, placeholder: String },
+    #[cfg(feature = "std")]
+    #[error("IO错误：{0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptRole {
+    Reflector,
+    Curator,
+}
+
+impl PromptRole {
+    /// 模板目录中的文件名（见 [`PromptSet::load_dir`]）
+    pub fn file_name(self) -> &'static str {
+        match self {
+            PromptRole::Reflector => "reflector.txt",
+            PromptRole::Curator => "curator.txt",
+        }
+    }
+
+    pub fn schema(self) -> &'static str {
+        match self {
+            PromptRole::Reflector => REFLECTOR_SCHEMA,
+            PromptRole::Curator => CURATOR_SCHEMA,
+        }
+    }
+
+    fn builtin(self) -> &'static str {
+        match self {
+            PromptRole::Reflector => include_str!("prompts/reflector.txt"),
+            PromptRole::Curator => include_str!("prompts/curator.txt"),
+        }
+    }
+}
+
+impl core::fmt::Display for PromptRole {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PromptRole::Reflector => write!(f, "reflector"),
+            PromptRole::Curator => write!(f, "curator"),
+        }
+    }
+}
+
+/// 检查过占位符的模板
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    role: PromptRole,
+    text: String,
+}
+
+/// 模板中的一段：字面文本或占位符
+enum Piece<'a> {
+    Text(&'a str),
+    Slot(&'a str),
+}
+
+/// 逐段扫描模板；`{{`与`}}`是转义，`{`后跟标识符与`}`才是占位符
+fn scan(text: &str, mut each: impl FnMut(Piece<'_>)) {
+    let mut rest = text;
+    while let Some(i) = rest.find(['{', '}']) {
+        each(Piece::Text(&rest[..i]));
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            each(Piece::Text(&tail[..1]));
+            rest = &tail[2..];
+            continue;
+        }
+        let name = tail[1..].split('}').next().filter(|_| tail.starts_with('{'));
+        match name {
+            Some(name)
+                if !name.is_empty()
+                    && tail.len() > name.len() + 1
+                    && name.bytes().all(|b| b.is_ascii_lowercase() || b == b'_') =>
+            {
+                each(Piece::Slot(name));
+                rest = &tail[name.len() + 2..];
+            }
+            _ => {
+                each(Piece::Text(&tail[..1]));
+                rest = &tail[1..];
+            }
+        }
+    }
+    each(Piece::Text(rest));
+}
+
+impl PromptTemplate {
+    /// 检查占位符：必须包含 [`PLACEHOLDERS`] 全部，且不能有其他名字
+    pub fn new(role: PromptRole, text: impl Into<String>) -> Result<Self, PromptError> {
+        let text = text.into();
+        let mut seen = [false; PLACEHOLDERS.len()];
+        let mut unknown = None;
+        scan(&text, |piece| {
+            if let Piece::Slot(name) = piece {
+                match PLACEHOLDERS.iter().position(|p| *p == name) {
+                    Some(i) => seen[i] = true,
+                    None => {
+                        unknown.get_or_insert_with(|| name.to_string());
+                    }
+                }
+            }
+        });
+        if let Some(placeholder) = unknown {
+            return Err(PromptError::UnknownPlaceholder { role, placeholder });
+        }
+        if let Some(i) = seen.iter().position(|s| !s) {
+            return Err(PromptError::MissingPlaceholder { role, placeholder: PLACEHOLDERS[i] });
+        }
+        Ok(Self { role, text })
+    }
+
+    /// 内置模板
+    pub fn builtin(role: PromptRole) -> Self {
+        Self { role, text: role.builtin().to_string() }
+    }
+
+    pub fn role(&self) -> PromptRole {
+        self.role
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// 填入Playbook与轨迹，`{schema}`为该角色的输出格式说明
+    pub fn render(&self, playbook: &str, trajectory: &str) -> String {
+        let mut out = String::with_capacity(self.text.len() + playbook.len() + trajectory.len());
+        scan(&self.text, |piece| match piece {
+            Piece::Text(text) => out.push_str(text),
+            Piece::Slot("playbook") => out.push_str(playbook),
+            Piece::Slot("trajectory") => out.push_str(trajectory),
+            Piece::Slot(_) => out.push_str(self.role.schema()),
+        });
+        out
+    }
+}
+
+/// reflector与curator的模板
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptSet {
+    pub reflector: PromptTemplate,
+    pub curator: PromptTemplate,
+}
+
+impl Default for PromptSet {
+    fn default() -> Self {
+        Self {
+            reflector: PromptTemplate::builtin(PromptRole::Reflector),
+            curator: PromptTemplate::builtin(PromptRole::Curator),
+        }
+    }
+}
+
+impl PromptSet {
+    pub fn get(&self, role: PromptRole) -> &PromptTemplate {
+        match role {
+            PromptRole::Reflector => &self.reflector,
+            PromptRole::Curator => &self.curator,
+        }
+    }
+
+    /// 从目录加载`reflector.txt`与`curator.txt`，缺少的文件使用内置模板；
+    /// 任一模板无效时返回错误，适合在启动时调用
+    #[cfg(feature = "std")]
+    pub fn load_dir(dir: impl AsRef<std::path::Path>) -> Result<Self, PromptError> {
+        let load = |role: PromptRole| -> Result<PromptTemplate, PromptError> {
+            match std::fs::read_to_string(dir.as_ref().join(role.file_name())) {
+                Ok(text) => PromptTemplate::new(role, text),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    Ok(PromptTemplate::builtin(role))
+                }
+                Err(e) => Err(e.into()),
+            }
+        };
+        Ok(Self { reflector: load(PromptRole::Reflector)?, curator: load(PromptRole::Curator)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_templates_are_valid() {
+        for role in [PromptRole::Reflector, PromptRole::Curator] {
+            let builtin = PromptTemplate::builtin(role);
+            assert_eq!(PromptTemplate::new(role, builtin.text()).unwrap(), builtin);
+            let prompt = builtin.render("[api-00001] 先分页", "调用失败");
+            assert!(prompt.contains("[api-00001] 先分页") && prompt.contains(role.schema()));
+            assert!(!prompt.contains("{playbook}"));
+        }
+    }
+
+    #[test]
+    fn test_custom_template_validation_and_render() {
+        let role = PromptRole::Curator;
+        let text =
+            "剧本：{playbook}\n轨迹：{trajectory}\n输出{\"ok\": 1}，格式{schema}，字面{{playbook}}";
+        let template = PromptTemplate::new(role, text).unwrap();
+        let prompt = template.render("含{trajectory}的子弹", "T");
+        assert_eq!(
+            prompt,
+            alloc::format!(
+                "剧本：含{{trajectory}}的子弹\n轨迹：T\n输出{{\"ok\": 1}}，格式{}，字面{{playbook}}",
+                CURATOR_SCHEMA
+            )
+        );
+
+        let err = PromptTemplate::new(role, "{playbook} {trajectory}").unwrap_err();
+        assert!(matches!(err, PromptError::MissingPlaceholder { placeholder: "schema", .. }));
+        assert_eq!(err.to_string(), "curator模板缺少占位符{schema}");
+        let err = PromptTemplate::new(role, "{playbok} {trajectory} {schema}").unwrap_err();
+        assert!(
+            matches!(err, PromptError::UnknownPlaceholder { placeholder, .. } if placeholder == "playbok")
+        );
+        assert!(PromptTemplate::new(role, "{{playbook}} {trajectory} {schema}").is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_load_dir_overrides_and_validates() {
+        let dir = std::env::temp_dir().join(alloc::format!("ace-prompts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("reflector.txt"), "反思：{playbook}|{trajectory}|{schema}")
+            .unwrap();
+        let set = PromptSet::load_dir(&dir).unwrap();
+        assert!(set.get(PromptRole::Reflector).text().starts_with("反思"));
+        assert_eq!(set.curator, PromptSet::default().curator);
+
+        std::fs::write(dir.join("curator.txt"), "{playbook}").unwrap();
+        assert!(matches!(
+            PromptSet::load_dir(&dir),
+            Err(PromptError::MissingPlaceholder { role: PromptRole::Curator, .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
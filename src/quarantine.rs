@@ -0,0 +1,264 @@
+//! 样本隔离：某个样本反复失败（工具panic、模型输出无法解析……）时把它记入跳过列表文件，
+//! 运行继续处理其余样本，而不是无限重试或中止整个运行
+//!
+//! 跳过列表是JSONL文件，每行一个 [`QuarantineEntry`]，重启后加载即可继续跳过这些样本；
+//! 删除其中某行即可让该样本重新参与。样本以 [`sample_key`] 识别。
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write as _};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use ring::digest::{SHA256, digest};
+use serde::{Deserialize, Serialize};
+
+use crate::hex::to_hex;
+use crate::models::sample::Sample;
+use crate::models::timestamp;
+
+/// 默认连续失败几次后隔离
+pub const DEFAULT_MAX_FAILURES: u32 = 3;
+
+/// 样本的标识：元数据中的`id`或`task_id`，没有时为问题与上下文的SHA-256前16位
+pub fn sample_key(sample: &Sample) -> String {
+    for key in ["id", "task_id"] {
+        match sample.metadata.get(key) {
+            Some(serde_json::Value::String(id)) => return id.clone(),
+            Some(serde_json::Value::Number(id)) => return id.to_string(),
+            _ => {}
+        }
+    }
+    let mut text = sample.question.clone().into_bytes();
+    text.push(b'\n');
+    text.extend_from_slice(sample.context.as_bytes());
+    to_hex(&digest(&SHA256, &text).as_ref()[..8])
+}
+
+/// 跳过列表中的一条
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub key: String,
+    pub failures: u32,
+    pub last_error: String,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub quarantined_at: DateTime<Utc>,
+}
+
+/// 一次尝试的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum SampleOutcome<T> {
+    Done(T),
+    /// 失败，`quarantined`表示这次失败使样本被隔离
+    Failed {
+        error: String,
+        quarantined: bool,
+    },
+    /// 样本已在跳过列表中，没有执行
+    Skipped,
+}
+
+#[derive(Debug)]
+pub struct Quarantine {
+    max_failures: u32,
+    /// 未隔离样本的连续失败次数
+    failures: BTreeMap<String, u32>,
+    entries: BTreeMap<String, QuarantineEntry>,
+    path: Option<PathBuf>,
+}
+
+impl Default for Quarantine {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FAILURES)
+    }
+}
+
+impl Quarantine {
+    /// 只在内存中记录
+    pub fn new(max_failures: u32) -> Self {
+        Self {
+            max_failures: max_failures.max(1),
+            failures: BTreeMap::new(),
+            entries: BTreeMap::new(),
+            path: None,
+        }
+    }
+
+    /// 加载跳过列表（文件不存在时为空），之后新隔离的样本追加写入该文件
+    pub fn open(path: impl Into<PathBuf>, max_failures: u32) -> io::Result<Self> {
+        let path = path.into();
+        let mut quarantine = Self::new(max_failures);
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                    let entry: QuarantineEntry = serde_json::from_str(line)?;
+                    quarantine.entries.insert(entry.key.clone(), entry);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        quarantine.path = Some(path);
+        Ok(quarantine)
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &QuarantineEntry> {
+        self.entries.values()
+    }
+
+    pub fn is_quarantined(&self, sample: &Sample) -> bool {
+        self.entries.contains_key(&sample_key(sample))
+    }
+
+    /// 去掉已隔离的样本
+    pub fn filter<'a>(&'a self, samples: &'a [Sample]) -> impl Iterator<Item = &'a Sample> + 'a {
+        samples.iter().filter(|s| !self.is_quarantined(s))
+    }
+
+    /// 记录一次失败，达到上限时隔离并写入跳过列表；返回是否因此被隔离
+    pub fn record_failure(
+        &mut self,
+        sample: &Sample,
+        error: &str,
+        at: DateTime<Utc>,
+    ) -> io::Result<bool> {
+        let key = sample_key(sample);
+        if self.entries.contains_key(&key) {
+            return Ok(false);
+        }
+        let failures = self.failures.entry(key.clone()).or_default();
+        *failures += 1;
+        if *failures < self.max_failures {
+            return Ok(false);
+        }
+        let entry = QuarantineEntry {
+            key,
+            failures: *failures,
+            last_error: error.to_string(),
+            quarantined_at: at,
+        };
+        self.failures.remove(&entry.key);
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        }
+        self.entries.insert(entry.key.clone(), entry);
+        Ok(true)
+    }
+
+    /// 成功后清零连续失败次数
+    pub fn record_success(&mut self, sample: &Sample) {
+        self.failures.remove(&sample_key(sample));
+    }
+
+    /// 执行一次`f`：错误与panic都记为失败，已隔离的样本直接跳过
+    pub fn attempt<T, E: core::fmt::Display>(
+        &mut self,
+        sample: &Sample,
+        at: DateTime<Utc>,
+        f: impl FnOnce(&Sample) -> Result<T, E>,
+    ) -> io::Result<SampleOutcome<T>> {
+        if self.is_quarantined(sample) {
+            return Ok(SampleOutcome::Skipped);
+        }
+        let error = match panic::catch_unwind(AssertUnwindSafe(|| f(sample))) {
+            Ok(Ok(value)) => {
+                self.record_success(sample);
+                return Ok(SampleOutcome::Done(value));
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(payload) => panic_message(payload.as_ref()),
+        };
+        let quarantined = self.record_failure(sample, &error, at)?;
+        Ok(SampleOutcome::Failed { error, quarantined })
+    }
+
+    /// 重试`f`直到成功或样本被隔离（最多`max_failures`次）
+    pub fn process<T, E: core::fmt::Display>(
+        &mut self,
+        sample: &Sample,
+        at: DateTime<Utc>,
+        mut f: impl FnMut(&Sample) -> Result<T, E>,
+    ) -> io::Result<SampleOutcome<T>> {
+        loop {
+            match self.attempt(sample, at, &mut f)? {
+                SampleOutcome::Failed { quarantined: false, .. } => continue,
+                outcome => return Ok(outcome),
+            }
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn core::any::Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    format!("panic: {}", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str) -> Sample {
+        let mut sample = Sample::new("Pay my phone bill");
+        sample.metadata.insert("task_id".to_string(), id.into());
+        sample
+    }
+
+    #[test]
+    fn test_repeated_failures_quarantine_sample() {
+        let path =
+            std::env::temp_dir().join(format!("ace-quarantine-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let at = DateTime::UNIX_EPOCH;
+        let (good, bad, flaky) = (sample("t-1"), sample("t-2"), sample("t-3"));
+
+        let mut quarantine = Quarantine::open(&path, 2).unwrap();
+        let ok = quarantine.process(&good, at, |_| Ok::<_, String>(42)).unwrap();
+        assert_eq!(ok, SampleOutcome::Done(42));
+
+        let outcome = quarantine
+            .process(&bad, at, |_| -> Result<(), String> { panic!("tool crashed") })
+            .unwrap();
+        assert_eq!(
+            outcome,
+            SampleOutcome::Failed { error: "panic: tool crashed".to_string(), quarantined: true }
+        );
+        assert_eq!(
+            quarantine.attempt(&bad, at, |_| Ok::<_, String>(())).unwrap(),
+            SampleOutcome::Skipped
+        );
+
+        // 失败一次后成功，计数清零
+        let mut calls = 0;
+        let outcome = quarantine
+            .process(&flaky, at, |_| {
+                calls += 1;
+                if calls == 1 { Err("unparseable output") } else { Ok(calls) }
+            })
+            .unwrap();
+        assert_eq!(outcome, SampleOutcome::Done(2));
+        assert!(!quarantine.record_failure(&flaky, "again", at).unwrap());
+
+        let samples = [good.clone(), bad.clone(), flaky.clone()];
+        assert_eq!(quarantine.filter(&samples).count(), 2);
+
+        let reopened = Quarantine::open(&path, 2).unwrap();
+        let entries: Vec<&QuarantineEntry> = reopened.entries().collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].key.as_str(), entries[0].failures), ("t-2", 2));
+        assert!(reopened.is_quarantined(&bad));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(sample_key(&Sample::new("q")).len(), 16);
+        assert_ne!(sample_key(&Sample::new("q")), sample_key(&Sample::new("q2")));
+    }
+}
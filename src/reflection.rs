@@ -0,0 +1,310 @@
+//! reflector与curator之间的质量过滤：丢弃空泛、与已有子弹重复或置信度过低的洞见
+//!
+//! 置信度可以由reflector自带，也可以用 [`InsightScorer`]（例如单独的LLM打分调用）补上。
+
+use alloc::{
+    collections::BTreeSet,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::playbook::{BulletId, Playbook};
+
+#[derive(Debug, Error)]
+pub enum ReflectionError {
+    #[cfg(feature = "llm")]
+    #[error("打分调用LLM失败：{0}")]
+    Llm(#[from] crate::llm::LlmError),
+    #[error("打分输出无效：{0}")]
+    InvalidScore(String),
+    #[error("reflector输出无效：{0}")]
+    InvalidOutput(String),
+}
+
+/// reflector产出的一条候选洞见
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Insight {
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// [0, 1]；为空表示未打分
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+}
+
+impl Insight {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            section: None,
+            confidence: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum DropReason {
+    /// 去掉空泛套话后信息量不足
+    TooVague,
+    /// 与已有子弹重复
+    DuplicateBullet { bullet_id: BulletId, similarity: f64 },
+    /// 与同批次中更早的洞见重复
+    DuplicateInsight { index: usize, similarity: f64 },
+    LowConfidence { confidence: f64 },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterOutcome {
+    pub kept: Vec<Insight>,
+    pub dropped: Vec<(Insight, DropReason)>,
+}
+
+/// 给洞见打置信度分
+pub trait InsightScorer {
+    fn score(&self, insight: &Insight, playbook: &Playbook) -> Result<f64, ReflectionError>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityFilter {
+    /// 去掉空泛套话后至少保留的词数（中日韩字符每字计一词）
+    pub min_tokens: usize,
+    /// 视为空泛的套话（小写匹配）
+    pub vague_phrases: Vec<String>,
+    /// 词集合Jaccard相似度达到该值视为重复
+    pub max_similarity: f64,
+    /// 置信度低于该值的洞见丢弃；未打分的洞见不受限制
+    pub min_confidence: f64,
+}
+
+impl Default for QualityFilter {
+    fn default() -> Self {
+        let vague_phrases = [
+            "be careful",
+            "make sure",
+            "pay attention",
+            "double check",
+            "it depends",
+            "in general",
+            "always",
+            "注意",
+            "小心",
+            "确保",
+        ];
+        Self {
+            min_tokens: 4,
+            vague_phrases: vague_phrases.iter().map(|p| p.to_string()).collect(),
+            max_similarity: 0.8,
+            min_confidence: 0.5,
+        }
+    }
+}
+
+impl QualityFilter {
+    pub fn filter(&self, playbook: &Playbook, insights: Vec<Insight>) -> FilterOutcome {
+        let existing: Vec<(&str, BTreeSet<String>)> = playbook
+            .bullets()
+            .into_iter()
+            .map(|b| (b.id.as_str(), tokens(&b.content)))
+            .collect();
+        let mut seen: Vec<(usize, BTreeSet<String>)> = Vec::new();
+        let mut outcome = FilterOutcome::default();
+
+        for (index, insight) in insights.into_iter().enumerate() {
+            let words = tokens(&insight.content);
+            let reason = if self.is_vague(&insight.content) {
+                Some(DropReason::TooVague)
+            } else if let Some(confidence) = insight.confidence.filter(|c| *c < self.min_confidence) {
+                Some(DropReason::LowConfidence { confidence })
+            } else if let Some((id, similarity)) = self.most_similar(&words, &existing) {
+                Some(DropReason::DuplicateBullet {
+                    bullet_id: id.to_string(),
+                    similarity,
+                })
+            } else {
+                self.most_similar(&words, &seen)
+                    .map(|(index, similarity)| DropReason::DuplicateInsight { index, similarity })
+            };
+            match reason {
+                Some(reason) => outcome.dropped.push((insight, reason)),
+                None => {
+                    seen.push((index, words));
+                    outcome.kept.push(insight);
+                }
+            }
+        }
+        outcome
+    }
+
+    /// 先给未打分的洞见打分再过滤
+    pub fn filter_scored(
+        &self,
+        playbook: &Playbook,
+        mut insights: Vec<Insight>,
+        scorer: &dyn InsightScorer,
+    ) -> Result<FilterOutcome, ReflectionError> {
+        for insight in insights.iter_mut().filter(|i| i.confidence.is_none()) {
+            insight.confidence = Some(scorer.score(insight, playbook)?.clamp(0.0, 1.0));
+        }
+        Ok(self.filter(playbook, insights))
+    }
+
+    fn is_vague(&self, content: &str) -> bool {
+        let mut text = content.to_lowercase();
+        for phrase in &self.vague_phrases {
+            text = text.replace(phrase.as_str(), " ");
+        }
+        tokens(&text).len() < self.min_tokens
+    }
+
+    fn most_similar<K: Copy>(&self, words: &BTreeSet<String>, candidates: &[(K, BTreeSet<String>)]) -> Option<(K, f64)> {
+        candidates
+            .iter()
+            .map(|(key, other)| (*key, jaccard(words, other)))
+            .filter(|(_, similarity)| *similarity >= self.max_similarity)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+#[cfg(feature = "llm")]
+pub use llm_scorer::LlmInsightScorer;
+
+#[cfg(feature = "llm")]
+mod llm_scorer {
+    use alloc::format;
+
+    use super::*;
+    use crate::llm::{LlmClient, extract_json};
+
+    /// 用独立的LLM调用判断洞见是否具体、可执行、对Playbook有增量
+    #[derive(Debug)]
+    pub struct LlmInsightScorer<C> {
+        client: C,
+    }
+
+    impl<C: LlmClient> LlmInsightScorer<C> {
+        pub fn new(client: C) -> Self {
+            Self { client }
+        }
+    }
+
+    impl<C: LlmClient> InsightScorer for LlmInsightScorer<C> {
+        fn score(&self, insight: &Insight, playbook: &Playbook) -> Result<f64, ReflectionError> {
+            let prompt = format!(
+                "Rate how useful this insight is as a new playbook strategy: specific, actionable and \
+                 not already covered by the playbook.\n\nPlaybook:\n{}\n\nInsight: {}\n\n\
+                 Reply with JSON only: {{\"confidence\": <number between 0 and 1>}}",
+                playbook.as_prompt(),
+                insight.content
+            );
+            let text = self.client.complete(&prompt)?.text;
+            extract_json(&text)
+                .and_then(|value| value["confidence"].as_f64())
+                .ok_or(ReflectionError::InvalidScore(text))
+        }
+    }
+}
+
+// --------------------------
+// 辅助函数
+// --------------------------
+
+/// 小写的字母数字词；中日韩等非ASCII字母每个字单独成词
+pub(crate) fn tokens(text: &str) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    let mut word = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            out.insert(core::mem::take(&mut word));
+        }
+        if c.is_alphanumeric() {
+            out.insert(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        out.insert(word);
+    }
+    out
+}
+
+pub(crate) fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_drops_vague_duplicate_and_low_confidence() {
+        let mut pb = Playbook::new();
+        let id = pb.add_bullet("api usage", "Pass the cursor token when paginating list endpoints", None, None);
+
+        let low = Insight {
+            confidence: Some(0.2),
+            ..Insight::new("Retry idempotent requests with exponential backoff")
+        };
+        let insights = Vec::from([
+            Insight::new("Be careful with the API."),
+            Insight::new("pass the cursor token when paginating list endpoints!"),
+            low,
+            Insight::new("Log the request id before raising errors"),
+            Insight::new("Log the request id before raising errors."),
+            Insight::new("分页接口需要传入上一页返回的游标"),
+        ]);
+        let outcome = QualityFilter::default().filter(&pb, insights);
+
+        let reasons: Vec<&DropReason> = outcome.dropped.iter().map(|(_, r)| r).collect();
+        assert_eq!(reasons[0], &DropReason::TooVague);
+        assert!(matches!(reasons[1], DropReason::DuplicateBullet { bullet_id, .. } if *bullet_id == id));
+        assert_eq!(reasons[2], &DropReason::LowConfidence { confidence: 0.2 });
+        assert!(matches!(reasons[3], DropReason::DuplicateInsight { index: 3, .. }));
+        assert_eq!(outcome.kept.len(), 2);
+    }
+
+    #[test]
+    fn test_filter_scored_fills_missing_confidence() {
+        struct LengthScorer;
+        impl InsightScorer for LengthScorer {
+            fn score(&self, insight: &Insight, _: &Playbook) -> Result<f64, ReflectionError> {
+                Ok(if insight.content.len() > 40 { 0.9 } else { 0.1 })
+            }
+        }
+
+        let insights = Vec::from([
+            Insight::new("Validate webhook signatures before parsing the payload body"),
+            Insight::new("Use smaller batch sizes here"),
+        ]);
+        let outcome = QualityFilter::default()
+            .filter_scored(&Playbook::new(), insights, &LengthScorer)
+            .unwrap();
+        assert_eq!(outcome.kept.len(), 1);
+        assert_eq!(outcome.kept[0].confidence, Some(0.9));
+    }
+
+    #[cfg(feature = "llm")]
+    #[test]
+    fn test_llm_scorer() {
+        use crate::llm::DummyLlmClient;
+
+        let client = DummyLlmClient::with_responses([r#"{"confidence": 0.75}"#, "no idea"]);
+        let scorer = LlmInsightScorer::new(&client);
+        let insight = Insight::new("Validate webhook signatures first");
+        assert_eq!(scorer.score(&insight, &Playbook::new()).unwrap(), 0.75);
+        assert!(matches!(
+            scorer.score(&insight, &Playbook::new()),
+            Err(ReflectionError::InvalidScore(_))
+        ));
+    }
+}
@@ -0,0 +1,925 @@
+//! 子弹检索：词面BM25与向量相似度混合，单独任一信号都会漏掉相关子弹
+//!
+//! 两路分数的融合方式见 [`Fusion`]，可序列化写入运行配置；融合后的前N个候选可再交给
+//! [`Reranker`]（交叉编码器或LLM）精排；简短的任务描述可先经 [`QueryExpander`] 改写出
+//! 多个查询以提高召回。
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::embedding::{
+    Degraded, EmbeddingError, EmbeddingStore, SharedDegradationSink, content_hash,
+    cosine_similarity,
+};
+use crate::models::context::{Candidate, ContextError, ContextRequest, ContextStage};
+use crate::models::playbook::{Bullet, BulletId, Playbook};
+use crate::models::search;
+
+// --------------------------
+// BM25
+// --------------------------
+
+/// 对Playbook建立的BM25倒排索引；内容变化后用 [`Bm25Index::refresh`] 只重新索引变化的子弹
+///
+/// 可保存为mmap格式（见 [`crate::persist::fts`]），冷启动时不必重新分词。
+#[derive(Debug, Clone)]
+pub struct Bm25Index {
+    pub(crate) k1: f64,
+    pub(crate) b: f64,
+    /// 词 → (子弹, 词频)
+    pub(crate) postings: HashMap<String, Vec<(BulletId, u32)>>,
+    pub(crate) lengths: BTreeMap<BulletId, usize>,
+    /// 建索引时的内容哈希（[`content_hash`]），用于找出变化的子弹
+    pub(crate) hashes: BTreeMap<BulletId, u64>,
+    pub(crate) avg_len: f64,
+}
+
+impl Bm25Index {
+    /// 常用参数 k1 = 1.2, b = 0.75
+    pub fn build(playbook: &Playbook) -> Self {
+        Self::with_params(playbook, 1.2, 0.75)
+    }
+
+    pub fn with_params(playbook: &Playbook, k1: f64, b: f64) -> Self {
+        let mut index = Self {
+            k1,
+            b,
+            postings: HashMap::new(),
+            lengths: BTreeMap::new(),
+            hashes: BTreeMap::new(),
+            avg_len: 0.0,
+        };
+        for bullet in playbook.bullets() {
+            index.insert(&bullet.id, &bullet.content, content_hash(&bullet.content));
+        }
+        index.update_avg_len();
+        index
+    }
+
+    /// 已建索引的子弹数
+    pub fn len(&self) -> usize {
+        self.lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lengths.is_empty()
+    }
+
+    /// 与Playbook当前内容一致（没有新增、删除或内容变化的子弹）
+    pub fn is_current(&self, playbook: &Playbook) -> bool {
+        self.hashes.len() == playbook.bullets.len()
+            && playbook
+                .bullets
+                .values()
+                .all(|b| self.hashes.get(&b.id) == Some(&content_hash(&b.content)))
+    }
+
+    /// 按内容哈希找出新增、删除和内容变化的子弹，只对这些子弹重新分词，返回其数量
+    pub fn refresh(&mut self, playbook: &Playbook) -> usize {
+        let changed: Vec<(&Bullet, u64)> = playbook
+            .bullets()
+            .into_iter()
+            .map(|b| (b, content_hash(&b.content)))
+            .filter(|(b, hash)| self.hashes.get(&b.id) != Some(hash))
+            .collect();
+        let stale: HashSet<BulletId> = self
+            .hashes
+            .keys()
+            .filter(|id| !playbook.bullets.contains_key(*id))
+            .chain(changed.iter().map(|(b, _)| &b.id).filter(|id| self.hashes.contains_key(*id)))
+            .cloned()
+            .collect();
+        let removed = stale.iter().filter(|id| !playbook.bullets.contains_key(*id)).count();
+        if !stale.is_empty() {
+            self.postings.retain(|_, postings| {
+                postings.retain(|(id, _)| !stale.contains(id));
+                !postings.is_empty()
+            });
+            for id in &stale {
+                self.lengths.remove(id);
+                self.hashes.remove(id);
+            }
+        }
+        for (bullet, hash) in &changed {
+            self.insert(&bullet.id, &bullet.content, *hash);
+        }
+        self.update_avg_len();
+        changed.len() + removed
+    }
+
+    fn insert(&mut self, id: &BulletId, content: &str, hash: u64) {
+        let terms = terms(content);
+        self.lengths.insert(id.clone(), terms.len());
+        self.hashes.insert(id.clone(), hash);
+        let mut tf: BTreeMap<String, u32> = BTreeMap::new();
+        for term in terms {
+            *tf.entry(term).or_default() += 1;
+        }
+        for (term, count) in tf {
+            self.postings.entry(term).or_default().push((id.clone(), count));
+        }
+    }
+
+    fn update_avg_len(&mut self) {
+        self.avg_len = if self.lengths.is_empty() {
+            0.0
+        } else {
+            self.lengths.values().sum::<usize>() as f64 / self.lengths.len() as f64
+        };
+    }
+
+    /// 与查询至少共享一个词的子弹及其BM25分数
+    pub fn scores(&self, query: &str) -> BTreeMap<BulletId, f64> {
+        let n = self.lengths.len() as f64;
+        let mut scores = BTreeMap::new();
+        for term in query_terms(query) {
+            let Some(postings) = self.postings.get(&term) else {
+                continue;
+            };
+            let idf = idf(n, postings.len() as f64);
+            for (id, tf) in postings {
+                let len = self.lengths[id] as f64;
+                let score = idf * tf_weight(self.k1, self.b, *tf as f64, len, self.avg_len);
+                *scores.entry(id.clone()).or_insert(0.0) += score;
+            }
+        }
+        scores
+    }
+}
+
+/// 去重并排序的查询词；持久化索引按同样的顺序累加分数，结果逐位一致
+pub(crate) fn query_terms(query: &str) -> Vec<String> {
+    let mut query_terms = terms(query);
+    query_terms.sort();
+    query_terms.dedup();
+    query_terms
+}
+
+pub(crate) fn idf(n: f64, df: f64) -> f64 {
+    ((n - df + 0.5) / (df + 0.5) + 1.0).ln()
+}
+
+pub(crate) fn tf_weight(k1: f64, b: f64, tf: f64, len: f64, avg_len: f64) -> f64 {
+    let norm = k1 * (1.0 - b + b * len / avg_len.max(1.0));
+    tf * (k1 + 1.0) / (tf + norm)
+}
+
+/// 小写ASCII单词，非ASCII的字母数字逐字成词（中文按字检索）
+fn terms(text: &str) -> Vec<String> {
+    search::terms(text, false)
+}
+
+// --------------------------
+// 混合检索
+// --------------------------
+
+/// 两路分数的融合方式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Fusion {
+    /// 各自归一化到[0, 1]后加权求和（BM25除以本次最高分，余弦相似度截断负值）
+    Weighted { lexical: f64, semantic: f64 },
+    /// 倒数排名融合：每路贡献 1 / (k + 名次)，名次从1开始；只看排名，不受分数尺度影响
+    ReciprocalRank { k: f64 },
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Self::ReciprocalRank { k: 60.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Retrieved {
+    pub bullet_id: BulletId,
+    /// 融合后的分数
+    pub score: f64,
+    /// BM25原始分数（没有共享词时为0）
+    pub lexical: f64,
+    /// 余弦相似度（没有向量时为0）
+    pub semantic: f64,
+    /// 重排序分数（没有经过重排序时为空）
+    pub rerank: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HybridRetriever {
+    pub fusion: Fusion,
+    reranker: Option<SharedReranker>,
+    rerank: RerankConfig,
+    expander: Option<SharedQueryExpander>,
+    degradation: Option<SharedDegradationSink>,
+}
+
+impl HybridRetriever {
+    pub fn new(fusion: Fusion) -> Self {
+        Self {
+            fusion,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_reranker(mut self, reranker: SharedReranker, config: RerankConfig) -> Self {
+        self.reranker = Some(reranker);
+        self.rerank = config;
+        self
+    }
+
+    /// 向量模型不可用时检索只用BM25，并向`sink`报告 [`Degraded`]
+    pub fn with_degradation_sink(mut self, sink: SharedDegradationSink) -> Self {
+        self.degradation = Some(sink);
+        self
+    }
+
+    /// 检索前用`expander`改写查询，原查询与改写结果分别检索后按子弹取最高分合并
+    pub fn with_query_expander(mut self, expander: SharedQueryExpander) -> Self {
+        self.expander = Some(expander);
+        self
+    }
+
+    /// 检索最相关的`limit`个子弹（分数从高到低，同分按ID）
+    ///
+    /// `store`需已对`playbook`调用过 [`EmbeddingStore::refresh`]，没有向量的子弹语义分为0。
+    /// 查询扩展失败时只用原查询检索；无法计算查询向量时退回只用BM25（见
+    /// [`HybridRetriever::with_degradation_sink`]）。
+    pub fn retrieve(
+        &self,
+        playbook: &Playbook,
+        index: &Bm25Index,
+        store: &EmbeddingStore,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Retrieved>, EmbeddingError> {
+        let mut results = self.fuse(playbook, index, store, query)?;
+        let expansions = match &self.expander {
+            Some(expander) => expander.expand(query).unwrap_or_default(),
+            None => Vec::new(),
+        };
+        for expanded in expansions.iter().filter(|q| q.as_str() != query) {
+            let more = self.fuse(playbook, index, store, expanded)?;
+            for (best, candidate) in results.iter_mut().zip(more) {
+                if candidate.score > best.score {
+                    *best = candidate;
+                }
+            }
+        }
+
+        results.retain(|r| r.score > 0.0);
+        results.sort_by(|a, b| {
+            b.score.total_cmp(&a.score).then_with(|| a.bullet_id.cmp(&b.bullet_id))
+        });
+        if let Some(reranker) = &self.reranker {
+            self.apply_reranker(reranker.as_ref(), playbook, query, &mut results);
+        }
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    /// 单个查询的融合分数，按Playbook子弹ID顺序对应每个子弹
+    fn fuse(
+        &self,
+        playbook: &Playbook,
+        index: &Bm25Index,
+        store: &EmbeddingStore,
+        query: &str,
+    ) -> Result<Vec<Retrieved>, EmbeddingError> {
+        let lexical = index.scores(query);
+        let query_vector = match store.embed_query(query) {
+            Ok(vector) => vector,
+            Err(e) => {
+                if let Some(sink) = &self.degradation {
+                    sink.degraded(&Degraded { operation: "retrieve", error: e.to_string() });
+                }
+                Vec::new()
+            }
+        };
+        let semantic: BTreeMap<&str, f64> = playbook
+            .bullets
+            .keys()
+            .filter(|_| !query_vector.is_empty())
+            .filter_map(|id| {
+                let similarity = cosine_similarity(&query_vector, store.get(id)?) as f64;
+                Some((id.as_str(), similarity))
+            })
+            .collect();
+
+        let mut results: Vec<Retrieved> = playbook
+            .bullets()
+            .into_iter()
+            .map(|b| &b.id)
+            .map(|id| Retrieved {
+                bullet_id: id.clone(),
+                score: 0.0,
+                lexical: lexical.get(id).copied().unwrap_or(0.0),
+                semantic: semantic.get(id.as_str()).copied().unwrap_or(0.0),
+                rerank: None,
+            })
+            .collect();
+
+        match self.fusion {
+            Fusion::Weighted {
+                lexical: w_lex,
+                semantic: w_sem,
+            } => {
+                let max_lex = results.iter().map(|r| r.lexical).fold(0.0, f64::max);
+                for r in &mut results {
+                    let lex = if max_lex > 0.0 { r.lexical / max_lex } else { 0.0 };
+                    r.score = w_lex * lex + w_sem * r.semantic.max(0.0);
+                }
+            }
+            Fusion::ReciprocalRank { k } => {
+                let lex_ranks = ranks(&results, |r| r.lexical);
+                let sem_ranks = ranks(&results, |r| r.semantic);
+                for (i, r) in results.iter_mut().enumerate() {
+                    r.score = [lex_ranks[i], sem_ranks[i]]
+                        .into_iter()
+                        .flatten()
+                        .map(|rank| 1.0 / (k + rank as f64))
+                        .sum();
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// 按批次重排前N个候选，超出时间预算或重排序出错时停止；已重排的按重排分数排在前面，
+    /// 其余保持融合顺序
+    fn apply_reranker(
+        &self,
+        reranker: &dyn Reranker,
+        playbook: &Playbook,
+        query: &str,
+        results: &mut [Retrieved],
+    ) {
+        let config = &self.rerank;
+        let top_n = config.top_n.min(results.len());
+        let budget = config.budget_ms.map(Duration::from_millis);
+        let started = Instant::now();
+
+        let mut reranked = 0;
+        for batch in results[..top_n].chunks_mut(config.batch_size.max(1)) {
+            if reranked > 0 && budget.is_some_and(|budget| started.elapsed() >= budget) {
+                break;
+            }
+            let contents: Vec<&str> = batch
+                .iter()
+                .map(|r| playbook.get_bullet(&r.bullet_id).map_or("", |b| b.content.as_str()))
+                .collect();
+            match reranker.rerank(query, &contents) {
+                Ok(scores) if scores.len() == batch.len() => {
+                    for (r, score) in batch.iter_mut().zip(scores) {
+                        r.rerank = Some(score);
+                    }
+                    reranked += batch.len();
+                }
+                _ => break,
+            }
+        }
+        results[..reranked].sort_by(|a, b| {
+            let score = |r: &Retrieved| r.rerank.unwrap_or(f64::NEG_INFINITY);
+            score(b).total_cmp(&score(a))
+        });
+    }
+}
+
+/// [`ContextPipeline`](crate::models::context::ContextPipeline)的检索阶段：
+/// 只保留检索命中的子弹，按检索排名排列，分数替换为检索分数
+#[derive(Debug, Clone, Copy)]
+pub struct RetrieveStage<'s> {
+    pub retriever: &'s HybridRetriever,
+    pub index: &'s Bm25Index,
+    pub store: &'s EmbeddingStore,
+    pub limit: usize,
+}
+
+impl ContextStage for RetrieveStage<'_> {
+    fn apply<'a>(
+        &self,
+        request: &ContextRequest<'a>,
+        candidates: Vec<Candidate<'a>>,
+    ) -> Result<Vec<Candidate<'a>>, ContextError> {
+        let hits = self
+            .retriever
+            .retrieve(request.playbook, self.index, self.store, request.query, self.limit)
+            .map_err(|err| ContextError::Stage {
+                stage: "retrieve".to_string(),
+                message: err.to_string(),
+            })?;
+        Ok(hits
+            .iter()
+            .filter_map(|hit| {
+                let candidate = candidates.iter().find(|c| c.bullet.id == hit.bullet_id)?;
+                Some(Candidate {
+                    score: hit.score,
+                    ..*candidate
+                })
+            })
+            .collect())
+    }
+}
+
+// --------------------------
+// 结果缓存
+// --------------------------
+
+/// 带结果缓存的检索，在线场景下大量重复的查询不必重新检索
+///
+/// 键为规范化查询（合并空白）的哈希与`limit`；条目记录写入时的 [`Playbook::revision`]，
+/// Playbook有任何修改后旧条目自动失效。满时淘汰最早写入的条目。
+#[derive(Debug)]
+pub struct CachedRetriever {
+    retriever: HybridRetriever,
+    capacity: usize,
+    cache: Mutex<RetrievalCache>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Default)]
+struct RetrievalCache {
+    entries: HashMap<(u64, usize), CacheEntry>,
+    /// 写入顺序
+    order: VecDeque<(u64, usize)>,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    query: String,
+    revision: u64,
+    results: Vec<Retrieved>,
+}
+
+impl CachedRetriever {
+    pub fn new(retriever: HybridRetriever, capacity: usize) -> Self {
+        Self {
+            retriever,
+            capacity: capacity.max(1),
+            cache: Mutex::new(RetrievalCache::default()),
+        }
+    }
+
+    pub fn retriever(&self) -> &HybridRetriever {
+        &self.retriever
+    }
+
+    /// 同 [`HybridRetriever::retrieve`]，命中缓存时直接返回
+    pub fn retrieve(
+        &self,
+        playbook: &Playbook,
+        index: &Bm25Index,
+        store: &EmbeddingStore,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Retrieved>, EmbeddingError> {
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        let key = (content_hash(&query), limit);
+        let revision = playbook.revision();
+        {
+            let mut cache = self.cache.lock().unwrap();
+            let hit = cache
+                .entries
+                .get(&key)
+                .filter(|e| e.revision == revision && e.query == query)
+                .map(|e| e.results.clone());
+            if let Some(results) = hit {
+                cache.hits += 1;
+                return Ok(results);
+            }
+            cache.misses += 1;
+        }
+
+        // 检索期间不持锁，并发的相同查询可能各算一次
+        let results = self.retriever.retrieve(playbook, index, store, &query, limit)?;
+        let mut cache = self.cache.lock().unwrap();
+        let entry = CacheEntry {
+            query,
+            revision,
+            results: results.clone(),
+        };
+        if cache.entries.insert(key, entry).is_none() {
+            cache.order.push_back(key);
+        }
+        while cache.entries.len() > self.capacity {
+            let Some(oldest) = cache.order.pop_front() else {
+                break;
+            };
+            cache.entries.remove(&oldest);
+        }
+        Ok(results)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let cache = self.cache.lock().unwrap();
+        CacheStats {
+            hits: cache.hits,
+            misses: cache.misses,
+            entries: cache.entries.len(),
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.entries.clear();
+        cache.order.clear();
+    }
+}
+
+// --------------------------
+// 重排序
+// --------------------------
+
+#[derive(Debug, Error)]
+pub enum RerankError {
+    #[error("重排序失败：{0}")]
+    Failed(String),
+}
+
+pub trait Reranker: Debug + Send + Sync {
+    /// 给每个候选打相关性分数（越高越相关），长度与`candidates`相同
+    fn rerank(&self, query: &str, candidates: &[&str]) -> Result<Vec<f64>, RerankError>;
+}
+
+pub type SharedReranker = Arc<dyn Reranker>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RerankConfig {
+    /// 交给重排序的候选数（融合排名前N）
+    pub top_n: usize,
+    /// 每次调用重排序的候选数
+    pub batch_size: usize,
+    /// 时间预算（毫秒）：用完后不再发起新批次，第一批总会执行
+    #[serde(default)]
+    pub budget_ms: Option<u64>,
+}
+
+impl Default for RerankConfig {
+    fn default() -> Self {
+        Self {
+            top_n: 20,
+            batch_size: 20,
+            budget_ms: None,
+        }
+    }
+}
+
+// --------------------------
+// 查询扩展
+// --------------------------
+
+#[derive(Debug, Error)]
+pub enum ExpansionError {
+    #[error("查询扩展失败：{0}")]
+    Failed(String),
+}
+
+pub trait QueryExpander: Debug + Send + Sync {
+    /// 查询的改写版本（同义词、换种说法等），不需要包含原查询
+    fn expand(&self, query: &str) -> Result<Vec<String>, ExpansionError>;
+}
+
+pub type SharedQueryExpander = Arc<dyn QueryExpander>;
+
+#[cfg(feature = "llm")]
+pub use llm_backed::{LlmQueryExpander, LlmReranker};
+
+#[cfg(feature = "llm")]
+mod llm_backed {
+    use std::fmt::Write as _;
+
+    use super::*;
+    use crate::llm::{LlmClient, extract_json};
+
+    /// 让LLM给每个候选打0-10分
+    #[derive(Debug)]
+    pub struct LlmReranker<C> {
+        client: C,
+    }
+
+    impl<C: LlmClient + Debug> LlmReranker<C> {
+        pub fn new(client: C) -> Self {
+            Self { client }
+        }
+    }
+
+    impl<C: LlmClient + Debug> Reranker for LlmReranker<C> {
+        fn rerank(&self, query: &str, candidates: &[&str]) -> Result<Vec<f64>, RerankError> {
+            let mut prompt = format!(
+                "Rate how useful each strategy is for the task, from 0 (irrelevant) to 10.\n\n\
+                 Task: {}\n\nStrategies:\n",
+                query
+            );
+            for (i, candidate) in candidates.iter().enumerate() {
+                let _ = writeln!(prompt, "{}. {}", i + 1, candidate);
+            }
+            prompt.push_str("\nReply with JSON only: {\"scores\": [<one number per strategy>]}");
+
+            let response = self
+                .client
+                .complete(&prompt)
+                .map_err(|err| RerankError::Failed(err.to_string()))?;
+            let scores: Option<Vec<f64>> = extract_json(&response.text)
+                .and_then(|value| value["scores"].as_array().cloned())
+                .map(|scores| scores.iter().filter_map(|s| s.as_f64()).collect());
+            match scores {
+                Some(scores) if scores.len() == candidates.len() => Ok(scores),
+                _ => Err(RerankError::Failed(format!(
+                    "expected {} scores in: {}",
+                    candidates.len(),
+                    response.text
+                ))),
+            }
+        }
+    }
+
+    /// 让LLM把简短的任务描述改写成最多`max_queries`个检索查询
+    #[derive(Debug)]
+    pub struct LlmQueryExpander<C> {
+        client: C,
+        max_queries: usize,
+    }
+
+    impl<C: LlmClient + Debug> LlmQueryExpander<C> {
+        pub fn new(client: C, max_queries: usize) -> Self {
+            Self {
+                client,
+                max_queries,
+            }
+        }
+    }
+
+    impl<C: LlmClient + Debug> QueryExpander for LlmQueryExpander<C> {
+        fn expand(&self, query: &str) -> Result<Vec<String>, ExpansionError> {
+            let prompt = format!(
+                "Rewrite the task below into up to {} short search queries for a strategy \
+                 playbook, using synonyms and alternative phrasings.\n\nTask: {}\n\n\
+                 Reply with JSON only: {{\"queries\": [\"...\"]}}",
+                self.max_queries, query
+            );
+            let response = self
+                .client
+                .complete(&prompt)
+                .map_err(|err| ExpansionError::Failed(err.to_string()))?;
+            let value = extract_json(&response.text).ok_or_else(|| {
+                ExpansionError::Failed(format!("no JSON object in: {}", response.text))
+            })?;
+            let mut queries: Vec<String> = Vec::new();
+            for q in value["queries"].as_array().into_iter().flatten().filter_map(|q| q.as_str()) {
+                let q = q.trim();
+                if !q.is_empty() && q != query && !queries.iter().any(|known| known == q) {
+                    queries.push(q.to_string());
+                }
+            }
+            queries.truncate(self.max_queries);
+            Ok(queries)
+        }
+    }
+}
+
+/// 按某一路分数排名（从1开始），分数不为正的不参与排名
+fn ranks(results: &[Retrieved], score: impl Fn(&Retrieved) -> f64) -> Vec<Option<usize>> {
+    let mut order: Vec<usize> =
+        (0..results.len()).filter(|&i| score(&results[i]) > 0.0).collect();
+    order.sort_by(|&a, &b| score(&results[b]).total_cmp(&score(&results[a])));
+    let mut ranks = vec![None; results.len()];
+    for (rank, i) in order.into_iter().enumerate() {
+        ranks[i] = Some(rank + 1);
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::embedding::HashingEmbedder;
+
+    #[test]
+    fn test_hybrid_retrieve() {
+        let mut pb = Playbook::new();
+        let refund = pb.add_bullet("support", "Escalate refund requests over $500", None, None);
+        let retry = pb.add_bullet("api usage", "Retry payment API calls with backoff", None, None);
+        pb.add_bullet("debugging", "先看日志再看指标", None, None);
+
+        let index = Bm25Index::build(&pb);
+        let bm25 = index.scores("refund requests");
+        assert_eq!(bm25.keys().collect::<Vec<_>>(), [&refund]);
+
+        let mut store = EmbeddingStore::new(Arc::new(HashingEmbedder::default()));
+        store.refresh(&pb).unwrap();
+
+        for fusion in [
+            Fusion::default(),
+            Fusion::Weighted {
+                lexical: 0.5,
+                semantic: 0.5,
+            },
+        ] {
+            let hits = HybridRetriever::new(fusion)
+                .retrieve(&pb, &index, &store, "retry the payment api", 2)
+                .unwrap();
+            assert_eq!(hits[0].bullet_id, retry);
+            assert!(hits[0].lexical > 0.0 && hits[0].semantic > 0.0);
+        }
+
+        let config = r#"{"method": "reciprocal_rank", "k": 10}"#;
+        let config: Fusion = serde_json::from_str(config).unwrap();
+        assert_eq!(config, Fusion::ReciprocalRank { k: 10.0 });
+    }
+
+    #[test]
+    fn test_degrades_to_lexical_without_embedder() {
+        use crate::embedding::{DegradationLog, Embedder};
+
+        struct Down;
+        impl Embedder for Down {
+            fn model(&self) -> &str {
+                "down"
+            }
+
+            fn embed(&self, _: &[&str]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+                Err(EmbeddingError::Provider("connection refused".to_string()))
+            }
+        }
+        let mut pb = Playbook::new();
+        let refund = pb.add_bullet("support", "Escalate refund requests over $500", None, None);
+        let copy = pb.add_bullet("support", "Escalate refund requests over $500!", None, None);
+        pb.add_bullet("api usage", "Retry payment API calls with backoff", None, None);
+        let index = Bm25Index::build(&pb);
+        let log = Arc::new(DegradationLog::default());
+
+        let mut store = EmbeddingStore::new(Arc::new(Down));
+        let hits = HybridRetriever::new(Fusion::default())
+            .with_degradation_sink(log.clone())
+            .retrieve(&pb, &index, &store, "refund requests", 1)
+            .unwrap();
+        assert_eq!(hits[0].bullet_id, refund);
+        assert_eq!(hits[0].semantic, 0.0);
+
+        let delta = store.dedup_delta(&pb, 0.95, 0.8, log.as_ref());
+        assert_eq!(delta.operations[0].bullet_id.as_deref(), Some(copy.as_str()));
+        let events = log.events();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].operation, events[1].operation), ("retrieve", "dedup"));
+        assert!(events[0].error.contains("connection refused"));
+        let webhook = crate::webhook::WebhookEvent::from(&events[1]);
+        assert_eq!(webhook.name(), "degraded");
+
+        let mut store = EmbeddingStore::new(Arc::new(HashingEmbedder::default()));
+        let delta = store.dedup_delta(&pb, 0.95, 0.8, log.as_ref());
+        assert_eq!(delta.operations[0].bullet_id.as_deref(), Some(copy.as_str()));
+        assert!(delta.reasoning.ends_with("(embeddings)"));
+        assert_eq!(log.events().len(), 2);
+    }
+
+    #[test]
+    fn test_reranker_within_budget() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// 偏好提到"refund"的候选
+        #[derive(Debug, Default)]
+        struct KeywordReranker {
+            calls: AtomicUsize,
+        }
+
+        impl Reranker for KeywordReranker {
+            fn rerank(&self, _query: &str, candidates: &[&str]) -> Result<Vec<f64>, RerankError> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                Ok(candidates.iter().map(|c| c.contains("refund") as u8 as f64).collect())
+            }
+        }
+
+        let mut pb = Playbook::new();
+        let refund =
+            pb.add_bullet("support", "Check the refund policy for payment errors", None, None);
+        let retry = pb.add_bullet("api usage", "Retry payment API errors with backoff", None, None);
+        let index = Bm25Index::build(&pb);
+        let mut store = EmbeddingStore::new(Arc::new(HashingEmbedder::default()));
+        store.refresh(&pb).unwrap();
+        let query = "retry payment api errors";
+
+        let reranker = Arc::new(KeywordReranker::default());
+        let config = RerankConfig {
+            top_n: 2,
+            batch_size: 1,
+            budget_ms: None,
+        };
+        let retriever = HybridRetriever::default().with_reranker(reranker.clone(), config);
+        let hits = retriever.retrieve(&pb, &index, &store, query, 2).unwrap();
+        assert_eq!(hits[0].bullet_id, refund);
+        assert_eq!(hits[0].rerank, Some(1.0));
+        assert_eq!(reranker.calls.load(Ordering::SeqCst), 2);
+
+        // 预算用完后只执行第一批，未重排的保持融合顺序
+        let config = RerankConfig {
+            budget_ms: Some(0),
+            ..config
+        };
+        let retriever = HybridRetriever::default().with_reranker(reranker.clone(), config);
+        let hits = retriever.retrieve(&pb, &index, &store, query, 2).unwrap();
+        assert_eq!(hits[0].bullet_id, retry);
+        assert_eq!(hits[1].rerank, None);
+        assert_eq!(reranker.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[cfg(feature = "llm")]
+    #[test]
+    fn test_llm_backed() {
+        use crate::llm::DummyLlmClient;
+
+        let client = DummyLlmClient::with_responses([r#"{"scores": [2, 9]}"#, "no idea"]);
+        let reranker = LlmReranker::new(client);
+        assert_eq!(reranker.rerank("refund", &["a", "b"]).unwrap(), [2.0, 9.0]);
+        assert!(reranker.rerank("refund", &["a", "b"]).is_err());
+    }
+
+    #[cfg(feature = "llm")]
+    #[test]
+    fn test_query_expansion_improves_recall() {
+        use crate::llm::DummyLlmClient;
+
+        let mut pb = Playbook::new();
+        let refund = pb.add_bullet("support", "Escalate reimbursement disputes", None, None);
+        pb.add_bullet("debugging", "Read the logs first", None, None);
+        let index = Bm25Index::build(&pb);
+        let mut store = EmbeddingStore::new(Arc::new(HashingEmbedder::default()));
+        store.refresh(&pb).unwrap();
+
+        let plain = HybridRetriever::default();
+        assert!(plain.retrieve(&pb, &index, &store, "refund", 5).unwrap().is_empty());
+
+        let client = DummyLlmClient::with_responses([
+            r#"{"queries": ["refund", "reimbursement dispute", " ", "money back"]}"#,
+        ]);
+        let expander = LlmQueryExpander::new(client, 2);
+        let expanded = HybridRetriever::default().with_query_expander(Arc::new(expander));
+        let hits = expanded.retrieve(&pb, &index, &store, "refund", 5).unwrap();
+        assert_eq!(hits[0].bullet_id, refund);
+    }
+
+    #[test]
+    fn test_cache_invalidated_on_mutation() {
+        let mut pb = Playbook::new();
+        pb.add_bullet("api usage", "Retry payment API calls with backoff", None, None);
+        let mut index = Bm25Index::build(&pb);
+        let mut store = EmbeddingStore::new(Arc::new(HashingEmbedder::default()));
+        store.refresh(&pb).unwrap();
+
+        let cached = CachedRetriever::new(HybridRetriever::default(), 2);
+        let first = cached.retrieve(&pb, &index, &store, "retry payment", 3).unwrap();
+        let again = cached.retrieve(&pb, &index, &store, "  retry   payment ", 3).unwrap();
+        assert_eq!(first, again);
+        assert_eq!(cached.stats(), CacheStats { hits: 1, misses: 1, entries: 1 });
+
+        let added = pb.add_bullet("api usage", "Retry payment webhooks", None, None);
+        index = Bm25Index::build(&pb);
+        store.refresh(&pb).unwrap();
+        let fresh = cached.retrieve(&pb, &index, &store, "retry payment", 3).unwrap();
+        assert!(fresh.iter().any(|r| r.bullet_id == added));
+        assert_eq!(cached.stats().misses, 2);
+
+        for query in ["a", "b", "c"] {
+            cached.retrieve(&pb, &index, &store, query, 3).unwrap();
+        }
+        assert_eq!(cached.stats().entries, 2);
+    }
+
+    #[test]
+    fn test_retrieve_stage_in_pipeline() {
+        use crate::models::context::{ContextPipeline, TokenBudget};
+
+        let mut pb = Playbook::new();
+        let retry = pb.add_bullet("api usage", "Retry payment API calls with backoff", None, None);
+        pb.add_bullet("debugging", "Read the logs first", None, None);
+        let index = Bm25Index::build(&pb);
+        let mut store = EmbeddingStore::new(Arc::new(HashingEmbedder::default()));
+        store.refresh(&pb).unwrap();
+
+        let retriever = HybridRetriever::default();
+        let pipeline = ContextPipeline::new()
+            .with_stage(RetrieveStage {
+                retriever: &retriever,
+                index: &index,
+                store: &store,
+                limit: 1,
+            })
+            .with_stage(TokenBudget { max_tokens: 1000 });
+        let prompt = pipeline.run(&pb, "payment retry").unwrap();
+        assert!(prompt.contains(&retry));
+        assert!(!prompt.contains("debugging"));
+    }
+}
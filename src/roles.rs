@@ -0,0 +1,658 @@
+//! ACE的角色（对齐Python的`roles.py`）：Generator根据Playbook回答问题，Curator经reflector与
+//! curator两次调用把轨迹变成Delta；[`RecordedRun`]录制一次运行，之后可逐字节一致地重放
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::clock::ManualClock;
+use crate::embedding::{EmbeddingError, EmbeddingStore};
+use crate::episodes::ContextLog;
+use crate::llm::{
+    LlmClient, LlmError, Recording, RecordingClient, StreamEvent, ToolCall, extract_json,
+};
+use crate::models::delta::{DeltaBatch, DeltaOperation};
+use crate::models::playbook::{Bullet, BulletId, Playbook, PlaybookError};
+use crate::prompts::PromptSet;
+use crate::retrieval::{Bm25Index, HybridRetriever};
+
+#[derive(Debug, Error)]
+pub enum RoleError {
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+    #[error("模型输出无效：{0}")]
+    InvalidOutput(String),
+    #[error(transparent)]
+    Retrieval(#[from] EmbeddingError),
+    /// 流式生成中途失败（如超时），`partial`为失败前已收到的输出
+    #[error("生成中断（已收到{}个字符）：{source}", partial.text.chars().count())]
+    Interrupted { partial: StreamCapture, source: LlmError },
+    #[error(transparent)]
+    Playbook(#[from] PlaybookError),
+    /// 重放结束时仍有录制的响应没有用到，说明本次运行与录制的运行已经分叉
+    #[error("回放分叉：还有{0}个录制的响应未使用")]
+    ReplayDiverged(usize),
+}
+
+/// 流式生成中逐段记录的输出与工具调用
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamCapture {
+    pub text: String,
+    pub tool_calls: Vec<ToolCall>,
+    /// 已收到的事件数
+    pub events: usize,
+}
+
+impl StreamCapture {
+    fn record(&mut self, event: &StreamEvent) {
+        self.events += 1;
+        match event {
+            StreamEvent::Text(chunk) => self.text.push_str(chunk),
+            StreamEvent::ToolCall(call) => self.tool_calls.push(call.clone()),
+        }
+    }
+}
+
+/// 判定生成结果依赖了哪些子弹的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AttributionMode {
+    /// 只用模型在JSON中报告的`bullet_ids`
+    Reported,
+    /// 只解析推理和答案中的`[id]`引用
+    Citations,
+    /// 两者合并
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorOutput {
+    pub reasoning: String,
+    pub final_answer: String,
+    /// 归因到的子弹（只包含Playbook中确实存在的ID）
+    pub bullet_ids: Vec<BulletId>,
+    pub raw: Value,
+    /// 注入提示词的上下文，开启 [`Generator::with_context_logging`] 时才有
+    pub context: Option<ContextLog>,
+    /// 流式生成中模型发起的工具调用（见 [`Generator::generate_streaming`]）
+    pub tool_calls: Vec<ToolCall>,
+}
+
+impl GeneratorOutput {
+    /// 把归因结果转换为TAG操作，例如答对时 `("helpful", 1)`，答错时 `("harmful", 1)`
+    pub fn tag_delta(&self, playbook: &Playbook, tag: &str, increment: i32) -> DeltaBatch {
+        let operations = self
+            .bullet_ids
+            .iter()
+            .filter_map(|id| playbook.get_bullet(id))
+            .map(|bullet| {
+                DeltaOperation::tag(&*bullet.section, &bullet.id, [(tag.to_string(), increment)].into())
+            })
+            .collect();
+        DeltaBatch {
+            reasoning: format!("attribution: {} {:+}", tag, increment),
+            operations,
+        }
+    }
+}
+
+/// 用Playbook作为上下文回答问题，并报告用到的子弹
+#[derive(Debug)]
+pub struct Generator<C> {
+    client: C,
+    attribution: AttributionMode,
+    /// 检索器及注入提示词的子弹数（见 [`Generator::generate_retrieved`]）
+    retrieval: Option<(HybridRetriever, usize)>,
+    log_context: bool,
+}
+
+impl<C: LlmClient> Generator<C> {
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            attribution: AttributionMode::default(),
+            retrieval: None,
+            log_context: false,
+        }
+    }
+
+    /// 在输出中记录实际注入的子弹与文本（[`GeneratorOutput::context`]），
+    /// 可再用 [`EpisodeStore::set_context`](crate::episodes::EpisodeStore::set_context) 存入情景记录
+    pub fn with_context_logging(mut self, enabled: bool) -> Self {
+        self.log_context = enabled;
+        self
+    }
+
+    pub fn with_attribution(mut self, mode: AttributionMode) -> Self {
+        self.attribution = mode;
+        self
+    }
+
+    /// 配置检索（包括查询扩展、重排序），`generate_retrieved`只注入最相关的`limit`个子弹
+    pub fn with_retriever(mut self, retriever: HybridRetriever, limit: usize) -> Self {
+        self.retrieval = Some((retriever, limit));
+        self
+    }
+
+    pub fn generate(
+        &self,
+        question: &str,
+        context: &str,
+        playbook: &Playbook,
+        reflection: Option<&str>,
+    ) -> Result<GeneratorOutput, RoleError> {
+        let rendered = playbook.as_prompt();
+        let prompt = self.prompt(question, context, &rendered, None, reflection);
+        let log = self.log(playbook, &rendered_ids(playbook, |_| true), || rendered.clone());
+        self.complete(&prompt, playbook, log)
+    }
+
+    /// 上一次尝试出错后重试：除Playbook外，注入错误文本及与之匹配的已知修复方法
+    /// （[`Playbook::match_failure`]）
+    pub fn generate_after_error(
+        &self,
+        question: &str,
+        context: &str,
+        playbook: &Playbook,
+        error_text: &str,
+        reflection: Option<&str>,
+    ) -> Result<GeneratorOutput, RoleError> {
+        let mut notes = format!("{}\n", error_text.trim_end());
+        let failures = playbook.as_prompt_failures(&playbook.match_failure(error_text));
+        if !failures.is_empty() {
+            let _ = write!(notes, "\n{}\n", failures);
+        }
+        let notes = ("Last error", notes.as_str());
+        let rendered = playbook.as_prompt();
+        let prompt = self.prompt(question, context, &rendered, Some(notes), reflection);
+        let log = self.log(playbook, &rendered_ids(playbook, |_| true), || rendered.clone());
+        self.complete(&prompt, playbook, log)
+    }
+
+    /// 可以调用工具时使用：策略子弹照常注入，`tools`中各工具的技能子弹按工具分组单独注入
+    pub fn generate_with_tools(
+        &self,
+        question: &str,
+        context: &str,
+        playbook: &Playbook,
+        tools: &[&str],
+        reflection: Option<&str>,
+    ) -> Result<GeneratorOutput, RoleError> {
+        let strategies: Vec<BulletId> = playbook
+            .bullets
+            .values()
+            .filter(|b| b.skill.is_none())
+            .map(|b| b.id.clone())
+            .collect();
+        let selected = playbook.as_prompt_for_bullets(&strategies);
+        let tool_notes = playbook.as_tool_prompt_for(Some(tools));
+        let notes = ("Tool usage notes", tool_notes.as_str());
+        let prompt = self.prompt(question, context, &selected, Some(notes), reflection);
+        let ids = rendered_ids(playbook, |b| {
+            b.skill.as_ref().is_none_or(|skill| tools.contains(&skill.tool.as_str()))
+        });
+        let log = self.log(playbook, &ids, || format!("{}\n\n{}", selected, tool_notes));
+        self.complete(&prompt, playbook, log)
+    }
+
+    /// 同`generate`，但以流式补全调用模型：每收到一段输出或一个工具调用就更新记录并调用
+    /// `observe`，便于实时观察长时间的生成；中途失败时返回 [`RoleError::Interrupted`]，
+    /// 其中保留已收到的部分
+    pub fn generate_streaming(
+        &self,
+        question: &str,
+        context: &str,
+        playbook: &Playbook,
+        reflection: Option<&str>,
+        mut observe: impl FnMut(&StreamEvent, &StreamCapture),
+    ) -> Result<GeneratorOutput, RoleError> {
+        let rendered = playbook.as_prompt();
+        let prompt = self.prompt(question, context, &rendered, None, reflection);
+        let log = self.log(playbook, &rendered_ids(playbook, |_| true), || rendered.clone());
+        let mut capture = StreamCapture::default();
+        let result = self.client.complete_stream(&prompt, &mut |event| {
+            capture.record(&event);
+            observe(&event, &capture);
+        });
+        let response = match result {
+            Ok(response) => response,
+            Err(source) => return Err(RoleError::Interrupted { partial: capture, source }),
+        };
+        let mut output = self.parse(&response.text, playbook, log)?;
+        output.tool_calls = capture.tool_calls;
+        Ok(output)
+    }
+
+    /// 先以问题为查询检索子弹，只把命中的子弹放进提示词；未配置检索器时与`generate`相同
+    ///
+    /// `index`与`store`需与`playbook`当前内容同步。
+    pub fn generate_retrieved(
+        &self,
+        question: &str,
+        context: &str,
+        playbook: &Playbook,
+        (index, store): (&Bm25Index, &EmbeddingStore),
+        reflection: Option<&str>,
+    ) -> Result<GeneratorOutput, RoleError> {
+        let Some((retriever, limit)) = &self.retrieval else {
+            return self.generate(question, context, playbook, reflection);
+        };
+        let hits = retriever.retrieve(playbook, index, store, question, *limit)?;
+        let ids: Vec<BulletId> = hits.into_iter().map(|hit| hit.bullet_id).collect();
+        let selected = playbook.as_prompt_for_bullets(&ids);
+        let prompt = self.prompt(question, context, &selected, None, reflection);
+        let ids = rendered_ids(playbook, |b| ids.contains(&b.id));
+        let log = self.log(playbook, &ids, || selected.clone());
+        self.complete(&prompt, playbook, log)
+    }
+
+    fn log(
+        &self,
+        playbook: &Playbook,
+        ids: &[BulletId],
+        text: impl FnOnce() -> String,
+    ) -> Option<ContextLog> {
+        self.log_context.then(|| ContextLog::capture(playbook, ids, text()))
+    }
+
+    fn complete(
+        &self,
+        prompt: &str,
+        playbook: &Playbook,
+        context: Option<ContextLog>,
+    ) -> Result<GeneratorOutput, RoleError> {
+        let text = self.client.complete(prompt)?.text;
+        self.parse(&text, playbook, context)
+    }
+
+    fn parse(
+        &self,
+        text: &str,
+        playbook: &Playbook,
+        context: Option<ContextLog>,
+    ) -> Result<GeneratorOutput, RoleError> {
+        let raw = extract_json(text)
+            .ok_or_else(|| RoleError::InvalidOutput(format!("no JSON object in: {}", text)))?;
+
+        let reasoning = raw["reasoning"].as_str().unwrap_or_default().to_string();
+        let final_answer = raw["final_answer"]
+            .as_str()
+            .ok_or_else(|| RoleError::InvalidOutput("missing final_answer".to_string()))?
+            .to_string();
+        let bullet_ids = self.attribute(&raw, &reasoning, &final_answer, playbook);
+
+        Ok(GeneratorOutput {
+            reasoning,
+            final_answer,
+            bullet_ids,
+            raw,
+            context,
+            tool_calls: Vec::new(),
+        })
+    }
+
+    fn prompt(
+        &self,
+        question: &str,
+        context: &str,
+        playbook: &str,
+        notes: Option<(&str, &str)>,
+        reflection: Option<&str>,
+    ) -> String {
+        let mut prompt = String::from(
+            "You are an expert assistant. Use the playbook strategies below when they apply.\n\
+             Cite every strategy you rely on by its id in square brackets, e.g. [api-00012].\n",
+        );
+        let _ = write!(prompt, "\nPlaybook:\n{}\n", playbook);
+        if let Some((title, body)) = notes.filter(|(_, body)| !body.is_empty()) {
+            let _ = write!(prompt, "\n{}:\n{}\n", title, body.trim_end());
+        }
+        if let Some(reflection) = reflection.filter(|r| !r.is_empty()) {
+            let _ = write!(prompt, "\nRecent reflection:\n{}\n", reflection);
+        }
+        let _ = write!(prompt, "\nQuestion: {}\n", question);
+        if !context.is_empty() {
+            let _ = writeln!(prompt, "Context: {}", context);
+        }
+        prompt.push_str(
+            "\nReply with JSON only: {\"reasoning\": \"...\", \"bullet_ids\": [\"<ids you used>\"], \
+             \"final_answer\": \"...\"}",
+        );
+        prompt
+    }
+
+    fn attribute(&self, raw: &Value, reasoning: &str, answer: &str, playbook: &Playbook) -> Vec<BulletId> {
+        let mut ids: Vec<BulletId> = Vec::new();
+        let mut push = |id: &str| {
+            if playbook.get_bullet(id).is_some() && !ids.iter().any(|known| known == id) {
+                ids.push(id.to_string());
+            }
+        };
+
+        if self.attribution != AttributionMode::Citations {
+            let reported = raw["bullet_ids"].as_array().into_iter().flatten();
+            reported.filter_map(Value::as_str).for_each(&mut push);
+        }
+        if self.attribution != AttributionMode::Reported {
+            for text in [reasoning, answer] {
+                playbook.cited_bullets(text).iter().for_each(|id| push(id));
+            }
+        }
+        ids
+    }
+}
+
+/// 先由reflector总结轨迹，再由curator把总结变成DeltaBatch（模板见 [`PromptSet`]）
+#[derive(Debug)]
+pub struct Curator<C> {
+    client: C,
+    prompts: PromptSet,
+}
+
+impl<C: LlmClient> Curator<C> {
+    pub fn new(client: C) -> Self {
+        Self { client, prompts: PromptSet::default() }
+    }
+
+    pub fn with_prompts(mut self, prompts: PromptSet) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// 根据一条轨迹给出对`playbook`的修改（不应用）
+    pub fn curate(&self, playbook: &Playbook, trajectory: &str) -> Result<DeltaBatch, RoleError> {
+        let rendered = playbook.as_prompt();
+        let prompt = self.prompts.reflector.render(&rendered, trajectory);
+        let reflection = self.client.complete(&prompt)?.text;
+        let trajectory = format!("{}\n\nReflection:\n{}", trajectory.trim_end(), reflection.trim());
+        let prompt = self.prompts.curator.render(&rendered, &trajectory);
+        let text = self.client.complete(&prompt)?.text;
+        let raw = extract_json(&text)
+            .ok_or_else(|| RoleError::InvalidOutput(format!("no JSON object in: {}", text)))?;
+        DeltaBatch::from_json(&raw).map_err(|e| RoleError::InvalidOutput(e.to_string()))
+    }
+
+    /// 依次处理每条轨迹并应用得到的Delta，返回应用的操作数
+    pub fn run<S: AsRef<str>>(
+        &self,
+        playbook: &mut Playbook,
+        trajectories: &[S],
+    ) -> Result<usize, RoleError> {
+        let mut applied = 0;
+        for trajectory in trajectories {
+            let delta = self.curate(playbook, trajectory.as_ref())?;
+            applied += delta.operations.len();
+            playbook.apply_delta(delta)?;
+        }
+        Ok(applied)
+    }
+}
+
+/// 一次录制的运行：LLM调用的录制（含起始时刻与种子）与按顺序处理的轨迹
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRun {
+    pub recording: Recording,
+    pub trajectories: Vec<String>,
+}
+
+impl RecordedRun {
+    /// 用 [`Curator::run`] 在`playbook`上执行一次运行并录制。Playbook的时钟换成停在当前时刻的
+    /// [`ManualClock`]，重放时注入同一时刻；`seed`原样记入录制，供调用方带随机性的步骤使用
+    pub fn record<C: LlmClient>(
+        client: C,
+        prompts: PromptSet,
+        playbook: &mut Playbook,
+        trajectories: Vec<String>,
+        seed: u64,
+    ) -> Result<Self, RoleError> {
+        let started_at = playbook.clock().now();
+        playbook.set_clock(Arc::new(ManualClock::new(started_at)));
+        let recorder = RecordingClient::new(client);
+        Curator::new(&recorder).with_prompts(prompts).run(playbook, &trajectories)?;
+        Ok(Self { recording: recorder.recording(started_at, seed), trajectories })
+    }
+
+    /// 以录制的时钟与响应在`playbook`上重新执行（起点须与录制时的Playbook相同），得到与录制时
+    /// 逐字节一致的Playbook，返回应用的操作数。提示词与录制不一致时返回
+    /// [`LlmError::ReplayMiss`]，有响应没有用到时返回 [`RoleError::ReplayDiverged`]
+    pub fn replay(&self, playbook: &mut Playbook, prompts: PromptSet) -> Result<usize, RoleError> {
+        playbook.set_clock(self.recording.clock());
+        let client = self.recording.client();
+        let curator = Curator::new(&client).with_prompts(prompts);
+        let applied = curator.run(playbook, &self.trajectories)?;
+        match client.remaining() {
+            0 => Ok(applied),
+            unused => Err(RoleError::ReplayDiverged(unused)),
+        }
+    }
+}
+
+/// 渲染时会输出的子弹（章节顺序、未过期）中满足`keep`的ID
+fn rendered_ids(playbook: &Playbook, keep: impl Fn(&Bullet) -> bool) -> Vec<BulletId> {
+    let now = playbook.clock().now();
+    playbook
+        .sorted_sections()
+        .into_iter()
+        .flat_map(|(_, ids)| ids)
+        .filter_map(|id| playbook.get_bullet(id))
+        .filter(|b| !b.is_expired_at(now) && keep(b))
+        .map(|b| b.id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::DummyLlmClient;
+
+    fn playbook() -> Playbook {
+        let mut pb = Playbook::new();
+        pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        pb.add_bullet("debugging", "先看日志", None, None);
+        pb
+    }
+
+    #[test]
+    fn test_attribution_merges_reported_and_cited() {
+        let pb = playbook();
+        let client = DummyLlmClient::with_responses([r#"{
+            "reasoning": "Following [debugging-00002] and [unknown-00009].",
+            "bullet_ids": ["api-00001", "missing-1"],
+            "final_answer": "42"
+        }"#]);
+        let output = Generator::new(&client).generate("q", "", &pb, None).unwrap();
+
+        assert_eq!(output.final_answer, "42");
+        assert_eq!(output.bullet_ids, vec!["api-00001", "debugging-00002"]);
+        assert!(client.prompts()[0].contains("[api-00001]"));
+    }
+
+    #[test]
+    fn test_tag_delta_from_attribution() {
+        let mut pb = playbook();
+        let client = DummyLlmClient::with_responses([
+            r#"{"reasoning": "see [api-00001]", "final_answer": "ok"}"#,
+        ]);
+        let output = Generator::new(&client)
+            .with_attribution(AttributionMode::Citations)
+            .generate("q", "", &pb, None)
+            .unwrap();
+
+        let delta = output.tag_delta(&pb, "helpful", 1);
+        assert_eq!(delta.operations.len(), 1);
+        assert_eq!(delta.operations[0].section, "api usage");
+        pb.apply_delta(delta).unwrap();
+        assert_eq!(pb.get_bullet("api-00001").unwrap().helpful, 1);
+    }
+
+    #[test]
+    fn test_generate_with_retrieval() {
+        use std::sync::Arc;
+
+        use crate::embedding::HashingEmbedder;
+
+        let pb = playbook();
+        let index = Bm25Index::build(&pb);
+        let mut store = EmbeddingStore::new(Arc::new(HashingEmbedder::default()));
+        store.refresh(&pb).unwrap();
+
+        let client = DummyLlmClient::with_responses([r#"{"final_answer": "ok"}"#]);
+        Generator::new(&client)
+            .with_retriever(HybridRetriever::default(), 1)
+            .generate_retrieved("怎么看日志", "", &pb, (&index, &store), None)
+            .unwrap();
+        let prompt = &client.prompts()[0];
+        assert!(prompt.contains("[debugging-00002]"));
+        assert!(!prompt.contains("[api-00001]"));
+    }
+
+    #[test]
+    fn test_generate_with_tool_notes() {
+        use crate::models::skill::Skill;
+
+        let mut pb = playbook();
+        for (tool, content) in [("grep", "先grep错误码"), ("curl", "带上超时参数")] {
+            let id = pb.add_bullet("tools", content, None, None);
+            let skill = Skill {
+                tool: tool.into(),
+                ..Default::default()
+            };
+            pb.set_skill(&id, Some(skill)).unwrap();
+        }
+
+        let client = DummyLlmClient::with_responses([r#"{"final_answer": "ok"}"#]);
+        Generator::new(&client).generate_with_tools("q", "", &pb, &["grep"], None).unwrap();
+        let prompt = &client.prompts()[0];
+        let (strategies, notes) = prompt.split_once("Tool usage notes:").unwrap();
+        assert!(strategies.contains("[api-00001]") && !strategies.contains("grep"));
+        assert!(notes.contains("## Tool: grep") && !notes.contains("curl"));
+
+        let client = DummyLlmClient::with_responses([r#"{"final_answer": "ok"}"#]);
+        let generator = Generator::new(&client).with_context_logging(true);
+        let output = generator.generate_with_tools("q", "", &pb, &["grep"], None).unwrap();
+        let log = output.context.unwrap();
+        assert_eq!(log.bullets.len(), 3);
+        assert!(log.text.contains("## Tool: grep"));
+    }
+
+    #[test]
+    fn test_context_logging_into_episodes() {
+        use crate::episodes::EpisodeStore;
+        use crate::feedback::Outcome;
+
+        let pb = playbook();
+        let response = r#"{"final_answer": "ok"}"#;
+        let client = DummyLlmClient::with_responses([response, response]);
+        let output = Generator::new(&client).generate("q", "", &pb, None).unwrap();
+        assert!(output.context.is_none());
+
+        let generator = Generator::new(&client).with_context_logging(true);
+        let output = generator.generate("q", "", &pb, None).unwrap();
+        let log = output.context.unwrap();
+        let ids: Vec<&str> = log.bullets.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, ["api-00001", "debugging-00002"]);
+        assert_eq!(log.bullets[1].content, "先看日志");
+        assert_eq!(log.text, pb.as_prompt());
+
+        let mut store = EpisodeStore::new();
+        let now = chrono::DateTime::UNIX_EPOCH;
+        let episode = store.record("q", Outcome::TestPassed, "", output.bullet_ids, now);
+        assert!(store.set_context(episode, log.clone()));
+        assert!(!store.set_context(episode + 1, log.clone()));
+        let json = serde_json::to_string(&store).unwrap();
+        let restored: EpisodeStore = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.episodes[0].context, Some(log));
+    }
+
+    #[test]
+    fn test_streaming_records_partial_output() {
+        let pb = playbook();
+        let answer = r#"{"reasoning": "[api-00001]", "final_answer": "42"}"#;
+        let client = DummyLlmClient::with_responses([answer]).with_chunking(8);
+        let mut seen = Vec::new();
+        let output = Generator::new(&client)
+            .generate_streaming("q", "", &pb, None, |_, capture| seen.push(capture.text.clone()))
+            .unwrap();
+        assert_eq!(output.final_answer, "42");
+        assert_eq!(output.bullet_ids, ["api-00001"]);
+        assert_eq!(seen.len(), answer.len().div_ceil(8));
+        assert_eq!(seen[0], &answer[..8]);
+        assert_eq!(seen.last().unwrap(), answer);
+
+        // 超时前收到的输出与工具调用都保留下来
+        struct Timeout;
+        impl LlmClient for Timeout {
+            fn complete(&self, _: &str) -> Result<crate::llm::LlmResponse, LlmError> {
+                unreachable!()
+            }
+
+            fn complete_stream(
+                &self,
+                _: &str,
+                on_event: &mut dyn FnMut(StreamEvent),
+            ) -> Result<crate::llm::LlmResponse, LlmError> {
+                on_event(StreamEvent::Text("Checking the logs".to_string()));
+                let call = ToolCall { name: "grep".to_string(), arguments: "error".into() };
+                on_event(StreamEvent::ToolCall(call));
+                Err(LlmError::Provider("timeout".to_string()))
+            }
+        }
+        let err = Generator::new(Timeout).generate_streaming("q", "", &pb, None, |_, _| {});
+        let Err(RoleError::Interrupted { partial, source }) = err else { panic!() };
+        assert_eq!(partial.text, "Checking the logs");
+        assert_eq!(partial.tool_calls[0].name, "grep");
+        assert_eq!(partial.events, 2);
+        assert!(matches!(source, LlmError::Provider(_)));
+    }
+
+    #[test]
+    fn test_generate_after_error_injects_matching_fixes() {
+        let mut pb = playbook();
+        let refused = pb.add_failure("connection refused", "服务没启动", "先启动服务");
+        let limited = pb.add_failure("rate limit exceeded", "请求太快", "退避后重试");
+
+        let client = DummyLlmClient::with_responses([r#"{"final_answer": "ok"}"#]);
+        Generator::new(&client)
+            .generate_after_error("q", "", &pb, "curl: (7) Connection refused", None)
+            .unwrap();
+        let prompt = &client.prompts()[0];
+        assert!(prompt.contains("Last error:\ncurl: (7) Connection refused\n\n## Known failures"));
+        assert!(prompt.contains(&format!("[{}]", refused)));
+        assert!(!prompt.contains(&format!("[{}]", limited)));
+    }
+
+    #[test]
+    fn test_recorded_run_replays_byte_for_byte() {
+        let base = playbook();
+        let client = DummyLlmClient::with_responses([
+            r#"{"insights": [{"content": "先分页再过滤"}]}"#,
+            r#"{"reasoning": "r", "operations": [
+                {"type": "add", "section": "api usage", "content": "先分页再过滤"}]}"#,
+            r#"{"insights": []}"#,
+            r#"{"reasoning": "r", "operations": [{"type": "tag", "section": "api usage",
+                "bullet_id": "api-00001", "metadata": {"helpful": 1}}]}"#,
+        ]);
+        let trajectories = vec!["第一次".to_string(), "第二次".to_string()];
+        let mut recorded = base.clone();
+        let run = RecordedRun::record(&client, PromptSet::default(), &mut recorded, trajectories, 7)
+            .unwrap();
+        assert_eq!(run.recording.calls.len(), 4);
+        assert!(client.prompts()[2].contains("先分页再过滤"));
+
+        let run: RecordedRun = serde_json::from_str(&serde_json::to_string(&run).unwrap()).unwrap();
+        let mut replayed = base.clone();
+        assert_eq!(run.replay(&mut replayed, PromptSet::default()).unwrap(), 2);
+        assert_eq!(replayed.to_json().unwrap(), recorded.to_json().unwrap());
+
+        // 起点不同：提示词对不上录制
+        let err = run.replay(&mut Playbook::new(), PromptSet::default()).unwrap_err();
+        assert!(matches!(err, RoleError::Llm(LlmError::ReplayMiss(_))));
+        // 只处理第一条轨迹：第二条的响应没有用到
+        let partial = RecordedRun { trajectories: run.trajectories[..1].to_vec(), ..run };
+        let err = partial.replay(&mut base.clone(), PromptSet::default()).unwrap_err();
+        assert!(matches!(err, RoleError::ReplayDiverged(2)));
+    }
+}
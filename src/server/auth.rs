@@ -0,0 +1,264 @@
+//! API key鉴权：每个key带一组权限范围，请求按方法和路径确定所需范围
+//!
+//! 只保存token的SHA-256，token本身仅在签发时返回一次。HTTP层对每个请求调用
+//! [`ApiKeys::authorize_request`]，按 [`AuthError::status`] 返回401/403。
+
+use std::collections::BTreeSet;
+use std::io;
+
+use chrono::{DateTime, Utc};
+use ring::digest::{SHA256, digest};
+use ring::rand::{SecureRandom as _, SystemRandom};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::hex::to_hex;
+use crate::models::timestamp;
+
+/// 权限范围，高级别包含低级别：`admin` ⊃ `curate` ⊃ `tag` ⊃ `read`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// 读取Playbook、渲染提示词
+    Read,
+    /// 给子弹打标签（反馈）
+    Tag,
+    /// 应用Delta、增删改子弹
+    Curate,
+    /// 管理API key等
+    Admin,
+}
+
+impl Scope {
+    /// 请求所需的范围：`/keys`、`/admin`下的请求需要admin，其余GET/HEAD为read，
+    /// 打标签与反馈为tag，其他修改为curate；路径先规范化（见`normalize_path`）
+    pub fn required_for(method: &str, path: &str) -> Self {
+        let path = normalize_path(path);
+        let path = path.as_str();
+        let under = |prefix: &str| {
+            path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        };
+        if under("/keys") || under("/admin") {
+            return Self::Admin;
+        }
+        if method.eq_ignore_ascii_case("GET") || method.eq_ignore_ascii_case("HEAD") {
+            return Self::Read;
+        }
+        if path.ends_with("/tag") || under("/feedback") {
+            return Self::Tag;
+        }
+        Self::Curate
+    }
+}
+
+/// 去掉查询串与片段，转为小写，合并重复的`/`并解析`.`、`..`段（包括`%2e`编码的点），
+/// 避免`/KEYS`、`/playbook/../keys`之类的写法绕过范围判断
+fn normalize_path(path: &str) -> String {
+    let path = path.split(['?', '#']).next().unwrap_or_default().to_ascii_lowercase();
+    let mut segments: Vec<String> = Vec::new();
+    for segment in path.split('/') {
+        let segment = segment.replace("%2e", ".");
+        match segment.as_str() {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("缺少API key")]
+    Missing,
+    #[error("API key无效或已吊销")]
+    Invalid,
+    #[error("API key {key_id} 没有 {required:?} 权限")]
+    Forbidden { key_id: String, required: Scope },
+}
+
+impl AuthError {
+    /// 对应的HTTP状态码
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::Missing | Self::Invalid => 401,
+            Self::Forbidden { .. } => 403,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    /// 使用方说明，如 `curator-bot`
+    pub name: String,
+    token_sha256: String,
+    pub scopes: BTreeSet<Scope>,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+impl ApiKey {
+    pub fn allows(&self, required: Scope) -> bool {
+        !self.revoked && self.scopes.iter().any(|&scope| scope >= required)
+    }
+}
+
+/// API key集合，可序列化进服务配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeys {
+    pub keys: Vec<ApiKey>,
+    #[serde(default)]
+    next_id: u64,
+}
+
+impl ApiKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 签发新key，返回 `(key ID, token)`；token只在此时可见
+    ///
+    /// token为系统随机数源（ring的`SystemRandom`）的32字节；读取失败时返回错误，不退回其他来源。
+    pub fn issue(
+        &mut self,
+        name: impl Into<String>,
+        scopes: impl IntoIterator<Item = Scope>,
+        now: DateTime<Utc>,
+    ) -> io::Result<(String, String)> {
+        let token = format!("ace_{}", random_hex()?);
+        let id = self.insert(name, &token, scopes, now);
+        Ok((id, token))
+    }
+
+    /// 登记外部生成的token
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        token: &str,
+        scopes: impl IntoIterator<Item = Scope>,
+        now: DateTime<Utc>,
+    ) -> String {
+        self.next_id += 1;
+        let id = format!("key-{:03}", self.next_id);
+        self.keys.push(ApiKey {
+            id: id.clone(),
+            name: name.into(),
+            token_sha256: to_hex(digest(&SHA256, token.as_bytes()).as_ref()),
+            scopes: scopes.into_iter().collect(),
+            created_at: now,
+            revoked: false,
+        });
+        id
+    }
+
+    pub fn revoke(&mut self, key_id: &str) -> bool {
+        let key = self.keys.iter_mut().find(|key| key.id == key_id && !key.revoked);
+        key.map(|key| key.revoked = true).is_some()
+    }
+
+    /// 按token查找未吊销的key（哈希按常数时间比较）
+    pub fn authenticate(&self, token: &str) -> Option<&ApiKey> {
+        let hash = to_hex(digest(&SHA256, token.as_bytes()).as_ref());
+        self.keys
+            .iter()
+            .filter(|key| !key.revoked)
+            .find(|key| constant_time_eq(key.token_sha256.as_bytes(), hash.as_bytes()))
+    }
+
+    /// 校验`Authorization`头（`Bearer <token>`）是否具备所需范围
+    pub fn authorize(
+        &self,
+        authorization: Option<&str>,
+        required: Scope,
+    ) -> Result<&ApiKey, AuthError> {
+        let header = authorization.map(str::trim).filter(|h| !h.is_empty());
+        let header = header.ok_or(AuthError::Missing)?;
+        let token = header.strip_prefix("Bearer ").ok_or(AuthError::Invalid)?.trim();
+        let key = self.authenticate(token).ok_or(AuthError::Invalid)?;
+        if !key.allows(required) {
+            return Err(AuthError::Forbidden {
+                key_id: key.id.clone(),
+                required,
+            });
+        }
+        Ok(key)
+    }
+
+    /// 鉴权中间件入口：按 [`Scope::required_for`] 确定范围后调用 [`ApiKeys::authorize`]
+    pub fn authorize_request(
+        &self,
+        method: &str,
+        path: &str,
+        authorization: Option<&str>,
+    ) -> Result<&ApiKey, AuthError> {
+        self.authorize(authorization, Scope::required_for(method, path))
+    }
+}
+
+/// 从操作系统的密码学随机数源读取256位，编码为十六进制
+fn random_hex() -> io::Result<String> {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| io::Error::other("系统随机数源不可用"))?;
+    Ok(to_hex(&bytes))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes_and_bearer_tokens() {
+        let now = DateTime::UNIX_EPOCH;
+        let mut keys = ApiKeys::new();
+        let (reader, read_token) = keys.issue("dashboard", [Scope::Read], now).unwrap();
+        let (_, curate_token) = keys.issue("curator-bot", [Scope::Curate], now).unwrap();
+        assert!(read_token.starts_with("ace_") && read_token.len() == 68);
+        assert_ne!(read_token, curate_token);
+
+        let bearer = |token: &str| format!("Bearer {}", token);
+        let read = bearer(&read_token);
+        let curate = bearer(&curate_token);
+        assert!(keys.authorize_request("GET", "/playbook", Some(&read)).is_ok());
+        let err = keys.authorize_request("POST", "/delta", Some(&read)).unwrap_err();
+        assert_eq!(err.status(), 403);
+        let required = Scope::Curate;
+        assert_eq!(err, AuthError::Forbidden { key_id: reader.clone(), required });
+        assert!(keys.authorize_request("POST", "/bullets/x/tag", Some(&curate)).is_ok());
+        let admin = keys.authorize_request("DELETE", "/keys/key-001", Some(&curate));
+        assert_eq!(admin.unwrap_err().status(), 403);
+        assert_eq!(keys.authorize_request("GET", "/", None).unwrap_err(), AuthError::Missing);
+        let wrong = keys.authorize_request("GET", "/", Some("Bearer nope"));
+        assert_eq!(wrong.unwrap_err(), AuthError::Invalid);
+
+        assert!(keys.revoke(&reader));
+        let revoked = keys.authorize_request("GET", "/playbook", Some(&read));
+        assert_eq!(revoked.unwrap_err().status(), 401);
+
+        let json = serde_json::to_string(&keys).unwrap();
+        assert!(!json.contains(&curate_token));
+        assert_eq!(serde_json::from_str::<ApiKeys>(&json).unwrap(), keys);
+    }
+
+    #[test]
+    fn test_required_scope_normalizes_paths() {
+        let admin = ["/KEYS", "/keys/", "//keys", "/playbook/../keys", "/./admin/x", "/x/%2E%2e/keys"];
+        for path in admin {
+            assert_eq!(Scope::required_for("GET", path), Scope::Admin, "{}", path);
+        }
+        assert_eq!(Scope::required_for("POST", "/Bullets/x/TAG?y=1"), Scope::Tag);
+        assert_eq!(Scope::required_for("POST", "/feedback/./"), Scope::Tag);
+        assert_eq!(Scope::required_for("GET", "/../playbook"), Scope::Read);
+        assert_eq!(Scope::required_for("POST", "/keysmith"), Scope::Curate);
+    }
+}
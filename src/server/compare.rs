@@ -0,0 +1,172 @@
+//! 版本比较接口：`GET /compare?from=<label>[&to=<label>]`，返回两个Playbook快照之间
+//! 新增、删除、有变化的子弹及计数变化（[`PlaybookComparison`]），用于"这周改了什么"报告
+//!
+//! 快照是 [`SnapshotStore`] 目录下的`<label>.json`；省略`to`时与当前Playbook比较。
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::models::playbook::{Playbook, PlaybookError};
+use crate::models::stats::PlaybookComparison;
+
+/// 接口路径
+pub const PATH: &str = "/compare";
+
+#[derive(Debug, Error)]
+pub enum CompareError {
+    #[error("查询参数无效: {0}")]
+    BadQuery(String),
+    #[error("快照不存在: {0}")]
+    NotFound(String),
+    #[error(transparent)]
+    Playbook(#[from] PlaybookError),
+}
+
+impl CompareError {
+    /// 对应的HTTP状态码
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::BadQuery(_) => 400,
+            Self::NotFound(_) => 404,
+            Self::Playbook(_) => 500,
+        }
+    }
+}
+
+/// 命名的Playbook快照，每个标签（如`2026-w41`）一个JSON文件
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 保存快照，同名标签覆盖
+    pub fn save(&self, label: &str, playbook: &Playbook) -> Result<(), CompareError> {
+        playbook.save_to_file(self.path_of(label)?)?;
+        Ok(())
+    }
+
+    pub fn load(&self, label: &str) -> Result<Playbook, CompareError> {
+        let path = self.path_of(label)?;
+        if !path.exists() {
+            return Err(CompareError::NotFound(label.to_string()));
+        }
+        Ok(Playbook::load_from_file(path)?)
+    }
+
+    /// 已有的快照标签（按名称排序）；目录不存在时为空
+    pub fn labels(&self) -> Result<Vec<String>, CompareError> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut labels = Vec::new();
+        for entry in fs::read_dir(&self.dir).map_err(PlaybookError::from)? {
+            let path = entry.map_err(PlaybookError::from)?.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("json")
+                && let Some(stem) = path.file_stem().and_then(|s| s.to_str())
+            {
+                labels.push(stem.to_string());
+            }
+        }
+        labels.sort();
+        Ok(labels)
+    }
+
+    /// 处理比较请求：`to`省略时以`current`作为新版本
+    pub fn compare(
+        &self,
+        query: &CompareQuery,
+        current: &Playbook,
+    ) -> Result<PlaybookComparison, CompareError> {
+        let old = self.load(&query.from)?;
+        match &query.to {
+            Some(to) => Ok(self.load(to)?.compare_stats(&old)),
+            None => Ok(current.compare_stats(&old)),
+        }
+    }
+
+    /// 标签只允许字母、数字与`-_.`，且不能以`.`开头，避免路径穿越
+    fn path_of(&self, label: &str) -> Result<PathBuf, CompareError> {
+        let valid = !label.is_empty()
+            && !label.starts_with('.')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+        if !valid {
+            return Err(CompareError::BadQuery(format!("非法的快照标签 {:?}", label)));
+        }
+        Ok(self.dir.join(format!("{}.json", label)))
+    }
+}
+
+/// `/compare`的查询参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompareQuery {
+    pub from: String,
+    pub to: Option<String>,
+}
+
+impl CompareQuery {
+    /// 解析请求路径或查询串（如`/compare?from=w40&to=w41`）；未知参数忽略
+    pub fn parse(path_or_query: &str) -> Result<Self, CompareError> {
+        let query = path_or_query.split_once('?').map_or(path_or_query, |(_, q)| q);
+        let (mut from, mut to) = (None, None);
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("from", v)) if !v.is_empty() => from = Some(v.to_string()),
+                Some(("to", v)) if !v.is_empty() => to = Some(v.to_string()),
+                _ => {}
+            }
+        }
+        let from = from.ok_or_else(|| CompareError::BadQuery("缺少from参数".to_string()))?;
+        Ok(Self { from, to })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::server::auth::Scope;
+
+    #[test]
+    fn test_compare_snapshots() {
+        let dir = std::env::temp_dir().join(format!("ace-compare-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = SnapshotStore::new(&dir);
+        assert!(store.labels().unwrap().is_empty());
+
+        let mut pb = Playbook::new();
+        let a = pb.add_bullet("api usage", "分页时带上cursor", None, None);
+        store.save("w40", &pb).unwrap();
+        pb.tag_bullet(&a, "helpful", 2).unwrap();
+        store.save("w41", &pb).unwrap();
+        let b = pb.add_bullet("debugging", "先看日志", None, None);
+        assert_eq!(store.labels().unwrap(), ["w40", "w41"]);
+
+        let query = CompareQuery::parse("/compare?from=w40&to=w41").unwrap();
+        let cmp = store.compare(&query, &pb).unwrap();
+        assert!(cmp.added.is_empty());
+        assert_eq!(cmp.drift.helpful, 2);
+
+        let query = CompareQuery::parse("from=w41").unwrap();
+        assert_eq!(store.compare(&query, &pb).unwrap().added, [b]);
+
+        let err = store.compare(&CompareQuery::parse("from=w39").unwrap(), &pb).unwrap_err();
+        assert_eq!(err.status(), 404);
+        assert_eq!(CompareQuery::parse("to=w41").unwrap_err().status(), 400);
+        assert_eq!(store.load("../w40").unwrap_err().status(), 400);
+        assert_eq!(Scope::required_for("GET", "/compare?from=w40"), Scope::Read);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+//! 内置的Web管理页：浏览章节与子弹、搜索、查看统计、批准或拒绝待审核的Delta
+//!
+//! 页面是单个编译进二进制的HTML文件（不依赖外部资源），由 [`http`](crate::server::http)
+//! 挂载在 [`MOUNT`] 下按 [`asset`] 返回；静态资源不含数据，不经鉴权。页面用`localStorage`中的
+//! API key调用以下接口：
+//!
+//! - `GET /playbook`：Playbook的JSON
+//! - `GET /stats`：[`StatsSnapshot`](crate::models::stats::StatsSnapshot)
+//! - `GET /compare?from=&to=`：两个快照的比较，见 [`compare`](crate::server::compare)
+//! - `GET /reviews`：[`ReviewQueue`](crate::models::review::ReviewQueue)
+//! - `POST /reviews/{id}/approve`、`POST /reviews/{id}/reject`
+
+/// 管理页挂载的路径前缀
+pub const MOUNT: &str = "/dashboard";
+
+const INDEX_HTML: &str = include_str!("dashboard/index.html");
+
+/// 按请求路径查找静态资源，返回 `(Content-Type, 内容)`
+pub fn asset(path: &str) -> Option<(&'static str, &'static [u8])> {
+    let path = path.split('?').next().unwrap_or_default();
+    let rest = path.strip_prefix(MOUNT)?;
+    match rest {
+        "" | "/" | "/index.html" => Some(("text/html; charset=utf-8", INDEX_HTML.as_bytes())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::server::auth::Scope;
+
+    #[test]
+    fn test_dashboard_asset_lookup() {
+        let (content_type, body) = asset("/dashboard/?tab=stats").unwrap();
+        assert_eq!(content_type, "text/html; charset=utf-8");
+        let html = std::str::from_utf8(body).unwrap();
+        for endpoint in ["\"/playbook\"", "\"/stats\"", "\"/reviews\"", "\"/reviews/\""] {
+            assert!(html.contains(endpoint), "{}", endpoint);
+        }
+        assert!(asset("/dashboard/app.js").is_none());
+        assert!(asset("/playbook").is_none());
+        // 页面调用的接口与鉴权范围一致
+        assert_eq!(Scope::required_for("POST", "/reviews/3/approve"), Scope::Curate);
+        assert_eq!(Scope::required_for("GET", "/reviews"), Scope::Read);
+    }
+}
@@ -0,0 +1,189 @@
+//! Playbook变更流：通过 [`ChangeFeed`] 应用的每个Delta产生一个 [`ChangeEvent`]，
+//! 推送给所有订阅者，HTTP层以Server-Sent Events转发给浏览器（[`ChangeEvent::to_sse`]）
+//!
+//! 最近的事件保留在环形缓冲中，断线重连时按`Last-Event-ID`补发（[`ChangeFeed::since`]）。
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::models::delta::DeltaBatch;
+use crate::models::playbook::{Bullet, BulletId, Playbook, PlaybookError};
+
+/// 单个子弹的变化
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Change {
+    Added { bullet: Bullet },
+    /// 内容、计数等发生变化（含打标签）
+    Updated { bullet: Bullet },
+    Removed { bullet_id: BulletId },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeEvent {
+    /// 单调递增的序号，作为SSE的事件ID
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    pub reasoning: String,
+    pub changes: Vec<Change>,
+}
+
+impl ChangeEvent {
+    /// SSE帧：`id`、`event: change`与单行JSON的`data`
+    pub fn to_sse(&self) -> String {
+        let data = serde_json::to_string(self).unwrap_or_default();
+        format!("id: {}\nevent: change\ndata: {}\n\n", self.seq, data)
+    }
+}
+
+#[derive(Debug, Default)]
+struct FeedState {
+    next_seq: u64,
+    recent: VecDeque<Arc<ChangeEvent>>,
+    subscribers: Vec<Sender<Arc<ChangeEvent>>>,
+}
+
+/// 变更广播；可放在`Arc`中由请求处理线程共享
+#[derive(Debug)]
+pub struct ChangeFeed {
+    /// 为断线重连保留的最近事件数
+    capacity: usize,
+    state: Mutex<FeedState>,
+}
+
+impl Default for ChangeFeed {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl ChangeFeed {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::default(),
+        }
+    }
+
+    /// 全部成功或全部不生效地应用Delta（见 [`Playbook::apply_delta_transactional`]），
+    /// 广播产生的变化（包括容量淘汰删除的子弹）；没有变化时不产生事件
+    ///
+    /// 变化取自应用时记录的被触及子弹的原状态，不扫描整个Playbook。
+    pub fn apply_delta(
+        &self,
+        playbook: &mut Playbook,
+        delta: DeltaBatch,
+    ) -> Result<Option<Arc<ChangeEvent>>, PlaybookError> {
+        let reasoning = delta.reasoning.clone();
+        let now = playbook.clock().now();
+        let mut journal = playbook.apply_delta_journaled_at(delta, now)?;
+        journal.commit(playbook);
+
+        let (mut added, mut updated, mut removed) = (Vec::new(), Vec::new(), Vec::new());
+        for (id, prior) in journal.bullets() {
+            match (prior, playbook.bullets.get(id)) {
+                (None, Some(bullet)) => added.push(Change::Added { bullet: (**bullet).clone() }),
+                (Some(_), Some(bullet)) => {
+                    updated.push(Change::Updated { bullet: (**bullet).clone() })
+                }
+                (Some(_), None) => removed.push(Change::Removed { bullet_id: id.clone() }),
+                (None, None) => {}
+            }
+        }
+        let mut changes = added;
+        changes.extend(updated);
+        changes.extend(removed);
+        if changes.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(self.publish(reasoning, changes, now)))
+    }
+
+    /// 广播一组变化，返回事件
+    pub fn publish(
+        &self,
+        reasoning: String,
+        changes: Vec<Change>,
+        at: DateTime<Utc>,
+    ) -> Arc<ChangeEvent> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.next_seq += 1;
+        let event = Arc::new(ChangeEvent {
+            seq: state.next_seq,
+            at,
+            reasoning,
+            changes,
+        });
+        state.subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+        state.recent.push_back(event.clone());
+        while state.recent.len() > self.capacity {
+            state.recent.pop_front();
+        }
+        event
+    }
+
+    /// 订阅之后的事件；接收端丢弃后自动退订
+    pub fn subscribe(&self) -> Receiver<Arc<ChangeEvent>> {
+        let (tx, rx) = mpsc::channel();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.subscribers.push(tx);
+        rx
+    }
+
+    /// 序号大于`last_seq`、仍在缓冲中的事件
+    pub fn since(&self, last_seq: u64) -> Vec<Arc<ChangeEvent>> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.recent.iter().filter(|e| e.seq > last_seq).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::models::delta::DeltaOperation;
+
+    #[test]
+    fn test_feed_broadcasts_and_replays() {
+        let feed = ChangeFeed::new(1);
+        let mut pb = Playbook::new();
+        let rx = feed.subscribe();
+        let batch = |operations| DeltaBatch {
+            reasoning: "learned".into(),
+            operations,
+        };
+
+        let added = feed.apply_delta(&mut pb, batch(vec![DeltaOperation::add("tips", "a")]));
+        let added = added.unwrap().unwrap();
+        let Change::Added { bullet } = &added.changes[0] else {
+            panic!("expected an added bullet");
+        };
+        let id = bullet.id.clone();
+        let mut tag = DeltaOperation::tag("tips", &id, Default::default());
+        tag.metadata.insert("helpful".into(), 1);
+        let tagged = feed.apply_delta(&mut pb, batch(vec![tag])).unwrap().unwrap();
+        assert!(matches!(&tagged.changes[..], [Change::Updated { bullet }] if bullet.helpful == 1));
+        let removed = batch(vec![DeltaOperation::remove("tips", &id)]);
+        feed.apply_delta(&mut pb, removed).unwrap().unwrap();
+        assert!(feed.apply_delta(&mut pb, batch(vec![])).unwrap().is_none());
+        // 失败的批次整体不生效，也不产生事件
+        let missing = DeltaOperation::tag("tips", &id, [("helpful".into(), 1)].into());
+        let failing = batch(vec![DeltaOperation::add("tips", "b"), missing]);
+        assert!(feed.apply_delta(&mut pb, failing).is_err());
+        assert!(pb.bullets.is_empty());
+
+        let seqs: Vec<u64> = rx.try_iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [1, 2, 3]);
+        // 缓冲只保留最近一个事件
+        let replay = feed.since(1);
+        assert_eq!(replay.len(), 1);
+        let sse = replay[0].to_sse();
+        assert!(sse.starts_with("id: 3\nevent: change\ndata: {"));
+        assert!(sse.contains(&format!(r#"{{"type":"removed","bullet_id":"{}"}}"#, id)));
+        assert!(sse.ends_with("}\n\n"));
+    }
+}
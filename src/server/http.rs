@@ -0,0 +1,644 @@
+//! axum实现的HTTP服务：鉴权（[`auth`](crate::server::auth)）、限流
+//! （[`limits`](crate::server::limits)）、变更流（[`events`](crate::server::events)）、
+//! 版本比较（[`compare`]）、审核队列与管理页（[`dashboard`]）在这里接到路由上
+//!
+//! 除管理页的静态资源与 [`openapi::PATH`] 的文档外，每个请求先经
+//! [`ApiKeys::authorize_request`] 鉴权，再经 [`RequestLimiter::check`] 限流；请求体上限同时
+//! 作为axum的`DefaultBodyLimit`，没有`Content-Length`的请求体同样受限。处理函数带
+//! `#[utoipa::path]`标注，[`openapi`] 的文档由同一组函数生成。状态放在同步锁里，
+//! 处理函数持锁期间不`await`。
+
+use std::convert::Infallible;
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{DefaultBodyLimit, Path, Query, RawQuery, Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, Uri, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::delta::DeltaBatch;
+use crate::models::playbook::{Playbook, PlaybookError};
+use crate::models::review::{ReviewError, ReviewItem, ReviewQueue};
+use crate::models::stats::{PlaybookComparison, StatsSnapshot};
+use crate::server::auth::{ApiKeys, AuthError};
+use crate::server::compare::{self, CompareError, CompareQuery, SnapshotStore};
+use crate::server::dashboard;
+use crate::server::events::{ChangeEvent, ChangeFeed};
+use crate::server::limits::{LimitConfig, LimitError, RequestLimiter};
+use crate::server::openapi::{self, openapi_document};
+
+/// SSE连接检查客户端是否已断开的间隔
+const POLL: Duration = Duration::from_secs(1);
+
+/// 服务的共享状态
+#[derive(Debug)]
+pub struct ServerState {
+    playbook: RwLock<Playbook>,
+    /// 修改后写回的文件；`None`时只保存在内存
+    playbook_path: Option<PathBuf>,
+    keys: ApiKeys,
+    limiter: Mutex<RequestLimiter>,
+    feed: ChangeFeed,
+    reviews: Mutex<ReviewQueue>,
+    reviews_path: Option<PathBuf>,
+    snapshots: Option<SnapshotStore>,
+}
+
+impl ServerState {
+    /// 默认限流（[`LimitConfig::default`]）、空的审核队列、不保存到文件
+    pub fn new(playbook: Playbook, keys: ApiKeys) -> Self {
+        Self {
+            playbook: RwLock::new(playbook),
+            playbook_path: None,
+            keys,
+            limiter: Mutex::new(RequestLimiter::default()),
+            feed: ChangeFeed::default(),
+            reviews: Mutex::new(ReviewQueue::new()),
+            reviews_path: None,
+            snapshots: None,
+        }
+    }
+
+    /// 每次修改后把Playbook保存到`path`
+    pub fn with_playbook_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.playbook_path = Some(path.into());
+        self
+    }
+
+    pub fn with_limits(mut self, config: LimitConfig) -> Self {
+        self.limiter = Mutex::new(RequestLimiter::new(config));
+        self
+    }
+
+    /// 审核队列；`path`不为空时每次审核后保存
+    pub fn with_reviews(mut self, queue: ReviewQueue, path: Option<PathBuf>) -> Self {
+        self.reviews = Mutex::new(queue);
+        self.reviews_path = path;
+        self
+    }
+
+    /// `/compare`使用的快照目录；未配置时该接口返回404
+    pub fn with_snapshots(mut self, store: SnapshotStore) -> Self {
+        self.snapshots = Some(store);
+        self
+    }
+
+    pub fn playbook(&self) -> RwLockReadGuard<'_, Playbook> {
+        self.playbook.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn playbook_mut(&self) -> RwLockWriteGuard<'_, Playbook> {
+        self.playbook.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 审核队列，供curator等在服务之外提交待审核的Delta
+    pub fn reviews(&self) -> MutexGuard<'_, ReviewQueue> {
+        self.reviews.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn feed(&self) -> &ChangeFeed {
+        &self.feed
+    }
+
+    fn limiter(&self) -> MutexGuard<'_, RequestLimiter> {
+        self.limiter.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save_playbook(&self, playbook: &Playbook) -> Result<(), ApiError> {
+        match &self.playbook_path {
+            Some(path) => playbook.save_to_file(path).map_err(ApiError::Storage),
+            None => Ok(()),
+        }
+    }
+
+    fn save_reviews(&self, reviews: &ReviewQueue) -> Result<(), ApiError> {
+        match &self.reviews_path {
+            Some(path) => reviews.save(path).map_err(ApiError::Storage),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 错误响应体
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+    #[error(transparent)]
+    Limit(#[from] LimitError),
+    #[error(transparent)]
+    Compare(#[from] CompareError),
+    #[error(transparent)]
+    Review(#[from] ReviewError),
+    /// Delta无效，Playbook未修改
+    #[error(transparent)]
+    Rejected(PlaybookError),
+    /// 修改已生效，但写回文件失败
+    #[error("保存失败: {0}")]
+    Storage(PlaybookError),
+    /// 请求体不是合法的JSON或超过上限
+    #[error(transparent)]
+    Body(#[from] JsonRejection),
+    #[error("服务未配置{0}")]
+    NotConfigured(&'static str),
+}
+
+impl ApiError {
+    /// 对应的HTTP状态码
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::Auth(err) => err.status(),
+            Self::Limit(err) => err.status(),
+            Self::Compare(err) => err.status(),
+            Self::Review(ReviewError::NotFound(_)) | Self::NotConfigured(_) => 404,
+            Self::Review(ReviewError::AlreadyDecided(..) | ReviewError::Stale(_)) => 409,
+            Self::Review(ReviewError::Playbook(_)) | Self::Rejected(_) => 400,
+            Self::Storage(_) => 500,
+            Self::Body(rejection) => rejection.status().as_u16(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status()).unwrap_or(StatusCode::BAD_REQUEST);
+        let retry_after = match &self {
+            Self::Limit(LimitError::TooManyRequests { retry_after_secs, .. }) => {
+                Some(*retry_after_secs)
+            }
+            _ => None,
+        };
+        let mut response = (status, Json(ErrorBody { error: self.to_string() })).into_response();
+        if let Some(secs) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
+    }
+}
+
+type Shared = State<Arc<ServerState>>;
+
+/// 全部路由：需要鉴权的接口，加上管理页与OpenAPI文档
+pub fn router(state: Arc<ServerState>) -> Router {
+    let body_limit = match state.limiter().config.max_body_bytes {
+        Some(max) => DefaultBodyLimit::max(max),
+        None => DefaultBodyLimit::disable(),
+    };
+    let api = Router::new()
+        .route("/playbook", get(get_playbook))
+        .route("/prompt", get(render_prompt))
+        .route("/delta", post(apply_delta))
+        .route("/stats", get(get_stats))
+        .route(compare::PATH, get(compare_snapshots))
+        .route("/events", get(stream_changes))
+        .route("/reviews", get(list_reviews))
+        .route("/reviews/{id}/approve", post(approve_review))
+        .route("/reviews/{id}/reject", post(reject_review))
+        .route_layer(middleware::from_fn_with_state(state.clone(), guard));
+    Router::new()
+        .route(openapi::PATH, get(|| async { Json(openapi_document()) }))
+        .route(dashboard::MOUNT, get(dashboard_asset))
+        .route(&format!("{}/", dashboard::MOUNT), get(dashboard_asset))
+        .route(&format!("{}/{{*asset}}", dashboard::MOUNT), get(dashboard_asset))
+        .merge(api)
+        .layer(body_limit)
+        .with_state(state)
+}
+
+/// 在`listener`上提供服务，直到出错
+pub async fn serve(listener: TcpListener, state: Arc<ServerState>) -> io::Result<()> {
+    axum::serve(listener, router(state)).await
+}
+
+/// 鉴权与限流；请求体大小先按`Content-Length`检查，实际读取时由`DefaultBodyLimit`限制
+async fn guard(State(state): Shared, request: Request, next: Next) -> Result<Response, ApiError> {
+    {
+        let method = request.method().as_str();
+        let path = request.uri().path();
+        let headers = request.headers();
+        let authorization = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+        let key = state.keys.authorize_request(method, path, authorization)?;
+        let body_len = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok()?.parse().ok())
+            .unwrap_or(0);
+        let now = state.playbook().clock().now();
+        state.limiter().check(&key.id, method, path, body_len, now)?;
+    }
+    Ok(next.run(request).await)
+}
+
+async fn dashboard_asset(uri: Uri) -> Response {
+    match dashboard::asset(uri.path()) {
+        Some((content_type, body)) => {
+            ([(header::CONTENT_TYPE, content_type)], body).into_response()
+        }
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// 当前的Playbook
+#[utoipa::path(
+    get,
+    path = "/playbook",
+    operation_id = "getPlaybook",
+    responses((status = 200, description = "Playbook", body = Playbook))
+)]
+pub(crate) async fn get_playbook(State(state): Shared) -> Json<Playbook> {
+    // 子弹在克隆之间共享，复制的只是索引
+    Json(state.playbook().clone())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct PromptQuery {
+    /// 逗号分隔的章节（可用别名），省略时渲染全部章节
+    sections: Option<String>,
+}
+
+/// 渲染为提示词（`Playbook::as_prompt_for`）
+#[utoipa::path(
+    get,
+    path = "/prompt",
+    operation_id = "renderPrompt",
+    params(PromptQuery),
+    responses((status = 200, description = "提示词文本", body = String, content_type = "text/plain"))
+)]
+pub(crate) async fn render_prompt(
+    State(state): Shared,
+    Query(query): Query<PromptQuery>,
+) -> String {
+    let playbook = state.playbook();
+    match query.sections {
+        Some(sections) => {
+            let sections: Vec<&str> = sections.split(',').map(str::trim).collect();
+            playbook.as_prompt_for(&sections)
+        }
+        None => playbook.as_prompt(),
+    }
+}
+
+/// 全部成功或全部不生效地应用DeltaBatch，产生的变化同时推送到`/events`
+#[utoipa::path(
+    post,
+    path = "/delta",
+    operation_id = "applyDelta",
+    request_body = DeltaBatch,
+    responses(
+        (status = 200, description = "变更事件（ChangeEvent），没有变化时为null", body = Object),
+        (status = 400, description = "Delta无效，Playbook未修改", body = ErrorBody),
+    )
+)]
+pub(crate) async fn apply_delta(
+    State(state): Shared,
+    delta: Result<Json<DeltaBatch>, JsonRejection>,
+) -> Result<Json<Option<Arc<ChangeEvent>>>, ApiError> {
+    let Json(delta) = delta?;
+    let mut playbook = state.playbook_mut();
+    let event = state.feed.apply_delta(&mut playbook, delta).map_err(ApiError::Rejected)?;
+    state.save_playbook(&playbook)?;
+    Ok(Json(event))
+}
+
+/// 统计快照（StatsSnapshot）
+#[utoipa::path(
+    get,
+    path = "/stats",
+    operation_id = "getStats",
+    responses((status = 200, description = "统计", body = Object))
+)]
+pub(crate) async fn get_stats(State(state): Shared) -> Json<StatsSnapshot> {
+    Json(state.playbook().snapshot_stats())
+}
+
+/// 两个快照之间的比较（PlaybookComparison）
+#[utoipa::path(
+    get,
+    path = "/compare",
+    operation_id = "compareSnapshots",
+    params(
+        ("from" = String, Query, description = "旧版本的快照标签"),
+        ("to" = Option<String>, Query, description = "新版本的快照标签，省略时与当前Playbook比较"),
+    ),
+    responses(
+        (status = 200, description = "比较结果", body = Object),
+        (status = 400, description = "查询参数无效", body = ErrorBody),
+        (status = 404, description = "快照不存在或未配置快照目录", body = ErrorBody),
+    )
+)]
+pub(crate) async fn compare_snapshots(
+    State(state): Shared,
+    RawQuery(query): RawQuery,
+) -> Result<Json<PlaybookComparison>, ApiError> {
+    let store = state.snapshots.as_ref().ok_or(ApiError::NotConfigured("快照目录"))?;
+    let query = CompareQuery::parse(query.as_deref().unwrap_or_default())?;
+    // 读快照文件期间不持锁
+    let current = state.playbook().clone();
+    Ok(Json(store.compare(&query, &current)?))
+}
+
+/// Playbook变更流（Server-Sent Events，事件名change）
+#[utoipa::path(
+    get,
+    path = "/events",
+    operation_id = "streamChanges",
+    params(
+        ("Last-Event-ID" = Option<u64>, Header, description = "断线重连时补发该序号之后的事件"),
+    ),
+    responses(
+        (status = 200, description = "事件流", body = String, content_type = "text/event-stream"),
+    )
+)]
+pub(crate) async fn stream_changes(State(state): Shared, headers: HeaderMap) -> Response {
+    let last_seq = headers.get("last-event-id").and_then(|v| v.to_str().ok()?.trim().parse().ok());
+    // 先订阅再取缓冲，两边都有的事件按序号去重
+    let live = state.feed.subscribe();
+    let backlog = last_seq.map(|seq| state.feed.since(seq)).unwrap_or_default();
+    let (tx, rx) = mpsc::channel(16);
+    tokio::task::spawn_blocking(move || forward_changes(backlog, live, tx));
+    let body = Body::from_stream(ReceiverStream::new(rx));
+    let content_type = (header::CONTENT_TYPE, "text/event-stream");
+    ([content_type, (header::CACHE_CONTROL, "no-cache")], body).into_response()
+}
+
+/// 把补发的和之后的事件转发给一个SSE连接，连接关闭后退出（最多延迟 [`POLL`]）
+fn forward_changes(
+    backlog: Vec<Arc<ChangeEvent>>,
+    live: Receiver<Arc<ChangeEvent>>,
+    tx: mpsc::Sender<Result<String, Infallible>>,
+) {
+    let mut last = 0;
+    let mut send = |event: Arc<ChangeEvent>| {
+        if event.seq <= last {
+            return true;
+        }
+        last = event.seq;
+        tx.blocking_send(Ok(event.to_sse())).is_ok()
+    };
+    if !backlog.into_iter().all(&mut send) {
+        return;
+    }
+    loop {
+        let open = match live.recv_timeout(POLL) {
+            Ok(event) => send(event),
+            Err(RecvTimeoutError::Timeout) => !tx.is_closed(),
+            Err(RecvTimeoutError::Disconnected) => false,
+        };
+        if !open {
+            return;
+        }
+    }
+}
+
+/// 审核队列（ReviewQueue）
+#[utoipa::path(
+    get,
+    path = "/reviews",
+    operation_id = "listReviews",
+    responses((status = 200, description = "审核队列", body = Object))
+)]
+pub(crate) async fn list_reviews(State(state): Shared) -> Json<ReviewQueue> {
+    Json(state.reviews().clone())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ReviewNote {
+    /// 审核备注（如拒绝原因）
+    note: Option<String>,
+}
+
+/// 批准并应用待审核的Delta
+#[utoipa::path(
+    post,
+    path = "/reviews/{id}/approve",
+    operation_id = "approveReview",
+    params(("id" = u64, Path, description = "审核项ID"), ReviewNote),
+    responses(
+        (status = 200, description = "处理后的审核项（ReviewItem）", body = Object),
+        (status = 400, description = "Delta无法应用，审核项保持待审核", body = ErrorBody),
+        (status = 404, description = "审核项不存在", body = ErrorBody),
+        (status = 409, description = "审核项已处理，或提交后Playbook已变化需重新审核", body = ErrorBody),
+    )
+)]
+pub(crate) async fn approve_review(
+    State(state): Shared,
+    Path(id): Path<u64>,
+    Query(query): Query<ReviewNote>,
+) -> Result<Json<ReviewItem>, ApiError> {
+    let mut playbook = state.playbook_mut();
+    let mut reviews = state.reviews();
+    reviews.approve(id, &mut playbook, query.note)?;
+    state.save_playbook(&playbook)?;
+    state.save_reviews(&reviews)?;
+    Ok(Json(reviews.get(id).cloned().expect("approved above")))
+}
+
+/// 拒绝待审核的Delta
+#[utoipa::path(
+    post,
+    path = "/reviews/{id}/reject",
+    operation_id = "rejectReview",
+    params(("id" = u64, Path, description = "审核项ID"), ReviewNote),
+    responses(
+        (status = 200, description = "处理后的审核项（ReviewItem）", body = Object),
+        (status = 404, description = "审核项不存在", body = ErrorBody),
+        (status = 409, description = "审核项已处理", body = ErrorBody),
+    )
+)]
+pub(crate) async fn reject_review(
+    State(state): Shared,
+    Path(id): Path<u64>,
+    Query(query): Query<ReviewNote>,
+) -> Result<Json<ReviewItem>, ApiError> {
+    let now = state.playbook().clock().now();
+    let mut reviews = state.reviews();
+    reviews.reject(id, query.note, now)?;
+    state.save_reviews(&reviews)?;
+    Ok(Json(reviews.get(id).cloned().expect("rejected above")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::body::to_bytes;
+    use chrono::DateTime;
+    use tokio_stream::StreamExt as _;
+    use tower::ServiceExt as _;
+
+    use crate::models::delta::DeltaOperation;
+    use crate::server::auth::Scope;
+    use crate::server::limits::KeyLimit;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(future)
+    }
+
+    fn request(method: &str, uri: &str, token: Option<&str>, body: &str) -> Request {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        if !body.is_empty() {
+            request = request
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::CONTENT_LENGTH, body.len());
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn call(app: &Router, request: Request) -> (StatusCode, HeaderMap, String) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await.unwrap();
+        (parts.status, parts.headers, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn delta_json(content: &str) -> String {
+        let delta = DeltaBatch {
+            reasoning: "learned".into(),
+            operations: vec![DeltaOperation::add("api", content)],
+        };
+        serde_json::to_string(&delta).unwrap()
+    }
+
+    #[test]
+    fn test_routes_authorize_limit_and_apply() {
+        let now = DateTime::UNIX_EPOCH;
+        let mut keys = ApiKeys::new();
+        let (_, reader) = keys.issue("dashboard", [Scope::Read], now).unwrap();
+        let (_, curator) = keys.issue("curator-bot", [Scope::Curate], now).unwrap();
+        let limits = LimitConfig {
+            default: Some(KeyLimit { per_minute: 1.0, burst: 1 }),
+            max_body_bytes: Some(512),
+            ..Default::default()
+        };
+        let state = Arc::new(ServerState::new(Playbook::new(), keys).with_limits(limits));
+        let app = router(state.clone());
+        let (reader, curator) = (Some(reader.as_str()), Some(curator.as_str()));
+
+        block_on(async {
+            let (status, _, body) = call(&app, request("GET", "/playbook", None, "")).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(serde_json::from_str::<ErrorBody>(&body).unwrap().error, "缺少API key");
+            let delta = delta_json("先分页");
+            let (status, ..) = call(&app, request("POST", "/delta", reader, &delta)).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+
+            let (status, _, body) = call(&app, request("POST", "/delta", curator, &delta)).await;
+            assert_eq!(status, StatusCode::OK);
+            let event: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(event["changes"][0]["type"], "added");
+            assert_eq!(state.playbook().bullets.len(), 1);
+            let (status, _, prompt) = call(&app, request("GET", "/prompt", reader, "")).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(prompt.contains("先分页"));
+
+            // 超过请求体上限、超出令牌桶
+            let large = delta_json(&"x".repeat(600));
+            let (status, ..) = call(&app, request("POST", "/delta", curator, &large)).await;
+            assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+            let (status, headers, _) =
+                call(&app, request("POST", "/delta", curator, "{}")).await;
+            assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(headers[header::RETRY_AFTER], "60");
+            assert_eq!(state.playbook().bullets.len(), 1);
+
+            // 管理页与文档不需要API key
+            let (status, headers, _) = call(&app, request("GET", "/dashboard/", None, "")).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(headers[header::CONTENT_TYPE], "text/html; charset=utf-8");
+            let (status, ..) = call(&app, request("GET", "/dashboard/app.js", None, "")).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            let (status, ..) = call(&app, request("GET", openapi::PATH, None, "")).await;
+            assert_eq!(status, StatusCode::OK);
+        });
+    }
+
+    #[test]
+    fn test_documented_paths_are_routed() {
+        let now = DateTime::UNIX_EPOCH;
+        let mut keys = ApiKeys::new();
+        let (_, admin) = keys.issue("ops", [Scope::Admin], now).unwrap();
+        let mut playbook = Playbook::new();
+        let mut queue = ReviewQueue::new();
+        let delta: DeltaBatch = serde_json::from_str(&delta_json("重试三次")).unwrap();
+        queue.submit(delta.clone(), "rate limit", &playbook);
+        queue.submit(delta, "rate limit", &playbook);
+        playbook.add_bullet("api", "先分页", None, None);
+        let state = ServerState::new(playbook, keys).with_reviews(queue, None);
+        let app = router(Arc::new(state));
+
+        let doc = openapi_document();
+        let paths = doc["paths"].as_object().unwrap();
+        assert_eq!(paths.len(), 9);
+        block_on(async {
+            for (path, item) in paths {
+                for method in item.as_object().unwrap().keys() {
+                    let uri = path.replace("{id}", "1");
+                    let uri = if path == compare::PATH { format!("{}?from=w40", uri) } else { uri };
+                    let body = if path == "/delta" { delta_json("先看日志") } else { String::new() };
+                    let method = method.to_uppercase();
+                    let request = request(&method, &uri, Some(&admin), &body);
+                    let response = app.clone().oneshot(request).await.unwrap();
+                    let content_type = &response.headers()[header::CONTENT_TYPE];
+                    // 未挂载的路由是axum的空404；处理函数的错误响应带JSON体
+                    assert!(
+                        response.status().is_success() || content_type == "application/json",
+                        "{} {} -> {}",
+                        method,
+                        uri,
+                        response.status()
+                    );
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_events_replay_after_last_event_id() {
+        let now = DateTime::UNIX_EPOCH;
+        let mut keys = ApiKeys::new();
+        let (_, token) = keys.issue("ops", [Scope::Curate], now).unwrap();
+        let state = Arc::new(ServerState::new(Playbook::new(), keys));
+        let app = router(state.clone());
+
+        block_on(async {
+            for content in ["先分页", "重试三次"] {
+                let body = delta_json(content);
+                let (status, ..) = call(&app, request("POST", "/delta", Some(&token), &body)).await;
+                assert_eq!(status, StatusCode::OK);
+            }
+            let mut events = request("GET", "/events", Some(&token), "");
+            events.headers_mut().insert("Last-Event-ID", HeaderValue::from(1));
+            let response = app.clone().oneshot(events).await.unwrap();
+            assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+            let mut stream = response.into_body().into_data_stream();
+            let frame = stream.next().await.unwrap().unwrap();
+            let frame = String::from_utf8(frame.to_vec()).unwrap();
+            assert!(frame.starts_with("id: 2\nevent: change\n"), "{}", frame);
+            assert!(frame.contains("重试三次"));
+        });
+    }
+}
@@ -0,0 +1,170 @@
+//! 修改类请求的限流：每个API key一个令牌桶，另限制请求体大小，
+//! 防止失控的agent刷写共享的Playbook
+//!
+//! 只读请求不受限制。HTTP层在鉴权通过后调用 [`RequestLimiter::check`]，
+//! 按 [`LimitError::status`] 返回413/429。
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::server::auth::Scope;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LimitError {
+    #[error("请求体{size}字节，超过上限{max}字节")]
+    PayloadTooLarge { size: usize, max: usize },
+    #[error("API key {key_id} 请求过于频繁，{retry_after_secs}秒后重试")]
+    TooManyRequests { key_id: String, retry_after_secs: u64 },
+}
+
+impl LimitError {
+    /// 对应的HTTP状态码
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::PayloadTooLarge { .. } => 413,
+            Self::TooManyRequests { .. } => 429,
+        }
+    }
+}
+
+/// 令牌桶参数
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KeyLimit {
+    /// 每分钟补充的请求数
+    pub per_minute: f64,
+    /// 桶容量（允许的突发请求数）
+    pub burst: u32,
+}
+
+/// 限流配置，可序列化进服务配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitConfig {
+    /// 未单独配置的key使用的限额；`None`表示不限流
+    pub default: Option<KeyLimit>,
+    /// 按key ID单独配置
+    pub per_key: BTreeMap<String, KeyLimit>,
+    /// 修改类请求的最大请求体字节数
+    pub max_body_bytes: Option<usize>,
+}
+
+impl Default for LimitConfig {
+    fn default() -> Self {
+        Self {
+            default: Some(KeyLimit {
+                per_minute: 60.0,
+                burst: 20,
+            }),
+            per_key: BTreeMap::new(),
+            max_body_bytes: Some(1 << 20),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: DateTime<Utc>,
+}
+
+/// 按key维护令牌桶
+#[derive(Debug, Clone, Default)]
+pub struct RequestLimiter {
+    pub config: LimitConfig,
+    buckets: BTreeMap<String, Bucket>,
+}
+
+impl RequestLimiter {
+    pub fn new(config: LimitConfig) -> Self {
+        Self {
+            config,
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// 检查一次请求；只读请求（[`Scope::required_for`]为read）直接放行。
+    /// 通过时消耗一个令牌
+    pub fn check(
+        &mut self,
+        key_id: &str,
+        method: &str,
+        path: &str,
+        body_len: usize,
+        now: DateTime<Utc>,
+    ) -> Result<(), LimitError> {
+        if Scope::required_for(method, path) == Scope::Read {
+            return Ok(());
+        }
+        if let Some(max) = self.config.max_body_bytes
+            && body_len > max
+        {
+            return Err(LimitError::PayloadTooLarge {
+                size: body_len,
+                max,
+            });
+        }
+        let Some(limit) = self.config.per_key.get(key_id).or(self.config.default.as_ref()) else {
+            return Ok(());
+        };
+
+        let per_sec = limit.per_minute / 60.0;
+        let capacity = f64::from(limit.burst.max(1));
+        let bucket = self.buckets.entry(key_id.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+        let elapsed = (now - bucket.refilled_at).as_seconds_f64().max(0.0);
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.refilled_at = now;
+        if bucket.tokens < 1.0 {
+            let wait = if per_sec > 0.0 { (1.0 - bucket.tokens) / per_sec } else { f64::MAX };
+            return Err(LimitError::TooManyRequests {
+                key_id: key_id.to_string(),
+                retry_after_secs: wait.ceil().min(u64::MAX as f64) as u64,
+            });
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_token_bucket_and_body_limit() {
+        let mut config = LimitConfig {
+            max_body_bytes: Some(100),
+            ..Default::default()
+        };
+        let slow = KeyLimit {
+            per_minute: 6.0,
+            burst: 2,
+        };
+        config.per_key.insert("key-002".into(), slow);
+        let mut limiter = RequestLimiter::new(config);
+        let now = DateTime::UNIX_EPOCH;
+
+        let err = limiter.check("key-002", "POST", "/deltas", 101, now).unwrap_err();
+        assert_eq!(err.status(), 413);
+        assert!(limiter.check("key-002", "POST", "/deltas", 10, now).is_ok());
+        assert!(limiter.check("key-002", "POST", "/bullets/a/tag", 10, now).is_ok());
+        let err = limiter.check("key-002", "POST", "/deltas", 10, now).unwrap_err();
+        let expected = LimitError::TooManyRequests {
+            key_id: "key-002".into(),
+            retry_after_secs: 10,
+        };
+        assert_eq!(err, expected);
+        // 只读请求与其他key不受影响
+        assert!(limiter.check("key-002", "GET", "/playbook", 1000, now).is_ok());
+        assert!(limiter.check("key-001", "POST", "/deltas", 10, now).is_ok());
+
+        let later = now + TimeDelta::seconds(10);
+        assert!(limiter.check("key-002", "POST", "/deltas", 10, later).is_ok());
+    }
+}
@@ -0,0 +1,10 @@
+//! Playbook的HTTP服务（[`http`]，axum）及其使用的鉴权、限流、变更流、管理页等，
+//! 需要 `server` feature；除`http`外各模块与HTTP框架无关
+
+pub mod auth;
+pub mod compare;
+pub mod dashboard;
+pub mod events;
+pub mod http;
+pub mod limits;
+pub mod openapi;
@@ -0,0 +1,228 @@
+//! 服务接口的OpenAPI 3.1文档，供其他语言生成客户端SDK，需要 `server` feature
+//!
+//! 路径与操作由 [`http`](crate::server::http) 中处理函数的`#[utoipa::path]`标注生成，与实际
+//! 挂载的路由是同一组函数。Playbook与DeltaBatch的Schema取自 [`playbook_schema`] /
+//! [`delta_batch_schema`]，与文件格式保持一致；每个操作以`x-required-scope`标注
+//! [`Scope::required_for`] 给出的权限范围，修改类操作附带限流的413/429响应
+//! （见 [`limits`](crate::server::limits)）。
+
+use std::borrow::Cow;
+
+use serde_json::Value;
+use utoipa::openapi::extensions::ExtensionsBuilder;
+use utoipa::openapi::path::Operation;
+use utoipa::openapi::schema::{Object, Schema};
+use utoipa::openapi::security::{
+    HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme,
+};
+use utoipa::openapi::{Content, Header, Ref, RefOr, Response, ResponseBuilder};
+use utoipa::{Modify, OpenApi, PartialSchema, ToSchema};
+
+use crate::models::delta::DeltaBatch;
+use crate::models::playbook::Playbook;
+use crate::models::schema::{delta_batch_schema, playbook_schema};
+use crate::server::auth::Scope;
+use crate::server::http;
+
+/// 文档的路径
+pub const PATH: &str = "/openapi.json";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "ace-rs playbook API"),
+    paths(
+        http::get_playbook,
+        http::render_prompt,
+        http::apply_delta,
+        http::get_stats,
+        http::compare_snapshots,
+        http::stream_changes,
+        http::list_reviews,
+        http::approve_review,
+        http::reject_review,
+    ),
+    components(schemas(http::ErrorBody)),
+    modifiers(&Security)
+)]
+struct ApiDoc;
+
+/// 完整的OpenAPI文档
+pub fn openapi_document() -> Value {
+    let mut doc = ApiDoc::openapi();
+    // utoipa按Cargo.toml填写license，清单里没有该字段时会生成空的license
+    doc.info.license = None;
+    serde_json::to_value(doc).unwrap_or_default()
+}
+
+/// schemars生成的根Schema拆成utoipa的Schema：`definitions`逐个作为components返回，
+/// 引用`#/definitions/X`改为`#/components/schemas/X`
+fn split_schema(mut root: Value) -> (RefOr<Schema>, Vec<(String, RefOr<Schema>)>) {
+    rewrite_refs(&mut root);
+    let mut definitions = Vec::new();
+    if let Some(root) = root.as_object_mut() {
+        root.remove("$schema");
+        if let Some(Value::Object(defs)) = root.remove("definitions") {
+            definitions.extend(defs.into_iter().map(|(name, schema)| (name, convert(schema))));
+        }
+    }
+    (convert(root), definitions)
+}
+
+fn convert(schema: Value) -> RefOr<Schema> {
+    serde_json::from_value(schema).unwrap_or_else(|_| RefOr::T(Schema::Object(Object::new())))
+}
+
+/// `#/definitions/X`改为`#/components/schemas/X`
+fn rewrite_refs(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(target) if key == "$ref" => {
+                        if let Some(name) = target.strip_prefix("#/definitions/") {
+                            *target = format!("#/components/schemas/{}", name);
+                        }
+                    }
+                    _ => rewrite_refs(value),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(rewrite_refs),
+        _ => {}
+    }
+}
+
+impl PartialSchema for Playbook {
+    fn schema() -> RefOr<Schema> {
+        split_schema(playbook_schema()).0
+    }
+}
+
+impl ToSchema for Playbook {
+    fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        schemas.extend(split_schema(playbook_schema()).1);
+    }
+
+    fn name() -> Cow<'static, str> {
+        "Playbook".into()
+    }
+}
+
+impl PartialSchema for DeltaBatch {
+    fn schema() -> RefOr<Schema> {
+        split_schema(delta_batch_schema()).0
+    }
+}
+
+impl ToSchema for DeltaBatch {
+    fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        schemas.extend(split_schema(delta_batch_schema()).1);
+    }
+
+    fn name() -> Cow<'static, str> {
+        "DeltaBatch".into()
+    }
+}
+
+/// Bearer鉴权与按权限范围附加的错误响应
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let error = |description: &str| -> RefOr<Response> {
+            let content = Content::new(Some(Ref::from_schema_name("ErrorBody")));
+            let response = ResponseBuilder::new().description(description);
+            response.content("application/json", content).build().into()
+        };
+        let components = openapi.components.get_or_insert_with(Default::default);
+        let bearer = HttpBuilder::new().scheme(HttpAuthScheme::Bearer).description(Some("API key"));
+        components.add_security_scheme("bearer", SecurityScheme::Http(bearer.build()));
+        let responses = &mut components.responses;
+        responses.insert("Unauthorized".into(), error("缺少API key，或API key无效、已吊销"));
+        responses.insert("Forbidden".into(), error("API key没有所需的权限范围"));
+        responses.insert("PayloadTooLarge".into(), error("请求体超过上限"));
+        let mut too_many = error("请求过于频繁");
+        if let RefOr::T(response) = &mut too_many {
+            let retry_after = Header::new(u64::schema());
+            response.headers.insert("Retry-After".into(), retry_after);
+        }
+        responses.insert("TooManyRequests".into(), too_many);
+        openapi.security = Some(vec![SecurityRequirement::new("bearer", Vec::<String>::new())]);
+
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            let methods = [("GET", &mut item.get), ("POST", &mut item.post)];
+            for (method, operation) in methods {
+                if let Some(operation) = operation {
+                    require_scope(operation, Scope::required_for(method, path));
+                }
+            }
+        }
+    }
+}
+
+/// 标注权限范围，加上鉴权失败的401/403，修改类操作再加413/429
+fn require_scope(operation: &mut Operation, scope: Scope) {
+    let mut responses = vec![("401", "Unauthorized"), ("403", "Forbidden")];
+    if scope != Scope::Read {
+        responses.extend([("413", "PayloadTooLarge"), ("429", "TooManyRequests")]);
+    }
+    for (status, name) in responses {
+        let response = RefOr::Ref(Ref::from_response_name(name));
+        operation.responses.responses.insert(status.into(), response);
+    }
+    let scope = serde_json::to_value(scope).unwrap_or_default();
+    let extension = ExtensionsBuilder::new().add("x-required-scope", scope).build();
+    operation.extensions.get_or_insert_with(Default::default).merge(extension);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 收集文档中所有`$ref`的目标
+    fn refs(value: &Value, out: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match value {
+                        Value::String(target) if key == "$ref" => out.push(target.clone()),
+                        _ => refs(value, out),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| refs(item, out)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_document_references_resolve() {
+        let doc = openapi_document();
+        assert!(doc["openapi"].as_str().unwrap().starts_with("3.1"));
+        let delta = &doc["paths"]["/delta"]["post"];
+        assert_eq!(
+            delta["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/DeltaBatch"
+        );
+        assert_eq!(delta["x-required-scope"], "curate");
+        assert_eq!(doc["paths"]["/playbook"]["get"]["x-required-scope"], "read");
+        assert!(doc["paths"]["/playbook"]["get"]["responses"].get("429").is_none());
+        assert!(doc["paths"]["/reviews/{id}/approve"]["post"]["responses"]["429"].is_object());
+
+        // 文件格式的Schema原样进入components
+        let schemas = &doc["components"]["schemas"];
+        let bullet = &schemas["Bullet"]["properties"];
+        assert_eq!(bullet["id"]["type"], "string");
+        let ops = &schemas["OperationType"]["enum"];
+        assert_eq!(ops.as_array().unwrap().len(), 8);
+        assert!(schemas["Playbook"]["required"].as_array().unwrap().contains(&"bullets".into()));
+
+        let mut targets = Vec::new();
+        refs(&doc, &mut targets);
+        assert!(targets.iter().any(|t| t == "#/components/schemas/OperationType"));
+        for target in targets {
+            let pointer = target.strip_prefix('#').unwrap();
+            assert!(doc.pointer(pointer).is_some(), "dangling {}", target);
+        }
+    }
+}
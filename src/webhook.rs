@@ -0,0 +1,647 @@
+//! Webhook通知：Delta应用、清理、阈值事件发生时向配置的地址POST带签名的JSON，
+//! 让Slack、运维工具等观察自学习过程
+//!
+//! 签名为 `HMAC-SHA256(secret, "{timestamp}.{body}")` 的十六进制，放在
+//! `X-Ace-Signature: sha256=...` 头中，时间戳放在`X-Ace-Timestamp`头中；接收方用同样的
+//! 方式计算并比较，同时拒绝时间戳过旧的请求以防重放。
+//!
+//! [`Webhooks`]实现了 [`PlaybookObserver`]，登记到Playbook上后随Delta应用与维护任务自动发送：
+//! 回调里只生成并签名请求，放进有界队列，由后台线程发送，不在应用Delta的线程上做网络I/O。
+
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, PoisonError};
+#[cfg(feature = "webhook")]
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::embedding::Degraded;
+use crate::hex::to_hex;
+use crate::maintenance::JobReport;
+use crate::models::observer::{AppliedDelta, PlaybookObserver};
+use crate::models::playbook::{Playbook, SectionName};
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("不支持的webhook地址：{0}")]
+    UnsupportedUrl(String),
+    #[error("webhook请求失败：{0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "webhook")]
+    #[error("webhook请求失败：{0}")]
+    Http(ureq::Error),
+    #[error("webhook返回状态码{status}：{url}")]
+    Status { url: String, status: u16 },
+    #[error("webhook事件无法序列化：{0}")]
+    Json(#[from] serde_json::Error),
+    #[error("webhook发送队列已满，丢弃事件{0}")]
+    QueueFull(&'static str),
+}
+
+/// 通知事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    DeltaApplied {
+        reasoning: String,
+        operations: usize,
+        added: usize,
+        removed: usize,
+    },
+    /// 维护任务删除了子弹（清理、去重或容量压缩）
+    Pruned { job: String, removed: usize },
+    /// 章节子弹数超过阈值（回落到阈值以下前只通知一次）
+    SectionOverThreshold {
+        section: String,
+        bullets: usize,
+        threshold: usize,
+    },
+    /// 向量模型不可用，检索或去重已退回词法方法
+    Degraded { operation: String, error: String },
+}
+
+impl WebhookEvent {
+    /// 事件名，与序列化后的`event`字段一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DeltaApplied { .. } => "delta_applied",
+            Self::Pruned { .. } => "pruned",
+            Self::SectionOverThreshold { .. } => "section_over_threshold",
+            Self::Degraded { .. } => "degraded",
+        }
+    }
+}
+
+impl From<&Degraded> for WebhookEvent {
+    fn from(event: &Degraded) -> Self {
+        Self::Degraded { operation: event.operation.to_string(), error: event.error.clone() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    pub url: String,
+    /// 签名密钥
+    pub secret: String,
+    /// 订阅的事件名；为空时订阅全部
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+}
+
+impl WebhookEndpoint {
+    fn subscribes(&self, event: &WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event.name())
+    }
+}
+
+/// Webhook配置，可序列化进运行配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// 章节子弹数阈值，超过时发送 [`WebhookEvent::SectionOverThreshold`]
+    pub section_max_bullets: Option<usize>,
+}
+
+/// 发送已签名的请求
+pub trait WebhookTransport: Send + Sync {
+    fn post(&self, url: &str, headers: &[(&str, String)], body: &[u8]) -> Result<(), WebhookError>;
+}
+
+/// 基于ureq的HTTP传输；`https://`地址需要启用`webhook-tls` feature
+/// （rustls，信任webpki-roots内置的根证书），否则返回 [`WebhookError::UnsupportedUrl`]
+#[cfg(feature = "webhook")]
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "webhook")]
+impl HttpTransport {
+    /// `timeout`限制单个请求的总时长（连接、发送与读取响应）
+    pub fn new(timeout: Duration) -> Self {
+        let config = ureq::Agent::config_builder()
+            .timeout_global(Some(timeout))
+            .max_redirects(0)
+            .build();
+        Self { agent: config.new_agent() }
+    }
+}
+
+#[cfg(feature = "webhook")]
+impl Default for HttpTransport {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5))
+    }
+}
+
+#[cfg(feature = "webhook")]
+impl WebhookTransport for HttpTransport {
+    fn post(&self, url: &str, headers: &[(&str, String)], body: &[u8]) -> Result<(), WebhookError> {
+        match url.split_once("://") {
+            Some(("http", _)) => {}
+            Some(("https", _)) if cfg!(feature = "webhook-tls") => {}
+            _ => return Err(WebhookError::UnsupportedUrl(url.to_string())),
+        }
+        let mut request = self.agent.post(url).content_type("application/json");
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        match request.send(body) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::StatusCode(status)) => {
+                Err(WebhookError::Status { url: url.to_string(), status })
+            }
+            Err(ureq::Error::Io(err)) => Err(WebhookError::Io(err)),
+            Err(ureq::Error::BadUri(_)) => Err(WebhookError::UnsupportedUrl(url.to_string())),
+            Err(err) => Err(WebhookError::Http(err)),
+        }
+    }
+}
+
+/// 已签名、待发送的请求
+struct Request {
+    url: String,
+    headers: [(&'static str, String); 3],
+    body: Arc<[u8]>,
+}
+
+impl Request {
+    fn post(&self, transport: &dyn WebhookTransport) -> Result<(), WebhookError> {
+        transport.post(&self.url, &self.headers, &self.body)
+    }
+}
+
+enum Job {
+    Post(&'static str, Vec<Request>),
+    /// 队列中之前的请求都已发送后回复
+    Flush(SyncSender<()>),
+}
+
+/// 后台发送队列的容量（按事件计），满了之后的事件直接丢弃并记为错误
+const QUEUE_CAPACITY: usize = 256;
+/// 最多暂存的发送错误，超出时丢弃最早的
+const MAX_ERRORS: usize = 64;
+
+type Errors = Arc<Mutex<VecDeque<WebhookError>>>;
+
+fn push_errors(errors: &Errors, new: Vec<WebhookError>) {
+    let mut errors = errors.lock().unwrap_or_else(PoisonError::into_inner);
+    for error in new {
+        if errors.len() == MAX_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back(error);
+    }
+}
+
+fn deliver(jobs: Receiver<Job>, transport: Arc<dyn WebhookTransport>, errors: Errors) {
+    for job in jobs {
+        match job {
+            Job::Post(_, requests) => {
+                let failed: Vec<_> =
+                    requests.iter().filter_map(|r| r.post(&*transport).err()).collect();
+                push_errors(&errors, failed);
+            }
+            Job::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+/// Webhook通知器
+///
+/// 可以直接调用各通知方法（在调用线程上同步发送并返回错误），也可以作为观察者登记到
+/// Playbook上（`playbook.add_observer(Arc::new(webhooks))`），在Delta应用与维护任务之后
+/// 自动通知。观察者回调只把请求放进后台队列，发送失败或队列已满的错误暂存（最多
+/// `MAX_ERRORS`条），用 [`Webhooks::take_errors`] 取出；[`Webhooks::flush`] 等待已入队的请求发完。
+pub struct Webhooks {
+    pub config: WebhookConfig,
+    transport: Arc<dyn WebhookTransport>,
+    /// 已通知超过阈值、尚未回落的章节
+    over_threshold: Mutex<BTreeSet<SectionName>>,
+    /// 后台发送线程的队列，首次入队时启动
+    queue: Mutex<Option<SyncSender<Job>>>,
+    /// 后台发送时的错误
+    errors: Errors,
+}
+
+impl std::fmt::Debug for Webhooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Webhooks")
+            .field("config", &self.config)
+            .field("over_threshold", &self.over_threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Webhooks {
+    #[cfg(feature = "webhook")]
+    pub fn new(config: WebhookConfig) -> Self {
+        Self::with_transport(config, HttpTransport::default())
+    }
+
+    pub fn with_transport(
+        config: WebhookConfig,
+        transport: impl WebhookTransport + 'static,
+    ) -> Self {
+        Self {
+            config,
+            transport: Arc::new(transport),
+            over_threshold: Mutex::default(),
+            queue: Mutex::default(),
+            errors: Errors::default(),
+        }
+    }
+
+    /// 向订阅了该事件的所有地址发送；一个地址失败不影响其他地址，返回全部错误
+    pub fn notify(&self, event: &WebhookEvent, now: DateTime<Utc>) -> Vec<WebhookError> {
+        match self.requests(event, now) {
+            Ok(requests) => {
+                requests.iter().filter_map(|r| r.post(&*self.transport).err()).collect()
+            }
+            Err(err) => vec![err],
+        }
+    }
+
+    /// 为订阅了该事件的每个地址生成签名请求
+    fn requests(
+        &self,
+        event: &WebhookEvent,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Request>, WebhookError> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            #[serde(flatten)]
+            event: &'a WebhookEvent,
+            sent_at: DateTime<Utc>,
+        }
+
+        let body: Arc<[u8]> = serde_json::to_vec(&Payload { event, sent_at: now })?.into();
+        let timestamp = now.timestamp().to_string();
+        let requests = self
+            .config
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.subscribes(event))
+            .map(|endpoint| {
+                let signature = sign(&endpoint.secret, &timestamp, &body);
+                Request {
+                    url: endpoint.url.clone(),
+                    headers: [
+                        ("X-Ace-Event", event.name().to_string()),
+                        ("X-Ace-Timestamp", timestamp.clone()),
+                        ("X-Ace-Signature", format!("sha256={}", signature)),
+                    ],
+                    body: body.clone(),
+                }
+            })
+            .collect();
+        Ok(requests)
+    }
+
+    /// 签名后放进后台队列（观察者回调使用）
+    fn enqueue(&self, events: Vec<WebhookEvent>, now: DateTime<Utc>) {
+        for event in events {
+            let job = match self.requests(&event, now) {
+                Ok(requests) if requests.is_empty() => continue,
+                Ok(requests) => Job::Post(event.name(), requests),
+                Err(err) => {
+                    push_errors(&self.errors, vec![err]);
+                    continue;
+                }
+            };
+            let Some(queue) = self.queue() else {
+                continue;
+            };
+            match queue.try_send(job) {
+                Ok(()) => {}
+                Err(TrySendError::Full(Job::Post(name, _))) => {
+                    push_errors(&self.errors, vec![WebhookError::QueueFull(name)]);
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// 后台发送线程的队列，尚未启动时启动；线程无法启动时记录错误并返回`None`
+    fn queue(&self) -> Option<SyncSender<Job>> {
+        let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(sender) = &*queue {
+            return Some(sender.clone());
+        }
+        let (sender, jobs) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let (transport, errors) = (self.transport.clone(), self.errors.clone());
+        let spawned = std::thread::Builder::new()
+            .name("ace-webhooks".into())
+            .spawn(move || deliver(jobs, transport, errors));
+        match spawned {
+            Ok(_) => Some(queue.insert(sender).clone()),
+            Err(err) => {
+                push_errors(&self.errors, vec![WebhookError::Io(err)]);
+                None
+            }
+        }
+    }
+
+    /// 等待后台队列中已有的请求发送完毕（没有启动后台线程时立即返回）
+    pub fn flush(&self) {
+        let queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        let Some(queue) = queue else {
+            return;
+        };
+        let (done, wait) = mpsc::sync_channel(1);
+        if queue.send(Job::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+
+    /// Delta应用后调用：发送 [`WebhookEvent::DeltaApplied`]，并检查章节阈值
+    pub fn delta_applied(&self, playbook: &Playbook, applied: &AppliedDelta) -> Vec<WebhookError> {
+        let mut errors = self.notify(&delta_event(applied), applied.at);
+        errors.extend(self.check_thresholds(playbook));
+        errors
+    }
+
+    /// 维护任务完成后调用：删除了子弹的任务各发送一次 [`WebhookEvent::Pruned`]
+    pub fn maintenance_ran(&self, playbook: &Playbook, reports: &[JobReport]) -> Vec<WebhookError> {
+        let now = playbook.clock().now();
+        let mut errors: Vec<WebhookError> =
+            pruned_events(reports).iter().flat_map(|event| self.notify(event, now)).collect();
+        errors.extend(self.check_thresholds(playbook));
+        errors
+    }
+
+    /// 对新超过阈值的章节发送 [`WebhookEvent::SectionOverThreshold`]
+    pub fn check_thresholds(&self, playbook: &Playbook) -> Vec<WebhookError> {
+        let now = playbook.clock().now();
+        let events = self.threshold_events(playbook);
+        events.iter().flat_map(|event| self.notify(event, now)).collect()
+    }
+
+    /// 新超过阈值的章节，同时更新已通知的章节集合
+    fn threshold_events(&self, playbook: &Playbook) -> Vec<WebhookEvent> {
+        let Some(threshold) = self.config.section_max_bullets else {
+            return Vec::new();
+        };
+        let mut events = Vec::new();
+        let mut over_threshold = self.over_threshold.lock().unwrap_or_else(PoisonError::into_inner);
+        for (section, bullet_ids) in playbook.sorted_sections() {
+            let bullets = bullet_ids.len();
+            if bullets <= threshold {
+                over_threshold.remove(section);
+            } else if over_threshold.insert(section.clone()) {
+                events.push(WebhookEvent::SectionOverThreshold {
+                    section: section.to_string(),
+                    bullets,
+                    threshold,
+                });
+            }
+        }
+        over_threshold.retain(|section| playbook.sections.contains_key(section));
+        events
+    }
+
+    /// 取出后台发送累积的错误（最多保留最近`MAX_ERRORS`条）
+    pub fn take_errors(&self) -> Vec<WebhookError> {
+        let mut errors = self.errors.lock().unwrap_or_else(PoisonError::into_inner);
+        errors.drain(..).collect()
+    }
+}
+
+fn delta_event(applied: &AppliedDelta) -> WebhookEvent {
+    WebhookEvent::DeltaApplied {
+        reasoning: applied.reasoning.clone(),
+        operations: applied.operations,
+        added: applied.impact.added,
+        removed: applied.impact.removed,
+    }
+}
+
+fn pruned_events(reports: &[JobReport]) -> Vec<WebhookEvent> {
+    reports
+        .iter()
+        .filter_map(|report| {
+            let (job, removed) = match *report {
+                JobReport::Pruned { removed } => ("prune", removed),
+                JobReport::Deduped { removed } => ("dedup", removed),
+                JobReport::Compacted { removed } => ("compact", removed),
+                JobReport::Archived { archived } => ("archive", archived),
+                JobReport::Decayed { .. } | JobReport::Snapshot(_) | JobReport::Failed { .. } => {
+                    return None;
+                }
+            };
+            (removed > 0).then(|| WebhookEvent::Pruned { job: job.to_string(), removed })
+        })
+        .collect()
+}
+
+impl PlaybookObserver for Webhooks {
+    fn on_delta_applied(&self, playbook: &Playbook, applied: &AppliedDelta) {
+        let mut events = vec![delta_event(applied)];
+        events.extend(self.threshold_events(playbook));
+        self.enqueue(events, applied.at);
+    }
+
+    fn on_maintenance(&self, playbook: &Playbook, reports: &[JobReport]) {
+        let mut events = pruned_events(reports);
+        events.extend(self.threshold_events(playbook));
+        self.enqueue(events, playbook.clock().now());
+    }
+}
+
+/// 计算签名：`HMAC-SHA256(secret, "{timestamp}.{body}")`的小写十六进制
+pub fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut context = hmac::Context::with_key(&key);
+    context.update(timestamp.as_bytes());
+    context.update(b".");
+    context.update(body);
+    to_hex(context.sign().as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "webhook")]
+    use std::io::{Read, Write};
+    #[cfg(feature = "webhook")]
+    use std::time::Duration;
+
+    use crate::models::delta::{DeltaBatch, DeltaOperation};
+
+    type Sent = Arc<Mutex<Vec<(String, Vec<(String, String)>, String)>>>;
+
+    struct Recording(Sent);
+
+    impl WebhookTransport for Recording {
+        fn post(
+            &self,
+            url: &str,
+            headers: &[(&str, String)],
+            body: &[u8],
+        ) -> Result<(), WebhookError> {
+            let headers = headers.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            self.0.lock().unwrap().push((url.to_string(), headers, body));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events_are_signed_and_filtered() {
+        let sent: Sent = Arc::default();
+        let config = WebhookConfig {
+            endpoints: vec![
+                WebhookEndpoint {
+                    url: "http://ops.local/hook".into(),
+                    secret: "s3cret".into(),
+                    events: vec![],
+                },
+                WebhookEndpoint {
+                    url: "http://slack.local/hook".into(),
+                    secret: "other".into(),
+                    events: vec!["section_over_threshold".into()],
+                },
+            ],
+            section_max_bullets: Some(1),
+        };
+        let hooks = Arc::new(Webhooks::with_transport(config, Recording(sent.clone())));
+        let mut pb = Playbook::new();
+        pb.add_observer(hooks.clone());
+        let delta = DeltaBatch {
+            reasoning: "learned".into(),
+            operations: vec![DeltaOperation::add("tips", "a"), DeltaOperation::add("tips", "b")],
+        };
+        pb.apply_delta(delta).unwrap();
+        // 阈值事件只在首次超过时发送
+        assert!(hooks.check_thresholds(&pb).is_empty());
+        hooks.flush();
+        assert!(hooks.take_errors().is_empty());
+
+        let sent = sent.lock().unwrap();
+        let urls: Vec<&str> = sent.iter().map(|(url, _, _)| url.as_str()).collect();
+        let (ops, slack) = ("http://ops.local/hook", "http://slack.local/hook");
+        assert_eq!(urls, [ops, ops, slack]);
+        let (_, headers, body) = &sent[0];
+        assert!(body.contains(r#""event":"delta_applied""#) && body.contains(r#""added":2"#));
+        let header = |name: &str| &headers.iter().find(|(k, _)| k == name).unwrap().1;
+        let expected = sign("s3cret", header("X-Ace-Timestamp"), body.as_bytes());
+        assert_eq!(header("X-Ace-Signature"), &format!("sha256={}", expected));
+    }
+
+    #[test]
+    fn test_maintenance_reports_are_sent() {
+        let sent: Sent = Arc::default();
+        let endpoint = WebhookEndpoint {
+            url: "http://ops.local/hook".into(),
+            secret: "s3cret".into(),
+            events: vec!["pruned".into()],
+        };
+        let config = WebhookConfig { endpoints: vec![endpoint], section_max_bullets: None };
+        let hooks = Arc::new(Webhooks::with_transport(config, Recording(sent.clone())));
+        let mut pb = Playbook::new();
+        pb.add_observer(hooks.clone());
+        let reports = [JobReport::Decayed { bullets: 3 }, JobReport::Pruned { removed: 2 }];
+        pb.notify_maintenance(&reports);
+        hooks.flush();
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].2.contains(r#""job":"prune","removed":2"#));
+    }
+
+    /// 阻塞到测试放行为止，放行前每个请求都失败
+    struct Stalled(Mutex<Receiver<()>>);
+
+    impl WebhookTransport for Stalled {
+        fn post(&self, url: &str, _: &[(&str, String)], _: &[u8]) -> Result<(), WebhookError> {
+            let _ = self.0.lock().unwrap().recv();
+            Err(WebhookError::UnsupportedUrl(url.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_observer_queues_instead_of_blocking() {
+        let (release, stalled) = mpsc::channel();
+        let endpoint = WebhookEndpoint {
+            url: "http://ops.local/hook".into(),
+            secret: "s3cret".into(),
+            events: vec![],
+        };
+        let config = WebhookConfig { endpoints: vec![endpoint], section_max_bullets: None };
+        let hooks = Arc::new(Webhooks::with_transport(config, Stalled(Mutex::new(stalled))));
+        let mut pb = Playbook::new();
+        pb.add_observer(hooks.clone());
+
+        // 发送线程卡在第一个请求上，应用Delta照常返回，队列满后丢弃事件
+        let events = QUEUE_CAPACITY + MAX_ERRORS + 10;
+        for i in 0..events {
+            pb.apply_delta(DeltaBatch {
+                reasoning: String::new(),
+                operations: vec![DeltaOperation::add("tips", format!("tip {}", i))],
+            })
+            .unwrap();
+        }
+        let queued = hooks.take_errors();
+        assert!(queued.len() >= 10);
+        assert!(queued.iter().all(|e| matches!(e, WebhookError::QueueFull("delta_applied"))));
+
+        drop(release);
+        hooks.flush();
+        let failed = hooks.take_errors();
+        assert_eq!(failed.len(), MAX_ERRORS);
+        assert!(failed.iter().all(|e| matches!(e, WebhookError::UnsupportedUrl(_))));
+    }
+
+    /// 在本地端口上接收一个请求，按`chunks`分多次写回响应
+    #[cfg(feature = "webhook")]
+    fn serve_once(chunks: &'static [&'static str]) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            for chunk in chunks {
+                stream.write_all(chunk.as_bytes()).unwrap();
+                stream.flush().unwrap();
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        });
+        format!("http://{}/hook", addr)
+    }
+
+    #[cfg(feature = "webhook")]
+    #[test]
+    fn test_http_status_line_split_across_reads() {
+        let transport = HttpTransport::default();
+        let url = serve_once(&["HTTP/1.1 2", "04 No Content\r\n\r\n"]);
+        transport.post(&url, &[], b"{}").unwrap();
+
+        let url = serve_once(&["HTTP/1.1 50", "3 Service Unavailable\r\n\r\n"]);
+        let err = transport.post(&url, &[], b"{}").unwrap_err();
+        assert!(matches!(err, WebhookError::Status { status: 503, .. }));
+    }
+
+    #[cfg(feature = "webhook")]
+    #[test]
+    fn test_https_requires_tls_feature() {
+        let transport = HttpTransport::new(Duration::from_millis(200));
+        // 拿到一个空闲端口后关闭监听，连接会被拒绝
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let err = transport.post(&format!("https://127.0.0.1:{}/hook", port), &[], b"{}");
+        if cfg!(feature = "webhook-tls") {
+            assert!(matches!(err, Err(WebhookError::Io(_))));
+        } else {
+            assert!(matches!(err, Err(WebhookError::UnsupportedUrl(_))));
+        }
+        let err = transport.post("ftp://ops.local/hook", &[], b"{}");
+        assert!(matches!(err, Err(WebhookError::UnsupportedUrl(_))));
+    }
+}